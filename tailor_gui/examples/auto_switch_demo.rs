@@ -1,8 +1,7 @@
 // examples/auto_switch_demo.rs
 //! Demonstrates automatic profile switching based on running applications
 
-use tailor_gui::profile_controller::{ProfileController, ProfileBuilder};
-use tailor_gui::profile_system::CpuPerformanceProfile;
+use tailor_gui::prelude::*;
 use std::thread;
 use std::time::Duration;
 
@@ -10,7 +9,7 @@ fn main() -> anyhow::Result<()> {
     println!("=== Automatic Profile Switching Demo ===\n");
     
     // Check permissions
-    if !tailor_gui::hardware_control::check_permissions()? {
+    if !check_permissions()? {
         eprintln!("⚠️  Warning: Not running as root. Hardware control may fail.");
         eprintln!("   Run with: sudo cargo run --example auto_switch_demo\n");
     }
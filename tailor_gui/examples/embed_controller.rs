@@ -0,0 +1,69 @@
+// examples/embed_controller.rs
+//! Uses the hardware control as a library, e.g. for a custom frontend or
+//! a headless service. Needs root like the other examples.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tailor_gui::prelude::*;
+
+const PROFILE_NAME: &str = "Embedded example";
+
+fn main() -> anyhow::Result<()> {
+    if !check_permissions()? {
+        eprintln!("Warning: Not running as root, applying the profile will likely fail");
+    }
+
+    // Stats: loads are computed between two samples
+    let mut monitor = HardwareMonitor::new()?;
+    monitor.get_system_stats()?;
+    thread::sleep(Duration::from_secs(1));
+    let stats = monitor.get_system_stats()?;
+    println!("CPU: {}", stats.cpu.load_summary());
+    for fan in &stats.fans {
+        println!("{}: {}", fan.display_name(), fan.speed_summary());
+    }
+
+    // Profiles: build one in code and apply it
+    let controller = ProfileController::new()?;
+    let profile = ProfileBuilder::new(PROFILE_NAME)
+        .cpu_performance(CpuPerformanceProfile::Balanced)
+        .fan_control_mode(FanControlMode::Software)
+        .keyboard_brightness(30)
+        .build();
+    if !controller.get_all_profiles().iter().any(|p| p.name == PROFILE_NAME) {
+        controller.add_profile(profile)?;
+    }
+    controller.apply_profile_by_name(PROFILE_NAME)?;
+    println!("Applied '{}'", PROFILE_NAME);
+
+    // Fans: the daemon follows the software curves of the active profile.
+    // ProfileController::start_fan_daemon does the same with its own parts.
+    let mut profile_manager = ProfileManager::new()?;
+    let index = profile_manager
+        .get_profiles()
+        .iter()
+        .position(|p| p.name == PROFILE_NAME)
+        .expect("profile was just added");
+    profile_manager.set_active_profile(index)?;
+
+    let daemon = FanDaemon::new(
+        Arc::new(Mutex::new(profile_manager)),
+        Arc::new(HardwareController::new()?),
+        Arc::new(Mutex::new(monitor)),
+    );
+    daemon.start();
+    for _ in 0..3 {
+        thread::sleep(Duration::from_secs(2));
+        println!("Fan targets: {:?}", daemon.last_targets().lock().unwrap());
+    }
+
+    // Hand the fans back to the firmware and clean up
+    daemon.stop();
+    controller.shutdown();
+    if let Some(index) = controller.get_all_profiles().iter().position(|p| p.name == PROFILE_NAME) {
+        controller.delete_profile(index)?;
+    }
+    Ok(())
+}
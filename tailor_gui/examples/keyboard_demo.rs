@@ -1,4 +1,4 @@
-use tailor_gui::keyboard_control::*;
+use tailor_gui::prelude::*;
 use std::thread;
 use std::time::Duration;
 
@@ -18,7 +18,13 @@ fn main() -> anyhow::Result<()> {
     
     println!("Current state:");
     println!("  Brightness: {}%", current_brightness);
-    println!("  Color: RGB({}, {}, {})\n", r, g, b);
+    println!("  Color: RGB({}, {}, {})", r, g, b);
+    let effects = kbd.list_hw_effects();
+    if effects.is_empty() {
+        println!("  Hardware effects: none\n");
+    } else {
+        println!("  Hardware effects: {}\n", effects.join(", "));
+    }
     
     // Brightness sweep
     println!("Demo: Brightness sweep");
@@ -1,5 +1,5 @@
-use tailor_gui::hardware_monitor::HardwareMonitor;
-use tailor_gui::profile_system::ProfileManager;
+use tailor_gui::app_config::PowerUnit;
+use tailor_gui::prelude::*;
 use std::thread;
 use std::time::Duration;
 
@@ -10,6 +10,10 @@ fn main() -> anyhow::Result<()> {
     let mut monitor = HardwareMonitor::new()?;
     let profile_mgr = ProfileManager::new()?;
     
+    let system = monitor.get_system_info();
+    println!("{} {}", system.manufacturer, system.product_name);
+    println!("{}\n", system.firmware_summary());
+    
     println!("Profile Manager initialized with {} profiles", 
              profile_mgr.get_profiles().len());
     println!("Active profile: {}\n", 
@@ -23,6 +27,7 @@ fn main() -> anyhow::Result<()> {
         
         // CPU Info
         println!("CPU:");
+        println!("  Driver: {}", stats.cpu.driver_summary());
         println!("  Package Temp: {:.1}°C", 
                  stats.cpu.package_temp.unwrap_or(0.0));
         
@@ -30,6 +35,12 @@ fn main() -> anyhow::Result<()> {
             println!("  Core {}: {} MHz, {:.1}% load", 
                      core.core_id, core.frequency_mhz, core.load_percent);
         }
+        if let Some(power) = stats.cpu.power_summary(PowerUnit::PercentOfTdp) {
+            println!("  Power: {}", power);
+        }
+        if !stats.cpu.offline_cores.is_empty() {
+            println!("  Offline cores: {:?}", stats.cpu.offline_cores);
+        }
         
         // GPU Info
         println!("\nGPUs:");
@@ -38,9 +49,34 @@ fn main() -> anyhow::Result<()> {
             if let Some(temp) = gpu.temperature {
                 println!("    Temp: {:.1}°C", temp);
             }
+            if let (Some(usage), Some(used), Some(total)) =
+                (gpu.vram_usage(), gpu.vram_used_mb, gpu.vram_total_mb) {
+                let filled = ((usage * 20.0).round() as usize).min(20);
+                println!("    VRAM: [{}{}] {} / {} MB",
+                         "#".repeat(filled), "-".repeat(20 - filled), used, total);
+            }
         }
         
-        println!("  Active GPU: {:?}\n", stats.active_gpu);
+        println!("  Active GPU: {:?}", stats.active_gpu);
+        
+        // Fan Info
+        println!("\nFans:");
+        for fan in &stats.fans {
+            let warning = if fan.is_stalled() { " [!] not spinning" } else { "" };
+            println!("  {}: {}{}", fan.display_name(), fan.speed_summary(), warning);
+        }
+        
+        // Battery Info
+        println!("\nBatteries:");
+        for battery in &stats.batteries {
+            println!("  {}: {}% ({})", battery.name,
+                     battery.charge_percent.unwrap_or(0),
+                     battery.status.as_deref().unwrap_or("Unknown"));
+        }
+        if let Some(total) = stats.total_battery_percent() {
+            println!("  Total: {:.0}%", total);
+        }
+        println!();
         
         thread::sleep(Duration::from_secs(2));
     }
@@ -1,8 +1,7 @@
 // examples/profile_application_demo.rs
 //! Demonstrates applying profiles and controlling hardware
 
-use tailor_gui::profile_controller::{ProfileController, ProfileBuilder};
-use tailor_gui::profile_system::CpuPerformanceProfile;
+use tailor_gui::prelude::*;
 use std::thread;
 use std::time::Duration;
 
@@ -10,7 +9,7 @@ fn main() -> anyhow::Result<()> {
     println!("=== Profile Application Demo ===\n");
     
     // Check permissions
-    if !tailor_gui::hardware_control::check_permissions()? {
+    if !check_permissions()? {
         eprintln!("⚠️  Warning: Not running as root. Hardware control may fail.");
         eprintln!("   Run with: sudo cargo run --example profile_application_demo\n");
     }
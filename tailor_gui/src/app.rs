@@ -1,10 +1,13 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use gtk::prelude::{
-    ApplicationExt, ApplicationWindowExt, GtkWindowExt, ObjectExt, SettingsExt, WidgetExt,
+    ActionableExt, ApplicationExt, ApplicationWindowExt, ButtonExt, GtkWindowExt, ObjectExt,
+    SettingsExt, WidgetExt,
 };
 use gtk::{gio, glib};
-use relm4::actions::{RelmAction, RelmActionGroup};
+use relm4::actions::{AccelsPlus, RelmAction, RelmActionGroup};
 use relm4::gtk::prelude::{BoxExt, OrientableExt};
 use relm4::{
     adw, gtk, main_application, Component, ComponentController, ComponentParts, ComponentSender,
@@ -13,13 +16,36 @@ use relm4::{
 use relm4_icons::icon_names;
 use tailor_api::ProfileInfo;
 
+use crate::app_config::AppConfig;
 use crate::components::fan_list::FanList;
 use crate::components::hardware_info::HardwareInfo;
+use crate::components::overlay::{OverlayInput, OverlayWindow};
 use crate::components::led_list::LedList;
+use crate::components::preferences::Preferences;
 use crate::components::profiles::Profiles;
+use crate::components::tuning::Tuning;
 use crate::config::{APP_ID, PROFILE};
+use crate::diagnostics::{self, Conflict};
+use crate::error::HardwareError;
+use crate::global_shortcuts::{self, GlobalShortcut};
+use crate::hardware_monitor::SystemStats;
 use crate::modals::about::AboutDialog;
-use crate::state::{initialize_tailor_state, TailorStateInner, STATE};
+use crate::notifications::Notifier;
+use crate::state::{
+    initialize_tailor_state, profile_controller, TailorStateInner, LOCAL_PROFILES_CHANGED,
+    POWER_SETTINGS_CHANGED, STATE,
+};
+use crate::sysfs_watcher::SysfsWatcher;
+use crate::profile_controller::{DriftWatcher, KeyboardEffectWatcher};
+use crate::profile_system::READ_ONLY_WARNING;
+use crate::stats_broadcaster::{StatsBroadcaster, StatsSubscription};
+use crate::thermal_history::{ThermalHistory, ThermalHistoryRecorder, DEFAULT_RISE_THRESHOLD};
+use crate::threshold_monitor::ThresholdWatcher;
+use crate::tray::TrayManager;
+use crate::util;
+
+/// How often the automatic profile switching is checked for a hang
+const APP_WATCHDOG_INTERVAL: Duration = Duration::from_secs(30);
 
 const CONNECT_ERROR_MSG: &str = r#"Please make sure <a href="https://github.com/AaronErhardt/tuxedo-rs#tailord">tailord</a> is running correctly on your system. Tailor will connect automatically once tailord becomes available."#;
 
@@ -45,17 +71,66 @@ pub(super) struct App {
     about_dialog: Controller<AboutDialog>,
     connection_state: ConnectionState,
     error: Option<adw::Toast>,
+    /// Keeps the local profiles in sync with edits made outside the GUI
+    _profiles_watcher: Option<SysfsWatcher>,
+    _power_source_watcher: Option<SysfsWatcher>,
+    /// Notices charge thresholds and performance profiles set by other tools
+    _external_changes_watcher: Option<SysfsWatcher>,
+    /// Turns the keyboard backlight off while the screen is off
+    _screen_power_watcher: Option<SysfsWatcher>,
+    /// Reads the stats once for the overlay, the tray and the watchers
+    stats: Option<StatsBroadcaster>,
+    /// Only reads while the overlay is shown
+    _overlay_stats: Option<StatsSubscription>,
+    /// Only reads while the tray icon is shown, for its tooltip
+    _tray_stats: Option<StatsSubscription>,
+    /// Fires alerts for the threshold rules of the app config
+    _threshold_watcher: Option<ThresholdWatcher>,
+    /// Keeps daily temperatures to notice cooling getting worse, paused
+    /// while neither the window nor the overlay can be seen
+    thermal_history: Option<ThermalHistoryRecorder>,
+    /// Colors the keyboard by temperature for profiles with that effect
+    _keyboard_effect: Option<KeyboardEffectWatcher>,
+    /// Keeps the recent CPU load for the cooling warnings of profile switches
+    /// and fan curve edits, read while the window is shown
+    _load: Option<StatsSubscription>,
+    window_shown: Arc<AtomicBool>,
+    /// Re-applies CPU settings that drifted, e.g. after a resume
+    _drift_watcher: Option<DriftWatcher>,
+    notifier: Notifier,
+    overlay: Controller<OverlayWindow>,
+    /// Session of the quick switch shortcut, if the desktop supports it
+    _quick_switch: Option<GlobalShortcut>,
+    /// Tray icon, if the session bus could export it
+    tray: Option<TrayManager>,
+    /// Other tools changing the same settings, shown until dismissed
+    conflicts: Option<String>,
 }
 
 #[derive(Debug)]
 pub(super) enum Command {
     SetInitializedState { error: Option<String> },
+    ConflictsDetected(Vec<Conflict>),
 }
 
 #[derive(Debug)]
 pub(super) enum AppMsg {
     AddError(String),
-    Quit,
+    ProfilesReloaded(usize),
+    ThresholdAlert(String),
+    QuickSwitchBound(GlobalShortcut),
+    TrayExported(TrayManager),
+    /// New stats for the tooltip of the tray icon
+    TrayStats(Box<SystemStats>),
+    /// The quick switch shortcut was pressed
+    NextProfile,
+    /// Push the active profile to the hardware again
+    ReapplyProfile,
+    DismissConflicts,
+    /// The main window was shown or hidden, e.g. minimized
+    WindowShown(bool),
+    /// Quit, or hide the window, see [`AppConfig::hide_on_close`]
+    CloseWindow,
 }
 
 relm4::new_action_group!(pub(super) WindowActionGroup, "win");
@@ -63,6 +138,8 @@ relm4::new_stateless_action!(PreferencesAction, WindowActionGroup, "preferences"
 relm4::new_stateless_action!(pub(super) ShortcutsAction, WindowActionGroup, "show-help-overlay");
 relm4::new_stateless_action!(AboutAction, WindowActionGroup, "about");
 relm4::new_stateless_action!(HardwareInfoAction, WindowActionGroup, "hw-info");
+relm4::new_stateless_action!(OverlayAction, WindowActionGroup, "overlay");
+relm4::new_stateless_action!(ReapplyAction, crate::AppActionGroup, "reapply");
 
 #[relm4::component(pub)]
 impl Component for App {
@@ -73,10 +150,15 @@ impl Component for App {
 
     menu! {
         primary_menu: {
+            section! {
+                "_Maximum performance" => crate::MaxPerformanceAction,
+                "_Restore profile settings" => crate::RestoreProfileAction,
+            },
             section! {
                 "_Preferences" => PreferencesAction,
                 "_Keyboard Shortcuts" => ShortcutsAction,
                 "_Hardware information" => HardwareInfoAction,
+                "_Monitoring overlay" => OverlayAction,
                 "_About Tailor" => AboutAction,
             }
         }
@@ -84,9 +166,9 @@ impl Component for App {
 
     view! {
         main_window = adw::ApplicationWindow::new(&main_application()) {
-            set_visible: true,
+            set_visible: !config.start_minimized,
             connect_close_request[sender] => move |_| {
-                sender.input(AppMsg::Quit);
+                sender.input(AppMsg::CloseWindow);
                 gtk::glib::Propagation::Stop
             },
 
@@ -113,6 +195,15 @@ impl Component for App {
                 gtk::Box {
                     set_orientation: gtk::Orientation::Vertical,
 
+                    adw::Banner {
+                        set_button_label: Some("Dismiss"),
+                        #[watch]
+                        set_title: model.conflicts.as_deref().unwrap_or_default(),
+                        #[watch]
+                        set_revealed: model.conflicts.is_some(),
+                        connect_button_clicked => AppMsg::DismissConflicts,
+                    },
+
                     adw::HeaderBar {
                         set_centering_policy: adw::CenteringPolicy::Strict,
 
@@ -130,6 +221,18 @@ impl Component for App {
                             }
                         },
 
+                        pack_start = &gtk::Button {
+                            set_icon_name: icon_names::SPEEDOMETER,
+                            set_tooltip_text: Some("Maximum performance"),
+                            set_action_name: Some("app.max-performance"),
+                        },
+
+                        pack_start = &gtk::Button {
+                            set_icon_name: "view-refresh-symbolic",
+                            set_tooltip_text: Some("Reapply the active profile"),
+                            set_action_name: Some("app.reapply"),
+                        },
+
                         pack_end = &gtk::MenuButton {
                             set_icon_name: icon_names::MENU_LARGE,
                             set_menu_model: Some(&primary_menu),
@@ -160,6 +263,10 @@ impl Component for App {
                                     add_titled[Some("fan"), "Fan control"] = fan_list -> gtk::ScrolledWindow {} -> {
                                         set_icon_name: Some(icon_names::DATA_BAR_VERTICAL_ASCENDING_FILLED),
                                     },
+                                    #[local_ref]
+                                    add_titled[Some("tuning"), "Tuning"] = tuning_widget -> gtk::ScrolledWindow {} -> {
+                                        set_icon_name: Some(icon_names::SPEEDOMETER),
+                                    },
                                 },
                                 #[name = "view_bar"]
                                 adw::ViewSwitcherBar {
@@ -245,6 +352,11 @@ impl Component for App {
             .launch(())
             .detach();
 
+        let overlay_shown = Arc::new(AtomicBool::new(false));
+        let overlay = OverlayWindow::builder()
+            .launch(Arc::clone(&overlay_shown))
+            .detach();
+
         let mut led_list = LedList::builder().launch(()).detach();
         led_list.detach_runtime();
         let led_list_widget = &**led_list.widget();
@@ -257,12 +369,168 @@ impl Component for App {
         profiles.detach_runtime();
         let profile_widget = &**profiles.widget();
 
+        let mut tuning = Tuning::builder().launch(()).detach();
+        tuning.detach_runtime();
+        let tuning_widget = &**tuning.widget();
+
+        let profiles_watcher = profile_controller().map(|controller| {
+            let input = sender.input_sender().clone();
+            controller.watch_profiles(move |profiles| {
+                *LOCAL_PROFILES_CHANGED.write() += 1;
+                input.emit(AppMsg::ProfilesReloaded(profiles.len()));
+            })
+        });
+
+        let power_source_watcher = profile_controller().map(|c| c.watch_power_source());
+        let external_changes_watcher = profile_controller().map(|controller| {
+            controller.watch_external_changes(|| *POWER_SETTINGS_CHANGED.write() += 1)
+        });
+
+        if let Some(controller) = profile_controller() {
+            // tailor-fand starts its daemon right away, so it's told as well
+            let fan_daemon_enabled = AppConfig::load().fan_daemon_enabled;
+            if !fan_daemon_enabled {
+                tracing::info!("Software fan control is turned off, the firmware controls the fans");
+            }
+            controller.set_fan_daemon_enabled(fan_daemon_enabled);
+
+            // Also writes nobody in the UI waits for, e.g. after applying
+            let input = sender.input_sender().clone();
+            controller.on_save_error(move |err| input.emit(AppMsg::AddError(err.to_owned())));
+            // Forgets a GPU switch that took effect with this boot. Asks
+            // prime-select, which can take a while.
+            std::thread::spawn(move || controller.pending_gpu_switch());
+
+            let input = sender.input_sender().clone();
+            let mut watch_apps = move || {
+                // Picks up trigger apps added after startup as well
+                if !controller.app_monitoring_enabled() && controller.has_trigger_apps() {
+                    if let Err(e) = controller.start_app_monitoring() {
+                        tracing::warn!("Can't start automatic profile switching: {e:#}");
+                    }
+                } else if controller.check_app_monitoring() {
+                    input.emit(AppMsg::AddError(
+                        "Automatic profile switching stopped responding and was restarted"
+                            .to_owned(),
+                    ));
+                }
+            };
+            watch_apps();
+            glib::timeout_add_local(APP_WATCHDOG_INTERVAL, move || {
+                watch_apps();
+                glib::ControlFlow::Continue
+            });
+        }
+
+        let config = AppConfig::load();
+        let stats = profile_controller().map(|controller| {
+            controller.stats_broadcaster(Duration::from_secs(
+                config.stats_refresh_interval_secs.into(),
+            ))
+        });
+        let overlay_stats = stats
+            .as_ref()
+            .map(|stats| Self::subscribe_overlay(stats, &overlay, overlay_shown));
+        let threshold_watcher = profile_controller()
+            .zip(stats.as_ref())
+            .filter(|_| !config.threshold_rules.is_empty())
+            .map(|(controller, stats)| {
+                let input = sender.input_sender().clone();
+                controller.watch_thresholds(
+                    stats,
+                    config.threshold_rules.clone(),
+                    move |event| input.emit(AppMsg::ThresholdAlert(event.message())),
+                )
+            });
+
+        let thermal_history = stats
+            .as_ref()
+            .filter(|_| config.record_thermal_history)
+            .and_then(|stats| {
+                let path = ThermalHistory::history_file().ok()?;
+                Some(ThermalHistoryRecorder::subscribe(stats, path))
+            });
+        let keyboard_effect = profile_controller()
+            .zip(stats.as_ref())
+            .map(|(controller, stats)| controller.watch_keyboard_effect(stats));
+        let window_shown = Arc::new(AtomicBool::new(true));
+        let load = profile_controller().zip(stats.as_ref()).map(|(controller, stats)| {
+            let shown = Arc::clone(&window_shown);
+            controller.watch_load(stats, move || shown.load(Ordering::Relaxed))
+        });
+        let cooling_warning = thermal_history
+            .as_ref()
+            .and_then(|recorder| recorder.history().check_cooling_health(DEFAULT_RISE_THRESHOLD));
+
+        let screen_power_watcher = profile_controller()
+            .filter(|_| config.keyboard_off_with_screen)
+            .map(|controller| controller.watch_screen_power());
+
+        let drift_watcher = profile_controller()
+            .filter(|_| config.reconcile_interval_secs > 0)
+            .map(|controller| {
+                controller.watch_drift(Duration::from_secs(config.reconcile_interval_secs.into()))
+            });
+
+        // Warn up front instead of after the first edit got lost
+        let read_only = profile_controller().is_some_and(|c| !c.profiles_writable());
+
         let model = Self {
             about_dialog,
             connection_state: ConnectionState::Connecting,
-            error: None,
+            error: if read_only {
+                Some(adw::Toast::new(READ_ONLY_WARNING))
+            } else {
+                cooling_warning.map(|warning| adw::Toast::new(&warning.to_string()))
+            },
+            _profiles_watcher: profiles_watcher,
+            _power_source_watcher: power_source_watcher,
+            _external_changes_watcher: external_changes_watcher,
+            _screen_power_watcher: screen_power_watcher,
+            stats,
+            _overlay_stats: overlay_stats,
+            _tray_stats: None,
+            _threshold_watcher: threshold_watcher,
+            thermal_history,
+            _keyboard_effect: keyboard_effect,
+            _load: load,
+            window_shown,
+            _drift_watcher: drift_watcher,
+            notifier: Notifier::new(config.notification_backend),
+            overlay,
+            _quick_switch: None,
+            tray: None,
+            conflicts: None,
         };
 
+        // Runs systemctl, so it stays off the main thread
+        sender.spawn_oneshot_command(|| Command::ConflictsDetected(diagnostics::detect_conflicts()));
+
+        if let Some(trigger) = config.quick_switch_shortcut.clone() {
+            let input = sender.input_sender().clone();
+            relm4::spawn_local(async move {
+                let activate = {
+                    let input = input.clone();
+                    move || input.emit(AppMsg::NextProfile)
+                };
+                match global_shortcuts::bind_next_profile(&trigger, activate).await {
+                    Ok(shortcut) => input.emit(AppMsg::QuickSwitchBound(shortcut)),
+                    Err(err) => tracing::info!("Quick switch shortcut is not available: {err:#}"),
+                }
+            });
+        }
+
+        // Clicking the icon activates the app like launching it again does
+        {
+            let input = sender.input_sender().clone();
+            relm4::spawn_local(async move {
+                match TrayManager::show(|| main_application().activate()).await {
+                    Ok(tray) => input.emit(AppMsg::TrayExported(tray)),
+                    Err(err) => tracing::info!("Tray icon is not available: {err:#}"),
+                }
+            });
+        }
+
         let widgets = view_output!();
 
         widgets
@@ -287,6 +555,23 @@ impl Component for App {
             })
         };
 
+        let preferences_action = {
+            let window = widgets.main_window.clone();
+            RelmAction::<PreferencesAction>::new_stateless(move |_| {
+                Preferences::builder()
+                    .transient_for(&window)
+                    .launch(())
+                    .detach();
+            })
+        };
+
+        let overlay_action = {
+            let sender = model.overlay.sender().clone();
+            RelmAction::<OverlayAction>::new_stateless(move |_| {
+                sender.emit(OverlayInput::Toggle);
+            })
+        };
+
         let about_action = {
             let sender = model.about_dialog.sender().clone();
             RelmAction::<AboutAction>::new_stateless(move |_| {
@@ -296,23 +581,144 @@ impl Component for App {
 
         let mut actions = RelmActionGroup::<WindowActionGroup>::new();
         actions.add_action(shortcuts_action);
+        actions.add_action(preferences_action);
         actions.add_action(about_action);
         actions.add_action(hardware_action);
+        actions.add_action(overlay_action);
         actions.register_for_widget(&widgets.main_window);
+        main_application().set_accelerators_for_action::<OverlayAction>(&["<Control><Shift>o"]);
+
+        // Needs the window for its toast, so it's added here instead of main
+        let reapply_action = {
+            let input = sender.input_sender().clone();
+            RelmAction::<ReapplyAction>::new_stateless(move |_| {
+                input.emit(AppMsg::ReapplyProfile);
+            })
+        };
+        let mut app_actions = RelmActionGroup::<crate::AppActionGroup>::new();
+        app_actions.add_action(reapply_action);
+        app_actions.register_for_main_application();
 
         widgets.load_window_size();
 
+        {
+            let input = sender.input_sender().clone();
+            util::connect_shown_changed(&widgets.main_window, move |shown| {
+                input.emit(AppMsg::WindowShown(shown));
+            });
+            // Started minimized, it wasn't shown to begin with
+            if !widgets.main_window.is_visible() {
+                sender.input(AppMsg::WindowShown(false));
+            }
+        }
+
+        // Launching Tailor again shows the window hidden by minimize to
+        // tray or start minimized, so does the tray icon by activating the
+        // app. The window keeps the app running.
+        {
+            let window = widgets.main_window.clone();
+            main_application().connect_activate(move |_| window.present());
+        }
+
         Self::initialize_connection(&sender, None);
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>, _root: &Self::Root) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match message {
             AppMsg::AddError(error) => {
                 self.error = Some(adw::Toast::new(&error));
             }
-            AppMsg::Quit => main_application().quit(),
+            AppMsg::ProfilesReloaded(count) => {
+                self.error = Some(adw::Toast::new(&format!(
+                    "Reloaded {count} profiles changed outside of Tailor"
+                )));
+            }
+            AppMsg::ThresholdAlert(message) => {
+                self.notifier.notify("threshold-alert", "Tailor", &message);
+                self.error = Some(adw::Toast::new(&message));
+            }
+            AppMsg::QuickSwitchBound(shortcut) => {
+                self._quick_switch = Some(shortcut);
+            }
+            AppMsg::TrayExported(tray) => {
+                self._tray_stats = self
+                    .stats
+                    .as_ref()
+                    .map(|stats| Self::subscribe_tray(stats, &tray, sender.input_sender().clone()));
+                self.tray = Some(tray);
+            }
+            AppMsg::TrayStats(stats) => {
+                if let Some(tray) = &self.tray {
+                    let profile = profile_controller().map(|c| c.get_active_profile().name);
+                    tray.update_tooltip(&stats, profile.as_deref());
+                }
+            }
+            AppMsg::NextProfile => {
+                let Some(controller) = profile_controller() else {
+                    return;
+                };
+                match controller.apply_next_profile() {
+                    Ok(profile) => {
+                        let message = format!("Switched to profile '{}'", profile.name);
+                        if controller.in_quiet_hours() {
+                            tracing::info!("Quiet hours, not notifying: {message}");
+                        } else {
+                            self.notifier.notify("profile-switch", "Tailor", &message);
+                        }
+                        // Too slow fan curves are worth more than the switch itself
+                        let warnings = controller.cooling_warnings(&profile);
+                        self.error = Some(adw::Toast::new(&if warnings.is_empty() {
+                            message
+                        } else {
+                            format!("{message}. {}", warnings.join(". "))
+                        }));
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to switch to the next profile: {err:#}");
+                        self.error = Some(hardware_error_toast(&err));
+                    }
+                }
+            }
+            AppMsg::ReapplyProfile => {
+                let Some(controller) = profile_controller() else {
+                    return;
+                };
+                match controller.reapply_active_profile() {
+                    Ok(profile) => {
+                        self.error = Some(adw::Toast::new(&format!(
+                            "Reapplied profile '{}'",
+                            profile.name
+                        )));
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to reapply the profile: {err:#}");
+                        self.error = Some(hardware_error_toast(&err));
+                    }
+                }
+            }
+            AppMsg::DismissConflicts => {
+                self.conflicts = None;
+            }
+            // Monitoring keeps its subscriptions, the thermal history and
+            // the load for the cooling warnings pause. The overlay and the keyboard effect only read while
+            // they are shown or the profile has one.
+            AppMsg::WindowShown(shown) => {
+                self.window_shown.store(shown, Ordering::Relaxed);
+                if let Some(recorder) = &self.thermal_history {
+                    // The overlay is meant for full screen apps hiding the window
+                    recorder.set_paused(!shown && !self.overlay.widget().is_visible());
+                }
+            }
+            AppMsg::CloseWindow => {
+                let tray_shown = self.tray.as_ref().is_some_and(TrayManager::is_shown);
+                if AppConfig::load().hide_on_close(tray_shown) {
+                    root.set_visible(false);
+                } else {
+                    main_application().quit();
+                }
+            }
         }
     }
 
@@ -332,6 +738,15 @@ impl Component for App {
                     self.connection_state = ConnectionState::Ok;
                 }
             }
+            Command::ConflictsDetected(conflicts) => {
+                for conflict in &conflicts {
+                    tracing::warn!("{conflict}");
+                }
+                if !conflicts.is_empty() {
+                    let lines: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+                    self.conflicts = Some(lines.join("\n"));
+                }
+            }
         }
     }
 
@@ -368,6 +783,15 @@ impl AppWidgets {
     }
 }
 
+/// Toast for a failed hardware change, asks for root only if that's the cause
+fn hardware_error_toast(err: &anyhow::Error) -> adw::Toast {
+    if HardwareError::is_permission_denied_in(err) {
+        adw::Toast::new("Changing hardware settings needs root, restart Tailor with sudo")
+    } else {
+        adw::Toast::new(&format!("{err:#}"))
+    }
+}
+
 impl App {
     fn initialize_connection(sender: &ComponentSender<Self>, delay: Option<Duration>) {
         sender.oneshot_command(async move {
@@ -379,4 +803,29 @@ impl App {
             }
         });
     }
+
+    fn subscribe_overlay(
+        stats: &StatsBroadcaster,
+        overlay: &Controller<OverlayWindow>,
+        shown: Arc<AtomicBool>,
+    ) -> StatsSubscription {
+        let overlay = overlay.sender().clone();
+        stats.subscribe_while(
+            move || shown.load(Ordering::Relaxed),
+            move |stats| overlay.emit(OverlayInput::Stats(Box::new(stats.clone()))),
+        )
+    }
+
+    /// The tooltip follows the stats interval, the broadcaster reads them
+    fn subscribe_tray(
+        stats: &StatsBroadcaster,
+        tray: &TrayManager,
+        input: relm4::Sender<AppMsg>,
+    ) -> StatsSubscription {
+        let shown = tray.shown_flag();
+        stats.subscribe_while(
+            move || shown.load(Ordering::Relaxed),
+            move |stats| input.emit(AppMsg::TrayStats(Box::new(stats.clone()))),
+        )
+    }
 }
@@ -0,0 +1,639 @@
+// src/app_config.rs
+use crate::hardware_monitor::{PendingGpuSwitch, TempSensorId};
+use crate::profile_system::{FanSafetyPolicy, ProfileManager};
+use crate::threshold_monitor::ThresholdRule;
+use crate::user_service::{self, UserService};
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Allowed range for the statistics refresh interval in seconds
+pub const REFRESH_INTERVAL_RANGE: RangeInclusive<u32> = 1..=60;
+
+/// Allowed range for the CPU settings reconciliation interval in seconds,
+/// 0 disables it
+pub const RECONCILE_INTERVAL_RANGE: RangeInclusive<u32> = 5..=600;
+
+/// Allowed range for the auto switch dwell time in seconds
+pub const AUTO_SWITCH_DWELL_RANGE: RangeInclusive<u32> = 0..=600;
+
+/// Allowed range for the temperature (°C) from which on fan curves must cool
+pub const FAN_DANGER_TEMP_RANGE: RangeInclusive<u8> = 50..=100;
+
+/// Allowed range for the CPU temperature offset in °C
+pub const CPU_TEMP_OFFSET_RANGE: RangeInclusive<f32> = -30.0..=30.0;
+
+/// Name of the desktop entry placed in ~/.config/autostart
+const AUTOSTART_FILE: &str = "com.github.aaronerhardt.Tailor.desktop";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 2] = [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "Celsius",
+            TemperatureUnit::Fahrenheit => "Fahrenheit",
+        }
+    }
+
+    /// Format a temperature given in °C in this unit
+    pub fn format(self, celsius: f32) -> String {
+        match self {
+            TemperatureUnit::Celsius => format!("{:.0}°C", celsius),
+            TemperatureUnit::Fahrenheit => format!("{:.0}°F", celsius * 9.0 / 5.0 + 32.0),
+        }
+    }
+}
+
+/// How CPU package power is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUnit {
+    #[default]
+    Watts,
+    /// Watts and their share of the TDP, where it's known
+    PercentOfTdp,
+}
+
+impl PowerUnit {
+    pub const ALL: [PowerUnit; 2] = [PowerUnit::Watts, PowerUnit::PercentOfTdp];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUnit::Watts => "Watts",
+            PowerUnit::PercentOfTdp => "Percent of TDP",
+        }
+    }
+
+    /// Format a power in watts, e.g. "27.9 W (93% of 30 W TDP)". Only the
+    /// watts with an unknown TDP.
+    pub fn format(self, watts: f32, tdp_watts: Option<f32>) -> String {
+        match self.tdp_share(watts, tdp_watts) {
+            Some(share) => format!("{:.1} W ({})", watts, share),
+            None => format!("{:.1} W", watts),
+        }
+    }
+
+    /// "93% of 30 W TDP", `None` in watts or with an unknown TDP
+    pub fn tdp_share(self, watts: f32, tdp_watts: Option<f32>) -> Option<String> {
+        let tdp = tdp_watts.filter(|tdp| *tdp > 0.0 && self == PowerUnit::PercentOfTdp)?;
+        Some(format!("{:.0}% of {:.0} W TDP", watts / tdp * 100.0, tdp))
+    }
+}
+
+/// How desktop notifications are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationBackend {
+    /// Probe on startup, preferring GNotification
+    #[default]
+    Auto,
+    /// GNotification, needs the installed .desktop file
+    GNotification,
+    /// libnotify protocol, works on any desktop with a notification daemon
+    Libnotify,
+}
+
+impl NotificationBackend {
+    pub const ALL: [NotificationBackend; 3] = [
+        NotificationBackend::Auto,
+        NotificationBackend::GNotification,
+        NotificationBackend::Libnotify,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationBackend::Auto => "Automatic",
+            NotificationBackend::GNotification => "GNotification",
+            NotificationBackend::Libnotify => "libnotify",
+        }
+    }
+}
+
+/// Time of day from `start` until `end`, every day. Windows with `end`
+/// before `start` run overnight into the next day, equal times are empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl DailyWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for DailyWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Parses "22:00-07:00"
+impl FromStr for DailyWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M");
+        let (start, end) = s
+            .split_once('-')
+            .and_then(|(start, end)| Some((parse(start).ok()?, parse(end).ok()?)))
+            .context(format!("Invalid time window '{}', use e.g. 22:00-07:00", s))?;
+        Ok(DailyWindow { start, end })
+    }
+}
+
+/// Settings of the GUI itself, stored next to the profiles in
+/// ~/.config/tuxedo-control/config.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Sensor used for the CPU temperature instead of the detected one
+    pub cpu_temp_sensor: Option<TempSensorId>,
+    /// °C added to displayed CPU temperatures, e.g. -10 for a Tctl sensor
+    /// that reads 10°C above the die
+    pub cpu_temp_offset: f32,
+    /// Let software fan curves follow the adjusted CPU temperature as well
+    pub cpu_temp_offset_for_fans: bool,
+    /// Closing the window hides it in the tray, see [`Self::hide_on_close`]
+    pub minimize_to_tray: bool,
+    /// Start with the window hidden. Launching Tailor again or clicking the
+    /// tray icon shows it.
+    pub start_minimized: bool,
+    pub stats_refresh_interval_secs: u32,
+    pub temperature_unit: TemperatureUnit,
+    pub power_unit: PowerUnit,
+    /// Profile applied when the GUI starts
+    pub startup_profile: Option<String>,
+    /// tailord profiles locked against changes and deletion in the GUI.
+    /// Local profiles keep their lock in the profile file.
+    pub locked_profiles: Vec<String>,
+    /// Start the GUI on login
+    pub autostart: bool,
+    /// Start the GUI with a systemd user service instead, see
+    /// [`crate::user_service::UserService`]. Replaces the autostart entry
+    /// where systemd runs, so only one instance starts.
+    pub autostart_service: bool,
+    /// Fade screen and keyboard brightness when applying a profile
+    pub smooth_brightness: bool,
+    /// Profiles set the brightness of every display, unless they override it
+    pub brightness_all_displays: bool,
+    /// Map brightness percentages on a gamma curve, so low values get finer
+    /// steps. Linear when off.
+    pub perceptual_brightness: bool,
+    /// Turn the keyboard backlight off while the screen is off
+    pub keyboard_off_with_screen: bool,
+    /// Keep daily CPU temperatures to warn when the cooling gets worse,
+    /// see [`crate::thermal_history::ThermalHistory`]. Reads the sensors
+    /// while the window or the overlay is shown.
+    pub record_thermal_history: bool,
+    /// Run the fan daemon for software fan curves. Off leaves the fans to
+    /// the firmware across restarts.
+    pub fan_daemon_enabled: bool,
+    /// Alerts when hardware stats cross a limit
+    pub threshold_rules: Vec<ThresholdRule>,
+    pub notification_backend: NotificationBackend,
+    /// Seconds between checks whether the CPU settings drifted from the
+    /// active profile, 0 disables the checks
+    pub reconcile_interval_secs: u32,
+    /// Show the monitoring overlay, restored on the next start
+    pub overlay_visible: bool,
+    /// Show how long the cores ran at each frequency in the overlay
+    pub overlay_frequency_histogram: bool,
+    /// Top left corner of the overlay when it was last hidden, only known
+    /// on X11, see [`crate::color_temperature::SessionType`]
+    pub overlay_position: Option<(i32, i32)>,
+    /// Seconds an auto-switched profile stays at least, and a trigger app
+    /// must be gone before the default profile comes back
+    pub auto_switch_dwell_secs: u32,
+    /// Preferred trigger of the global shortcut cycling through the
+    /// profiles, e.g. "CTRL+ALT+P". `None` doesn't register the shortcut.
+    pub quick_switch_shortcut: Option<String>,
+    /// Time of day without profile switch notifications, keyboard effects
+    /// rest at a dim static color meanwhile. Alerts still notify. `None`
+    /// disables it.
+    pub quiet_hours: Option<DailyWindow>,
+    /// Fan curves slower than `fan_danger_min_speed` (percent) at or above
+    /// `fan_danger_temp` (°C) need a confirmation before they are saved
+    pub fan_danger_temp: u8,
+    pub fan_danger_min_speed: u8,
+    /// GPU switch waiting for a restart
+    pub pending_gpu_switch: Option<PendingGpuSwitch>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            cpu_temp_sensor: None,
+            cpu_temp_offset: 0.0,
+            cpu_temp_offset_for_fans: false,
+            minimize_to_tray: false,
+            start_minimized: false,
+            stats_refresh_interval_secs: 2,
+            temperature_unit: TemperatureUnit::Celsius,
+            power_unit: PowerUnit::Watts,
+            startup_profile: None,
+            locked_profiles: Vec::new(),
+            autostart: false,
+            autostart_service: false,
+            smooth_brightness: false,
+            brightness_all_displays: false,
+            perceptual_brightness: false,
+            keyboard_off_with_screen: false,
+            record_thermal_history: false,
+            fan_daemon_enabled: true,
+            threshold_rules: Vec::new(),
+            notification_backend: NotificationBackend::Auto,
+            reconcile_interval_secs: 30,
+            overlay_visible: false,
+            overlay_frequency_histogram: false,
+            overlay_position: None,
+            auto_switch_dwell_secs: 30,
+            quick_switch_shortcut: None,
+            quiet_hours: None,
+            fan_danger_temp: FanSafetyPolicy::default().danger_temp,
+            fan_danger_min_speed: FanSafetyPolicy::default().min_speed,
+            pending_gpu_switch: None,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !REFRESH_INTERVAL_RANGE.contains(&self.stats_refresh_interval_secs) {
+            anyhow::bail!(
+                "Refresh interval must be {}-{} seconds",
+                REFRESH_INTERVAL_RANGE.start(),
+                REFRESH_INTERVAL_RANGE.end()
+            );
+        }
+        if self.reconcile_interval_secs != 0
+            && !RECONCILE_INTERVAL_RANGE.contains(&self.reconcile_interval_secs)
+        {
+            anyhow::bail!(
+                "Reconciliation interval must be 0 or {}-{} seconds",
+                RECONCILE_INTERVAL_RANGE.start(),
+                RECONCILE_INTERVAL_RANGE.end()
+            );
+        }
+        if !AUTO_SWITCH_DWELL_RANGE.contains(&self.auto_switch_dwell_secs) {
+            anyhow::bail!(
+                "Auto switch delay must be {}-{} seconds",
+                AUTO_SWITCH_DWELL_RANGE.start(),
+                AUTO_SWITCH_DWELL_RANGE.end()
+            );
+        }
+        if !CPU_TEMP_OFFSET_RANGE.contains(&self.cpu_temp_offset) {
+            anyhow::bail!(
+                "CPU temperature offset must be {} to {}°C",
+                CPU_TEMP_OFFSET_RANGE.start(),
+                CPU_TEMP_OFFSET_RANGE.end()
+            );
+        }
+        if !FAN_DANGER_TEMP_RANGE.contains(&self.fan_danger_temp) {
+            anyhow::bail!(
+                "Fan safety temperature must be {}-{}°C",
+                FAN_DANGER_TEMP_RANGE.start(),
+                FAN_DANGER_TEMP_RANGE.end()
+            );
+        }
+        if self.fan_danger_min_speed > 100 {
+            anyhow::bail!("Fan safety speed must be 0-100%");
+        }
+        if self.startup_profile.as_deref().is_some_and(str::is_empty) {
+            anyhow::bail!("Startup profile name must not be empty");
+        }
+        if let Some(shortcut) = &self.quick_switch_shortcut {
+            // Modifiers and the key name joined by '+', see the XDG shortcuts spec
+            if shortcut.split('+').any(|part| part.trim().is_empty()) {
+                anyhow::bail!(
+                    "Invalid quick switch shortcut '{}', use e.g. CTRL+ALT+P",
+                    shortcut
+                );
+            }
+        }
+        for rule in &self.threshold_rules {
+            rule.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Whether `time` falls into the quiet hours
+    pub fn is_quiet_at(&self, time: NaiveTime) -> bool {
+        self.quiet_hours.is_some_and(|window| window.contains(time))
+    }
+
+    /// Whether closing the window only hides it. Only with a tray icon to
+    /// bring it back, quitting is less surprising than an app running with
+    /// no sign of it.
+    pub fn hide_on_close(&self, tray_shown: bool) -> bool {
+        self.minimize_to_tray && tray_shown
+    }
+
+    /// CPU temperature as displayed, calibrated by `cpu_temp_offset`
+    pub fn adjust_cpu_temp(&self, celsius: f32) -> f32 {
+        celsius + self.cpu_temp_offset
+    }
+
+    /// Offset the fan curves apply to the CPU temperature, 0 unless opted in
+    pub fn fan_cpu_temp_offset(&self) -> f32 {
+        if self.cpu_temp_offset_for_fans {
+            self.cpu_temp_offset
+        } else {
+            0.0
+        }
+    }
+
+    /// Policy fan curve editors check before saving
+    pub fn fan_safety_policy(&self) -> FanSafetyPolicy {
+        FanSafetyPolicy {
+            danger_temp: self.fan_danger_temp,
+            min_speed: self.fan_danger_min_speed,
+        }
+    }
+
+    fn autostart_file() -> Result<PathBuf> {
+        let home = std::env::var("HOME").context("HOME environment variable not set")?;
+        Ok(PathBuf::from(home)
+            .join(".config/autostart")
+            .join(AUTOSTART_FILE))
+    }
+
+    /// Whether the XDG autostart entry starts the GUI. Not next to the
+    /// user service, both would launch an instance.
+    fn desktop_autostart(&self) -> bool {
+        self.autostart && !(self.autostart_service && user_service::systemd_available())
+    }
+
+    /// Create or remove the autostart entry to match `autostart`
+    pub fn sync_autostart(&self) -> Result<()> {
+        let path = Self::autostart_file()?;
+
+        if !self.desktop_autostart() {
+            if path.exists() {
+                fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+            }
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create autostart directory")?;
+        }
+        let entry = "[Desktop Entry]\n\
+                     Type=Application\n\
+                     Name=Tailor\n\
+                     Exec=tailor_gui\n\
+                     Icon=com.github.aaronerhardt.Tailor\n\
+                     X-GNOME-Autostart-enabled=true\n";
+        fs::write(&path, entry).context(format!("Failed to write {}", path.display()))
+    }
+
+    /// Install or remove the systemd user service to match `autostart_service`
+    pub fn sync_user_service(&self) -> Result<()> {
+        if !user_service::systemd_available() {
+            return Ok(());
+        }
+        UserService::new()?.sync(self.autostart_service)
+    }
+
+    pub fn config_file() -> Result<PathBuf> {
+        Ok(ProfileManager::get_config_dir()?.join("config.json"))
+    }
+
+    /// Load the config, falling back to defaults if it is missing or broken
+    pub fn load() -> Self {
+        match Self::config_file().and_then(|path| Self::load_from(&path)) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Warning: Using default settings: {:#}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse settings")
+    }
+
+    /// Delete the config file and return the default settings
+    pub fn reset() -> Result<Self> {
+        Self::reset_at(&Self::config_file()?)
+    }
+
+    pub fn reset_at(path: &Path) -> Result<Self> {
+        if path.exists() {
+            fs::remove_file(path).context(format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(Self::default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::config_file()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize settings")?;
+        fs::write(path, content).context(format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+
+        // Missing file means defaults
+        assert_eq!(AppConfig::load_from(&path).unwrap(), AppConfig::default());
+
+        let config = AppConfig {
+            cpu_temp_sensor: Some(TempSensorId {
+                chip: "acpitz".to_string(),
+                label: "temp1".to_string(),
+            }),
+            temperature_unit: TemperatureUnit::Fahrenheit,
+            ..AppConfig::default()
+        };
+        config.save_to(&path).unwrap();
+        assert_eq!(AppConfig::load_from(&path).unwrap(), config);
+
+        // Unknown and missing fields don't break older or newer configs
+        fs::write(&path, r#"{"unknown": true}"#).unwrap();
+        assert_eq!(AppConfig::load_from(&path).unwrap(), AppConfig::default());
+        // Configs from before the switch keep the fan daemon running
+        assert!(AppConfig::load_from(&path).unwrap().fan_daemon_enabled);
+        // and probe for a notification backend that works
+        assert_eq!(
+            AppConfig::load_from(&path).unwrap().notification_backend,
+            NotificationBackend::Auto
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+
+        let config = AppConfig {
+            autostart: true,
+            ..AppConfig::default()
+        };
+        config.save_to(&path).unwrap();
+
+        assert_eq!(AppConfig::reset_at(&path).unwrap(), AppConfig::default());
+        assert!(!path.exists());
+        // Resetting twice is fine
+        assert!(AppConfig::reset_at(&path).is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.stats_refresh_interval_secs = 0;
+        assert!(config.validate().is_err());
+
+        config.stats_refresh_interval_secs = 5;
+        config.startup_profile = Some(String::new());
+        assert!(config.validate().is_err());
+
+        config.startup_profile = None;
+        config.reconcile_interval_secs = 0;
+        assert!(config.validate().is_ok());
+        config.reconcile_interval_secs = 1;
+        assert!(config.validate().is_err());
+
+        config.reconcile_interval_secs = 0;
+        config.auto_switch_dwell_secs = 3600;
+        assert!(config.validate().is_err());
+
+        config.auto_switch_dwell_secs = 30;
+        config.fan_danger_temp = 20;
+        assert!(config.validate().is_err());
+
+        config.fan_danger_temp = 70;
+        config.cpu_temp_offset = -45.0;
+        assert!(config.validate().is_err());
+
+        config.cpu_temp_offset = 0.0;
+        config.quick_switch_shortcut = Some("CTRL+ALT+P".to_string());
+        assert!(config.validate().is_ok());
+        for invalid in ["", "CTRL+", "CTRL++P"] {
+            config.quick_switch_shortcut = Some(invalid.to_string());
+            assert!(config.validate().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_cpu_temp_offset() {
+        let mut config = AppConfig {
+            cpu_temp_offset: -10.0,
+            ..AppConfig::default()
+        };
+        assert_eq!(config.adjust_cpu_temp(72.5), 62.5);
+        // Display only unless the fans opt in
+        assert_eq!(config.fan_cpu_temp_offset(), 0.0);
+        config.cpu_temp_offset_for_fans = true;
+        assert_eq!(config.fan_cpu_temp_offset(), -10.0);
+    }
+
+    #[test]
+    fn test_hide_on_close() {
+        let mut config = AppConfig::default();
+        assert!(!config.hide_on_close(true));
+
+        config.minimize_to_tray = true;
+        assert!(config.hide_on_close(true));
+        // No panel shows the icon
+        assert!(!config.hide_on_close(false));
+    }
+
+    #[test]
+    fn test_temperature_format() {
+        assert_eq!(TemperatureUnit::Celsius.format(65.0), "65°C");
+        assert_eq!(TemperatureUnit::Fahrenheit.format(65.0), "149°F");
+    }
+
+    #[test]
+    fn test_power_format() {
+        assert_eq!(PowerUnit::Watts.format(27.94, Some(30.0)), "27.9 W");
+        assert_eq!(
+            PowerUnit::PercentOfTdp.format(27.94, Some(30.0)),
+            "27.9 W (93% of 30 W TDP)"
+        );
+        // Above a power limit that was lowered below the TDP
+        assert_eq!(
+            PowerUnit::PercentOfTdp.format(40.0, Some(35.0)),
+            "40.0 W (114% of 35 W TDP)"
+        );
+        // Unknown TDP
+        assert_eq!(PowerUnit::PercentOfTdp.format(12.0, None), "12.0 W");
+        assert_eq!(PowerUnit::PercentOfTdp.format(12.0, Some(0.0)), "12.0 W");
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let time = |hour, min| NaiveTime::from_hms_opt(hour, min, 0).unwrap();
+        let mut config = AppConfig::default();
+        assert!(!config.is_quiet_at(time(23, 0)));
+
+        // Overnight
+        config.quiet_hours = Some("22:00-07:00".parse().unwrap());
+        assert!(config.is_quiet_at(time(22, 0)));
+        assert!(config.is_quiet_at(time(23, 59)));
+        assert!(config.is_quiet_at(time(0, 0)));
+        assert!(config.is_quiet_at(time(6, 59)));
+        assert!(!config.is_quiet_at(time(7, 0)));
+        assert!(!config.is_quiet_at(time(12, 0)));
+        assert!(!config.is_quiet_at(time(21, 59)));
+
+        // Within a day
+        config.quiet_hours = Some("12:30 - 14:00".parse().unwrap());
+        assert!(config.is_quiet_at(time(13, 0)));
+        assert!(!config.is_quiet_at(time(14, 0)));
+        assert!(!config.is_quiet_at(time(2, 0)));
+        assert_eq!(config.quiet_hours.unwrap().to_string(), "12:30-14:00");
+
+        // Empty
+        config.quiet_hours = Some("08:00-08:00".parse().unwrap());
+        assert!(!config.is_quiet_at(time(8, 0)));
+
+        for invalid in ["", "22:00", "22:00-25:00", "late-early"] {
+            assert!(invalid.parse::<DailyWindow>().is_err(), "{invalid}");
+        }
+    }
+}
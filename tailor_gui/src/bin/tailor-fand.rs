@@ -0,0 +1,52 @@
+//! Runs fan control and applies profiles as root, for a GUI running as the
+//! user. See [`tailor_gui::fand`] for the protocol.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::{process, ptr, thread};
+
+use tailor_gui::fand::{self, FandServer};
+use tailor_gui::hardware_control::check_permissions;
+
+fn main() -> anyhow::Result<()> {
+    if !check_permissions()? {
+        anyhow::bail!("tailor-fand needs to run as root");
+    }
+
+    // Blocked before any thread starts, so every thread inherits it and
+    // only the signal thread receives them
+    let signals = termination_signals();
+    // SAFETY: `signals` is an initialized signal set
+    unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &signals, ptr::null_mut()) };
+
+    let server = Arc::new(FandServer::new()?);
+    let listener = fand::bind(Path::new(fand::SOCKET_PATH), fand::SOCKET_GROUP)?;
+    server.start();
+
+    let signal_server = Arc::clone(&server);
+    thread::spawn(move || {
+        let mut signal = 0;
+        // SAFETY: both pointers are valid for the call
+        unsafe { libc::sigwait(&signals, &mut signal) };
+        println!("Received signal {signal}, handing the fans back to the firmware");
+        signal_server.shutdown();
+        process::exit(0);
+    });
+
+    println!("Listening on {}", fand::SOCKET_PATH);
+    let result = server.serve(listener);
+    server.shutdown();
+    result
+}
+
+/// SIGTERM from systemd and SIGINT, so fans aren't left at a fixed speed
+fn termination_signals() -> libc::sigset_t {
+    // SAFETY: sigemptyset initializes the zeroed set before it's used
+    unsafe {
+        let mut signals: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut signals);
+        libc::sigaddset(&mut signals, libc::SIGTERM);
+        libc::sigaddset(&mut signals, libc::SIGINT);
+        signals
+    }
+}
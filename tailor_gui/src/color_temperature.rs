@@ -0,0 +1,297 @@
+// src/color_temperature.rs
+use anyhow::{Context, Result};
+use std::env;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Color temperatures a profile may request, 6500K is neutral
+pub const COLOR_TEMP_RANGE: RangeInclusive<u32> = 1000..=10000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    Wayland,
+    X11,
+}
+
+impl SessionType {
+    /// Session type of the environment we run in
+    pub fn detect() -> Option<Self> {
+        match env::var("XDG_SESSION_TYPE").as_deref() {
+            Ok("wayland") => return Some(SessionType::Wayland),
+            Ok("x11") => return Some(SessionType::X11),
+            _ => {}
+        }
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            Some(SessionType::Wayland)
+        } else if env::var_os("DISPLAY").is_some() {
+            Some(SessionType::X11)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTempBackend {
+    Gammastep,
+    Wlsunset,
+    Xrandr,
+}
+
+impl ColorTempBackend {
+    fn program(self) -> &'static str {
+        match self {
+            ColorTempBackend::Gammastep => "gammastep",
+            ColorTempBackend::Wlsunset => "wlsunset",
+            ColorTempBackend::Xrandr => "xrandr",
+        }
+    }
+
+    /// Pick the tool for the session from the ones `is_installed` accepts
+    pub fn select(session: SessionType, is_installed: impl Fn(&str) -> bool) -> Result<Self> {
+        let candidates: &[ColorTempBackend] = match session {
+            SessionType::Wayland => &[ColorTempBackend::Gammastep, ColorTempBackend::Wlsunset],
+            SessionType::X11 => &[ColorTempBackend::Xrandr],
+        };
+
+        match candidates.iter().find(|backend| is_installed(backend.program())) {
+            Some(backend) => Ok(*backend),
+            None if session == SessionType::Wayland => {
+                anyhow::bail!("Color temperature on Wayland needs gammastep or wlsunset, neither is installed")
+            }
+            None => anyhow::bail!("Color temperature on X11 needs xrandr, which is not installed"),
+        }
+    }
+}
+
+/// Whether an executable with this name is in PATH
+pub fn is_installed(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file())
+    })
+}
+
+/// Relative RGB intensity of a black body at `kelvin`, each in 0.0-1.0
+pub fn whitepoint(kelvin: u32) -> (f32, f32, f32) {
+    let temp = kelvin as f32 / 100.0;
+
+    let red = if temp <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (temp - 60.0).powf(-0.133_204_76)
+    };
+    let green = if temp <= 66.0 {
+        99.470_8 * temp.ln() - 161.119_57
+    } else {
+        288.122_17 * (temp - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if temp >= 66.0 {
+        255.0
+    } else if temp <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (temp - 10.0).ln() - 305.044_8
+    };
+
+    let scale = |value: f32| (value / 255.0).clamp(0.0, 1.0);
+    (scale(red), scale(green), scale(blue))
+}
+
+#[derive(Default)]
+struct ColorTempState {
+    /// gammastep/wlsunset must keep running on Wayland, the gamma ramps
+    /// are reset when they exit
+    child: Option<Child>,
+    /// Outputs whose gamma was changed with xrandr
+    xrandr_outputs: Vec<String>,
+    applied: Option<u32>,
+}
+
+/// Screen color temperature of the active profile. Changes are made on a
+/// background thread because they shell out to external tools, one worker
+/// applies them in order so the last request wins.
+#[derive(Default)]
+pub struct ColorTemperature {
+    state: Arc<Mutex<ColorTempState>>,
+    /// Started on the first change, stopped by [`Self::reset`]
+    worker: Mutex<Option<Worker>>,
+}
+
+struct Worker {
+    sender: Sender<Option<u32>>,
+    handle: JoinHandle<()>,
+}
+
+impl ColorTemperature {
+    /// Set the color temperature, `None` restores the neutral one
+    pub fn apply(&self, kelvin: Option<u32>) {
+        let mut worker = self.worker.lock().unwrap();
+        let Worker { sender, .. } = worker.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            let state = Arc::clone(&self.state);
+            let handle = thread::spawn(move || Self::run_worker(&state, receiver));
+            Worker { sender, handle }
+        });
+        if sender.send(kelvin).is_err() {
+            eprintln!("Warning: Color temperature worker stopped");
+            *worker = None;
+        }
+    }
+
+    fn run_worker(state: &Mutex<ColorTempState>, receiver: Receiver<Option<u32>>) {
+        while let Ok(kelvin) = receiver.recv() {
+            // Requests that came in meanwhile are outdated, only the
+            // latest one matters
+            let kelvin = receiver.try_iter().last().unwrap_or(kelvin);
+            let mut state = state.lock().unwrap();
+            if state.applied == kelvin {
+                continue;
+            }
+            if let Err(e) = Self::apply_blocking(&mut state, kelvin) {
+                eprintln!("Warning: Failed to set color temperature: {:#}", e);
+            }
+        }
+    }
+
+    /// Restore the neutral color temperature and wait until it's done.
+    /// Pending changes are made first so none of them comes after.
+    pub fn reset(&self) {
+        if let Some(Worker { sender, handle }) = self.worker.lock().unwrap().take() {
+            drop(sender);
+            let _ = handle.join();
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Err(e) = Self::apply_blocking(&mut state, None) {
+            eprintln!("Warning: Failed to reset color temperature: {:#}", e);
+        }
+    }
+
+    fn apply_blocking(state: &mut ColorTempState, kelvin: Option<u32>) -> Result<()> {
+        if let Some(mut child) = state.child.take() {
+            // Ignore errors, the tool may have exited on its own
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        for output in std::mem::take(&mut state.xrandr_outputs) {
+            set_xrandr_gamma(&output, (1.0, 1.0, 1.0))?;
+        }
+        state.applied = None;
+
+        let Some(kelvin) = kelvin else {
+            return Ok(());
+        };
+
+        let session = SessionType::detect().context("No graphical session found")?;
+        let backend = ColorTempBackend::select(session, is_installed)?;
+
+        match backend {
+            ColorTempBackend::Gammastep | ColorTempBackend::Wlsunset => {
+                let mut command = Command::new(backend.program());
+                if backend == ColorTempBackend::Gammastep {
+                    command.args(["-P", "-O", &kelvin.to_string()]);
+                } else {
+                    // Day and night temperature must differ, the location
+                    // doesn't matter with (almost) equal temperatures
+                    command.args(["-t", &kelvin.to_string(), "-T", &(kelvin + 1).to_string()]);
+                    command.args(["-l", "0", "-L", "0"]);
+                }
+                let child = command
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .context(format!("Failed to start {}", backend.program()))?;
+                state.child = Some(child);
+            }
+            ColorTempBackend::Xrandr => {
+                let gamma = whitepoint(kelvin);
+                for output in connected_xrandr_outputs()? {
+                    set_xrandr_gamma(&output, gamma)?;
+                    state.xrandr_outputs.push(output);
+                }
+            }
+        }
+
+        state.applied = Some(kelvin);
+        println!("  ✓ Color temperature: {}K ({})", kelvin, backend.program());
+        Ok(())
+    }
+}
+
+fn connected_xrandr_outputs() -> Result<Vec<String>> {
+    let output = Command::new("xrandr")
+        .arg("--query")
+        .output()
+        .context("Failed to execute xrandr")?;
+    if !output.status.success() {
+        anyhow::bail!("xrandr failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            (parts.next() == Some("connected")).then(|| name.to_string())
+        })
+        .collect())
+}
+
+fn set_xrandr_gamma(output: &str, (red, green, blue): (f32, f32, f32)) -> Result<()> {
+    // xrandr rejects a gamma of 0
+    let gamma = format!("{:.2}:{:.2}:{:.2}", red.max(0.1), green.max(0.1), blue.max(0.1));
+    let status = Command::new("xrandr")
+        .args(["--output", output, "--gamma", &gamma])
+        .status()
+        .context("Failed to execute xrandr")?;
+    if !status.success() {
+        anyhow::bail!("xrandr failed to set the gamma of {}", output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_backend() {
+        let all = |_: &str| true;
+        let none = |_: &str| false;
+        let only_wlsunset = |program: &str| program == "wlsunset";
+
+        assert_eq!(
+            ColorTempBackend::select(SessionType::Wayland, all).unwrap(),
+            ColorTempBackend::Gammastep
+        );
+        assert_eq!(
+            ColorTempBackend::select(SessionType::Wayland, only_wlsunset).unwrap(),
+            ColorTempBackend::Wlsunset
+        );
+        assert_eq!(
+            ColorTempBackend::select(SessionType::X11, all).unwrap(),
+            ColorTempBackend::Xrandr
+        );
+
+        let err = ColorTempBackend::select(SessionType::Wayland, none).unwrap_err();
+        assert!(err.to_string().contains("gammastep or wlsunset"));
+        assert!(ColorTempBackend::select(SessionType::X11, only_wlsunset).is_err());
+    }
+
+    #[test]
+    fn test_whitepoint() {
+        let (red, green, blue) = whitepoint(6500);
+        assert_eq!(red, 1.0);
+        assert!(green > 0.99 && blue > 0.97);
+
+        // Warmer means less blue than green than red
+        let (red, green, blue) = whitepoint(3000);
+        assert_eq!(red, 1.0);
+        assert!(green < red && blue < green);
+
+        assert_eq!(whitepoint(1000).2, 0.0);
+    }
+}
@@ -1,5 +1,6 @@
 use adw::prelude::{ExpanderRowExt, PreferencesRowExt};
-use gtk::prelude::{BoxExt, ButtonExt, CheckButtonExt, ObjectExt, WidgetExt};
+use chrono::{DateTime, Utc};
+use gtk::prelude::{BoxExt, ButtonExt, CheckButtonExt, ObjectExt, ToggleButtonExt, WidgetExt};
 use once_cell::unsync::Lazy;
 use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender, FactoryVecDeque};
 use relm4::{adw, factory, gtk, Component, ComponentController, Controller};
@@ -9,8 +10,15 @@ use tailor_api::{LedDeviceInfo, LedProfile, ProfileInfo};
 
 use super::profile_item_fan::{ProfileItemFan, ProfileItemFanInit};
 use super::profile_item_led::{ProfileItemLed, ProfileItemLedInit};
+use crate::app_config::AppConfig;
 use crate::components::profiles::ProfilesInput;
-use crate::state::{hardware_capabilities, TailorStateMsg, STATE};
+use crate::profile_system::{
+    format_last_applied, FanCurve, FanCurvePoint, Interpolation, NoiseLevel, PowerLevel,
+    TempSource,
+};
+use crate::state::{
+    hardware_capabilities, tailor_connection, TailorStateMsg, STATE,
+};
 use crate::templates;
 
 thread_local! {
@@ -22,6 +30,13 @@ pub struct Profile {
     pub name: String,
     pub info: ProfileInfo,
     pub active: bool,
+    /// When tailord last activated the profile
+    pub last_applied: Option<DateTime<Utc>>,
+    /// "quiet", estimated from the tailord fan profiles once they loaded
+    pub noise_hint: Option<NoiseLevel>,
+    /// Locked against changes and deletion, see
+    /// [`AppConfig::locked_profiles`]
+    pub locked: bool,
     pub leds: FactoryVecDeque<ProfileItemLed>,
     pub fans: FactoryVecDeque<ProfileItemFan>,
     pub performance: Option<Controller<SimpleComboBox<String>>>,
@@ -31,6 +46,7 @@ pub struct Profile {
 pub struct ProfileInit {
     pub name: String,
     pub info: ProfileInfo,
+    pub last_applied: Option<DateTime<Utc>>,
     pub active: bool,
     pub led_profiles: Vec<String>,
     pub fan_profiles: Vec<String>,
@@ -40,11 +56,12 @@ pub struct ProfileInit {
 pub enum ProfileInput {
     Enabled,
     UpdateProfile,
+    SetLocked(bool),
 }
 
 #[factory(pub)]
 impl FactoryComponent for Profile {
-    type CommandOutput = ();
+    type CommandOutput = Option<NoiseLevel>;
     type Init = ProfileInit;
     type Input = ProfileInput;
     type Output = ProfilesInput;
@@ -53,6 +70,11 @@ impl FactoryComponent for Profile {
     view! {
         self.leds.widget().clone() -> adw::ExpanderRow {
             set_title: &self.name,
+            #[watch]
+            set_subtitle: &self
+                .last_applied
+                .map(|applied| format_last_applied(applied, Utc::now()))
+                .unwrap_or_default(),
             set_hexpand: true,
 
             #[chain(build())]
@@ -79,6 +101,44 @@ impl FactoryComponent for Profile {
             add_action = &gtk::Box {
                 set_valign: gtk::Align::Center,
                 set_margin_end: 2,
+                set_spacing: 6,
+
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_tooltip_text: Some("Estimated from the fan curves"),
+                    #[watch]
+                    set_label: self.noise_hint.map_or("", NoiseLevel::label),
+                },
+
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_tooltip_text: Some("Estimated from the performance profile"),
+                    #[watch]
+                    set_label: self
+                        .info
+                        .performance_profile
+                        .as_deref()
+                        .and_then(PowerLevel::from_odm_profile)
+                        .map_or("", PowerLevel::label),
+                },
+
+                gtk::ToggleButton {
+                    set_icon_name: "changes-prevent-symbolic",
+                    add_css_class: "flat",
+                    #[watch]
+                    set_active: self.locked,
+                    #[watch]
+                    set_tooltip_text: Some(if self.locked {
+                        "Locked, click to allow changes"
+                    } else {
+                        "Lock to prevent changes and deletion"
+                    }),
+                    connect_toggled[sender] => move |btn| {
+                        sender.input(ProfileInput::SetLocked(btn.is_active()));
+                    },
+                },
 
                 #[name = "delete_button"]
                 gtk::Button {
@@ -86,7 +146,7 @@ impl FactoryComponent for Profile {
                     add_css_class: "destructive-action",
                     set_visible: false,
                     #[watch]
-                    set_sensitive: !self.active,
+                    set_sensitive: !self.active && !self.locked,
                     connect_clicked[sender, index] => move |_| {
                         sender.output(ProfilesInput::Remove(index.clone())).unwrap();
                     }
@@ -120,6 +180,7 @@ impl FactoryComponent for Profile {
             name,
             mut info,
             active,
+            last_applied,
             led_profiles,
             fan_profiles,
         } = init;
@@ -205,10 +266,19 @@ impl FactoryComponent for Profile {
                     .forward(sender.input_sender(), |_| ProfileInput::UpdateProfile)
             });
 
+        // Once per row, the list is rebuilt when a profile changes
+        let fan_profiles = info.fans.clone();
+        sender.oneshot_command(async move { tailord_noise_hint(fan_profiles).await });
+
+        let locked = AppConfig::load().locked_profiles.contains(&name);
+
         Self {
             name,
             info,
             active,
+            last_applied,
+            noise_hint: None,
+            locked,
             leds,
             fans,
             performance,
@@ -224,7 +294,25 @@ impl FactoryComponent for Profile {
                     STATE.emit(TailorStateMsg::SetActiveProfile(name));
                 });
             }
+            ProfileInput::SetLocked(locked) => {
+                if self.locked == locked {
+                    return;
+                }
+                let mut config = AppConfig::load();
+                config.locked_profiles.retain(|profile| profile != &name);
+                if locked {
+                    config.locked_profiles.push(name.clone());
+                }
+                match config.save() {
+                    Ok(()) => self.locked = locked,
+                    Err(err) => tracing::error!("Failed to change the lock of '{name}': {err:#}"),
+                }
+            }
             ProfileInput::UpdateProfile => {
+                if self.locked {
+                    tracing::warn!("Profile '{name}' is locked, not saving the change");
+                    return;
+                }
                 let leds = self.leds.iter().map(|led| led.get_profile()).collect();
 
                 let fans = self.fans.iter().map(|fan| fan.get_profile_name()).collect();
@@ -248,4 +336,33 @@ impl FactoryComponent for Profile {
             }
         }
     }
+
+    fn update_cmd(&mut self, noise_hint: Self::CommandOutput, _sender: FactorySender<Self>) {
+        self.noise_hint = noise_hint;
+    }
+}
+
+/// Loudness of the tailord fan profiles `fan_profiles`, `None` if one of
+/// them can't be loaded
+async fn tailord_noise_hint(fan_profiles: Vec<String>) -> Option<NoiseLevel> {
+    let connection = tailor_connection()?;
+    let mut curves = Vec::new();
+    for name in fan_profiles {
+        let points = connection.get_fan_profile(&name).await.ok()?;
+        // tailord ramps linearly between the points
+        curves.push(FanCurve {
+            points: points
+                .into_iter()
+                .map(|point| FanCurvePoint {
+                    temp: point.temp,
+                    speed: point.fan,
+                })
+                .collect(),
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: true,
+        });
+    }
+    Some(NoiseLevel::from_curves(&curves))
 }
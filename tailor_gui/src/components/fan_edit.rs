@@ -1,12 +1,17 @@
 use std::f64::consts::PI;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 use gtk::cairo::Operator;
 use gtk::gdk;
 use gtk::gdk::RGBA;
-use gtk::glib::{timeout_add_local_once, MainContext, SourceId};
-use gtk::prelude::{BoxExt, ButtonExt, DrawingAreaExt, GestureDragExt, OrientableExt, WidgetExt};
+use gtk::glib::{timeout_add_local, timeout_add_local_once, ControlFlow, MainContext, SourceId};
+use gtk::prelude::{
+    BoxExt, ButtonExt, CheckButtonExt, DrawingAreaExt, GestureDragExt, OrientableExt, RangeExt,
+    ToggleButtonExt, WidgetExt,
+};
 use relm4::abstractions::DrawHandler;
+use relm4::adw::prelude::{MessageDialogExt, MessageDialogExtManual};
 use relm4::{
     adw, component, gtk, Component, ComponentController, ComponentParts, ComponentSender,
     Controller, RelmWidgetExt,
@@ -14,8 +19,13 @@ use relm4::{
 use relm4_components::simple_combo_box::{SimpleComboBox, SimpleComboBoxMsg};
 use tailor_api::FanProfilePoint;
 
-use crate::state::{hardware_capabilities, tailor_connection, TailorStateMsg, STATE};
+use crate::app_config::AppConfig;
+use crate::profile_system::{FanControlMode, FanCurve, FanCurvePoint, Interpolation, TempSource};
+use crate::state::{
+    hardware_capabilities, profile_controller, tailor_connection, TailorStateMsg, STATE,
+};
 use crate::templates;
+use crate::util;
 
 struct Colors {
     stroke: RGBA,
@@ -36,8 +46,25 @@ impl Colors {
     }
 }
 
+/// Fan curve of a local profile, edited in place of a tailord fan profile
+#[derive(Debug, Clone)]
+pub struct LocalCurve {
+    pub profile_name: String,
+    pub fan_id: String,
+    pub curve: FanCurve,
+    pub mode: FanControlMode,
+}
+
+#[derive(Debug)]
+enum EditTarget {
+    /// tailord fan profile of this name
+    Tailord(String),
+    /// Keeps the settings of the curve the points don't cover
+    Local(LocalCurve),
+}
+
 pub struct FanEdit {
-    profile_name: Option<String>,
+    target: Option<EditTarget>,
     profile: Vec<FanProfilePoint>,
     drawing_handler: DrawHandler,
     drawn_points: Vec<(f64, f64)>,
@@ -48,11 +75,32 @@ pub struct FanEdit {
     visible: bool,
     last_override_event: Option<SourceId>,
     preview_fan: Controller<SimpleComboBox<String>>,
+    live_temp: Option<f32>,
+    live_update_source: Option<SourceId>,
+    /// How the fans follow the curve between the points. Only software fan
+    /// control of local profiles interpolates otherwise, tailord and the EC
+    /// always ramp linearly.
+    interpolation: Interpolation,
+    /// Show the temperature of the simulate slider instead of the live one
+    simulate: bool,
+    simulated_temp: f32,
+    /// The curve may be too slow for the CPU load of the last minute
+    cooling_warning: Option<String>,
 }
 
+/// tailord takes curves of any length, as long as there is a point
+const TAILORD_CURVE_POINTS: RangeInclusive<usize> = 1..=usize::MAX;
+
+/// Range of the simulate slider in °C
+const SIMULATE_MIN_TEMP: f64 = 30.0;
+const SIMULATE_MAX_TEMP: f64 = 100.0;
+
 #[derive(Debug)]
 pub enum FanEditInput {
     Load(String),
+    /// Edit a fan curve of a local profile, saving emits
+    /// [`FanEditOutput::LocalCurve`]
+    LoadLocal(LocalCurve),
     DragStart((f64, f64)),
     DragUpdate((f64, f64)),
     DragEnd((f64, f64)),
@@ -64,6 +112,29 @@ pub enum FanEditInput {
     UpdateColors,
     #[doc(hidden)]
     Apply,
+    /// Save without checking the curve again
+    #[doc(hidden)]
+    Save,
+    #[doc(hidden)]
+    LiveTemp(Option<f32>),
+    #[doc(hidden)]
+    SetInterpolation(Interpolation),
+    /// Remove the point clicked last
+    #[doc(hidden)]
+    RemovePoint,
+    #[doc(hidden)]
+    SetSimulate(bool),
+    #[doc(hidden)]
+    SimulateTemp(f32),
+    /// The dialog was shown or hidden, e.g. minimized
+    #[doc(hidden)]
+    SetShown(bool),
+}
+
+#[derive(Debug)]
+pub enum FanEditOutput {
+    /// The edited curve of [`FanEditInput::LoadLocal`]
+    LocalCurve { fan_id: String, curve: FanCurve },
 }
 
 #[component(pub)]
@@ -71,7 +142,7 @@ impl Component for FanEdit {
     type CommandOutput = Option<Vec<FanProfilePoint>>;
     type Init = ();
     type Input = FanEditInput;
-    type Output = ();
+    type Output = FanEditOutput;
 
     view! {
         #[template]
@@ -96,7 +167,26 @@ impl Component for FanEdit {
                             add_css_class: "title-4",
                             set_margin_all: 12,
                             #[watch]
-                            set_label: &format!("Edit fan profile '{}'", model.profile_name.as_deref().unwrap_or_default()),
+                            set_label: &model.title(),
+                        },
+
+                        #[wrap(Some)]
+                        set_end_widget = &gtk::DropDown {
+                            set_margin_all: 6,
+                            set_model: Some(&gtk::StringList::new(&["Linear", "Stepped", "Smooth"])),
+                            set_tooltip: "How software fan control follows the curve between the points",
+                            #[watch]
+                            set_visible: model.interpolation_editable(),
+                            #[watch]
+                            set_selected: Interpolation::ALL
+                                .iter()
+                                .position(|&interpolation| interpolation == model.interpolation)
+                                .unwrap_or_default() as u32,
+                            connect_selected_notify[sender] => move |dropdown| {
+                                if let Some(&interpolation) = Interpolation::ALL.get(dropdown.selected() as usize) {
+                                    sender.input(FanEditInput::SetInterpolation(interpolation));
+                                }
+                            },
                         },
                     },
                 },
@@ -158,6 +248,62 @@ impl Component for FanEdit {
                     }
                 },
 
+                gtk::Label {
+                    add_css_class: "warning",
+                    set_wrap: true,
+                    set_margin_start: 12,
+                    set_margin_end: 12,
+                    #[watch]
+                    set_visible: model.cooling_warning.is_some(),
+                    #[watch]
+                    set_label: model.cooling_warning.as_deref().unwrap_or_default(),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 12,
+                    set_margin_start: 12,
+                    set_margin_end: 12,
+
+                    gtk::Button {
+                        set_label: "Remove point",
+                        set_tooltip: "Remove the point clicked last, clicking an empty spot adds one",
+                        #[watch]
+                        set_sensitive: model.selection.is_some()
+                            && model.profile.len() > *model.point_count().start(),
+                        connect_clicked => FanEditInput::RemovePoint,
+                    },
+
+                    gtk::CheckButton {
+                        set_label: Some("Simulate"),
+                        set_tooltip: "Show the fan speed at the temperature of the slider instead of the current one",
+                        connect_toggled[sender] => move |check| {
+                            sender.input(FanEditInput::SetSimulate(check.is_active()));
+                        },
+                    },
+
+                    gtk::Scale::with_range(gtk::Orientation::Horizontal, SIMULATE_MIN_TEMP, SIMULATE_MAX_TEMP, 1.0) {
+                        set_hexpand: true,
+                        set_value: model.simulated_temp as f64,
+                        #[watch]
+                        set_sensitive: model.simulate,
+                        connect_value_changed[sender] => move |scale| {
+                            sender.input(FanEditInput::SimulateTemp(scale.value() as f32));
+                        },
+                    },
+
+                    gtk::Label {
+                        set_width_chars: 12,
+                        #[watch]
+                        set_label: &if model.simulate && !model.profile.is_empty() {
+                            let temp = model.simulated_temp;
+                            format!("{temp:.0}°C → {}%", model.target_speed(temp))
+                        } else {
+                            String::new()
+                        },
+                    },
+                },
+
                 #[template]
                 templates::MsgDialogButtons {
                     #[template_child]
@@ -188,7 +334,7 @@ impl Component for FanEdit {
             .detach();
 
         let model = Self {
-            profile_name: None,
+            target: None,
             profile: Vec::new(),
             drawing_handler: DrawHandler::new(),
             active_drag_info: None,
@@ -199,6 +345,12 @@ impl Component for FanEdit {
             visible: false,
             last_override_event: None,
             preview_fan,
+            live_temp: None,
+            live_update_source: None,
+            interpolation: Interpolation::Linear,
+            simulate: false,
+            simulated_temp: 60.0,
+            cooling_warning: None,
         };
 
         let drawing_area = model.drawing_handler.drawing_area();
@@ -206,6 +358,13 @@ impl Component for FanEdit {
         let fan_selection = model.preview_fan.widget();
         let widgets = view_output!();
 
+        {
+            let sender = sender.clone();
+            util::connect_shown_changed(&root, move |shown| {
+                sender.input(FanEditInput::SetShown(shown));
+            });
+        }
+
         adw::StyleManager::default().connect_color_scheme_notify(move |_| {
             sender.input(FanEditInput::UpdateColors);
         });
@@ -216,13 +375,15 @@ impl Component for FanEdit {
     fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match input {
             FanEditInput::Load(name) => {
-                self.profile_name = Some(name.clone());
+                self.target = Some(EditTarget::Tailord(name.clone()));
+                self.interpolation = Interpolation::Linear;
 
                 let capabilities = hardware_capabilities().unwrap();
-                if capabilities.num_of_fans <= 1 {
-                    // If we just have one fan, there's nothing to select
-                    self.preview_fan.widget().set_visible(false);
-                } else {
+                // If we just have one fan, there's nothing to select
+                self.preview_fan
+                    .widget()
+                    .set_visible(capabilities.num_of_fans > 1);
+                if capabilities.num_of_fans > 1 {
                     self.preview_fan
                         .emit(SimpleComboBoxMsg::UpdateData(SimpleComboBox {
                             variants: (0..capabilities.num_of_fans)
@@ -243,14 +404,93 @@ impl Component for FanEdit {
                 });
             }
             FanEditInput::Apply => {
+                if self.is_unsafe() {
+                    self.confirm_unsafe(&sender, root);
+                } else {
+                    sender.input(FanEditInput::Save);
+                }
+            }
+            FanEditInput::LoadLocal(local) => {
+                // The fan daemon follows local curves, tailord can't preview them
+                self.preview_fan.widget().set_visible(false);
+                self.interpolation = if local.mode == FanControlMode::Software {
+                    local.curve.interpolation
+                } else {
+                    Interpolation::Linear
+                };
+                let points = local
+                    .curve
+                    .points
+                    .iter()
+                    .map(|point| FanProfilePoint {
+                        temp: point.temp,
+                        fan: point.speed,
+                    })
+                    .collect();
+                self.target = Some(EditTarget::Local(local));
+                self.show(points, &sender);
+            }
+            FanEditInput::Save => {
                 self.visible = false;
-                if let Some(name) = self.profile_name.clone() {
-                    let profile = self.profile.drain(..).collect();
-                    STATE.emit(TailorStateMsg::AddFanProfile { name, profile });
+                self.stop_live_updates();
+                let curve = self.curve();
+                self.profile.clear();
+                match &self.target {
+                    Some(EditTarget::Tailord(name)) => STATE.emit(TailorStateMsg::AddFanProfile {
+                        name: name.clone(),
+                        profile: curve
+                            .points
+                            .iter()
+                            .map(|point| FanProfilePoint {
+                                temp: point.temp,
+                                fan: point.speed,
+                            })
+                            .collect(),
+                    }),
+                    Some(EditTarget::Local(local)) => {
+                        let _ = sender.output(FanEditOutput::LocalCurve {
+                            fan_id: local.fan_id.clone(),
+                            curve,
+                        });
+                    }
+                    None => (),
                 }
             }
             FanEditInput::Cancel => {
                 self.visible = false;
+                self.stop_live_updates();
+            }
+            FanEditInput::SetSimulate(simulate) => {
+                self.simulate = simulate;
+            }
+            FanEditInput::SimulateTemp(temp) => {
+                self.simulated_temp = temp;
+            }
+            // A read can still arrive after the updates were stopped
+            FanEditInput::LiveTemp(temp) if self.live_update_source.is_some() => {
+                self.live_temp = temp;
+            }
+            FanEditInput::LiveTemp(_) => {}
+            // No need to read the sensors while nobody sees the marker
+            FanEditInput::SetShown(false) => self.stop_live_updates(),
+            FanEditInput::SetShown(true) => {
+                if self.visible {
+                    self.start_live_updates(&sender);
+                }
+            }
+            FanEditInput::SetInterpolation(interpolation) => {
+                if self.interpolation_editable() {
+                    self.interpolation = interpolation;
+                }
+            }
+            FanEditInput::RemovePoint => {
+                let min_points = *self.point_count().start();
+                if let Some(idx) = self.selection.take() {
+                    if idx < self.profile.len() && self.profile.len() > min_points {
+                        self.profile.remove(idx);
+                        self.update_drawn_points();
+                    }
+                }
             }
             FanEditInput::Update => {
                 self.update_drawn_points();
@@ -284,24 +524,151 @@ impl Component for FanEdit {
                 self.active_drag_info = None;
             }
         }
+        // The load only changes every few seconds, not with every drag step
+        if self.active_drag_info.is_none() {
+            self.update_cooling_warning();
+        }
         self.draw();
     }
 
     fn update_cmd(
         &mut self,
         profile: Self::CommandOutput,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
         _root: &Self::Root,
     ) {
-        self.profile = profile.unwrap_or_default();
-        self.visible = true;
-
-        self.update_drawn_points();
+        self.show(profile.unwrap_or_default(), &sender);
         self.draw();
     }
 }
 
 impl FanEdit {
+    /// Open the dialog with `points`
+    fn show(&mut self, points: Vec<FanProfilePoint>, sender: &ComponentSender<Self>) {
+        self.profile = points;
+        self.selection = None;
+        self.visible = true;
+        self.start_live_updates(sender);
+
+        self.update_drawn_points();
+        self.update_cooling_warning();
+    }
+
+    fn update_cooling_warning(&mut self) {
+        self.cooling_warning = profile_controller()
+            .filter(|_| self.visible && !self.profile.is_empty())
+            .and_then(|controller| controller.curve_cooling_warning(&self.curve()));
+    }
+
+    fn title(&self) -> String {
+        match &self.target {
+            Some(EditTarget::Tailord(name)) => format!("Edit fan profile '{name}'"),
+            Some(EditTarget::Local(local)) => {
+                format!("Edit {} of '{}'", local.fan_id, local.profile_name)
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Number of points the curve may have where it's saved
+    fn point_count(&self) -> RangeInclusive<usize> {
+        match &self.target {
+            Some(EditTarget::Local(local)) => local.mode.point_count(),
+            _ => TAILORD_CURVE_POINTS,
+        }
+    }
+
+    /// Only software fan control of local profiles follows other
+    /// interpolations than linear
+    fn interpolation_editable(&self) -> bool {
+        matches!(&self.target, Some(EditTarget::Local(local)) if local.mode == FanControlMode::Software)
+    }
+
+    /// Whether the curve is too slow when hot, see [`FanCurve::is_unsafe`]
+    fn is_unsafe(&self) -> bool {
+        self.curve()
+            .is_unsafe(&AppConfig::load().fan_safety_policy())
+    }
+
+    /// Fan speed the curve yields at `temp`, as the preview draws it
+    fn target_speed(&self, temp: f32) -> u8 {
+        if self.interpolation != Interpolation::Linear {
+            self.curve().calculate_fan_speed(temp)
+        } else {
+            target_fan_speed(&self.profile, temp.round().clamp(0.0, 255.0) as u8)
+        }
+    }
+
+    /// The edited points with the settings of the loaded curve
+    fn curve(&self) -> FanCurve {
+        let points = self
+            .profile
+            .iter()
+            .map(|point| FanCurvePoint {
+                temp: point.temp,
+                speed: point.fan,
+            })
+            .collect();
+        match &self.target {
+            Some(EditTarget::Local(local)) => FanCurve {
+                points,
+                interpolation: self.interpolation,
+                ..local.curve.clone()
+            },
+            _ => FanCurve {
+                points,
+                firmware_below: None,
+                temp_source: TempSource::Auto,
+                interpolation: Interpolation::Linear,
+                allow_fan_stop: true,
+            },
+        }
+    }
+
+    /// Ask before saving a curve that could let the CPU overheat
+    fn confirm_unsafe(&self, sender: &ComponentSender<Self>, root: &adw::Window) {
+        let policy = AppConfig::load().fan_safety_policy();
+        let dialog = adw::MessageDialog::builder()
+            .modal(true)
+            .transient_for(root)
+            .heading("Save a curve with little cooling?")
+            .body(format!(
+                "The fans run slower than {}% at {}°C or above. The laptop may overheat under load.",
+                policy.min_speed, policy.danger_temp
+            ))
+            .default_response("cancel")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", "Keep editing"), ("save", "Save anyway")]);
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Destructive);
+
+        let sender = sender.clone();
+        relm4::spawn_local(async move {
+            if dialog.choose_future().await == "save" {
+                sender.input(FanEditInput::Save);
+            }
+        });
+    }
+
+    /// Periodically read the current temperature to show where on the curve we are.
+    fn start_live_updates(&mut self, sender: &ComponentSender<Self>) {
+        self.stop_live_updates();
+
+        read_live_temperature(sender);
+        let sender = sender.clone();
+        self.live_update_source = Some(timeout_add_local(Duration::from_secs(2), move || {
+            read_live_temperature(&sender);
+            ControlFlow::Continue
+        }));
+    }
+
+    fn stop_live_updates(&mut self) {
+        if let Some(source_id) = self.live_update_source.take() {
+            source_id.remove();
+        }
+        self.live_temp = None;
+    }
+
     fn dimensions(&self) -> (f64, f64) {
         let width = self.drawing_handler.width() as f64;
         let height = (self.drawing_handler.height() - 5) as f64;
@@ -342,7 +709,7 @@ impl FanEdit {
         let temp = x.clamp(20.0, 100.0) as u8;
         let fan = y.clamp(0.0, 100.0) as u8;
 
-        if fan < temp.saturating_sub(50) * 2 {
+        if fan < temp.saturating_sub(50) * 2 || self.profile.len() >= *self.point_count().end() {
             return None;
         }
 
@@ -436,8 +803,24 @@ impl FanEdit {
             height,
         );
 
-        for (x, y) in &self.drawn_points {
-            ctx.line_to(*x, *y);
+        if self.interpolation != Interpolation::Linear && self.drawn_points.len() > 1 {
+            // One sample every other pixel is smooth enough
+            let curve = self.curve();
+            let temp_range = self.temp_range();
+            let start = self.drawn_points[0];
+            let end = self.drawn_points[self.drawn_points.len() - 1];
+            ctx.line_to(start.0, start.1);
+            let mut x = start.0;
+            while x < end.0 {
+                let speed = curve.speed_at(Self::x_to_temp(x, temp_range, width) as f32);
+                ctx.line_to(x, Self::fan_to_y(speed as f64, height));
+                x += 2.0;
+            }
+            ctx.line_to(end.0, end.1);
+        } else {
+            for (x, y) in &self.drawn_points {
+                ctx.line_to(*x, *y);
+            }
         }
         ctx.stroke().unwrap();
 
@@ -461,6 +844,39 @@ impl FanEdit {
                 ctx.stroke().unwrap();
             }
         }
+
+        // Without live data or a simulated temperature, only the curve is drawn
+        let marker_temp = if self.simulate {
+            Some(self.simulated_temp)
+        } else {
+            self.live_temp
+        };
+        if let Some(temp) = marker_temp.filter(|_| !self.profile.is_empty()) {
+            let temp_range = self.temp_range();
+            let target = self.target_speed(temp);
+
+            let x = Self::temp_to_x(temp as f64, temp_range, width).clamp(0.0, width);
+            let y = Self::fan_to_y(target as f64, height);
+
+            ctx.new_path();
+            ctx.set_line_width(1.0);
+            ctx.set_dash(&[4.0, 4.0], 0.0);
+            set_source_rgba(&ctx, &self.colors.stroke, 0.5);
+            ctx.move_to(x, height);
+            ctx.line_to(x, 0.0);
+            ctx.stroke().unwrap();
+            ctx.set_dash(&[], 0.0);
+
+            set_source_rgb(&ctx, &self.colors.warn);
+            ctx.arc(x, y, 4.0, 0.0, PI * 2.0);
+            ctx.fill().unwrap();
+
+            let label = format!("{temp:.0}°C → {target}%");
+            ctx.set_font_size(12.0);
+            let text_x = if x > width / 2.0 { x - 90.0 } else { x + 8.0 };
+            ctx.move_to(text_x, (y - 10.0).max(12.0));
+            ctx.show_text(&label).unwrap();
+        }
     }
 
     fn move_point(&mut self, x: f64, y: f64) {
@@ -528,6 +944,11 @@ impl FanEdit {
             self.profile[idx].temp = temp;
             self.profile[idx].fan = fan;
 
+            // Only tailord's fans can follow the drag
+            if !matches!(self.target, Some(EditTarget::Tailord(_))) {
+                return;
+            }
+
             // Cancel the previous timeout if a new value has arrived.
             if let Some(source_id) = self.last_override_event.take() {
                 let main_context = MainContext::default();
@@ -557,6 +978,44 @@ impl FanEdit {
     }
 }
 
+/// Read the temperature on another thread, the sensors can block the
+/// drawing otherwise.
+fn read_live_temperature(sender: &ComponentSender<FanEdit>) {
+    let sender = sender.clone();
+    std::thread::spawn(move || sender.input(FanEditInput::LiveTemp(live_temperature())));
+}
+
+/// Hottest of the current CPU and GPU temperatures, if any can be read.
+fn live_temperature() -> Option<f32> {
+    let stats = profile_controller()?.get_hardware_stats().ok()?;
+    let cpu_temp = stats.cpu.package_temp.or(stats.cpu.max_core_temp);
+
+    stats
+        .gpus
+        .iter()
+        .filter_map(|gpu| gpu.temperature)
+        .chain(cpu_temp)
+        .reduce(f32::max)
+}
+
+/// Fan speed the curve yields at `temp`, interpolated the same way tailord does it.
+fn target_fan_speed(profile: &[FanProfilePoint], temp: u8) -> u8 {
+    match profile.iter().position(|p| p.temp >= temp) {
+        Some(0) => profile[0].fan,
+        Some(idx) => {
+            let (prev, next) = (&profile[idx - 1], &profile[idx]);
+            let temp_diff = next.temp.saturating_sub(prev.temp) as u16;
+            if temp_diff == 0 {
+                return next.fan;
+            }
+            let curr_temp_diff = (temp - prev.temp) as u16;
+            let fan_diff = next.fan as i16 - prev.fan as i16;
+            (prev.fan as i16 + fan_diff * curr_temp_diff as i16 / temp_diff as i16) as u8
+        }
+        None => 100,
+    }
+}
+
 fn set_source_rgb(ctx: &gtk::cairo::Context, color: &RGBA) {
     ctx.set_source_rgb(
         color.red() as f64,
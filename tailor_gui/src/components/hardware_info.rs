@@ -1,17 +1,79 @@
+use std::cell::RefCell;
 use std::fmt::Write;
+use std::rc::Rc;
+use std::time::Duration;
 
-use relm4::gtk::prelude::{ButtonExt, GridExt, GtkWindowExt, OrientableExt, WidgetExt};
+use relm4::gtk::prelude::{
+    BoxExt, ButtonExt, CheckButtonExt, GridExt, GtkWindowExt, OrientableExt, RangeExt, ScaleExt,
+    WidgetExt,
+};
+use gtk::glib::{timeout_add_local, timeout_add_local_once, ControlFlow, MainContext, SourceId};
 use relm4::{gtk, ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
 
-use crate::state::hardware_capabilities;
+use crate::app_config::AppConfig;
+use crate::hardware_control::{ChargePreset, EppSupport};
+use crate::hardware_monitor::{
+    BatteryInfo, CacheInfo, CpuTopology, FanInfo, GpuInfo, GpuMode, GpuPowerCap,
+    PowerSettings, SystemStats,
+};
+use crate::state::{hardware_capabilities, profile_controller, POWER_SETTINGS_CHANGED};
 use crate::templates;
 
-pub struct HardwareInfo;
+/// Time the GPU power cap slider has to rest before the cap is written
+const GPU_POWER_CAP_DELAY: Duration = Duration::from_millis(300);
+
+/// How often the fan speeds and the fan daemon's targets are read again
+const FAN_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct HardwareInfo {
+    /// Current charge thresholds, e.g. "75-80%"
+    charge_thresholds: String,
+    /// GPU mode waiting for a restart
+    pending_gpu: Option<GpuMode>,
+    /// Measured fan speeds and what the fan daemon set
+    fan_speeds: String,
+    fan_stalled: bool,
+    /// Charge of each battery and of all together
+    batteries: String,
+    /// Usage bars of the GPUs that report their VRAM
+    vram_bars: Vec<VramBar>,
+    /// Energy performance preference of the cpufreq driver
+    epp: Option<EppSupport>,
+}
+
+/// VRAM usage of the GPU at `gpu` in [`SystemStats::gpus`]
+struct VramBar {
+    gpu: usize,
+    bar: gtk::LevelBar,
+    label: gtk::Label,
+}
+
+#[derive(Debug)]
+pub enum HardwareInfoMsg {
+    ApplyChargePreset(ChargePreset),
+    /// Another tool changed the charge thresholds
+    PowerSettingsChanged,
+    /// Pin a fan to a speed, `None` returns it to its curve
+    OverrideFan { fan_id: String, speed: Option<u8> },
+    SetGpuPowerCap { card: String, watts: u32 },
+    /// The GPU switching tool answered
+    PendingGpuSwitch(Option<GpuMode>),
+    /// Energy performance preference until the next profile with one
+    SetEpp(String),
+    /// Turn amd-pstate preferred core ranking on or off
+    SetPrefcore(bool),
+    /// Read the fan speeds, the fan daemon's targets, the batteries and
+    /// the VRAM usage again
+    RefreshFans,
+    /// The stats requested by [`HardwareInfoMsg::RefreshFans`] were read
+    #[doc(hidden)]
+    StatsRead(Option<Box<SystemStats>>),
+}
 
 #[relm4::component(pub)]
 impl SimpleComponent for HardwareInfo {
     type Init = ();
-    type Input = ();
+    type Input = HardwareInfoMsg;
     type Output = ();
 
     view! {
@@ -66,6 +128,198 @@ impl SimpleComponent for HardwareInfo {
                         set_halign: gtk::Align::Start,
                         set_label: &performance_info,
                     },
+                    attach[0, 3, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Firmware",
+                    },
+                    attach[1, 3, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_selectable: true,
+                        set_label: &firmware_info,
+                    },
+                    attach[0, 4, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "Fan speed",
+                    },
+                    attach[1, 4, 1, 1] = &gtk::Box {
+                        set_spacing: 6,
+
+                        gtk::Image {
+                            set_valign: gtk::Align::Start,
+                            set_icon_name: Some("dialog-warning-symbolic"),
+                            add_css_class: "warning",
+                            set_tooltip_text: Some("A fan is requested to spin but reports 0 RPM"),
+                            #[watch]
+                            set_visible: model.fan_stalled,
+                        },
+                        gtk::Label {
+                            set_halign: gtk::Align::Start,
+                            #[watch]
+                            set_label: &model.fan_speeds,
+                        },
+                    },
+                    attach[0, 5, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "CPU power",
+                    },
+                    attach[1, 5, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: &cpu_power,
+                    },
+                    attach[0, 6, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "CPU temperature",
+                    },
+                    attach[1, 6, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: &cpu_temp,
+                    },
+                    attach[0, 7, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "Charge limit",
+                    },
+                    attach[1, 7, 1, 1] = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 6,
+
+                        gtk::Label {
+                            set_halign: gtk::Align::Start,
+                            #[watch]
+                            set_label: &model.charge_thresholds,
+                        },
+                        gtk::Box {
+                            add_css_class: "linked",
+                            set_visible: charge_limit_supported,
+
+                            gtk::Button {
+                                set_label: ChargePreset::Full.label(),
+                                connect_clicked => HardwareInfoMsg::ApplyChargePreset(ChargePreset::Full),
+                            },
+                            gtk::Button {
+                                set_label: ChargePreset::Balanced.label(),
+                                connect_clicked => HardwareInfoMsg::ApplyChargePreset(ChargePreset::Balanced),
+                            },
+                            gtk::Button {
+                                set_label: ChargePreset::Travel.label(),
+                                connect_clicked => HardwareInfoMsg::ApplyChargePreset(ChargePreset::Travel),
+                            },
+                        },
+                    },
+                    attach[0, 8, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "Battery",
+                        set_visible: has_battery,
+                    },
+                    attach[1, 8, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_visible: has_battery,
+                        #[watch]
+                        set_label: &model.batteries,
+                    },
+                    attach[0, 9, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "Fan override",
+                        set_visible: fan_override_supported,
+                    },
+                    #[name = "fan_override_box"]
+                    attach[1, 9, 1, 1] = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 6,
+                        set_visible: fan_override_supported,
+                    },
+                    attach[0, 10, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_label: "Graphics",
+                        #[watch]
+                        set_visible: model.pending_gpu.is_some(),
+                    },
+                    attach[1, 10, 1, 1] = &gtk::Box {
+                        set_spacing: 6,
+                        #[watch]
+                        set_visible: model.pending_gpu.is_some(),
+
+                        gtk::Image {
+                            set_icon_name: Some("system-reboot-symbolic"),
+                        },
+                        gtk::Label {
+                            set_halign: gtk::Align::Start,
+                            #[watch]
+                            set_label: &model.pending_gpu.map(pending_gpu_text).unwrap_or_default(),
+                        },
+                    },
+                    attach[0, 11, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "GPU power limit",
+                        set_visible: !gpu_power_caps.is_empty(),
+                    },
+                    #[name = "gpu_power_box"]
+                    attach[1, 11, 1, 1] = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 6,
+                        set_visible: !gpu_power_caps.is_empty(),
+                    },
+                    attach[0, 12, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_label: "GPU memory",
+                        set_visible: !vram_rows.is_empty(),
+                    },
+                    #[name = "vram_box"]
+                    attach[1, 12, 1, 1] = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 6,
+                        set_visible: !vram_rows.is_empty(),
+                    },
+                    attach[0, 13, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_visible: !epp_choices.is_empty(),
+                        set_label: &epp_title,
+                    },
+                    attach[1, 13, 1, 1] = &gtk::DropDown {
+                        set_halign: gtk::Align::Start,
+                        set_visible: !epp_choices.is_empty(),
+                        set_model: Some(&gtk::StringList::new(&epp_choices.iter().map(String::as_str).collect::<Vec<_>>())),
+                        set_tooltip: "How eagerly the CPU clocks up, until the next profile with a preference",
+                        #[watch]
+                        set_selected: model.epp_selected(&epp_choices),
+                        connect_selected_notify[sender, epp_choices = epp_choices.clone()] => move |dropdown| {
+                            if let Some(epp) = epp_choices.get(dropdown.selected() as usize) {
+                                sender.input(HardwareInfoMsg::SetEpp(epp.clone()));
+                            }
+                        },
+                    },
+                    attach[0, 14, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_visible: prefcore_supported,
+                        set_label: "Preferred cores",
+                    },
+                    attach[1, 14, 1, 1] = &gtk::Switch {
+                        set_halign: gtk::Align::Start,
+                        set_visible: prefcore_supported,
+                        set_tooltip: "Schedule on the fastest cores first, most kernels only allow changing this at boot",
+                        #[watch]
+                        set_active: model.epp.as_ref().and_then(|epp| epp.prefcore).unwrap_or_default(),
+                        connect_active_notify[sender] => move |switch| {
+                            sender.input(HardwareInfoMsg::SetPrefcore(switch.is_active()));
+                        },
+                    },
+                    attach[0, 15, 2, 1] = &gtk::Expander {
+                        set_label: Some("CPU topology"),
+                        set_visible: !cpu_topology.is_empty(),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Label {
+                            set_halign: gtk::Align::Start,
+                            set_margin_top: 6,
+                            set_selectable: true,
+                            set_label: &cpu_topology,
+                        },
+                    },
                 },
 
                 gtk::Separator,
@@ -92,17 +346,445 @@ impl SimpleComponent for HardwareInfo {
     fn init(
         _init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = HardwareInfo;
+        let power_settings = profile_controller()
+            .map(|controller| controller.power_settings())
+            .unwrap_or_default();
+        let charge_limit_supported = power_settings.charge_end_threshold.is_some();
+        let mut model = HardwareInfo {
+            charge_thresholds: charge_thresholds_text(&power_settings),
+            pending_gpu: None,
+            fan_speeds: String::new(),
+            fan_stalled: false,
+            batteries: String::new(),
+            vram_bars: Vec::new(),
+            epp: profile_controller().and_then(|controller| controller.epp_support()),
+        };
+        let epp_choices = model
+            .epp
+            .as_ref()
+            .map(|epp| epp.available.clone())
+            .unwrap_or_default();
+        let epp_title = model
+            .epp
+            .as_ref()
+            .map(|epp| format!("Energy preference ({})", epp.driver.label()))
+            .unwrap_or_default();
+        let prefcore_supported = model.epp.as_ref().is_some_and(|epp| epp.prefcore.is_some());
 
         let info = hardware_capabilities().unwrap().clone();
 
         let led_info: String = comma_list(info.led_devices.iter().map(|d| d.device_id()));
         let performance_info = comma_list_optional(info.performance_profiles);
+        let firmware_info = profile_controller()
+            .map(|controller| controller.get_system_info())
+            .unwrap_or_default()
+            .firmware_summary();
+
+        let stats = profile_controller().and_then(|controller| controller.get_hardware_stats().ok());
+        let cpu_power = stats
+            .as_ref()
+            .and_then(|stats| stats.cpu.power_summary(AppConfig::load().power_unit))
+            .unwrap_or_else(|| "Not available".to_owned());
+        let cpu_temp = stats
+            .as_ref()
+            .and_then(|stats| cpu_temp_text(stats, &AppConfig::load()))
+            .unwrap_or_else(|| "Not available".to_owned());
+        let has_battery = stats.as_ref().is_some_and(|stats| !stats.batteries.is_empty());
+        let mut vram_rows = Vec::new();
+        if let Some(stats) = &stats {
+            model.batteries = batteries_text(stats);
+            let gpus_with_vram = stats.gpus.iter().filter(|gpu| gpu.vram_usage().is_some()).count();
+            for (index, gpu) in stats.gpus.iter().enumerate() {
+                if gpu.vram_usage().is_some() {
+                    let (row, bar) = vram_row(gpu, index, gpus_with_vram > 1);
+                    vram_rows.push(row);
+                    model.vram_bars.push(bar);
+                }
+            }
+        }
+        let fans = stats.map(|stats| stats.fans).unwrap_or_default();
+        let cpu_topology = profile_controller()
+            .map(|controller| topology_text(&controller.cpu_topology()))
+            .unwrap_or_default();
+        model.set_fans(&fans);
+        let fan_override_supported = !fans.is_empty()
+            && profile_controller().is_some_and(|controller| controller.fan_control_supported());
+        let fan_overrides = profile_controller()
+            .map(|controller| controller.fan_overrides())
+            .unwrap_or_default();
+        let gpu_power_caps = profile_controller()
+            .map(|controller| controller.gpu_power_caps())
+            .unwrap_or_default();
+        // Runs prime-select, which can take a while
+        if let Some(controller) = profile_controller() {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                sender.input(HardwareInfoMsg::PendingGpuSwitch(controller.pending_gpu_switch()));
+            });
+        }
+
+        POWER_SETTINGS_CHANGED.subscribe(sender.input_sender(), |_| {
+            HardwareInfoMsg::PowerSettingsChanged
+        });
+
+        // The daemon keeps changing the speeds while the dialog is open
+        let (window, refresh_sender) = (root.downgrade(), sender.clone());
+        timeout_add_local(FAN_REFRESH_INTERVAL, move || {
+            if window.upgrade().is_none() {
+                return ControlFlow::Break;
+            }
+            refresh_sender.input(HardwareInfoMsg::RefreshFans);
+            ControlFlow::Continue
+        });
+
         let widgets = view_output!();
+        for fan in &fans {
+            widgets.fan_override_box.append(&fan_override_row(
+                fan,
+                fan_overrides.get(&fan.fan_id).copied(),
+                &sender,
+            ));
+        }
+        for row in &vram_rows {
+            widgets.vram_box.append(row);
+        }
+        for cap in &gpu_power_caps {
+            widgets
+                .gpu_power_box
+                .append(&gpu_power_cap_row(cap, gpu_power_caps.len() > 1, &sender));
+        }
         ComponentParts { model, widgets }
     }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            HardwareInfoMsg::ApplyChargePreset(preset) => {
+                let Some(controller) = profile_controller() else {
+                    return;
+                };
+                // Show what the driver accepted, not what was requested
+                match controller.apply_charge_preset(preset) {
+                    Ok(settings) => self.charge_thresholds = charge_thresholds_text(&settings),
+                    Err(err) => {
+                        tracing::error!("Failed to set the charge limit: {err:#}");
+                        self.charge_thresholds = format!("{err:#}");
+                    }
+                }
+            }
+            HardwareInfoMsg::PowerSettingsChanged => {
+                if let Some(controller) = profile_controller() {
+                    self.charge_thresholds = charge_thresholds_text(&controller.power_settings());
+                }
+            }
+            HardwareInfoMsg::SetGpuPowerCap { card, watts } => {
+                if let Some(controller) = profile_controller() {
+                    if let Err(err) = controller.set_gpu_power_cap(&card, u64::from(watts) * 1_000_000) {
+                        tracing::error!("Failed to limit the power of {card}: {err:#}");
+                    }
+                }
+            }
+            HardwareInfoMsg::PendingGpuSwitch(pending) => self.pending_gpu = pending,
+            HardwareInfoMsg::SetEpp(epp) => {
+                // Also sent when the dropdown shows what was read back
+                let Some(current) = &self.epp else {
+                    return;
+                };
+                if current.current.as_deref() == Some(epp.as_str()) {
+                    return;
+                }
+                if let Some(controller) = profile_controller() {
+                    if let Err(err) = controller.set_energy_performance_preference(&epp) {
+                        tracing::error!("Failed to set the energy preference: {err:#}");
+                    }
+                    self.epp = controller.epp_support();
+                }
+            }
+            HardwareInfoMsg::SetPrefcore(enabled) => {
+                let Some(current) = &self.epp else {
+                    return;
+                };
+                if current.prefcore == Some(enabled) {
+                    return;
+                }
+                if let Some(controller) = profile_controller() {
+                    if let Err(err) = controller.set_amd_prefcore(enabled) {
+                        tracing::error!("Failed to change the preferred cores: {err:#}");
+                    }
+                    self.epp = controller.epp_support();
+                }
+            }
+            HardwareInfoMsg::RefreshFans => {
+                // Reading the sensors can block, keep it off the main thread
+                if let Some(controller) = profile_controller() {
+                    let sender = sender.clone();
+                    std::thread::spawn(move || {
+                        let stats = controller.get_hardware_stats().ok().map(Box::new);
+                        sender.input(HardwareInfoMsg::StatsRead(stats));
+                    });
+                }
+            }
+            HardwareInfoMsg::StatsRead(stats) => {
+                if let Some(stats) = &stats {
+                    self.batteries = batteries_text(stats);
+                    for vram in &self.vram_bars {
+                        if let Some(gpu) = stats.gpus.get(vram.gpu) {
+                            vram.show(gpu);
+                        }
+                    }
+                }
+                self.set_fans(&stats.map(|stats| stats.fans).unwrap_or_default());
+            }
+            HardwareInfoMsg::OverrideFan { fan_id, speed } => {
+                if let Some(controller) = profile_controller() {
+                    if let Err(err) = controller.set_fan_override(&fan_id, speed) {
+                        tracing::error!("Failed to override {fan_id}: {err:#}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl HardwareInfo {
+    /// Position of the current energy preference in `choices`
+    fn epp_selected(&self, choices: &[String]) -> u32 {
+        self.epp
+            .as_ref()
+            .and_then(|epp| epp.current.as_ref())
+            .and_then(|current| choices.iter().position(|choice| choice == current))
+            .map_or(gtk::INVALID_LIST_POSITION, |index| index as u32)
+    }
+
+    /// Show `fans` with the speeds the fan daemon set for them
+    fn set_fans(&mut self, fans: &[FanInfo]) {
+        let fan_targets = profile_controller()
+            .map(|controller| controller.fan_targets())
+            .unwrap_or_default();
+        self.fan_stalled = fans.iter().any(FanInfo::is_stalled);
+        self.fan_speeds = if fans.is_empty() {
+            "Not available".to_owned()
+        } else {
+            fans.iter()
+                .map(|fan| match fan_targets.get(&fan.fan_id) {
+                    Some(target) => format!(
+                        "{}: {}, target {target}%",
+                        fan.display_name(),
+                        fan.speed_summary()
+                    ),
+                    None => format!("{}: {}", fan.display_name(), fan.speed_summary()),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+    }
+}
+
+impl VramBar {
+    fn show(&self, gpu: &GpuInfo) {
+        self.bar.set_value(f64::from(gpu.vram_usage().unwrap_or_default()));
+        self.label.set_label(&vram_text(gpu));
+    }
+}
+
+/// Bar for the VRAM usage of `gpu`, named if there are several
+fn vram_row(gpu: &GpuInfo, index: usize, show_name: bool) -> (gtk::Box, VramBar) {
+    let row = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    if show_name {
+        let name = gtk::Label::new(Some(&gpu.name));
+        name.set_halign(gtk::Align::Start);
+        name.add_css_class("caption");
+        row.append(&name);
+    }
+
+    let bar = gtk::LevelBar::new();
+    bar.set_width_request(160);
+    // The default offsets color a nearly empty bar as a warning
+    for offset in [
+        gtk::LEVEL_BAR_OFFSET_LOW,
+        gtk::LEVEL_BAR_OFFSET_HIGH,
+        gtk::LEVEL_BAR_OFFSET_FULL,
+    ] {
+        bar.remove_offset_value(Some(offset));
+    }
+    row.append(&bar);
+
+    let label = gtk::Label::new(None);
+    label.set_halign(gtk::Align::Start);
+    row.append(&label);
+
+    let vram = VramBar { gpu: index, bar, label };
+    vram.show(gpu);
+    (row, vram)
+}
+
+/// "2048 / 8192 MB"
+fn vram_text(gpu: &GpuInfo) -> String {
+    match (gpu.vram_used_mb, gpu.vram_total_mb) {
+        (Some(used), Some(total)) => format!("{used} / {total} MB"),
+        _ => "Not available".to_owned(),
+    }
+}
+
+/// Slider for the power limit of a GPU in watts, named by its card if
+/// there are several. Applies until the next profile with a GPU power cap,
+/// once the slider stopped moving, so dragging doesn't flood the driver.
+fn gpu_power_cap_row(
+    cap: &GpuPowerCap,
+    show_card: bool,
+    sender: &ComponentSender<HardwareInfo>,
+) -> gtk::Box {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    let watts = |uw: u64| (uw / 1_000_000) as f64;
+
+    let scale = gtk::Scale::with_range(
+        gtk::Orientation::Horizontal,
+        watts(cap.min_uw),
+        watts(cap.max_uw).max(watts(cap.min_uw) + 1.0),
+        1.0,
+    );
+    scale.set_hexpand(true);
+    scale.set_width_request(160);
+    scale.set_value_pos(gtk::PositionType::Right);
+    scale.set_value(watts(cap.cap_uw));
+    scale.set_tooltip_text(Some("Watts"));
+
+    let (sender, card) = (sender.clone(), cap.card.clone());
+    let pending: Rc<RefCell<Option<SourceId>>> = Rc::default();
+    scale.connect_value_changed(move |scale| {
+        if let Some(source_id) = pending.borrow_mut().take() {
+            if MainContext::default().find_source_by_id(&source_id).is_some() {
+                source_id.remove();
+            }
+        }
+
+        let (sender, card, watts) = (sender.clone(), card.clone(), scale.value() as u32);
+        *pending.borrow_mut() = Some(timeout_add_local_once(GPU_POWER_CAP_DELAY, move || {
+            sender.input(HardwareInfoMsg::SetGpuPowerCap { card, watts });
+        }));
+    });
+
+    if show_card {
+        row.append(&gtk::Label::new(Some(&cap.card)));
+    }
+    row.append(&scale);
+    row
+}
+
+/// Check button pinning the fan and a slider for the pinned speed. The fan
+/// daemon holds the speed while it keeps running the other fans' curves.
+fn fan_override_row(
+    fan: &FanInfo,
+    pinned: Option<u8>,
+    sender: &ComponentSender<HardwareInfo>,
+) -> gtk::Box {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+
+    let check = gtk::CheckButton::with_label(&fan.display_name());
+    check.set_active(pinned.is_some());
+    check.set_tooltip_text(Some("Hold this fan at a fixed speed"));
+
+    let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 5.0);
+    scale.set_hexpand(true);
+    scale.set_width_request(160);
+    scale.set_value_pos(gtk::PositionType::Right);
+    scale.set_value(pinned.unwrap_or(100).into());
+    scale.set_sensitive(pinned.is_some());
+
+    {
+        let (sender, scale, fan_id) = (sender.clone(), scale.clone(), fan.fan_id.clone());
+        check.connect_toggled(move |check| {
+            let pinned = check.is_active();
+            scale.set_sensitive(pinned);
+            sender.input(HardwareInfoMsg::OverrideFan {
+                fan_id: fan_id.clone(),
+                speed: pinned.then(|| scale.value() as u8),
+            });
+        });
+    }
+    {
+        let (sender, check, fan_id) = (sender.clone(), check.clone(), fan.fan_id.clone());
+        scale.connect_value_changed(move |scale| {
+            if check.is_active() {
+                sender.input(HardwareInfoMsg::OverrideFan {
+                    fan_id: fan_id.clone(),
+                    speed: Some(scale.value() as u8),
+                });
+            }
+        });
+    }
+
+    row.append(&check);
+    row.append(&scale);
+    row
+}
+
+/// "Discrete GPU will be active after restart"
+fn pending_gpu_text(mode: GpuMode) -> String {
+    format!("{} will be active after restart", mode.label())
+}
+
+/// One line per battery, and their combined charge if there are several
+fn batteries_text(stats: &SystemStats) -> String {
+    let mut lines: Vec<String> = stats.batteries.iter().map(BatteryInfo::summary).collect();
+    if stats.batteries.len() > 1 {
+        if let Some(total) = stats.total_battery_percent() {
+            lines.push(format!("Total: {total:.0}%"));
+        }
+    }
+    lines.join("\n")
+}
+
+fn charge_thresholds_text(settings: &PowerSettings) -> String {
+    match (settings.charge_start_threshold, settings.charge_end_threshold) {
+        (Some(start), Some(end)) => format!("Charging from {start}% to {end}%"),
+        (None, Some(end)) => format!("Charging up to {end}%"),
+        _ => "Not available".to_owned(),
+    }
+}
+
+/// Calibrated CPU temperature, with the sensor reading if they differ and
+/// the dies if there are several, e.g. "66°C (Tdie 62°C, Tccd1 58°C, Tccd2 66°C)"
+fn cpu_temp_text(stats: &SystemStats, config: &AppConfig) -> Option<String> {
+    let raw = stats.cpu.package_temp.or(stats.cpu.max_core_temp)?;
+    let unit = config.temperature_unit;
+    let adjusted = unit.format(config.adjust_cpu_temp(raw));
+
+    let mut details = Vec::new();
+    if config.cpu_temp_offset != 0.0 {
+        details.push(format!("sensor {}", unit.format(raw)));
+    }
+    if stats.cpu.die_temps.len() > 1 {
+        details.extend(
+            stats
+                .cpu
+                .die_temps
+                .iter()
+                .map(|(label, temp)| format!("{label} {}", unit.format(*temp))),
+        );
+    }
+    if details.is_empty() {
+        Some(adjusted)
+    } else {
+        Some(format!("{adjusted} ({})", details.join(", ")))
+    }
+}
+
+/// Cores, SMT siblings and caches, one per line
+fn topology_text(topology: &CpuTopology) -> String {
+    if topology.threads == 0 {
+        return String::new();
+    }
+    let mut lines = vec![topology.core_summary()];
+    if topology.packages > 1 {
+        lines.push(format!("{} packages", topology.packages));
+    }
+    if let Some(siblings) = topology.siblings_summary() {
+        lines.push(format!("SMT siblings: {siblings}"));
+    }
+    lines.extend(topology.caches.iter().map(CacheInfo::summary));
+    lines.join("\n")
 }
 
 fn comma_list<I, S>(iter: I) -> String
@@ -1,3 +1,4 @@
+use adw::prelude::{ActionRowExt, ComboRowExt, PreferencesGroupExt, PreferencesRowExt};
 use futures::StreamExt;
 use gtk::prelude::{BoxExt, ButtonExt, ListBoxRowExt, OrientableExt, WidgetExt};
 use relm4::factory::FactoryVecDeque;
@@ -11,7 +12,10 @@ use relm4_icons::icon_names;
 use super::factories::list_item::{ListItem, ListMsg};
 use super::led_edit::{LedEdit, LedEditInput};
 use super::new_entry::{NewEntryDialog, NewEntryInit, NewEntryOutput};
-use crate::state::{TailorStateInner, TailorStateMsg, STATE};
+use crate::hardware_control::ApplyMask;
+use crate::state::{
+    profile_controller, TailorStateInner, TailorStateMsg, LOCAL_PROFILES_CHANGED, STATE,
+};
 use crate::templates;
 
 #[tracker::track]
@@ -20,6 +24,14 @@ pub struct LedList {
     profiles: FactoryVecDeque<ListItem<LedListInput>>,
     #[do_not_track]
     led_edit: Controller<LedEdit>,
+    /// Local profiles whose keyboard settings can be applied on their own.
+    /// Empty with tailor-fand, it only applies whole profiles.
+    local_profiles: Vec<String>,
+    /// Names of `local_profiles` for the combo row
+    #[do_not_track]
+    keyboard_choices: gtk::StringList,
+    #[do_not_track]
+    keyboard_profile: usize,
     toast: Option<adw::Toast>,
 }
 
@@ -30,6 +42,12 @@ pub enum LedListInput {
     Edit(usize),
     Remove(DynamicIndex),
     Add,
+    SelectKeyboardProfile(usize),
+    /// The local profiles changed, e.g. after a reset
+    ReloadLocalProfiles,
+    /// Apply only the keyboard settings of the selected local profile,
+    /// without switching to it
+    ApplyKeyboard,
 }
 
 impl ListMsg for LedListInput {
@@ -92,7 +110,28 @@ impl Component for LedList {
                                 let index = row.index();
                                 sender.input(LedListInput::Edit(index as usize));
                             }
-                        }
+                        },
+
+                        adw::PreferencesGroup {
+                            set_margin_top: 12,
+                            #[track(model.changed(LedList::local_profiles()))]
+                            set_visible: !model.local_profiles.is_empty(),
+
+                            add = &adw::ComboRow {
+                                set_title: "Keyboard of a profile",
+                                set_subtitle: "Apply only its keyboard backlight, without switching profiles",
+                                set_model: Some(&model.keyboard_choices),
+                                connect_selected_notify[sender] => move |row| {
+                                    sender.input(LedListInput::SelectKeyboardProfile(row.selected() as usize));
+                                },
+
+                                add_suffix = &gtk::Button {
+                                    set_label: "Apply",
+                                    set_valign: gtk::Align::Center,
+                                    connect_clicked => LedListInput::ApplyKeyboard,
+                                },
+                            },
+                        },
                     }
                 }
             }
@@ -120,9 +159,19 @@ impl Component for LedList {
 
         let led_edit = LedEdit::builder().transient_for(&*root).launch(()).detach();
 
+        LOCAL_PROFILES_CHANGED
+            .subscribe(sender.input_sender(), |_| LedListInput::ReloadLocalProfiles);
+
+        let local_profiles = local_profile_names();
+        let keyboard_choices: Vec<&str> = local_profiles.iter().map(String::as_str).collect();
+        let keyboard_choices = gtk::StringList::new(&keyboard_choices);
+
         let model = Self {
             profiles,
             led_edit,
+            local_profiles,
+            keyboard_choices,
+            keyboard_profile: 0,
             toast: None,
             tracker: 0,
         };
@@ -176,6 +225,31 @@ impl Component for LedList {
                     self.set_toast(Some(adw::Toast::new("There must be at least one profile")));
                 }
             }
+            LedListInput::SelectKeyboardProfile(index) => {
+                self.keyboard_profile = index;
+            }
+            LedListInput::ReloadLocalProfiles => {
+                let names = local_profile_names();
+                let choices: Vec<&str> = names.iter().map(String::as_str).collect();
+                // Selects the first choice again
+                self.keyboard_choices
+                    .splice(0, self.keyboard_choices.n_items(), &choices);
+                self.keyboard_profile = 0;
+                self.set_local_profiles(names);
+            }
+            LedListInput::ApplyKeyboard => {
+                let Some(name) = self.local_profiles.get(self.keyboard_profile) else {
+                    return;
+                };
+                let result = profile_controller()
+                    .map(|controller| controller.apply_partial(name, ApplyMask::KEYBOARD))
+                    .unwrap_or(Ok(()));
+                if let Err(err) = result {
+                    self.set_toast(Some(adw::Toast::new(&format!(
+                        "Failed to apply the keyboard of '{name}': {err:#}"
+                    ))));
+                }
+            }
             LedListInput::Add => {
                 let profiles = self.profiles.iter().map(|i| i.name.to_string()).collect();
                 let mut new_entry = NewEntryDialog::builder()
@@ -198,3 +272,18 @@ impl Component for LedList {
         }
     }
 }
+
+/// Local profiles whose keyboard can be applied on its own, none with
+/// tailor-fand
+fn local_profile_names() -> Vec<String> {
+    profile_controller()
+        .filter(|controller| !controller.uses_fand())
+        .map(|controller| {
+            controller
+                .get_all_profiles()
+                .into_iter()
+                .map(|profile| profile.name)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -6,4 +6,7 @@ pub mod hardware_info;
 pub mod led_edit;
 pub mod led_list;
 pub mod new_entry;
+pub mod overlay;
+pub mod preferences;
 pub mod profiles;
+pub mod tuning;
@@ -7,6 +7,7 @@ use relm4::{
 };
 use relm4_components::simple_combo_box::SimpleComboBox;
 
+use crate::profile_system::validate_profile_name;
 use crate::templates::{MsgDialogBox, MsgDialogButtons};
 
 pub struct NewEntryDialog {
@@ -68,13 +69,24 @@ impl SimpleComponent for NewEntryDialog {
                         connect_changed => NewEntryInput::Noop,
                     },
 
-                    attach[0, 1, 1, 1] = &gtk::Label {
+                    attach[1, 1, 1, 1] = &gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_wrap: true,
+                        add_css_class: "error",
+                        add_css_class: "caption",
+                        #[watch]
+                        set_visible: model.name_error().is_some(),
+                        #[watch]
+                        set_label: &model.name_error().unwrap_or_default(),
+                    },
+
+                    attach[0, 2, 1, 1] = &gtk::Label {
                         set_halign: gtk::Align::Start,
                         set_label: "Based on",
                     },
 
                     #[local_ref]
-                    attach[1, 1, 1, 1] = items -> gtk::ComboBoxText {},
+                    attach[1, 2, 1, 1] = items -> gtk::ComboBoxText {},
                 },
 
                 gtk::Separator {},
@@ -143,7 +155,20 @@ impl SimpleComponent for NewEntryDialog {
 
 impl NewEntryDialog {
     fn valid_name(&self) -> bool {
+        !self.buffer.text().trim().is_empty() && self.name_error().is_none()
+    }
+
+    /// Reason why the entered name can't be used, shown below the entry
+    fn name_error(&self) -> Option<String> {
         let name = self.buffer.text().trim().to_string();
-        !name.is_empty() && !self.items.model().variants.contains(&name)
+        if name.is_empty() {
+            None
+        } else if let Err(err) = validate_profile_name(&name) {
+            Some(err.to_string())
+        } else if self.items.model().variants.contains(&name) {
+            Some("An entry with this name already exists".to_string())
+        } else {
+            None
+        }
     }
 }
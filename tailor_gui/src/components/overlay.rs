@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::{BoxExt, GtkWindowExt, OrientableExt, WidgetExt};
+use relm4::{gtk, ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
+
+use crate::app_config::{AppConfig, TemperatureUnit};
+use crate::hardware_monitor::{GpuType, SystemStats};
+use crate::stats_broadcaster::StatsHistory;
+use crate::{util, window_placement};
+
+/// Title the window is found by for placing it
+const OVERLAY_TITLE: &str = "Tailor overlay";
+/// Time the window manager gets to take over a newly shown window
+const PLACE_DELAY: Duration = Duration::from_millis(200);
+
+/// Readings shown in the CPU load sparkline
+const SPARKLINE_SAMPLES: usize = 60;
+const SPARKLINE_SIZE: (i32, i32) = (120, 24);
+
+/// Width of a bar in the core frequency histogram
+const HISTOGRAM_BUCKET_MHZ: u32 = 1000;
+const HISTOGRAM_SIZE: (i32, i32) = (120, 24);
+
+/// Small undecorated window with the most important stats, e.g. for games.
+///
+/// The window doesn't belong to the application, so it never keeps the app
+/// running after the main window quit. GTK 4 leaves placement and stacking
+/// to the compositor, so on X11 [`window_placement`] keeps the window above
+/// the others and restores where it was dragged to. On Wayland "always on
+/// top" has to be set in the window menu of the desktop (Alt+Space on most).
+pub struct OverlayWindow {
+    text: String,
+    visible: bool,
+    /// Mirrors `visible` for the stats subscription, which only reads
+    /// while the overlay is shown
+    shown: Arc<AtomicBool>,
+    unit: TemperatureUnit,
+    cpu_temp_offset: f32,
+    load_history: StatsHistory,
+    /// CPU load sparkline, `None` until there are two readings. A texture,
+    /// so updates that don't redraw it don't upload it again.
+    sparkline: Option<gdk::Texture>,
+    show_histogram: bool,
+    /// Time at each core frequency over the same readings
+    histogram: Option<gdk::Texture>,
+}
+
+#[derive(Debug)]
+pub enum OverlayInput {
+    Toggle,
+    Hidden,
+    /// New reading of the app's stats broadcaster
+    Stats(Box<SystemStats>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for OverlayWindow {
+    type Init = Arc<AtomicBool>;
+    type Input = OverlayInput;
+    type Output = ();
+
+    view! {
+        gtk::Window {
+            set_title: Some(OVERLAY_TITLE),
+            set_decorated: false,
+            set_resizable: false,
+            set_hide_on_close: true,
+            add_css_class: "osd",
+            #[watch]
+            set_visible: model.visible,
+            // Not emitted when the app quits, so the overlay comes back
+            // on the next start
+            connect_close_request[sender] => move |_| {
+                sender.input(OverlayInput::Hidden);
+                glib::Propagation::Proceed
+            },
+            connect_map => move |_| {
+                if window_placement::supported() {
+                    let position = AppConfig::load().overlay_position;
+                    thread::spawn(move || {
+                        thread::sleep(PLACE_DELAY);
+                        if let Err(err) = window_placement::place(OVERLAY_TITLE, position) {
+                            tracing::warn!("Failed to place the overlay: {err:#}");
+                        }
+                    });
+                }
+            },
+
+            // Dragging anywhere moves the window
+            gtk::WindowHandle {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_all: 8,
+                    set_spacing: 6,
+
+                    gtk::Label {
+                        add_css_class: "monospace",
+                        #[watch]
+                        set_label: &model.text,
+                    },
+                    gtk::Picture {
+                        set_can_shrink: false,
+                        set_tooltip_text: Some("Total CPU load"),
+                        #[watch]
+                        set_visible: model.sparkline.is_some(),
+                        #[watch]
+                        set_paintable: model.sparkline.as_ref(),
+                    },
+                    gtk::Picture {
+                        set_can_shrink: false,
+                        set_tooltip_text: Some("Time the cores spent at each frequency, 1 GHz per bar"),
+                        #[watch]
+                        set_visible: model.histogram.is_some(),
+                        #[watch]
+                        set_paintable: model.histogram.as_ref(),
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        shown: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let config = AppConfig::load();
+        shown.store(config.overlay_visible, Ordering::Relaxed);
+        let model = OverlayWindow {
+            text: "Waiting for stats...".to_owned(),
+            visible: config.overlay_visible,
+            shown,
+            unit: config.temperature_unit,
+            cpu_temp_offset: config.cpu_temp_offset,
+            load_history: StatsHistory::new(SPARKLINE_SAMPLES),
+            sparkline: None,
+            show_histogram: config.overlay_frequency_histogram,
+            histogram: None,
+        };
+
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if self.visible {
+            self.save_position();
+        }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            OverlayInput::Toggle => self.set_visible(!self.visible),
+            OverlayInput::Hidden => {
+                if self.visible {
+                    self.set_visible(false);
+                }
+            }
+            OverlayInput::Stats(stats) => {
+                self.text = overlay_text(&stats, self.unit, self.cpu_temp_offset);
+                if self.load_history.push(&stats) {
+                    let loads = self.load_history.loads();
+                    if loads.len() > 1 {
+                        let (width, height) = SPARKLINE_SIZE;
+                        let pixbuf = util::render_sparkline(&loads, width, height);
+                        self.sparkline = Some(gdk::Texture::for_pixbuf(&pixbuf));
+                    }
+                }
+                if self.show_histogram {
+                    let shares: Vec<f32> = self
+                        .load_history
+                        .frequency_histogram(HISTOGRAM_BUCKET_MHZ)
+                        .into_iter()
+                        .map(|(_, share)| share)
+                        .collect();
+                    if !shares.is_empty() {
+                        let (width, height) = HISTOGRAM_SIZE;
+                        let pixbuf = util::render_histogram(&shares, width, height);
+                        self.histogram = Some(gdk::Texture::for_pixbuf(&pixbuf));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl OverlayWindow {
+    fn set_visible(&mut self, visible: bool) {
+        if visible {
+            // Nothing was read while hidden, old readings would be stale
+            self.text = "Waiting for stats...".to_owned();
+            self.load_history = StatsHistory::new(SPARKLINE_SAMPLES);
+            self.sparkline = None;
+            self.histogram = None;
+        }
+        if !visible {
+            // Still mapped, the view hides it after the update
+            self.save_position();
+        }
+        self.visible = visible;
+        self.shown.store(visible, Ordering::Relaxed);
+        self.save_visibility();
+    }
+
+    /// Remember where the overlay is for the next time it's shown
+    fn save_position(&self) {
+        if !window_placement::supported() {
+            return;
+        }
+        let position = match window_placement::position(OVERLAY_TITLE) {
+            Ok(position) => position,
+            Err(err) => {
+                tracing::warn!("Failed to read the overlay position: {err:#}");
+                return;
+            }
+        };
+        let config = AppConfig {
+            overlay_position: Some(position),
+            ..AppConfig::load()
+        };
+        if let Err(err) = config.save() {
+            tracing::warn!("Failed to save overlay position: {err:#}");
+        }
+    }
+
+    fn save_visibility(&self) {
+        let config = AppConfig {
+            overlay_visible: self.visible,
+            ..AppConfig::load()
+        };
+        if let Err(err) = config.save() {
+            tracing::warn!("Failed to save overlay visibility: {err:#}");
+        }
+    }
+}
+
+/// One line each for CPU, GPU and fans
+fn overlay_text(stats: &SystemStats, unit: TemperatureUnit, cpu_temp_offset: f32) -> String {
+    let temp = |celsius: Option<f32>| celsius.map_or("--".to_owned(), |c| unit.format(c));
+
+    let cpu_temp = stats
+        .cpu
+        .package_temp
+        .or(stats.cpu.max_core_temp)
+        .map(|temp| temp + cpu_temp_offset);
+    let mut lines = vec![format!(
+        "CPU {:>5} {:>4.0}%",
+        temp(cpu_temp),
+        stats.cpu.mean_load_percent
+    )];
+    if stats.cpu.median_frequency_mhz > 0 {
+        let boost = if stats.cpu.boost_active { " boosting" } else { "" };
+        lines.push(format!("Clk {:>5} MHz{boost}", stats.cpu.median_frequency_mhz));
+    }
+
+    // The discrete GPU is the one that matters while gaming
+    let gpu = stats
+        .gpus
+        .iter()
+        .find(|gpu| gpu.gpu_type == GpuType::Discrete)
+        .or(stats.gpus.first());
+    if let Some(gpu) = gpu {
+        let load = gpu.load_percent.map_or("--".to_owned(), |load| format!("{load:.0}%"));
+        lines.push(format!("GPU {:>5} {:>5}", temp(gpu.temperature), load));
+    }
+
+    let rpms: Vec<String> = stats
+        .fans
+        .iter()
+        .filter_map(|fan| fan.speed_rpm)
+        .map(|rpm| rpm.to_string())
+        .collect();
+    if !rpms.is_empty() {
+        lines.push(format!("Fan {} RPM", rpms.join(" / ")));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,655 @@
+use relm4::adw::prelude::{
+    ActionRowExt, ComboRowExt, MessageDialogExt, MessageDialogExtManual, PreferencesGroupExt,
+    PreferencesPageExt, PreferencesRowExt, PreferencesWindowExt,
+};
+use relm4::gtk::prelude::{ButtonExt, EditableExt, GtkWindowExt, WidgetExt};
+use relm4::{adw, gtk, Component, ComponentParts, ComponentSender};
+
+use crate::app_config::{
+    AppConfig, DailyWindow, NotificationBackend, PowerUnit, TemperatureUnit, AUTO_SWITCH_DWELL_RANGE,
+    CPU_TEMP_OFFSET_RANGE, FAN_DANGER_TEMP_RANGE, RECONCILE_INTERVAL_RANGE,
+    REFRESH_INTERVAL_RANGE,
+};
+use crate::state::{profile_controller, LOCAL_PROFILES_CHANGED};
+use crate::user_service;
+
+const FAN_CONTROL_UNSUPPORTED: &str =
+    "This model has no writable fan control, fan curves would have no effect";
+
+pub struct Preferences {
+    config: AppConfig,
+    /// Saving failed, closing the window again discards the changes
+    discard_on_close: bool,
+    /// Choices for the startup profile, index 0 of the combo row is "None"
+    profile_names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PreferencesMsg {
+    /// Save and close, see [`Preferences::discard_on_close`]
+    Close,
+    Save,
+    ConfirmReset,
+    /// Reset the settings and, if `profiles` is set, remove all but the default profile
+    Reset { profiles: bool },
+    /// systemctl installed or removed the user service, the window closes
+    /// afterwards if `close` is set
+    UserServiceSynced { result: Result<(), String>, close: bool },
+}
+
+#[relm4::component(pub)]
+impl Component for Preferences {
+    type CommandOutput = ();
+    type Init = ();
+    type Input = PreferencesMsg;
+    type Output = ();
+
+    view! {
+        #[name = "window"]
+        adw::PreferencesWindow {
+            set_visible: true,
+            set_modal: true,
+            set_search_enabled: false,
+            set_default_size: (500, 0),
+            connect_close_request[sender] => move |_| {
+                sender.input(PreferencesMsg::Close);
+                gtk::glib::Propagation::Stop
+            },
+
+            add = &adw::PreferencesPage {
+                add = &adw::PreferencesGroup {
+                    set_title: "Startup",
+
+                    add: autostart = &adw::SwitchRow {
+                        set_title: "Start on login",
+                        set_active: model.config.autostart,
+                        // Only one of them, both would start Tailor twice
+                        connect_active_notify[autostart_service] => move |row| {
+                            if row.is_active() {
+                                autostart_service.set_active(false);
+                            }
+                        },
+                    },
+                    add: autostart_service = &adw::SwitchRow {
+                        set_title: "Start on login with systemd",
+                        set_subtitle: if systemd_available {
+                            "Installs a user service that restarts Tailor if it crashes"
+                        } else {
+                            "Not available, the system doesn't use systemd"
+                        },
+                        set_sensitive: systemd_available,
+                        set_active: model.config.autostart_service,
+                        connect_active_notify[autostart] => move |row| {
+                            if row.is_active() {
+                                autostart.set_active(false);
+                            }
+                        },
+                    },
+                    add: start_minimized = &adw::SwitchRow {
+                        set_title: "Start minimized",
+                        set_subtitle: "Click the tray icon or launch Tailor again to show the window",
+                        set_active: model.config.start_minimized,
+                    },
+                    add: startup_profile = &adw::ComboRow {
+                        set_title: "Profile on startup",
+                        set_model: Some(&gtk::StringList::new(&startup_choices)),
+                        set_selected: startup_selected,
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Behavior",
+
+                    add: minimize_to_tray = &adw::SwitchRow {
+                        set_title: "Minimize to tray",
+                        set_subtitle: "Closing the window hides it in the tray, click the icon or launch Tailor again to show it. Without a tray icon closing quits",
+                        set_active: model.config.minimize_to_tray,
+                    },
+                    add: refresh_interval = &adw::SpinRow::with_range(
+                        *REFRESH_INTERVAL_RANGE.start() as f64,
+                        *REFRESH_INTERVAL_RANGE.end() as f64,
+                        1.0,
+                    ) {
+                        set_title: "Statistics refresh interval",
+                        set_subtitle: "Seconds",
+                        set_value: model.config.stats_refresh_interval_secs as f64,
+                    },
+                    add: reconcile_interval = &adw::SpinRow::with_range(
+                        0.0,
+                        *RECONCILE_INTERVAL_RANGE.end() as f64,
+                        5.0,
+                    ) {
+                        set_title: "CPU settings check interval",
+                        set_subtitle: "Seconds, 0 disables re-applying drifted settings. Applies after a restart",
+                        set_value: model.config.reconcile_interval_secs as f64,
+                    },
+                    add: auto_switch_dwell = &adw::SpinRow::with_range(
+                        *AUTO_SWITCH_DWELL_RANGE.start() as f64,
+                        *AUTO_SWITCH_DWELL_RANGE.end() as f64,
+                        5.0,
+                    ) {
+                        set_title: "Automatic switching delay",
+                        set_subtitle: "Seconds a profile switched for an app is kept. Applies after a restart",
+                        set_value: model.config.auto_switch_dwell_secs as f64,
+                    },
+                    add = &adw::ActionRow {
+                        set_title: "Automatic switching",
+                        set_subtitle: Preferences::app_monitoring_status(),
+                    },
+                    add: smooth_brightness = &adw::SwitchRow {
+                        set_title: "Smooth brightness changes",
+                        set_subtitle: "Fade screen and keyboard brightness when applying a profile",
+                        set_active: model.config.smooth_brightness,
+                    },
+                    add: brightness_all_displays = &adw::SwitchRow {
+                        set_title: "Brightness on all displays",
+                        set_subtitle: "Profiles set external monitors too (needs the ddcci driver), unless they choose otherwise",
+                        set_active: model.config.brightness_all_displays,
+                    },
+                    add: perceptual_brightness = &adw::SwitchRow {
+                        set_title: "Perceptual brightness",
+                        set_subtitle: "Finer steps at low brightness, the slider follows how bright it looks",
+                        set_active: model.config.perceptual_brightness,
+                    },
+                    add: keyboard_off_with_screen = &adw::SwitchRow {
+                        set_title: "Keyboard off with the screen",
+                        set_subtitle: "Turn the keyboard backlight off while the display sleeps. Applies after a restart",
+                        set_active: model.config.keyboard_off_with_screen,
+                    },
+                    add: record_thermal_history = &adw::SwitchRow {
+                        set_title: "Watch the cooling",
+                        set_subtitle: "Keep daily CPU temperatures while Tailor or its overlay is shown, to warn when the cooling gets worse. Applies after a restart",
+                        set_active: model.config.record_thermal_history,
+                    },
+                    add: overlay_frequency_histogram = &adw::SwitchRow {
+                        set_title: "Frequency chart in the overlay",
+                        set_subtitle: "Time the cores spent at each frequency, 1 GHz per bar. Applies after a restart",
+                        set_active: model.config.overlay_frequency_histogram,
+                    },
+                    add: temperature_unit = &adw::ComboRow {
+                        set_title: "Temperature unit",
+                        set_model: Some(&gtk::StringList::new(&unit_choices)),
+                        set_selected: unit_selected,
+                    },
+                    add: power_unit = &adw::ComboRow {
+                        set_title: "CPU power",
+                        set_subtitle: "Percent of TDP needs a power limit reported by RAPL",
+                        set_model: Some(&gtk::StringList::new(&power_unit_choices)),
+                        set_selected: power_unit_selected,
+                    },
+                    add: cpu_temp_offset = &adw::SpinRow::with_range(
+                        *CPU_TEMP_OFFSET_RANGE.start() as f64,
+                        *CPU_TEMP_OFFSET_RANGE.end() as f64,
+                        0.5,
+                    ) {
+                        set_title: "CPU temperature offset",
+                        set_subtitle: "°C added to displayed CPU temperatures, to calibrate the sensor",
+                        set_digits: 1,
+                        set_value: model.config.cpu_temp_offset as f64,
+                    },
+                    add: cpu_temp_offset_for_fans = &adw::SwitchRow {
+                        set_title: "Apply the offset to fan curves",
+                        set_subtitle: "Software fan curves follow the calibrated temperature. Applies after a restart",
+                        set_active: model.config.cpu_temp_offset_for_fans,
+                    },
+                    add: notification_backend = &adw::ComboRow {
+                        set_title: "Notifications",
+                        set_subtitle: "Applies after a restart",
+                        set_model: Some(&gtk::StringList::new(&backend_choices)),
+                        set_selected: backend_selected,
+                    },
+                    add: fan_daemon_enabled = &adw::SwitchRow {
+                        set_title: "Software fan control",
+                        set_subtitle: "Follow the fan curves of software controlled profiles. When off, the firmware controls the fans",
+                        set_active: model.config.fan_daemon_enabled,
+                        set_sensitive: fan_control_supported,
+                        set_tooltip_text: (!fan_control_supported).then_some(FAN_CONTROL_UNSUPPORTED),
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Fan safety",
+                    set_description: Some("Saving a fan curve slower than this asks for confirmation"),
+
+                    add: fan_danger_temp = &adw::SpinRow::with_range(
+                        *FAN_DANGER_TEMP_RANGE.start() as f64,
+                        *FAN_DANGER_TEMP_RANGE.end() as f64,
+                        5.0,
+                    ) {
+                        set_title: "From temperature",
+                        set_subtitle: "°C",
+                        set_value: model.config.fan_danger_temp as f64,
+                    },
+                    add: fan_danger_min_speed = &adw::SpinRow::with_range(0.0, 100.0, 5.0) {
+                        set_title: "Minimum fan speed",
+                        set_subtitle: "Percent",
+                        set_value: model.config.fan_danger_min_speed as f64,
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Quick switch",
+                    set_description: Some("Desktop-wide shortcut applying the next profile, needs a desktop supporting global shortcuts. Leave empty to disable it"),
+
+                    add: quick_switch_shortcut = &adw::EntryRow {
+                        set_title: "Shortcut, e.g. CTRL+ALT+P",
+                        set_text: model.config.quick_switch_shortcut.as_deref().unwrap_or_default(),
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Quiet hours",
+                    set_description: Some("No notifications about profile switches, and keyboard effects pause at a dim color. Leave empty to disable it"),
+
+                    add: quiet_hours = &adw::EntryRow {
+                        set_title: "Time of day, e.g. 22:00-07:00",
+                        set_text: &model.config.quiet_hours.map(|window| window.to_string()).unwrap_or_default(),
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    add = &adw::ActionRow {
+                        set_title: "Reset to defaults",
+                        set_subtitle: "Restore the default settings and optionally profiles",
+
+                        add_suffix = &gtk::Button {
+                            set_label: "Reset",
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "destructive-action",
+                            connect_clicked => PreferencesMsg::ConfirmReset,
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let config = AppConfig::load();
+        let profile_names = Self::profile_names(&config);
+        let fan_control_supported =
+            profile_controller().is_some_and(|controller| controller.fan_control_supported());
+
+        let startup_choices = Self::startup_choices(&profile_names);
+        let startup_selected = config
+            .startup_profile
+            .as_ref()
+            .and_then(|name| profile_names.iter().position(|p| p == name))
+            .map_or(0, |idx| idx as u32 + 1);
+
+        let unit_choices: Vec<&str> = TemperatureUnit::ALL.iter().map(|u| u.label()).collect();
+        let unit_selected = TemperatureUnit::ALL
+            .iter()
+            .position(|unit| *unit == config.temperature_unit)
+            .unwrap_or_default() as u32;
+        let power_unit_choices: Vec<&str> = PowerUnit::ALL.iter().map(|u| u.label()).collect();
+        let power_unit_selected = PowerUnit::ALL
+            .iter()
+            .position(|unit| *unit == config.power_unit)
+            .unwrap_or_default() as u32;
+
+        let backend_choices: Vec<&str> =
+            NotificationBackend::ALL.iter().map(|b| b.label()).collect();
+        let backend_selected = Self::backend_index(config.notification_backend);
+
+        let systemd_available = user_service::systemd_available();
+
+        let model = Self {
+            config,
+            discard_on_close: false,
+            profile_names,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            PreferencesMsg::Close => {
+                if self.discard_on_close {
+                    root.destroy();
+                } else {
+                    sender.input(PreferencesMsg::Save);
+                }
+            }
+            PreferencesMsg::Save => {
+                let startup_profile = match widgets.startup_profile.selected() {
+                    0 => None,
+                    idx => self.profile_names.get(idx as usize - 1).cloned(),
+                };
+                let temperature_unit = TemperatureUnit::ALL
+                    .get(widgets.temperature_unit.selected() as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let power_unit = PowerUnit::ALL
+                    .get(widgets.power_unit.selected() as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let notification_backend = NotificationBackend::ALL
+                    .get(widgets.notification_backend.selected() as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let quick_switch_shortcut = Some(widgets.quick_switch_shortcut.text().trim().to_string())
+                    .filter(|shortcut| !shortcut.is_empty());
+                let quiet_hours = match widgets.quiet_hours.text().trim() {
+                    "" => None,
+                    text => match text.parse::<DailyWindow>() {
+                        Ok(window) => Some(window),
+                        Err(err) => {
+                            self.discard_on_close = true;
+                            root.add_toast(adw::Toast::new(&format!(
+                                "{err:#}. Close again to discard the changes"
+                            )));
+                            return;
+                        }
+                    },
+                };
+
+                // The overlay saves its own state while the dialog is open
+                let stored = AppConfig::load();
+                let config = AppConfig {
+                    overlay_visible: stored.overlay_visible,
+                    overlay_position: stored.overlay_position,
+                    minimize_to_tray: widgets.minimize_to_tray.is_active(),
+                    start_minimized: widgets.start_minimized.is_active(),
+                    stats_refresh_interval_secs: widgets.refresh_interval.value() as u32,
+                    // The spin row steps from 0 straight into the range
+                    reconcile_interval_secs: match widgets.reconcile_interval.value() as u32 {
+                        0 => 0,
+                        secs => secs.max(*RECONCILE_INTERVAL_RANGE.start()),
+                    },
+                    auto_switch_dwell_secs: widgets.auto_switch_dwell.value() as u32,
+                    temperature_unit,
+                    power_unit,
+                    cpu_temp_offset: widgets.cpu_temp_offset.value() as f32,
+                    cpu_temp_offset_for_fans: widgets.cpu_temp_offset_for_fans.is_active(),
+                    startup_profile,
+                    autostart: widgets.autostart.is_active(),
+                    autostart_service: widgets.autostart_service.is_active(),
+                    smooth_brightness: widgets.smooth_brightness.is_active(),
+                    brightness_all_displays: widgets.brightness_all_displays.is_active(),
+                    perceptual_brightness: widgets.perceptual_brightness.is_active(),
+                    keyboard_off_with_screen: widgets.keyboard_off_with_screen.is_active(),
+                    record_thermal_history: widgets.record_thermal_history.is_active(),
+                    overlay_frequency_histogram: widgets.overlay_frequency_histogram.is_active(),
+                    fan_daemon_enabled: widgets.fan_daemon_enabled.is_active(),
+                    fan_danger_temp: widgets.fan_danger_temp.value() as u8,
+                    fan_danger_min_speed: widgets.fan_danger_min_speed.value() as u8,
+                    notification_backend,
+                    quick_switch_shortcut,
+                    quiet_hours,
+                    ..self.config.clone()
+                };
+
+                let result = config
+                    .validate()
+                    .and_then(|_| config.save())
+                    .and_then(|_| config.sync_autostart());
+                match result {
+                    Ok(()) => {
+                        if let Some(controller) = profile_controller() {
+                            controller.set_smooth_brightness(config.smooth_brightness);
+                            controller.set_brightness_all_displays(config.brightness_all_displays);
+                            controller.set_perceptual_brightness(config.perceptual_brightness);
+                            controller.set_fan_daemon_enabled(config.fan_daemon_enabled);
+                            controller.set_quiet_hours(config.quiet_hours);
+                        }
+                        // systemctl is slow, only run it for changes
+                        let service_changed = config.autostart_service != self.config.autostart_service;
+                        self.config = config;
+                        if service_changed {
+                            root.set_sensitive(false);
+                            self.sync_user_service(&sender, true);
+                        } else {
+                            root.destroy();
+                        }
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to save preferences: {err:#}");
+                        self.discard_on_close = true;
+                        root.add_toast(adw::Toast::new(&format!(
+                            "{err:#}. Close again to discard the changes"
+                        )));
+                    }
+                }
+            }
+            PreferencesMsg::ConfirmReset => {
+                let dialog = adw::MessageDialog::builder()
+                    .modal(true)
+                    .transient_for(root)
+                    .heading("Reset to defaults?")
+                    .body("All settings will be restored to their defaults. Profiles can be reset as well.")
+                    .default_response("cancel")
+                    .close_response("cancel")
+                    .build();
+                dialog.add_responses(&[
+                    ("cancel", "Cancel"),
+                    ("settings", "Reset settings"),
+                    ("all", "Reset settings and profiles"),
+                ]);
+                dialog.set_response_appearance("settings", adw::ResponseAppearance::Destructive);
+                dialog.set_response_appearance("all", adw::ResponseAppearance::Destructive);
+                // Resetting read-only profiles would be undone on the next start
+                dialog.set_response_enabled(
+                    "all",
+                    profile_controller().is_some_and(|c| c.profiles_writable()),
+                );
+
+                let root = root.clone();
+                relm4::spawn_local(async move {
+                    match dialog.choose_future().await.as_str() {
+                        "settings" => sender.input(PreferencesMsg::Reset { profiles: false }),
+                        "all" => {
+                            // Deleting profiles can't be undone, so ask again
+                            let confirm = adw::MessageDialog::builder()
+                                .modal(true)
+                                .transient_for(&root)
+                                .heading("Delete all profiles?")
+                                .body("Every profile except the default profile will be deleted. This change is not reversible.")
+                                .default_response("cancel")
+                                .close_response("cancel")
+                                .build();
+                            confirm.add_responses(&[("cancel", "Cancel"), ("delete", "Delete")]);
+                            confirm.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                            if confirm.choose_future().await == "delete" {
+                                sender.input(PreferencesMsg::Reset { profiles: true });
+                            }
+                        }
+                        _ => {}
+                    }
+                });
+            }
+            PreferencesMsg::Reset { profiles } => {
+                let result = AppConfig::reset().and_then(|config| {
+                    config.sync_autostart()?;
+                    if let Some(controller) = profile_controller() {
+                        controller.set_smooth_brightness(config.smooth_brightness);
+                        controller.set_brightness_all_displays(config.brightness_all_displays);
+                        controller.set_perceptual_brightness(config.perceptual_brightness);
+                        controller.set_fan_daemon_enabled(config.fan_daemon_enabled);
+                        controller.set_quiet_hours(config.quiet_hours);
+                        if profiles {
+                            controller.reset_profiles()?;
+                        }
+                    }
+                    Ok(config)
+                });
+                match result {
+                    Ok(config) => {
+                        if profiles {
+                            *LOCAL_PROFILES_CHANGED.write() += 1;
+                        }
+                        self.profile_names = Self::profile_names(&config);
+                        self.config = config;
+                        self.refresh_widgets(widgets);
+                        self.sync_user_service(&sender, false);
+                        root.add_toast(adw::Toast::new("Restored the defaults"));
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to reset to defaults: {err:#}");
+                        root.add_toast(adw::Toast::new(&format!("{err:#}")));
+                    }
+                }
+            }
+            PreferencesMsg::UserServiceSynced { result, close } => match result {
+                Ok(()) if close => root.destroy(),
+                Ok(()) => {}
+                Err(err) => {
+                    tracing::error!("Failed to set up the user service: {err}");
+                    // Show what systemd actually does
+                    self.config.autostart_service = !self.config.autostart_service;
+                    widgets.autostart_service.set_active(self.config.autostart_service);
+                    if let Err(err) = self.config.save() {
+                        tracing::warn!("Failed to save preferences: {err:#}");
+                    }
+                    root.set_sensitive(true);
+                    root.add_toast(adw::Toast::new(&err));
+                }
+            },
+        }
+    }
+}
+
+impl Preferences {
+    /// Install or remove the user service in the background, systemctl
+    /// can take seconds. The window waits if it closes afterwards.
+    fn sync_user_service(&self, sender: &ComponentSender<Self>, close: bool) {
+        let (config, sender) = (self.config.clone(), sender.clone());
+        std::thread::spawn(move || {
+            let result = config.sync_user_service().map_err(|err| format!("{err:#}"));
+            sender.input(PreferencesMsg::UserServiceSynced { result, close });
+        });
+    }
+
+    /// Names of the local profiles, including a configured startup profile
+    /// that was deleted meanwhile so it isn't silently dropped
+    fn profile_names(config: &AppConfig) -> Vec<String> {
+        let mut profile_names: Vec<String> = profile_controller()
+            .map(|controller| {
+                controller
+                    .get_all_profiles()
+                    .into_iter()
+                    .map(|profile| profile.name)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(name) = &config.startup_profile {
+            if !profile_names.contains(name) {
+                profile_names.push(name.clone());
+            }
+        }
+        profile_names
+    }
+
+    /// State of the app monitoring that switches profiles for trigger apps
+    fn app_monitoring_status() -> &'static str {
+        match profile_controller() {
+            Some(controller) if controller.app_monitoring_alive() => "Running",
+            Some(controller) if controller.app_monitoring_enabled() => {
+                "Not responding, restarting it shortly"
+            }
+            _ => "Off, no profile has trigger apps",
+        }
+    }
+
+    fn startup_choices(profile_names: &[String]) -> Vec<&str> {
+        std::iter::once("None")
+            .chain(profile_names.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Show the values of `self.config`, e.g. after a reset
+    fn refresh_widgets(&self, widgets: &PreferencesWidgets) {
+        let config = &self.config;
+        widgets.autostart.set_active(config.autostart);
+        widgets.autostart_service.set_active(config.autostart_service);
+        widgets.start_minimized.set_active(config.start_minimized);
+        widgets.minimize_to_tray.set_active(config.minimize_to_tray);
+        widgets
+            .refresh_interval
+            .set_value(config.stats_refresh_interval_secs as f64);
+        widgets
+            .reconcile_interval
+            .set_value(config.reconcile_interval_secs as f64);
+        widgets
+            .auto_switch_dwell
+            .set_value(config.auto_switch_dwell_secs as f64);
+        widgets.smooth_brightness.set_active(config.smooth_brightness);
+        widgets
+            .brightness_all_displays
+            .set_active(config.brightness_all_displays);
+        widgets
+            .perceptual_brightness
+            .set_active(config.perceptual_brightness);
+        widgets
+            .keyboard_off_with_screen
+            .set_active(config.keyboard_off_with_screen);
+        widgets
+            .record_thermal_history
+            .set_active(config.record_thermal_history);
+        widgets
+            .overlay_frequency_histogram
+            .set_active(config.overlay_frequency_histogram);
+        widgets
+            .fan_daemon_enabled
+            .set_active(config.fan_daemon_enabled);
+        widgets
+            .fan_danger_temp
+            .set_value(config.fan_danger_temp as f64);
+        widgets
+            .fan_danger_min_speed
+            .set_value(config.fan_danger_min_speed as f64);
+        widgets
+            .quick_switch_shortcut
+            .set_text(config.quick_switch_shortcut.as_deref().unwrap_or_default());
+        widgets
+            .quiet_hours
+            .set_text(&config.quiet_hours.map(|window| window.to_string()).unwrap_or_default());
+
+        let startup_choices = Self::startup_choices(&self.profile_names);
+        widgets
+            .startup_profile
+            .set_model(Some(&gtk::StringList::new(&startup_choices)));
+        widgets.startup_profile.set_selected(0);
+
+        let unit_selected = TemperatureUnit::ALL
+            .iter()
+            .position(|unit| *unit == config.temperature_unit)
+            .unwrap_or_default();
+        widgets.temperature_unit.set_selected(unit_selected as u32);
+        let power_unit_selected = PowerUnit::ALL
+            .iter()
+            .position(|unit| *unit == config.power_unit)
+            .unwrap_or_default();
+        widgets.power_unit.set_selected(power_unit_selected as u32);
+        widgets
+            .cpu_temp_offset
+            .set_value(config.cpu_temp_offset as f64);
+        widgets
+            .cpu_temp_offset_for_fans
+            .set_active(config.cpu_temp_offset_for_fans);
+        widgets
+            .notification_backend
+            .set_selected(Self::backend_index(config.notification_backend));
+    }
+
+    fn backend_index(backend: NotificationBackend) -> u32 {
+        NotificationBackend::ALL
+            .iter()
+            .position(|b| *b == backend)
+            .unwrap_or_default() as u32
+    }
+}
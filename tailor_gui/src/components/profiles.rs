@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use adw::prelude::PreferencesGroupExt;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use gtk::prelude::ButtonExt;
 use relm4::factory::FactoryVecDeque;
@@ -25,6 +28,7 @@ pub enum ProfilesInput {
         active_profile: String,
         fan_profiles: Vec<String>,
         led_profiles: Vec<String>,
+        last_applied: HashMap<String, DateTime<Utc>>,
     },
     Enabled(DynamicIndex),
     Remove(DynamicIndex),
@@ -68,6 +72,7 @@ impl Component for Profiles {
                 active_profile: state.active_profile_name.clone(),
                 fan_profiles: state.fan_profiles.clone(),
                 led_profiles: state.led_profiles.clone(),
+                last_applied: state.last_applied.clone(),
             }
         });
 
@@ -94,6 +99,7 @@ impl Component for Profiles {
                 active_profile,
                 led_profiles,
                 fan_profiles,
+                last_applied,
             } => {
                 // Repopulate the profiles
                 let mut guard = self.profiles.guard();
@@ -101,6 +107,7 @@ impl Component for Profiles {
                 for profile in profiles {
                     let active = active_profile == profile.name;
                     guard.push_back(ProfileInit {
+                        last_applied: last_applied.get(&profile.name).copied(),
                         name: profile.name,
                         info: profile.data,
                         led_profiles: led_profiles.clone(),
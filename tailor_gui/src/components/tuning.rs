@@ -0,0 +1,543 @@
+use adw::prelude::{
+    ActionRowExt, ComboRowExt, MessageDialogExt, MessageDialogExtManual, PreferencesGroupExt,
+    PreferencesRowExt, SpinRowExt,
+};
+use gtk::prelude::{BoxExt, ButtonExt, GtkWindowExt, OrientableExt, WidgetExt};
+use relm4::{
+    adw, component, gtk, Component, ComponentController, ComponentParts, ComponentSender,
+    Controller, RelmWidgetExt,
+};
+
+use super::fan_edit::{FanEdit, FanEditInput, FanEditOutput, LocalCurve};
+use crate::hardware_control::FixedFrequencySupport;
+use crate::profile_system::{FanControlMode, FanCurve, FieldChange, KeyboardMode, Profile};
+use crate::state::{profile_controller, LOCAL_PROFILES_CHANGED};
+use crate::templates;
+
+/// Settings of the local profiles that tailord doesn't know about, edited
+/// on a copy until they are saved
+pub struct Tuning {
+    /// Local profiles as they are saved
+    profiles: Vec<Profile>,
+    selected: usize,
+    /// Edited copy of the selected profile
+    working: Option<Profile>,
+    /// Names of `profiles` for the combo row
+    choices: gtk::StringList,
+    profile_row: adw::ComboRow,
+    fan_group: adw::PreferencesGroup,
+    fan_rows: Vec<adw::ActionRow>,
+    fan_edit: Controller<FanEdit>,
+    /// `None` if the CPU can't be pinned to a frequency
+    fixed_frequency: Option<FixedFrequencySupport>,
+    toast: Option<adw::Toast>,
+}
+
+/// Step of the fixed frequency spin row in MHz
+const FIXED_FREQUENCY_STEP: f64 = 100.0;
+
+/// Longest firmware keyboard backlight timeout offered, in seconds
+const HW_TIMEOUT_MAX_SECS: f64 = 600.0;
+
+#[derive(Debug)]
+pub enum TuningInput {
+    SelectProfile(usize),
+    /// The local profiles changed, e.g. after a reset
+    ReloadProfiles,
+    EditCurve(String),
+    #[doc(hidden)]
+    CurveEdited {
+        fan_id: String,
+        curve: FanCurve,
+    },
+    /// Pin the CPU to the frequency of the spin row, or stop pinning it
+    EnableFixedFrequency(bool),
+    SetFixedFrequency(u32),
+    /// Leave the keyboard backlight alone when the profile is applied
+    SetKeyboardUnmanaged(bool),
+    /// Turn the keyboard backlight off when the profile is applied
+    SetKeyboardOff(bool),
+    /// Firmware backlight timeout in seconds
+    SetHwTimeout(u32),
+    /// Ask to save the changes of the working copy
+    Save,
+    /// Save without asking again
+    #[doc(hidden)]
+    SaveConfirmed,
+}
+
+#[component(pub)]
+impl Component for Tuning {
+    type CommandOutput = ();
+    type Init = ();
+    type Input = TuningInput;
+    type Output = ();
+
+    view! {
+        #[template]
+        templates::CustomClamp {
+            #[template_child]
+            clamp {
+                adw::ToastOverlay {
+                    #[watch]
+                    add_toast?: model.toast.clone(),
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 6,
+
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+
+                            gtk::Label {
+                                add_css_class: "heading",
+                                set_label: "Local profiles",
+                            },
+                            gtk::Box {
+                                set_hexpand: true,
+                            },
+                            gtk::Button {
+                                set_label: "Save",
+                                add_css_class: "suggested-action",
+                                #[watch]
+                                set_sensitive: model.has_changes(),
+                                connect_clicked => TuningInput::Save,
+                            }
+                        },
+
+                        adw::PreferencesGroup {
+                            #[local_ref]
+                            add = profile_row -> adw::ComboRow {
+                                set_title: "Profile",
+                                set_subtitle: "Changes are kept until you save them or pick another profile",
+                                connect_selected_notify[sender] => move |row| {
+                                    sender.input(TuningInput::SelectProfile(row.selected() as usize));
+                                },
+                            },
+                        },
+
+                        #[local_ref]
+                        fan_group -> adw::PreferencesGroup {
+                            set_margin_top: 12,
+                            set_title: "Fan curves",
+                            #[watch]
+                            set_description: Some(model.fan_description()),
+                        },
+
+                        adw::PreferencesGroup {
+                            set_margin_top: 12,
+                            set_title: "CPU",
+
+                            add = &adw::SwitchRow {
+                                set_title: "Fixed frequency",
+                                set_subtitle: if fixed_frequency_range.is_some() {
+                                    "Pin every core to one frequency, e.g. for benchmarks"
+                                } else {
+                                    "The cpufreq driver has no userspace governor"
+                                },
+                                set_sensitive: fixed_frequency_range.is_some(),
+                                #[watch]
+                                set_active: model.fixed_freq_mhz().is_some(),
+                                connect_active_notify[sender] => move |row| {
+                                    sender.input(TuningInput::EnableFixedFrequency(row.is_active()));
+                                },
+                            },
+                            add = &adw::SpinRow::with_range(
+                                fixed_frequency_range.map_or(0.0, |(min, _)| min as f64),
+                                fixed_frequency_range.map_or(0.0, |(_, max)| max as f64),
+                                FIXED_FREQUENCY_STEP,
+                            ) {
+                                set_title: "Frequency in MHz",
+                                #[watch]
+                                set_sensitive: model.fixed_freq_mhz().is_some(),
+                                #[watch]
+                                set_value: model
+                                    .fixed_freq_mhz()
+                                    .or(fixed_frequency_range.map(|(min, _)| min))
+                                    .unwrap_or_default() as f64,
+                                connect_value_notify[sender] => move |row| {
+                                    sender.input(TuningInput::SetFixedFrequency(row.value() as u32));
+                                },
+                            },
+                        },
+
+                        adw::PreferencesGroup {
+                            set_margin_top: 12,
+                            set_title: "Keyboard",
+
+                            add = &adw::SwitchRow {
+                                set_title: "Don't manage keyboard",
+                                set_subtitle: "Leave the backlight as it is when the profile is applied",
+                                #[watch]
+                                set_active: model.keyboard_mode() == KeyboardMode::Unmanaged,
+                                connect_active_notify[sender] => move |row| {
+                                    sender.input(TuningInput::SetKeyboardUnmanaged(row.is_active()));
+                                },
+                            },
+                            add = &adw::SwitchRow {
+                                set_title: "Turn keyboard off",
+                                set_subtitle: "Switch the backlight off when the profile is applied",
+                                #[watch]
+                                set_sensitive: model.keyboard_mode() != KeyboardMode::Unmanaged,
+                                #[watch]
+                                set_active: model.keyboard_mode() == KeyboardMode::Off,
+                                connect_active_notify[sender] => move |row| {
+                                    sender.input(TuningInput::SetKeyboardOff(row.is_active()));
+                                },
+                            },
+                            add = &adw::SpinRow::with_range(0.0, HW_TIMEOUT_MAX_SECS, 5.0) {
+                                set_title: "Firmware backlight timeout",
+                                set_subtitle: if hw_timeout_supported {
+                                    "Seconds without input until the keyboard turns its backlight off, 0 keeps it on"
+                                } else {
+                                    "The keyboard firmware has no backlight timeout"
+                                },
+                                set_sensitive: hw_timeout_supported,
+                                #[watch]
+                                set_value: model.hw_timeout() as f64,
+                                connect_value_notify[sender] => move |row| {
+                                    sender.input(TuningInput::SetHwTimeout(row.value() as u32));
+                                },
+                            },
+                        },
+                    }
+                }
+            }
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let fan_edit = FanEdit::builder().transient_for(&*root).launch(()).forward(
+            sender.input_sender(),
+            |output| match output {
+                FanEditOutput::LocalCurve { fan_id, curve } => {
+                    TuningInput::CurveEdited { fan_id, curve }
+                }
+            },
+        );
+
+        LOCAL_PROFILES_CHANGED.subscribe(sender.input_sender(), |_| TuningInput::ReloadProfiles);
+
+        let profiles = local_profiles();
+        let names: Vec<&str> = profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        let choices = gtk::StringList::new(&names);
+
+        let mut model = Self {
+            working: profiles.first().cloned(),
+            profiles,
+            selected: 0,
+            profile_row: adw::ComboRow::builder().model(&choices).build(),
+            choices,
+            fan_group: adw::PreferencesGroup::new(),
+            fan_rows: Vec::new(),
+            fan_edit,
+            fixed_frequency: profile_controller()
+                .and_then(|controller| controller.fixed_frequency_support()),
+            toast: None,
+        };
+        model.update_fan_rows(&sender);
+        let fixed_frequency_range = model.fixed_frequency.as_ref().and_then(frequency_range);
+        let hw_timeout_supported =
+            profile_controller().is_some_and(|controller| controller.has_keyboard_hw_timeout());
+
+        let profile_row = &model.profile_row;
+        let fan_group = &model.fan_group;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, input: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
+        self.toast = None;
+
+        match input {
+            TuningInput::SelectProfile(index) => {
+                self.selected = index;
+                self.working = self.profiles.get(index).cloned();
+                self.update_fan_rows(&sender);
+            }
+            TuningInput::ReloadProfiles => {
+                let name = self.working.as_ref().map(|profile| profile.name.clone());
+                self.profiles = local_profiles();
+                let names: Vec<&str> = self
+                    .profiles
+                    .iter()
+                    .map(|profile| profile.name.as_str())
+                    .collect();
+                let index = self
+                    .profiles
+                    .iter()
+                    .position(|profile| Some(&profile.name) == name.as_ref())
+                    .unwrap_or_default();
+                // Replacing the choices selects the first one, the last
+                // selection queued wins
+                self.choices.splice(0, self.choices.n_items(), &names);
+                self.profile_row.set_selected(index as u32);
+                sender.input(TuningInput::SelectProfile(index));
+            }
+            TuningInput::EditCurve(fan_id) => {
+                let Some(profile) = &self.working else {
+                    return;
+                };
+                if let Some(curve) = profile.fan_curves.get(&fan_id) {
+                    self.fan_edit.emit(FanEditInput::LoadLocal(LocalCurve {
+                        profile_name: profile.name.clone(),
+                        fan_id,
+                        curve: curve.clone(),
+                        mode: profile.fan_control_mode,
+                    }));
+                }
+            }
+            TuningInput::CurveEdited { fan_id, curve } => {
+                if let Some(profile) = &mut self.working {
+                    profile.fan_curves.insert(fan_id, curve);
+                    self.update_fan_rows(&sender);
+                }
+            }
+            TuningInput::EnableFixedFrequency(enabled) => {
+                let Some(profile) = &mut self.working else {
+                    return;
+                };
+                if enabled == profile.cpu_settings.fixed_freq_mhz.is_some() {
+                    return;
+                }
+                // Start at the slowest frequency, pinning is mostly for
+                // quiet or comparable runs
+                profile.cpu_settings.fixed_freq_mhz = self
+                    .fixed_frequency
+                    .as_ref()
+                    .filter(|_| enabled)
+                    .and_then(|support| Some(support.nearest(frequency_range(support)?.0)));
+            }
+            TuningInput::SetFixedFrequency(mhz) => {
+                let (Some(profile), Some(support)) = (&mut self.working, &self.fixed_frequency)
+                else {
+                    return;
+                };
+                let Some(current) = profile.cpu_settings.fixed_freq_mhz else {
+                    return;
+                };
+                if mhz != current {
+                    profile.cpu_settings.fixed_freq_mhz =
+                        Some(snap_frequency(support, current, mhz));
+                }
+            }
+            TuningInput::SetKeyboardUnmanaged(unmanaged) => {
+                // Turning the other switch on or off notifies this one too
+                if unmanaged != (self.keyboard_mode() == KeyboardMode::Unmanaged) {
+                    let mode = if unmanaged {
+                        KeyboardMode::Unmanaged
+                    } else {
+                        KeyboardMode::Managed
+                    };
+                    self.set_keyboard_mode(mode);
+                }
+            }
+            TuningInput::SetKeyboardOff(off) => {
+                if off != (self.keyboard_mode() == KeyboardMode::Off) {
+                    let mode = if off {
+                        KeyboardMode::Off
+                    } else {
+                        KeyboardMode::Managed
+                    };
+                    self.set_keyboard_mode(mode);
+                }
+            }
+            TuningInput::SetHwTimeout(secs) => {
+                if let Some(profile) = &mut self.working {
+                    if secs != self.hw_timeout() {
+                        profile.keyboard_backlight.hw_timeout_secs = Some(secs);
+                    }
+                }
+            }
+            TuningInput::Save => {
+                let (Some(controller), Some(profile)) = (profile_controller(), &self.working)
+                else {
+                    return;
+                };
+                match controller.profile_changes(self.selected, profile) {
+                    Ok(changes) if changes.is_empty() => (),
+                    Ok(changes) => confirm_save(&sender, root, &profile.name, &changes),
+                    Err(err) => {
+                        self.toast = Some(adw::Toast::new(&format!(
+                            "Failed to save '{}': {err:#}",
+                            profile.name
+                        )));
+                    }
+                }
+            }
+            TuningInput::SaveConfirmed => {
+                let (Some(controller), Some(profile)) = (profile_controller(), &self.working)
+                else {
+                    return;
+                };
+                match controller.update_profile(self.selected, profile.clone()) {
+                    // Shows the saved profiles everywhere, this page included
+                    Ok(()) => *LOCAL_PROFILES_CHANGED.write() += 1,
+                    Err(err) => {
+                        self.toast = Some(adw::Toast::new(&format!(
+                            "Failed to save '{}': {err:#}",
+                            profile.name
+                        )));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Tuning {
+    /// Whether the working copy differs from the saved profile
+    fn has_changes(&self) -> bool {
+        self.working
+            .as_ref()
+            .zip(self.profiles.get(self.selected))
+            .is_some_and(|(working, saved)| !working.diff(saved).is_empty())
+    }
+
+    fn keyboard_mode(&self) -> KeyboardMode {
+        self.working
+            .as_ref()
+            .map(|profile| profile.keyboard_backlight.mode)
+            .unwrap_or_default()
+    }
+
+    fn set_keyboard_mode(&mut self, mode: KeyboardMode) {
+        if let Some(profile) = &mut self.working {
+            profile.keyboard_backlight.mode = mode;
+        }
+    }
+
+    /// Firmware backlight timeout in seconds, 0 if the profile has none
+    fn hw_timeout(&self) -> u32 {
+        self.working
+            .as_ref()
+            .and_then(|profile| profile.keyboard_backlight.hw_timeout_secs)
+            .unwrap_or_default()
+    }
+
+    fn fixed_freq_mhz(&self) -> Option<u32> {
+        self.working
+            .as_ref()
+            .and_then(|profile| profile.cpu_settings.fixed_freq_mhz)
+    }
+
+    fn fan_description(&self) -> &'static str {
+        match &self.working {
+            Some(profile) if profile.fan_curves.is_empty() => {
+                "The profile leaves the fans to the firmware"
+            }
+            Some(profile) if profile.fan_control_mode == FanControlMode::Software => {
+                "Followed in software, each curve with its own interpolation"
+            }
+            Some(_) => {
+                "Written to the embedded controller, which ramps linearly between the points"
+            }
+            None => "",
+        }
+    }
+
+    /// Show a row for every fan curve of the working copy
+    fn update_fan_rows(&mut self, sender: &ComponentSender<Self>) {
+        for row in self.fan_rows.drain(..) {
+            self.fan_group.remove(&row);
+        }
+        let Some(profile) = &self.working else {
+            return;
+        };
+
+        let mut fan_ids: Vec<&String> = profile.fan_curves.keys().collect();
+        fan_ids.sort();
+        for fan_id in fan_ids {
+            let curve = &profile.fan_curves[fan_id];
+            let mut subtitle = format!("{} points", curve.points.len());
+            if profile.fan_control_mode == FanControlMode::Software {
+                subtitle.push_str(&format!(", {}", curve.interpolation.label()));
+            }
+
+            let row = adw::ActionRow::builder()
+                .title(fan_id)
+                .subtitle(subtitle)
+                .build();
+            let button = gtk::Button::builder()
+                .label("Edit")
+                .valign(gtk::Align::Center)
+                .build();
+            let (sender, fan_id) = (sender.clone(), fan_id.clone());
+            button.connect_clicked(move |_| sender.input(TuningInput::EditCurve(fan_id.clone())));
+            row.add_suffix(&button);
+
+            self.fan_group.add(&row);
+            self.fan_rows.push(row);
+        }
+    }
+}
+
+/// Show what saving the profile `name` changes, saving only if confirmed
+fn confirm_save(
+    sender: &ComponentSender<Tuning>,
+    root: &gtk::ScrolledWindow,
+    name: &str,
+    changes: &[FieldChange],
+) {
+    let body: Vec<String> = changes.iter().map(ToString::to_string).collect();
+    let dialog = adw::MessageDialog::builder()
+        .modal(true)
+        .heading(format!("Save the changes to '{name}'?"))
+        .body(body.join("\n"))
+        .default_response("save")
+        .close_response("cancel")
+        .build();
+    dialog.set_transient_for(root.toplevel_window().as_ref());
+    dialog.add_responses(&[("cancel", "Keep editing"), ("save", "Save")]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    let sender = sender.clone();
+    relm4::spawn_local(async move {
+        if dialog.choose_future().await == "save" {
+            sender.input(TuningInput::SaveConfirmed);
+        }
+    });
+}
+
+/// Lowest and highest frequency the CPU can be pinned to, in MHz
+fn frequency_range(support: &FixedFrequencySupport) -> Option<(u32, u32)> {
+    let min = support.min_mhz.or(support.available_mhz.first().copied())?;
+    let max = support.max_mhz.or(support.available_mhz.last().copied())?;
+    Some((min, max.max(min)))
+}
+
+/// Frequency the driver accepts for `requested`. A single step of the spin
+/// row moves to the neighboring available frequency, it would be rounded
+/// back to `current` otherwise.
+fn snap_frequency(support: &FixedFrequencySupport, current: u32, requested: u32) -> u32 {
+    if requested.abs_diff(current) <= FIXED_FREQUENCY_STEP as u32 {
+        let next = if requested > current {
+            support.available_mhz.iter().find(|&&mhz| mhz > current)
+        } else {
+            support
+                .available_mhz
+                .iter()
+                .rev()
+                .find(|&&mhz| mhz < current)
+        };
+        if let Some(&next) = next {
+            return next;
+        }
+    }
+    support.nearest(requested)
+}
+
+/// Local profiles, none without a profile controller
+fn local_profiles() -> Vec<Profile> {
+    profile_controller()
+        .map(|controller| controller.get_all_profiles())
+        .unwrap_or_default()
+}
@@ -0,0 +1,714 @@
+// src/diagnostics.rs
+use crate::app_config::AppConfig;
+use crate::fand::{self, FandClient};
+use crate::hardware_control::{FanStopProbe, HardwareController};
+use crate::hardware_monitor::{HardwareMonitor, SystemInfo, SystemStats};
+use crate::keyboard_control::{is_keyboard_backlight_available, list_led_devices};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TUXEDO_IO_PATH: &str = "/sys/devices/platform/tuxedo_io";
+const MODULES_PATH: &str = "/sys/module";
+
+/// Time fans get to spin down before the zero RPM check reads them
+const FAN_STOP_SETTLE: Duration = Duration::from_secs(8);
+
+/// How often the thermal test reads the sensors
+const THERMAL_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Per-CPU thermal throttling counters (Intel only)
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// Kernel modules of tuxedo-drivers that report a version
+const DRIVER_MODULES: [&str; 2] = ["tuxedo_io", "tuxedo_keyboard"];
+
+/// Tools that set the governor or the fans on their own
+const CONFLICTING_TOOLS: [ConflictingTool; 3] = [
+    ConflictingTool {
+        name: "TUXEDO Control Center",
+        processes: &["tccd"],
+        unit: "tccd.service",
+    },
+    ConflictingTool {
+        name: "TLP",
+        processes: &["tlp"],
+        unit: "tlp.service",
+    },
+    ConflictingTool {
+        name: "auto-cpufreq",
+        processes: &["auto-cpufreq"],
+        unit: "auto-cpufreq.service",
+    },
+];
+
+struct ConflictingTool {
+    name: &'static str,
+    /// Process names as in /proc/<pid>/comm
+    processes: &'static [&'static str],
+    unit: &'static str,
+}
+
+/// Running tool that fights over the same settings, so profile settings
+/// seem to revert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub name: &'static str,
+    /// Command that stops the tool and keeps it from starting again
+    pub suggestion: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is running, disable it with: {}",
+            self.name, self.suggestion
+        )
+    }
+}
+
+/// Snapshot of the driver and permission state, meant to be pasted
+/// into bug reports
+#[derive(Debug, Clone)]
+pub struct DiagnosticsReport {
+    pub gui_version: &'static str,
+    pub system: SystemInfo,
+    pub kernel_version: Option<String>,
+    /// cpufreq driver summary, see [`crate::hardware_monitor::CpuInfo::driver_summary`]
+    pub cpufreq_driver: Option<String>,
+    /// Raw CPU package (or hottest core) temperature in °C
+    pub cpu_temp: Option<f32>,
+    /// See [`AppConfig::cpu_temp_offset`]
+    pub cpu_temp_offset: f32,
+    pub tuxedo_io_available: bool,
+    pub driver_version: Option<String>,
+    pub keyboard_backlight_available: bool,
+    pub led_devices: Vec<String>,
+    pub hwmon_chips: Vec<String>,
+    /// See [`crate::hardware_monitor::SystemStats::warnings`]
+    pub sensor_warnings: Vec<String>,
+    /// Zero RPM check, `None` unless requested with [`Self::probe_fan_stop`]
+    pub fan_stop: Option<Vec<FanStopProbe>>,
+    pub running_as_root: bool,
+}
+
+impl DiagnosticsReport {
+    /// Add the zero RPM check to the report. Stops the fans for a few
+    /// seconds and needs root. Runs in tailor-fand if it runs, so its fan
+    /// daemon doesn't write the fans meanwhile.
+    pub fn probe_fan_stop(&mut self) {
+        let fand = FandClient::new(Path::new(fand::SOCKET_PATH));
+        let probes = match fand.running() {
+            Some(fand) => fand.probe_fan_stop(FAN_STOP_SETTLE).unwrap_or_else(|e| {
+                eprintln!("Warning: {:#}", e);
+                Vec::new()
+            }),
+            None => HardwareController::new()
+                .map(|controller| controller.probe_fan_stop(FAN_STOP_SETTLE))
+                .unwrap_or_default(),
+        };
+        self.fan_stop = Some(probes);
+    }
+}
+
+/// Probe the system for the information in [`DiagnosticsReport`]
+pub fn collect() -> DiagnosticsReport {
+    let mut monitor = HardwareMonitor::new().ok();
+    let hwmon_chips = monitor
+        .as_ref()
+        .map(|monitor| monitor.hwmon_chip_names())
+        .unwrap_or_default();
+    let system = monitor
+        .as_ref()
+        .map(|monitor| monitor.get_system_info())
+        .unwrap_or_default();
+    let stats = monitor
+        .as_mut()
+        .and_then(|monitor| monitor.get_system_stats().ok());
+    let cpufreq_driver = stats.as_ref().map(|stats| stats.cpu.driver_summary());
+    let cpu_temp = stats
+        .as_ref()
+        .and_then(|stats| stats.cpu.package_temp.or(stats.cpu.max_core_temp));
+    let sensor_warnings = stats.map(|stats| stats.warnings).unwrap_or_default();
+
+    DiagnosticsReport {
+        gui_version: env!("CARGO_PKG_VERSION"),
+        system,
+        kernel_version: kernel_version(),
+        cpufreq_driver,
+        cpu_temp,
+        cpu_temp_offset: AppConfig::load().cpu_temp_offset,
+        tuxedo_io_available: Path::new(TUXEDO_IO_PATH).exists(),
+        driver_version: read_driver_version(Path::new(MODULES_PATH)),
+        keyboard_backlight_available: is_keyboard_backlight_available(),
+        led_devices: list_led_devices().unwrap_or_default(),
+        hwmon_chips,
+        sensor_warnings,
+        fan_stop: None,
+        running_as_root: unsafe { libc::geteuid() } == 0,
+    }
+}
+
+/// Find running tools that change the governor or fans as well, by their
+/// processes and, with systemd, their units. TLP only runs briefly on
+/// power events, so its unit counts even without a process.
+pub fn detect_conflicts() -> Vec<Conflict> {
+    let processes = running_process_names(Path::new("/proc"));
+    let systemd = crate::user_service::systemd_available();
+    find_conflicts(&processes, |unit| systemd && unit_active(unit))
+}
+
+fn find_conflicts(processes: &[String], unit_active: impl Fn(&str) -> bool) -> Vec<Conflict> {
+    CONFLICTING_TOOLS
+        .iter()
+        .filter(|tool| {
+            tool.processes
+                .iter()
+                .any(|process| processes.iter().any(|running| running == process))
+                || unit_active(tool.unit)
+        })
+        .map(|tool| Conflict {
+            name: tool.name,
+            suggestion: format!("sudo systemctl disable --now {}", tool.unit),
+        })
+        .collect()
+}
+
+/// Names of the running processes, from /proc/<pid>/comm
+fn running_process_names(proc_path: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(proc_path) else {
+        return Vec::new();
+    };
+    // Only the numbered directories are processes
+    let is_pid = |name: &str| name.chars().all(|c| c.is_ascii_digit());
+    entries
+        .flatten()
+        .filter(|entry| is_pid(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .map(|comm| comm.trim_end().to_string())
+        .collect()
+}
+
+fn unit_active(unit: &str) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", unit])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Peak values while [`run_thermal_test`] loaded the CPU
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalTestReport {
+    /// How long the load ran, shorter than requested if cancelled
+    pub duration: Duration,
+    pub threads: usize,
+    pub cancelled: bool,
+    /// Hottest CPU package (or core) temperature in °C
+    pub peak_cpu_temp: Option<f32>,
+    /// Highest RPM of every fan with a sensor, by display name
+    pub peak_fan_rpm: BTreeMap<String, u32>,
+    /// Lowest median core frequency while loaded
+    pub min_frequency_mhz: Option<u32>,
+    /// Base frequency the driver reports (`base_frequency`), not a guess
+    /// like `cpuinfo_max_freq` that any non-boost clock falls below
+    pub base_freq_mhz: Option<u32>,
+    /// The kernel counted throttling events, or the cores fell below
+    /// their base frequency
+    pub throttled: bool,
+}
+
+impl ThermalTestReport {
+    fn new(threads: usize, base_freq_mhz: Option<u32>) -> Self {
+        ThermalTestReport {
+            duration: Duration::ZERO,
+            threads,
+            cancelled: false,
+            peak_cpu_temp: None,
+            peak_fan_rpm: BTreeMap::new(),
+            min_frequency_mhz: None,
+            base_freq_mhz,
+            throttled: false,
+        }
+    }
+
+    fn add_sample(&mut self, stats: &SystemStats) {
+        if let Some(temp) = stats.cpu.package_temp.or(stats.cpu.max_core_temp) {
+            self.peak_cpu_temp = Some(self.peak_cpu_temp.map_or(temp, |peak| peak.max(temp)));
+        }
+        for fan in &stats.fans {
+            if let Some(rpm) = fan.speed_rpm {
+                let peak = self.peak_fan_rpm.entry(fan.display_name()).or_default();
+                *peak = (*peak).max(rpm);
+            }
+        }
+        if stats.cpu.median_frequency_mhz > 0 {
+            let frequency = stats.cpu.median_frequency_mhz;
+            self.min_frequency_mhz = Some(
+                self.min_frequency_mhz
+                    .map_or(frequency, |min| min.min(frequency)),
+            );
+        }
+    }
+
+    fn below_base_frequency(&self) -> bool {
+        self.min_frequency_mhz
+            .zip(self.base_freq_mhz)
+            .is_some_and(|(min, base)| min < base)
+    }
+}
+
+impl fmt::Display for ThermalTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Thermal test: {}s on {} threads{}",
+            self.duration.as_secs(),
+            self.threads,
+            if self.cancelled { ", cancelled" } else { "" }
+        )?;
+        match self.peak_cpu_temp {
+            Some(temp) => writeln!(f, "Peak CPU temperature: {:.1}°C", temp)?,
+            None => writeln!(f, "Peak CPU temperature: unknown")?,
+        }
+        for (fan, rpm) in &self.peak_fan_rpm {
+            writeln!(f, "Peak {}: {} RPM", fan, rpm)?;
+        }
+        match (self.min_frequency_mhz, self.base_freq_mhz) {
+            (Some(min), Some(base)) => {
+                writeln!(f, "Lowest frequency: {} MHz (base {} MHz)", min, base)?
+            }
+            (Some(min), None) => writeln!(f, "Lowest frequency: {} MHz", min)?,
+            _ => {}
+        }
+        write!(f, "Throttled: {}", yes_no(self.throttled))
+    }
+}
+
+/// Load every logical core for `duration` while sampling temperatures,
+/// fan speeds and clocks, e.g. to check a quiet profile doesn't throttle.
+/// Setting `cancel` ends the test early. Blocks until the load stopped.
+pub fn run_thermal_test(duration: Duration, cancel: &AtomicBool) -> Result<ThermalTestReport> {
+    let mut monitor = HardwareMonitor::new()?;
+    let threads = thread::available_parallelism().map_or(1, |count| count.get());
+    let cpu_path = Path::new(CPU_PATH);
+    let base_freq_mhz = fs::read_to_string(cpu_path.join("cpu0/cpufreq/base_frequency"))
+        .ok()
+        .and_then(|khz| khz.trim().parse::<u32>().ok())
+        .map(|khz| khz / 1000);
+    Ok(thermal_test(
+        duration,
+        threads,
+        THERMAL_SAMPLE_INTERVAL,
+        base_freq_mhz,
+        cancel,
+        || monitor.get_system_stats(),
+        || read_throttle_count(cpu_path),
+    ))
+}
+
+fn thermal_test<S, T>(
+    duration: Duration,
+    threads: usize,
+    sample_interval: Duration,
+    base_freq_mhz: Option<u32>,
+    cancel: &AtomicBool,
+    mut sample: S,
+    throttle_count: T,
+) -> ThermalTestReport
+where
+    S: FnMut() -> Result<SystemStats>,
+    T: Fn() -> Option<u64>,
+{
+    let mut report = ThermalTestReport::new(threads, base_freq_mhz);
+    // Loads are computed between two samples
+    let _ = sample();
+    let throttle_before = throttle_count();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let mut x = 1u64;
+                while !stop.load(Ordering::Relaxed) {
+                    x = black_box(x.wrapping_mul(6364136223846793005).wrapping_add(1));
+                }
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+    let mut next_sample = start + sample_interval;
+    while start.elapsed() < duration {
+        if cancel.load(Ordering::Relaxed) {
+            report.cancelled = true;
+            break;
+        }
+        if Instant::now() >= next_sample {
+            next_sample += sample_interval;
+            match sample() {
+                Ok(stats) => report.add_sample(&stats),
+                Err(e) => eprintln!("Warning: Failed to read stats: {}", e),
+            }
+        }
+        thread::sleep(sample_interval.min(Duration::from_millis(100)));
+    }
+    report.duration = start.elapsed().min(duration);
+
+    stop.store(true, Ordering::Relaxed);
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    let counted = throttle_before
+        .zip(throttle_count())
+        .is_some_and(|(before, after)| after > before);
+    report.throttled = counted || report.below_base_frequency();
+    report
+}
+
+/// Sum of the core and package throttling counters, `None` where the
+/// kernel doesn't count them (AMD)
+fn read_throttle_count(cpu_path: &Path) -> Option<u64> {
+    let read = |path: &Path| -> Option<u64> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+    let mut total = None;
+    for entry in fs::read_dir(cpu_path).ok()?.flatten() {
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|name| name.strip_prefix("cpu")) else {
+            continue;
+        };
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let throttle = entry.path().join("thermal_throttle");
+        for counter in ["core_throttle_count", "package_throttle_count"] {
+            if let Some(count) = read(&throttle.join(counter)) {
+                *total.get_or_insert(0) += count;
+            }
+        }
+    }
+    total
+}
+
+/// Kernel release as reported by uname, e.g. "6.8.0-45-generic"
+fn kernel_version() -> Option<String> {
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return None;
+    }
+    let release = unsafe { CStr::from_ptr(uts.release.as_ptr()) };
+    Some(release.to_string_lossy().into_owned())
+}
+
+/// Read the version of the first loaded tuxedo-drivers module
+fn read_driver_version(modules_path: &Path) -> Option<String> {
+    DRIVER_MODULES.iter().find_map(|module| {
+        let version = fs::read_to_string(modules_path.join(module).join("version")).ok()?;
+        Some(format!("{} {}", module, version.trim()))
+    })
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+impl fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Tailor GUI version: {}", self.gui_version)?;
+        writeln!(
+            f,
+            "Model: {} {}",
+            self.system.manufacturer, self.system.product_name
+        )?;
+        writeln!(f, "Firmware: {}", self.system.firmware_summary())?;
+        writeln!(
+            f,
+            "Kernel: {}",
+            self.kernel_version.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            f,
+            "cpufreq driver: {}",
+            self.cpufreq_driver.as_deref().unwrap_or("unknown")
+        )?;
+        // Both values, so a calibrated reading isn't mistaken for a sensor bug
+        match self.cpu_temp {
+            Some(raw) if self.cpu_temp_offset != 0.0 => writeln!(
+                f,
+                "CPU temperature: {:.1}°C raw, {:.1}°C with a {:+.1}°C offset",
+                raw,
+                raw + self.cpu_temp_offset,
+                self.cpu_temp_offset
+            )?,
+            Some(raw) => writeln!(f, "CPU temperature: {:.1}°C", raw)?,
+            None => writeln!(f, "CPU temperature: unknown")?,
+        }
+        writeln!(
+            f,
+            "tuxedo_io ({}): {}",
+            TUXEDO_IO_PATH,
+            yes_no(self.tuxedo_io_available)
+        )?;
+        writeln!(
+            f,
+            "Driver version: {}",
+            self.driver_version.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            f,
+            "Keyboard backlight (rgb:kbd_backlight): {}",
+            yes_no(self.keyboard_backlight_available)
+        )?;
+        writeln!(f, "LED devices: {}", list_or_none(&self.led_devices))?;
+        writeln!(f, "hwmon chips: {}", list_or_none(&self.hwmon_chips))?;
+        if self.sensor_warnings.is_empty() {
+            writeln!(f, "Sensor warnings: none")?;
+        } else {
+            writeln!(f, "Sensor warnings:")?;
+            for warning in &self.sensor_warnings {
+                writeln!(f, "  {}", warning)?;
+            }
+        }
+        match &self.fan_stop {
+            None => writeln!(f, "Fan stop: not checked")?,
+            Some(probes) if probes.is_empty() => writeln!(f, "Fan stop: no PWM fans")?,
+            Some(probes) => {
+                writeln!(f, "Fan stop:")?;
+                for probe in probes {
+                    writeln!(f, "  {}", probe.summary())?;
+                }
+            }
+        }
+        write!(f, "Running as root: {}", yes_no(self.running_as_root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware_monitor::{CpuInfo, FanInfo, GpuType};
+    use std::cell::Cell;
+
+    fn stats(cpu_temp: f32, frequency_mhz: u32, fan_rpm: u32) -> SystemStats {
+        let mut cpu = CpuInfo::from_cores(Vec::new(), Some(cpu_temp), None);
+        cpu.median_frequency_mhz = frequency_mhz;
+        SystemStats {
+            cpu,
+            gpus: Vec::new(),
+            fans: vec![FanInfo {
+                fan_id: "fan1".to_string(),
+                name: "CPU fan".to_string(),
+                speed_rpm: Some(fan_rpm),
+                requested_percent: None,
+                gpu: None,
+            }],
+            batteries: Vec::new(),
+            active_gpu: GpuType::Integrated,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_detect_conflicts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let proc_path = temp_dir.path();
+        for (pid, comm) in [("1", "systemd"), ("812", "tccd"), ("2200", "bash")] {
+            fs::create_dir(proc_path.join(pid)).unwrap();
+            fs::write(proc_path.join(pid).join("comm"), format!("{comm}\n")).unwrap();
+        }
+        // Not a process
+        fs::create_dir(proc_path.join("sys")).unwrap();
+        fs::write(proc_path.join("sys/comm"), "tlp\n").unwrap();
+
+        let processes = running_process_names(proc_path);
+        assert_eq!(processes.len(), 3);
+        assert!(find_conflicts(&["bash".to_string()], |_| false).is_empty());
+
+        let conflicts = find_conflicts(&processes, |unit| unit == "tlp.service");
+        let names: Vec<&str> = conflicts.iter().map(|conflict| conflict.name).collect();
+        assert_eq!(names, ["TUXEDO Control Center", "TLP"]);
+        assert_eq!(
+            conflicts[1].to_string(),
+            "TLP is running, disable it with: sudo systemctl disable --now tlp.service"
+        );
+    }
+
+    #[test]
+    fn test_thermal_test() {
+        let interval = Duration::from_millis(10);
+        let samples = Cell::new(0);
+        let sample = || {
+            samples.set(samples.get() + 1);
+            let n = samples.get() as u32;
+            Ok(stats(60.0 + n.min(3) as f32, 3000, 2000 + n.min(3) * 10))
+        };
+
+        let cancel = AtomicBool::new(false);
+        let report = thermal_test(
+            interval * 30,
+            2,
+            interval,
+            Some(2000),
+            &cancel,
+            sample,
+            || Some(5),
+        );
+        assert!(!report.cancelled);
+        assert!(!report.throttled);
+        assert_eq!(report.duration, interval * 30);
+        assert_eq!(report.peak_cpu_temp, Some(63.0));
+        assert_eq!(report.peak_fan_rpm["CPU fan"], 2030);
+        assert_eq!(report.min_frequency_mhz, Some(3000));
+
+        // Throttling counted by the kernel
+        let count = Cell::new(0);
+        let throttle_count = || {
+            count.set(count.get() + 1);
+            Some(count.get())
+        };
+        let report = thermal_test(
+            interval * 5,
+            1,
+            interval,
+            Some(2000),
+            &cancel,
+            || Ok(stats(95.0, 3000, 0)),
+            throttle_count,
+        );
+        assert!(report.throttled);
+
+        // Clocks below base, and no counters on AMD
+        let report = thermal_test(
+            interval * 5,
+            1,
+            interval,
+            Some(2000),
+            &cancel,
+            || Ok(stats(95.0, 1600, 0)),
+            || None,
+        );
+        assert!(report.throttled);
+        assert!(report
+            .to_string()
+            .contains("Lowest frequency: 1600 MHz (base 2000 MHz)\nThrottled: yes"));
+
+        // Without a base frequency low clocks prove nothing
+        let report = thermal_test(
+            interval * 5,
+            1,
+            interval,
+            None,
+            &cancel,
+            || Ok(stats(95.0, 1600, 0)),
+            || None,
+        );
+        assert!(!report.throttled);
+
+        // Cancelled right away
+        cancel.store(true, Ordering::Relaxed);
+        let report = thermal_test(
+            Duration::from_secs(60),
+            1,
+            interval,
+            None,
+            &cancel,
+            || Ok(stats(50.0, 3000, 0)),
+            || None,
+        );
+        assert!(report.cancelled);
+        assert!(report.duration < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_read_throttle_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(read_throttle_count(temp_dir.path()), None);
+
+        for (cpu, core, package) in [("cpu0", "3", "10"), ("cpu1", "1", "10")] {
+            let dir = temp_dir.path().join(cpu).join("thermal_throttle");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("core_throttle_count"), format!("{core}\n")).unwrap();
+            fs::write(dir.join("package_throttle_count"), format!("{package}\n")).unwrap();
+        }
+        fs::create_dir_all(temp_dir.path().join("cpufreq")).unwrap();
+        assert_eq!(read_throttle_count(temp_dir.path()), Some(24));
+    }
+
+    #[test]
+    fn test_read_driver_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(read_driver_version(temp_dir.path()), None);
+
+        let module = temp_dir.path().join("tuxedo_keyboard");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(module.join("version"), "4.6.2\n").unwrap();
+        assert_eq!(
+            read_driver_version(temp_dir.path()).as_deref(),
+            Some("tuxedo_keyboard 4.6.2")
+        );
+    }
+
+    #[test]
+    fn test_kernel_version() {
+        let version = kernel_version().unwrap();
+        assert!(version.chars().next().unwrap().is_ascii_digit());
+    }
+
+    #[test]
+    fn test_report_text() {
+        let report = DiagnosticsReport {
+            gui_version: "0.1.0",
+            system: SystemInfo::default(),
+            kernel_version: Some("6.8.0".to_string()),
+            cpufreq_driver: None,
+            cpu_temp: Some(72.0),
+            cpu_temp_offset: -10.0,
+            tuxedo_io_available: false,
+            driver_version: None,
+            keyboard_backlight_available: true,
+            led_devices: vec!["rgb:kbd_backlight".to_string()],
+            hwmon_chips: Vec::new(),
+            sensor_warnings: vec!["/sys/class/hwmon/hwmon0/temp1_input parse failed: abc".to_string()],
+            fan_stop: None,
+            running_as_root: false,
+        };
+
+        let text = report.to_string();
+        assert!(text.contains("Firmware: BIOS Unknown (Unknown), EC Unknown"));
+        assert!(text.contains("Kernel: 6.8.0"));
+        assert!(text.contains("cpufreq driver: unknown"));
+        assert!(text.contains("CPU temperature: 72.0°C raw, 62.0°C with a -10.0°C offset"));
+        assert!(text.contains("tuxedo_io (/sys/devices/platform/tuxedo_io): no"));
+        assert!(text.contains("Driver version: unknown"));
+        assert!(text.contains("Keyboard backlight (rgb:kbd_backlight): yes"));
+        assert!(text.contains("hwmon chips: none"));
+        assert!(text.contains("Sensor warnings:\n  /sys/class/hwmon/hwmon0/temp1_input parse failed: abc\n"));
+        assert!(text.contains("Fan stop: not checked\n"));
+
+        let probe = FanStopProbe { fan_id: "fan1".to_string(), rpm_at_zero: Some(0) };
+        let report = DiagnosticsReport { fan_stop: Some(vec![probe]), ..report };
+        assert!(report.to_string().contains("Fan stop:\n  fan1: stops at 0%\n"));
+    }
+}
@@ -0,0 +1,214 @@
+// src/error.rs
+//! Error type of the public API of [`HardwareController`], [`KeyboardController`]
+//! and [`ProfileManager`].
+//!
+//! The library uses `anyhow` internally, the kind of a failure is worked out
+//! when it leaves one of these types. The message stays the same, so
+//! `{:#}` still prints the whole context chain.
+//!
+//! [`HardwareController`]: crate::hardware_control::HardwareController
+//! [`KeyboardController`]: crate::keyboard_control::KeyboardController
+//! [`ProfileManager`]: crate::profile_system::ProfileManager
+
+use std::fmt;
+use std::io;
+use std::num::{ParseFloatError, ParseIntError};
+
+pub type HardwareResult<T> = std::result::Result<T, HardwareError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HardwareError {
+    /// Needs root, sysfs attributes and `/etc` are writable by root only
+    #[error(transparent)]
+    PermissionDenied(anyhow::Error),
+    /// The driver or the model doesn't have the interface
+    #[error(transparent)]
+    Unsupported(anyhow::Error),
+    /// A value read from sysfs or a file has an unexpected format
+    #[error(transparent)]
+    Parse(anyhow::Error),
+    /// The caller passed a value out of range or an unknown name
+    #[error(transparent)]
+    InvalidInput(anyhow::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl HardwareError {
+    fn kind(&self) -> Kind {
+        match self {
+            HardwareError::PermissionDenied(_) => Kind::PermissionDenied,
+            HardwareError::Unsupported(_) => Kind::Unsupported,
+            HardwareError::Parse(_) => Kind::Parse,
+            HardwareError::InvalidInput(_) => Kind::InvalidInput,
+            HardwareError::Other(_) => Kind::Other,
+        }
+    }
+
+    /// Whether running as root would likely fix it
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, HardwareError::PermissionDenied(_))
+    }
+
+    /// Whether `err` or one of its causes is [`HardwareError::PermissionDenied`],
+    /// e.g. for errors of the profile controller
+    pub fn is_permission_denied_in(err: &anyhow::Error) -> bool {
+        err.chain()
+            .filter_map(|cause| cause.downcast_ref::<HardwareError>())
+            .any(HardwareError::is_permission_denied)
+    }
+}
+
+impl From<anyhow::Error> for HardwareError {
+    fn from(err: anyhow::Error) -> Self {
+        // Passed through code that still returns anyhow. With context on
+        // top, downcasting would drop the context.
+        let mut err = err;
+        if err
+            .chain()
+            .next()
+            .is_some_and(|top| top.is::<HardwareError>())
+        {
+            match err.downcast::<HardwareError>() {
+                Ok(err) => return err,
+                Err(other) => err = other,
+            }
+        }
+
+        match kind_of(&err) {
+            Kind::PermissionDenied => HardwareError::PermissionDenied(err),
+            Kind::Unsupported => HardwareError::Unsupported(err),
+            Kind::Parse => HardwareError::Parse(err),
+            Kind::InvalidInput => HardwareError::InvalidInput(err),
+            Kind::Other => HardwareError::Other(err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    PermissionDenied,
+    Unsupported,
+    Parse,
+    InvalidInput,
+    Other,
+}
+
+/// Message marking the kind of an error while it is an `anyhow::Error`
+#[derive(Debug)]
+struct Marked {
+    kind: Kind,
+    message: String,
+}
+
+impl fmt::Display for Marked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Marked {}
+
+/// Error for a missing interface, becomes [`HardwareError::Unsupported`]
+pub(crate) fn unsupported(message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(Marked {
+        kind: Kind::Unsupported,
+        message: message.into(),
+    })
+}
+
+/// Error for a rejected argument, becomes [`HardwareError::InvalidInput`]
+pub(crate) fn invalid_input(message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(Marked {
+        kind: Kind::InvalidInput,
+        message: message.into(),
+    })
+}
+
+/// Error for a value in an unexpected format, becomes [`HardwareError::Parse`]
+pub(crate) fn parse(message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(Marked {
+        kind: Kind::Parse,
+        message: message.into(),
+    })
+}
+
+/// Kind of the innermost cause that has one, the outer ones are context
+fn kind_of(err: &anyhow::Error) -> Kind {
+    err.chain()
+        .filter_map(|cause| {
+            if let Some(marked) = cause.downcast_ref::<Marked>() {
+                Some(marked.kind)
+            } else if let Some(err) = cause.downcast_ref::<HardwareError>() {
+                Some(err.kind()).filter(|kind| *kind != Kind::Other)
+            } else if let Some(io) = cause.downcast_ref::<io::Error>() {
+                match io.kind() {
+                    io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                        Some(Kind::PermissionDenied)
+                    }
+                    io::ErrorKind::NotFound | io::ErrorKind::Unsupported => Some(Kind::Unsupported),
+                    io::ErrorKind::InvalidInput => Some(Kind::InvalidInput),
+                    _ => None,
+                }
+            } else if cause.is::<ParseIntError>()
+                || cause.is::<ParseFloatError>()
+                || cause.is::<serde_json::Error>()
+            {
+                Some(Kind::Parse)
+            } else {
+                None
+            }
+        })
+        .last()
+        .unwrap_or(Kind::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    fn io_error(kind: io::ErrorKind) -> anyhow::Error {
+        anyhow::Error::new(io::Error::from(kind)).context("Failed to write /sys/some/attribute")
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let err = HardwareError::from(io_error(io::ErrorKind::PermissionDenied));
+        assert!(err.is_permission_denied());
+        // The message is the one of the anyhow error
+        assert_eq!(err.to_string(), "Failed to write /sys/some/attribute");
+
+        assert!(matches!(
+            HardwareError::from(io_error(io::ErrorKind::NotFound)),
+            HardwareError::Unsupported(_)
+        ));
+        let parse = "abc".parse::<u8>().context("Failed to parse brightness");
+        assert!(matches!(
+            HardwareError::from(parse.unwrap_err()),
+            HardwareError::Parse(_)
+        ));
+        assert!(matches!(
+            HardwareError::from(anyhow::anyhow!("Something else")),
+            HardwareError::Other(_)
+        ));
+
+        let err = invalid_input("Unknown policy").context("Failed to set the policy");
+        assert!(matches!(
+            HardwareError::from(err),
+            HardwareError::InvalidInput(_)
+        ));
+
+        // Converting back and forth keeps the kind
+        let err: anyhow::Error = HardwareError::from(unsupported("No fans")).into();
+        let err = HardwareError::from(err.context("Failed to apply the profile"));
+        assert!(matches!(err, HardwareError::Unsupported(_)));
+        assert_eq!(format!("{err:#}"), "Failed to apply the profile: No fans");
+
+        let err: anyhow::Error =
+            HardwareError::from(io_error(io::ErrorKind::PermissionDenied)).into();
+        assert!(HardwareError::is_permission_denied_in(
+            &err.context("Failed to apply")
+        ));
+    }
+}
@@ -0,0 +1,704 @@
+// src/fan_daemon.rs
+use crate::hardware_control::HardwareController;
+use crate::hardware_monitor::{HardwareMonitor, SensorDescriptor};
+use crate::profile_system::{FanControlMode, Profile, ProfileManager, TempSource};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the daemon re-evaluates the fan curves
+const DAEMON_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Unchanged speeds are written again after this long, in case the
+/// firmware reset them
+const FORCED_REFRESH: Duration = Duration::from_secs(30);
+
+/// How far below `FanCurve::firmware_below` the temperature must drop
+/// before the firmware gets the fan back, so it doesn't flip at the floor
+const FIRMWARE_HYSTERESIS: f32 = 3.0;
+
+/// Follows the fan curves of the active profile in software. Only profiles
+/// with `FanControlMode::Software` are handled, hardware curves are run by
+/// the EC after being written once.
+#[derive(Clone)]
+pub struct FanDaemon {
+    profile_manager: Arc<Mutex<ProfileManager>>,
+    hardware_controller: Arc<HardwareController>,
+    hardware_monitor: Arc<Mutex<HardwareMonitor>>,
+    /// Last speed (0-100%) set for each fan id. Only fans under manual
+    /// control are listed, fans left to the firmware are not.
+    last_targets: Arc<Mutex<HashMap<String, u8>>>,
+    /// When each speed in `last_targets` was written last
+    written_at: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Fans handed back to the firmware below their `firmware_below`
+    /// floor. Kept apart from `last_targets`, applying a profile sets
+    /// fans to manual without the daemon.
+    released: Arc<Mutex<HashSet<String>>>,
+    /// [`HardwareController::fan_generation`] the state above belongs to
+    fan_generation: Arc<AtomicU64>,
+    /// Fans whose curve sensor is gone, so the warning is printed once
+    missing_sensors: Arc<Mutex<HashSet<String>>>,
+    /// Fans pinned to a speed (0-100%) by the user, their curves are skipped
+    overrides: Arc<Mutex<HashMap<String, u8>>>,
+    /// Added to the CPU temperature before looking up the curves
+    cpu_temp_offset: f32,
+    running: Arc<AtomicBool>,
+    /// Bumped by every start, loops of an earlier start end even if the
+    /// daemon was restarted while they slept
+    generation: Arc<AtomicU64>,
+    /// Held while a tick writes the fans, see [`Self::paused`]
+    tick: Arc<Mutex<()>>,
+}
+
+impl FanDaemon {
+    pub fn new(
+        profile_manager: Arc<Mutex<ProfileManager>>,
+        hardware_controller: Arc<HardwareController>,
+        hardware_monitor: Arc<Mutex<HardwareMonitor>>,
+    ) -> Self {
+        FanDaemon {
+            profile_manager,
+            hardware_controller,
+            hardware_monitor,
+            last_targets: Arc::new(Mutex::new(HashMap::new())),
+            written_at: Arc::new(Mutex::new(HashMap::new())),
+            released: Arc::new(Mutex::new(HashSet::new())),
+            fan_generation: Arc::new(AtomicU64::new(0)),
+            missing_sensors: Arc::new(Mutex::new(HashSet::new())),
+            overrides: Arc::new(Mutex::new(HashMap::new())),
+            cpu_temp_offset: 0.0,
+            running: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            tick: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Calibrate the CPU temperature the curves follow, see
+    /// [`crate::app_config::AppConfig::fan_cpu_temp_offset`]
+    pub fn with_cpu_temp_offset(mut self, offset: f32) -> Self {
+        self.cpu_temp_offset = offset;
+        self
+    }
+
+    /// Speeds the daemon applied last, by fan id. Empty while the daemon
+    /// isn't controlling any fan.
+    pub fn last_targets(&self) -> Arc<Mutex<HashMap<String, u8>>> {
+        Arc::clone(&self.last_targets)
+    }
+
+    /// Pin `fan_id` to `speed` percent, or return it to its curve with
+    /// `None`. The speed is written right away and held by the daemon
+    /// loop, the other fans keep following their curves.
+    pub fn set_manual_override(&self, fan_id: &str, speed: Option<u8>) -> Result<()> {
+        match speed {
+            Some(speed) => {
+                if speed > 100 {
+                    anyhow::bail!("Fan speed must be 0-100%");
+                }
+                self.overrides
+                    .lock()
+                    .unwrap()
+                    .insert(fan_id.to_string(), speed);
+                self.write_target(fan_id, speed)
+                    .context(format!("Failed to set speed of {}", fan_id))
+            }
+            None => {
+                if self.overrides.lock().unwrap().remove(fan_id).is_none() {
+                    return Ok(());
+                }
+                // The firmware holds the fan until the next tick applies
+                // its curve, if the active profile has one
+                self.last_targets.lock().unwrap().remove(fan_id);
+                self.hardware_controller
+                    .set_fan_auto(fan_id)
+                    .context(format!("Failed to release {}", fan_id))
+            }
+        }
+    }
+
+    /// Write every speed again on the next tick even if it didn't change,
+    /// e.g. after something else took over the fans
+    pub fn force_refresh(&self) {
+        self.written_at.lock().unwrap().clear();
+    }
+
+    /// Run `f` without the daemon writing the fans meanwhile, e.g. to
+    /// probe them. The speeds are written again on the next tick.
+    pub fn paused<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _tick = self.tick.lock().unwrap();
+        let result = f();
+        self.force_refresh();
+        result
+    }
+
+    /// Fans pinned with [`Self::set_manual_override`] and their speeds
+    pub fn manual_overrides(&self) -> HashMap<String, u8> {
+        self.overrides.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn start(&self) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return; // Already running
+        }
+
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        let daemon = self.clone();
+        thread::spawn(move || daemon.run(generation));
+        println!("Fan daemon started");
+    }
+
+    /// Stop the daemon, drop all overrides and hand the fans back to the
+    /// firmware
+    pub fn stop(&self) {
+        if !self.running.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        // A tick in progress would take the fans again
+        let _tick = self.tick.lock().unwrap();
+        self.overrides.lock().unwrap().clear();
+        self.release_fans();
+        println!("Fan daemon stopped");
+    }
+
+    fn run(&self, generation: u64) {
+        let current = || self.is_running() && self.generation.load(Ordering::Relaxed) == generation;
+        while current() {
+            let tick = self.tick.lock().unwrap();
+            if !current() {
+                break;
+            }
+            let profile = self
+                .profile_manager
+                .lock()
+                .unwrap()
+                .get_active_profile()
+                .clone();
+
+            if profile.fan_control_mode == FanControlMode::Software {
+                if let Err(e) = self.apply_active_curves(&profile) {
+                    eprintln!("Warning: Fan daemon: {:#}", e);
+                }
+            } else if self.overrides.lock().unwrap().is_empty() {
+                self.release_fans();
+            } else if let Err(e) = self.apply_overrides() {
+                // The EC runs the curves of the other fans
+                eprintln!("Warning: Fan daemon: {:#}", e);
+            }
+            drop(tick);
+
+            thread::sleep(DAEMON_INTERVAL);
+        }
+    }
+
+    fn apply_active_curves(&self, profile: &Profile) -> Result<()> {
+        let (stats, sensors) = {
+            let mut monitor = self.hardware_monitor.lock().unwrap();
+            let stats = monitor.get_system_stats()?;
+            // Reading every sensor is only worth it if a curve needs one
+            let sensors = if profile
+                .fan_curves
+                .values()
+                .any(|c| c.temp_source != TempSource::Auto)
+            {
+                monitor.available_temp_sensors()
+            } else {
+                Vec::new()
+            };
+            (stats, sensors)
+        };
+
+        let gpu_temp = stats
+            .gpus
+            .iter()
+            .filter_map(|gpu| gpu.temperature)
+            .reduce(f32::max);
+        // Without a CPU reading, run the fans like at the top of the curve
+        let cpu_temp = match stats.cpu.package_temp.or(stats.cpu.max_core_temp) {
+            Some(temp) => temp + self.cpu_temp_offset,
+            None => {
+                eprintln!("Warning: Fan daemon: No CPU temperature, using maximum fan speed");
+                f32::MAX
+            }
+        };
+
+        self.apply_fan_curves_for_temps(profile, cpu_temp, gpu_temp, &sensors)
+    }
+
+    /// Set every fan of the profile to the speed its curve gives for the
+    /// current temperature. With `TempSource::Auto`, `fan1` follows the
+    /// CPU, the other fans, including GPU fans like `card1_fan1`, the GPU
+    /// if its temperature is known. Curves following a hwmon sensor look it
+    /// up in `sensors`. Fans below their `firmware_below` floor
+    /// are left to the firmware, overridden fans hold their pinned speed.
+    pub fn apply_fan_curves_for_temps(
+        &self,
+        profile: &Profile,
+        cpu_temp: f32,
+        gpu_temp: Option<f32>,
+        sensors: &[SensorDescriptor],
+    ) -> Result<()> {
+        let generation = self.hardware_controller.fan_generation();
+        if self.fan_generation.swap(generation, Ordering::Relaxed) != generation {
+            // A profile was applied, its fans are under manual control at
+            // speeds of its own
+            self.released.lock().unwrap().clear();
+            self.force_refresh();
+        }
+
+        let mut result = self.apply_overrides();
+        let overrides = self.manual_overrides();
+
+        for (fan_id, curve) in &profile.fan_curves {
+            if overrides.contains_key(fan_id) {
+                continue;
+            }
+            let temp = match &curve.temp_source {
+                TempSource::Auto if fan_id == "fan1" => cpu_temp,
+                TempSource::Auto => gpu_temp.unwrap_or(cpu_temp),
+                TempSource::Hwmon { chip, label } => self
+                    .sensor_temp(fan_id, chip, label, sensors)
+                    // Better too loud than too hot while the sensor is gone
+                    .unwrap_or_else(|| gpu_temp.map_or(cpu_temp, |gpu| gpu.max(cpu_temp))),
+            };
+
+            if let Some(floor) = curve.firmware_below {
+                let released = self.released.lock().unwrap().contains(fan_id);
+                let floor = if released {
+                    floor as f32
+                } else {
+                    floor as f32 - FIRMWARE_HYSTERESIS
+                };
+
+                if temp < floor {
+                    if !released {
+                        match self.hardware_controller.set_fan_auto(fan_id) {
+                            Ok(()) => {
+                                self.last_targets.lock().unwrap().remove(fan_id);
+                                self.released.lock().unwrap().insert(fan_id.to_string());
+                            }
+                            Err(e) if result.is_ok() => {
+                                result = Err(e).context(format!("Failed to release {}", fan_id));
+                            }
+                            Err(_) => {}
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            let speed = curve.target_speed(temp);
+            if let Err(e) = self.write_target(fan_id, speed) {
+                if result.is_ok() {
+                    result = Err(e).context(format!("Failed to set speed of {}", fan_id));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Write the pinned speed of every overridden fan
+    fn apply_overrides(&self) -> Result<()> {
+        let mut result = Ok(());
+        for (fan_id, speed) in self.manual_overrides() {
+            if let Err(e) = self.write_target(&fan_id, speed) {
+                if result.is_ok() {
+                    result = Err(e).context(format!("Failed to hold speed of {}", fan_id));
+                }
+            }
+        }
+        result
+    }
+
+    /// Set `fan_id` to `speed` unless that was just done, and keep track
+    /// of it in `last_targets`
+    fn write_target(&self, fan_id: &str, speed: u8) -> Result<()> {
+        if !self.needs_write(fan_id, speed) {
+            return Ok(());
+        }
+        match self.hardware_controller.set_fan_speed(fan_id, speed) {
+            Ok(()) => {
+                self.last_targets
+                    .lock()
+                    .unwrap()
+                    .insert(fan_id.to_string(), speed);
+                self.written_at
+                    .lock()
+                    .unwrap()
+                    .insert(fan_id.to_string(), Instant::now());
+                self.released.lock().unwrap().remove(fan_id);
+                Ok(())
+            }
+            Err(e) => {
+                self.last_targets.lock().unwrap().remove(fan_id);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Whether `speed` differs from the last written one or that write is
+    /// due for a refresh. Skipping the rest saves sysfs and EC traffic.
+    fn needs_write(&self, fan_id: &str, speed: u8) -> bool {
+        if self.last_targets.lock().unwrap().get(fan_id) != Some(&speed) {
+            return true;
+        }
+        self.written_at
+            .lock()
+            .unwrap()
+            .get(fan_id)
+            .is_none_or(|written| written.elapsed() >= FORCED_REFRESH)
+    }
+
+    /// Current value of a curve's sensor, `None` if it vanished (e.g. an
+    /// unplugged drive) or can't be read
+    fn sensor_temp(
+        &self,
+        fan_id: &str,
+        chip: &str,
+        label: &str,
+        sensors: &[SensorDescriptor],
+    ) -> Option<f32> {
+        let temp = sensors
+            .iter()
+            .find(|sensor| sensor.id.chip == chip && sensor.id.label == label)
+            .and_then(|sensor| sensor.temperature);
+
+        let mut missing = self.missing_sensors.lock().unwrap();
+        if temp.is_some() {
+            missing.remove(fan_id);
+        } else if missing.insert(fan_id.to_string()) {
+            eprintln!(
+                "Warning: Fan daemon: Sensor {} {} of {} is not available, following the hottest of CPU and GPU",
+                chip, label, fan_id
+            );
+        }
+        temp
+    }
+
+    fn release_fans(&self) {
+        let mut targets = self.last_targets.lock().unwrap();
+        if targets.is_empty() {
+            return;
+        }
+        targets.clear();
+        drop(targets);
+
+        if let Err(e) = self.hardware_controller.restore_auto_fan_control() {
+            eprintln!("Warning: Failed to restore automatic fan control: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware_control::{pwm_value, ApplyMask, DEFAULT_PWM_MAX};
+    use crate::profile_system::MIN_SPINNING_SPEED;
+    use crate::test_fixtures::SysfsFixture;
+
+    fn daemon(fixture: &SysfsFixture) -> FanDaemon {
+        let profile_manager = ProfileManager::with_config_dir(fixture.path("config")).unwrap();
+        FanDaemon::new(
+            Arc::new(Mutex::new(profile_manager)),
+            Arc::new(fixture.controller()),
+            Arc::new(Mutex::new(fixture.monitor())),
+        )
+    }
+
+    #[test]
+    fn test_last_targets() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+        let targets = daemon.last_targets();
+
+        let mut profile = Profile::default_profile();
+        profile.fan_control_mode = FanControlMode::Software;
+        profile.fan_curves.retain(|fan_id, _| fan_id == "fan1");
+        let expected = profile.fan_curves["fan1"].calculate_fan_speed(70.0);
+
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        assert_eq!(targets.lock().unwrap().get("fan1"), Some(&expected));
+        assert_eq!(fixture.read("/sys/class/hwmon/hwmon2/pwm1_enable"), "1");
+        assert_eq!(
+            fixture.read("/sys/class/hwmon/hwmon2/pwm1"),
+            pwm_value(expected, DEFAULT_PWM_MAX).to_string()
+        );
+
+        // Releasing the fans clears the targets and restores auto mode
+        daemon.release_fans();
+        assert!(targets.lock().unwrap().is_empty());
+        assert_eq!(fixture.read("/sys/class/hwmon/hwmon2/pwm1_enable"), "2");
+    }
+
+    #[test]
+    fn test_restart() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+
+        // Restarted while the first loop sleeps
+        daemon.start();
+        daemon.stop();
+        daemon.start();
+        thread::sleep(DAEMON_INTERVAL + Duration::from_millis(500));
+        // This daemon and one loop share the state
+        assert_eq!(Arc::strong_count(&daemon.generation), 2);
+
+        daemon.stop();
+    }
+
+    #[test]
+    fn test_firmware_below_floor() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+        let targets = daemon.last_targets();
+        let pwm_enable = || fixture.read("/sys/class/hwmon/hwmon2/pwm1_enable");
+
+        let mut profile = Profile::default_profile();
+        profile.fan_curves.retain(|fan_id, _| fan_id == "fan1");
+        profile.fan_curves.get_mut("fan1").unwrap().firmware_below = Some(50);
+
+        // Idle: the firmware keeps the fan
+        daemon
+            .apply_fan_curves_for_temps(&profile, 45.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm_enable().trim(), "2");
+        assert!(targets.lock().unwrap().is_empty());
+
+        daemon
+            .apply_fan_curves_for_temps(&profile, 55.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm_enable(), "1");
+        assert!(targets.lock().unwrap().contains_key("fan1"));
+
+        // Within the hysteresis the fan stays manual
+        daemon
+            .apply_fan_curves_for_temps(&profile, 48.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm_enable(), "1");
+
+        daemon
+            .apply_fan_curves_for_temps(&profile, 46.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm_enable(), "2");
+        assert!(targets.lock().unwrap().is_empty());
+
+        // Applying the profile writes the fan itself, it's handed back again
+        profile.fan_control_mode = FanControlMode::Software;
+        daemon
+            .hardware_controller
+            .apply_partial(&profile, ApplyMask::FANS)
+            .unwrap();
+        assert_eq!(pwm_enable(), "1");
+        daemon
+            .apply_fan_curves_for_temps(&profile, 40.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm_enable(), "2");
+    }
+
+    #[test]
+    fn test_unchanged_speed_not_rewritten() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+        let pwm = "/sys/class/hwmon/hwmon2/pwm1";
+
+        let mut profile = Profile::default_profile();
+        profile.fan_control_mode = FanControlMode::Software;
+        profile.fan_curves.retain(|fan_id, _| fan_id == "fan1");
+
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        let written = fixture.read(pwm);
+
+        // A marker shows whether the next ticks write again
+        fixture.write(pwm, "marker");
+        for _ in 0..3 {
+            daemon
+                .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+                .unwrap();
+        }
+        assert_eq!(fixture.read(pwm), "marker");
+
+        // A new target is written right away
+        daemon
+            .apply_fan_curves_for_temps(&profile, 85.0, None, &[])
+            .unwrap();
+        assert_ne!(fixture.read(pwm), "marker");
+
+        // The same target is refreshed after a while
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        fixture.write(pwm, "marker");
+        let long_ago = Instant::now().checked_sub(FORCED_REFRESH).unwrap();
+        daemon
+            .written_at
+            .lock()
+            .unwrap()
+            .insert("fan1".to_string(), long_ago);
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        assert_eq!(fixture.read(pwm), written);
+
+        // Or right away when forced
+        fixture.write(pwm, "marker");
+        daemon.force_refresh();
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        assert_eq!(fixture.read(pwm), written);
+
+        // Or after a profile wrote its own speed, the marker stands in
+        // for one that differs
+        daemon
+            .hardware_controller
+            .apply_partial(&profile, ApplyMask::FANS)
+            .unwrap();
+        fixture.write(pwm, "marker");
+        daemon
+            .apply_fan_curves_for_temps(&profile, 70.0, None, &[])
+            .unwrap();
+        assert_eq!(fixture.read(pwm), written);
+    }
+
+    #[test]
+    fn test_hwmon_temp_source() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+        let targets = daemon.last_targets();
+        let sensors = fixture.monitor().available_temp_sensors();
+
+        let mut profile = Profile::default_profile();
+        profile.fan_curves.retain(|fan_id, _| fan_id == "fan1");
+        let curve = profile.fan_curves.get_mut("fan1").unwrap();
+        curve.temp_source = TempSource::Hwmon {
+            chip: "k10temp".to_string(),
+            label: "Tdie".to_string(),
+        };
+        let curve = curve.clone();
+
+        // The fixture's Tdie reads 61.5°C, the CPU temperature is ignored
+        daemon
+            .apply_fan_curves_for_temps(&profile, 30.0, None, &sensors)
+            .unwrap();
+        assert_eq!(
+            targets.lock().unwrap()["fan1"],
+            curve.calculate_fan_speed(61.5)
+        );
+
+        // A vanished sensor falls back to the hottest known temperature
+        daemon
+            .apply_fan_curves_for_temps(&profile, 30.0, Some(80.0), &[])
+            .unwrap();
+        assert_eq!(
+            targets.lock().unwrap()["fan1"],
+            curve.calculate_fan_speed(80.0)
+        );
+        assert!(daemon.missing_sensors.lock().unwrap().contains("fan1"));
+
+        daemon
+            .apply_fan_curves_for_temps(&profile, 30.0, None, &sensors)
+            .unwrap();
+        assert!(daemon.missing_sensors.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_manual_override() {
+        let fixture = SysfsFixture::new();
+        // A second PWM fan next to the fixture's fan1
+        fixture.write("/sys/class/hwmon/hwmon2/pwm2", "0\n");
+        fixture.write("/sys/class/hwmon/hwmon2/pwm2_enable", "2\n");
+        let daemon = daemon(&fixture);
+        let targets = daemon.last_targets();
+
+        let mut profile = Profile::default_profile();
+        profile.fan_control_mode = FanControlMode::Software;
+        let curve = profile.fan_curves["fan1"].clone();
+
+        daemon.set_manual_override("fan2", Some(100)).unwrap();
+        assert_eq!(fixture.read("/sys/class/hwmon/hwmon2/pwm2"), "255");
+
+        // fan2 holds while fan1 tracks the curve
+        for temp in [50.0, 70.0, 85.0] {
+            daemon
+                .apply_fan_curves_for_temps(&profile, temp, Some(temp), &[])
+                .unwrap();
+            let targets = targets.lock().unwrap();
+            assert_eq!(targets["fan1"], curve.calculate_fan_speed(temp));
+            assert_eq!(targets["fan2"], 100);
+        }
+        assert!(daemon.set_manual_override("fan2", Some(101)).is_err());
+
+        // Released, fan2 follows its curve again
+        daemon.set_manual_override("fan2", None).unwrap();
+        assert_eq!(fixture.read("/sys/class/hwmon/hwmon2/pwm2_enable"), "2");
+        daemon
+            .apply_fan_curves_for_temps(&profile, 50.0, Some(50.0), &[])
+            .unwrap();
+        assert_eq!(
+            targets.lock().unwrap()["fan2"],
+            curve.calculate_fan_speed(50.0)
+        );
+
+        // Stopping drops the overrides
+        daemon.set_manual_override("fan1", Some(0)).unwrap();
+        daemon.running.store(true, Ordering::Relaxed);
+        daemon.stop();
+        assert!(daemon.manual_overrides().is_empty());
+        assert!(targets.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fan_stop() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+        let pwm = || fixture.read("/sys/class/hwmon/hwmon2/pwm1");
+
+        let mut profile = Profile::default_profile();
+        profile.fan_control_mode = FanControlMode::Software;
+        profile.fan_curves.retain(|fan_id, _| fan_id == "fan1");
+        let curve = profile.fan_curves.get_mut("fan1").unwrap();
+        curve.points[0].speed = 0;
+
+        // Idle: the fan spins slowly
+        daemon
+            .apply_fan_curves_for_temps(&profile, 30.0, None, &[])
+            .unwrap();
+        let min_pwm = pwm_value(MIN_SPINNING_SPEED, DEFAULT_PWM_MAX);
+        assert_eq!(pwm(), min_pwm.to_string());
+
+        profile.fan_curves.get_mut("fan1").unwrap().allow_fan_stop = true;
+        daemon
+            .apply_fan_curves_for_temps(&profile, 30.0, None, &[])
+            .unwrap();
+        assert_eq!(pwm(), "0");
+    }
+
+    #[test]
+    fn test_failed_fan_has_no_target() {
+        let fixture = SysfsFixture::new();
+        let daemon = daemon(&fixture);
+
+        // The fixture has no PWM interface for fan2
+        let profile = Profile::default_profile();
+
+        assert!(daemon
+            .apply_fan_curves_for_temps(&profile, 50.0, Some(40.0), &[])
+            .is_err());
+        let targets = daemon.last_targets();
+        let targets = targets.lock().unwrap();
+        assert!(targets.contains_key("fan1"));
+        assert!(!targets.contains_key("fan2"));
+    }
+}
@@ -0,0 +1,645 @@
+// src/fand.rs
+//! Privileged fan daemon (`tailor-fand`) and its client.
+//!
+//! The daemon runs as root with the [`FanDaemon`] and the
+//! [`HardwareController`], so the GUI doesn't have to. The GUI sends it the
+//! profile to apply, the writes it makes between profiles (keyboard, charge
+//! thresholds, GPU power cap) and asks it for the fan state over a Unix
+//! socket. Every connection carries one request and one response, each a
+//! line of JSON.
+//!
+//! Only root and members of the [`SOCKET_GROUP`] can connect. Profiles are
+//! validated and checked against the default [`FanSafetyPolicy`] before
+//! anything is written. The color temperature is left to the GUI, it needs
+//! the user's desktop session.
+
+use crate::fan_daemon::FanDaemon;
+use crate::hardware_control::{ApplyMask, FanStopProbe, HardwareController};
+use crate::hardware_monitor::HardwareMonitor;
+use crate::profile_controller::DriftWatcher;
+use crate::profile_system::{FanSafetyPolicy, Profile, ProfileManager, RGBColor};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Socket the daemon listens on, in the runtime directory systemd creates
+/// for the service
+pub const SOCKET_PATH: &str = "/run/tailor-fand/tailor-fand.sock";
+
+/// Group allowed to use the socket, see data/tailor-fand.sysusers
+pub const SOCKET_GROUP: &str = "tailor";
+
+/// Everything the daemon writes except the color temperature, which the
+/// GUI sets in the user's session
+pub const DAEMON_MASK: ApplyMask = ApplyMask::all().difference(ApplyMask::COLOR_TEMPERATURE);
+
+/// Profiles kept in [`STATE_DIR`]. Only the last one is restored, the
+/// oldest ones make room for new names.
+const MAX_STORED_PROFILES: usize = 8;
+
+/// Seconds between checks whether the CPU settings drifted, the default
+/// of the GUI's setting
+const DRIFT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where the daemon keeps the last profile, to restore it after a restart
+pub const STATE_DIR: &str = "/var/lib/tailor-fand";
+
+/// A client that doesn't send or read its line in time is dropped, so it
+/// can't block the others
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Far above any profile, just a bound for garbage
+const MAX_REQUEST_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FandRequest {
+    /// Apply the profile and follow its software fan curves
+    SetProfile(Box<Profile>),
+    GetFanState,
+    /// Write a color without changing the profile, e.g. for an effect
+    SetKeyboardColor(RGBColor),
+    /// Apply the keyboard settings of the profile for the power source
+    ApplyKeyboard {
+        on_battery: bool,
+    },
+    /// Switch the keyboard backlight off until the next `ApplyKeyboard`
+    TurnOffKeyboard,
+    SetChargeThresholds {
+        start: u8,
+        end: u8,
+    },
+    SetGpuPowerCap {
+        card: String,
+        microwatts: u64,
+    },
+    /// Set the energy performance preference until the next profile
+    SetEnergyPerformancePreference {
+        epp: String,
+    },
+    /// Turn amd-pstate preferred core ranking on or off
+    SetAmdPrefcore {
+        enabled: bool,
+    },
+    /// Check which fans stop at 0%, with the fan daemon paused
+    ProbeFanStop {
+        settle: Duration,
+    },
+    /// Run the software fan curves, or hand the fans to the firmware
+    SetFanDaemon {
+        enabled: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FandResponse {
+    Applied,
+    FanState(FanState),
+    /// Power cap in microwatts the driver got
+    GpuPowerCap(u64),
+    FanStop(Vec<FanStopProbe>),
+    Error(String),
+}
+
+/// What the daemon does with the fans
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanState {
+    /// Name of the applied profile
+    pub profile: String,
+    /// Speeds (0-100%) the daemon set last, by fan id. Empty while the
+    /// firmware controls the fans.
+    pub targets: HashMap<String, u8>,
+}
+
+/// Server side, owns the hardware
+pub struct FandServer {
+    profile_manager: Arc<Mutex<ProfileManager>>,
+    hardware_controller: Arc<HardwareController>,
+    fan_daemon: FanDaemon,
+    drift_watcher: Mutex<Option<DriftWatcher>>,
+}
+
+impl FandServer {
+    /// Server keeping its profile in [`STATE_DIR`]
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_parts(
+            ProfileManager::with_config_dir(PathBuf::from(STATE_DIR))?,
+            HardwareController::new()?,
+            HardwareMonitor::new()?,
+        ))
+    }
+
+    pub fn with_parts(
+        profile_manager: ProfileManager,
+        hardware_controller: HardwareController,
+        hardware_monitor: HardwareMonitor,
+    ) -> Self {
+        let profile_manager = Arc::new(Mutex::new(profile_manager));
+        let hardware_controller = Arc::new(hardware_controller);
+        let fan_daemon = FanDaemon::new(
+            Arc::clone(&profile_manager),
+            Arc::clone(&hardware_controller),
+            Arc::new(Mutex::new(hardware_monitor)),
+        );
+        FandServer {
+            profile_manager,
+            hardware_controller,
+            fan_daemon,
+            drift_watcher: Mutex::new(None),
+        }
+    }
+
+    /// Apply the last profile again and start the fan daemon and the
+    /// drift checks
+    pub fn start(&self) {
+        let mut mgr = self.profile_manager.lock().unwrap();
+        let last = (0..mgr.get_profiles().len())
+            .filter_map(|index| Some((mgr.last_applied(&mgr.get_profiles()[index].name)?, index)))
+            .max()
+            .map(|(_, index)| index);
+        if let Some(index) = last {
+            // In range, it was just looked up
+            let _ = mgr.set_active_profile(index);
+        }
+        let profile = mgr.get_active_profile().clone();
+        drop(mgr);
+        if let Err(e) = self
+            .hardware_controller
+            .apply_partial(&profile, DAEMON_MASK)
+        {
+            eprintln!(
+                "Warning: Failed to restore profile '{}': {}",
+                profile.name, e
+            );
+        }
+        if self.hardware_controller.fan_control_supported() {
+            self.fan_daemon.start();
+        } else {
+            eprintln!("Warning: No writable fan control found, software fan curves are disabled");
+        }
+        *self.drift_watcher.lock().unwrap() = Some(DriftWatcher::spawn(
+            Arc::clone(&self.hardware_controller),
+            DRIFT_INTERVAL,
+            None,
+        ));
+    }
+
+    /// Hand the fans back to the firmware
+    pub fn shutdown(&self) {
+        self.drift_watcher.lock().unwrap().take();
+        self.fan_daemon.stop();
+        if let Err(e) = self.hardware_controller.restore_auto_fan_control() {
+            eprintln!("Warning: Failed to restore automatic fan control: {}", e);
+        }
+    }
+
+    /// Answer clients one after the other, until the listener fails
+    pub fn serve(&self, listener: UnixListener) -> Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream.context("Failed to accept a client")?;
+            if let Err(e) = self.handle_connection(&stream) {
+                eprintln!("Warning: Dropped a client: {:#}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: &UnixStream) -> Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        let mut line = String::new();
+        BufReader::new(stream.take(MAX_REQUEST_BYTES))
+            .read_line(&mut line)
+            .context("Failed to read the request")?;
+        // Clients connect without a request to see whether the daemon runs
+        if line.is_empty() {
+            return Ok(());
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => self.handle(request),
+            Err(e) => FandResponse::Error(format!("Invalid request: {}", e)),
+        };
+        write_line(stream, &response).context("Failed to send the response")
+    }
+
+    pub fn handle(&self, request: FandRequest) -> FandResponse {
+        let applied = |result: Result<()>| match result {
+            Ok(()) => FandResponse::Applied,
+            Err(e) => FandResponse::Error(format!("{:#}", e)),
+        };
+        let hardware = &self.hardware_controller;
+        match request {
+            FandRequest::SetProfile(profile) => applied(self.set_profile(*profile)),
+            FandRequest::SetKeyboardColor(color) => {
+                applied(hardware.update_keyboard_color(&color).map_err(Into::into))
+            }
+            FandRequest::ApplyKeyboard { on_battery } => {
+                let profile = self.active_profile();
+                applied(
+                    hardware
+                        .apply_keyboard_settings(&profile, on_battery)
+                        .map_err(Into::into),
+                )
+            }
+            FandRequest::TurnOffKeyboard => {
+                applied(hardware.turn_off_keyboard().map_err(Into::into))
+            }
+            FandRequest::SetChargeThresholds { start, end } => applied(
+                hardware
+                    .set_charge_thresholds(start, end)
+                    .map_err(Into::into),
+            ),
+            FandRequest::SetGpuPowerCap { card, microwatts } => {
+                match hardware.set_gpu_power_cap(&card, microwatts) {
+                    Ok(microwatts) => FandResponse::GpuPowerCap(microwatts),
+                    Err(e) => FandResponse::Error(format!("{:#}", e)),
+                }
+            }
+            FandRequest::SetEnergyPerformancePreference { epp } => {
+                applied(hardware.set_energy_performance_preference(&epp))
+            }
+            FandRequest::SetAmdPrefcore { enabled } => {
+                applied(hardware.set_amd_prefcore(enabled).map_err(Into::into))
+            }
+            FandRequest::ProbeFanStop { settle } => {
+                FandResponse::FanStop(self.fan_daemon.paused(|| hardware.probe_fan_stop(settle)))
+            }
+            FandRequest::SetFanDaemon { enabled: true } => {
+                if !hardware.fan_control_supported() {
+                    return FandResponse::Error("No writable fan control found".to_string());
+                }
+                self.fan_daemon.start();
+                FandResponse::Applied
+            }
+            FandRequest::SetFanDaemon { enabled: false } => {
+                self.fan_daemon.stop();
+                applied(hardware.restore_auto_fan_control().map_err(Into::into))
+            }
+            FandRequest::GetFanState => FandResponse::FanState(FanState {
+                profile: self
+                    .profile_manager
+                    .lock()
+                    .unwrap()
+                    .get_active_profile()
+                    .name
+                    .clone(),
+                targets: self.fan_daemon.last_targets().lock().unwrap().clone(),
+            }),
+        }
+    }
+
+    fn active_profile(&self) -> Profile {
+        self.profile_manager
+            .lock()
+            .unwrap()
+            .get_active_profile()
+            .clone()
+    }
+
+    fn set_profile(&self, mut profile: Profile) -> Result<()> {
+        // Only a copy of the GUI's profile, which enforces the lock
+        profile.locked = false;
+        // The GUI asks before saving such curves, any user of the socket
+        // could skip that
+        let policy = FanSafetyPolicy::default();
+        for (fan_id, curve) in &profile.fan_curves {
+            if curve.is_unsafe(&policy) {
+                anyhow::bail!(
+                    "The fan curve of {} runs slower than {}% at {}°C or more",
+                    fan_id,
+                    policy.min_speed,
+                    policy.danger_temp
+                );
+            }
+        }
+
+        let mut mgr = self.profile_manager.lock().unwrap();
+        let name = profile.name.clone();
+        match mgr.get_profiles().iter().position(|p| p.name == name) {
+            Some(index) => mgr.update_profile(index, profile)?,
+            None => {
+                mgr.add_profile(profile)?;
+                while mgr.get_profiles().len() > MAX_STORED_PROFILES {
+                    let oldest = mgr
+                        .get_profiles()
+                        .iter()
+                        .position(|p| !p.is_default && p.name != name)
+                        .context("No profile to make room for a new one")?;
+                    mgr.delete_profile(oldest)?;
+                }
+            }
+        }
+        let index = mgr
+            .get_profiles()
+            .iter()
+            .position(|p| p.name == name)
+            .context(format!("Profile '{}' not found", name))?;
+        mgr.set_active_profile(index)?;
+        let profile = mgr.get_active_profile().clone();
+        drop(mgr);
+
+        self.hardware_controller
+            .apply_partial(&profile, DAEMON_MASK)?;
+        self.fan_daemon.force_refresh();
+        self.profile_manager.lock().unwrap().mark_applied(&name)?;
+        Ok(())
+    }
+}
+
+/// Listen on `path`, replacing the socket of an earlier run. Members of
+/// `group` can connect, only root if it doesn't exist.
+pub fn bind(path: &Path, group: &str) -> Result<UnixListener> {
+    // Started without systemd, which otherwise creates it
+    if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o755)
+            .create(dir)
+            .context(format!("Failed to create {}", dir.display()))?;
+    }
+    if path.exists() {
+        fs::remove_file(path).context(format!("Failed to remove {}", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).context(format!("Failed to listen on {}", path.display()))?;
+
+    let mode = match group_id(group) {
+        Some(gid) => {
+            std::os::unix::fs::chown(path, None, Some(gid)).context(format!(
+                "Failed to hand {} to group {}",
+                path.display(),
+                group
+            ))?;
+            0o660
+        }
+        None => {
+            eprintln!(
+                "Warning: Group '{}' doesn't exist, only root can use {}",
+                group,
+                path.display()
+            );
+            0o600
+        }
+    };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).context(format!(
+        "Failed to set the permissions of {}",
+        path.display()
+    ))?;
+    Ok(listener)
+}
+
+fn group_id(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    // SAFETY: `name` is a valid C string, the returned entry is only read
+    // before the next getgr* call
+    let group = unsafe { libc::getgrnam(name.as_ptr()) };
+    // SAFETY: checked for null, points to a valid group entry
+    (!group.is_null()).then(|| unsafe { (*group).gr_gid })
+}
+
+/// Client side, used by the GUI when it doesn't run as root
+#[derive(Debug, Clone)]
+pub struct FandClient {
+    socket: PathBuf,
+}
+
+impl FandClient {
+    /// Client of the daemon listening on `socket`, whether or not it runs
+    pub fn new(socket: &Path) -> Self {
+        FandClient {
+            socket: socket.to_path_buf(),
+        }
+    }
+
+    /// The client if the daemon runs right now. Checked on every use, so
+    /// a daemon started after the GUI is picked up.
+    pub fn running(&self) -> Option<&Self> {
+        UnixStream::connect(&self.socket).ok().map(|_| self)
+    }
+
+    pub fn set_profile(&self, profile: &Profile) -> Result<()> {
+        self.apply(&FandRequest::SetProfile(Box::new(profile.clone())))
+    }
+
+    pub fn set_keyboard_color(&self, color: &RGBColor) -> Result<()> {
+        self.apply(&FandRequest::SetKeyboardColor(color.clone()))
+    }
+
+    pub fn apply_keyboard(&self, on_battery: bool) -> Result<()> {
+        self.apply(&FandRequest::ApplyKeyboard { on_battery })
+    }
+
+    pub fn turn_off_keyboard(&self) -> Result<()> {
+        self.apply(&FandRequest::TurnOffKeyboard)
+    }
+
+    pub fn set_charge_thresholds(&self, start: u8, end: u8) -> Result<()> {
+        self.apply(&FandRequest::SetChargeThresholds { start, end })
+    }
+
+    /// Returns the limit the driver got
+    pub fn set_gpu_power_cap(&self, card: &str, microwatts: u64) -> Result<u64> {
+        let request = FandRequest::SetGpuPowerCap {
+            card: card.to_string(),
+            microwatts,
+        };
+        match self.request(&request)? {
+            FandResponse::GpuPowerCap(microwatts) => Ok(microwatts),
+            FandResponse::Error(e) => anyhow::bail!("tailor-fand: {}", e),
+            other => anyhow::bail!("Unexpected response of tailor-fand: {:?}", other),
+        }
+    }
+
+    pub fn set_energy_performance_preference(&self, epp: &str) -> Result<()> {
+        self.apply(&FandRequest::SetEnergyPerformancePreference {
+            epp: epp.to_string(),
+        })
+    }
+
+    pub fn set_amd_prefcore(&self, enabled: bool) -> Result<()> {
+        self.apply(&FandRequest::SetAmdPrefcore { enabled })
+    }
+
+    /// Start or stop the daemon's fan curves, see
+    /// [`crate::app_config::AppConfig::fan_daemon_enabled`]
+    pub fn set_fan_daemon(&self, enabled: bool) -> Result<()> {
+        self.apply(&FandRequest::SetFanDaemon { enabled })
+    }
+
+    /// See [`HardwareController::probe_fan_stop`]
+    pub fn probe_fan_stop(&self, settle: Duration) -> Result<Vec<FanStopProbe>> {
+        let request = FandRequest::ProbeFanStop { settle };
+        match self.request_within(&request, settle + CLIENT_TIMEOUT)? {
+            FandResponse::FanStop(probes) => Ok(probes),
+            FandResponse::Error(e) => anyhow::bail!("tailor-fand: {}", e),
+            other => anyhow::bail!("Unexpected response of tailor-fand: {:?}", other),
+        }
+    }
+
+    fn apply(&self, request: &FandRequest) -> Result<()> {
+        match self.request(request)? {
+            FandResponse::Applied => Ok(()),
+            FandResponse::Error(e) => anyhow::bail!("tailor-fand: {}", e),
+            other => anyhow::bail!("Unexpected response of tailor-fand: {:?}", other),
+        }
+    }
+
+    pub fn fan_state(&self) -> Result<FanState> {
+        match self.request(&FandRequest::GetFanState)? {
+            FandResponse::FanState(state) => Ok(state),
+            FandResponse::Error(e) => anyhow::bail!("tailor-fand: {}", e),
+            other => anyhow::bail!("Unexpected response of tailor-fand: {:?}", other),
+        }
+    }
+
+    fn request(&self, request: &FandRequest) -> Result<FandResponse> {
+        self.request_within(request, CLIENT_TIMEOUT)
+    }
+
+    fn request_within(&self, request: &FandRequest, timeout: Duration) -> Result<FandResponse> {
+        let stream = UnixStream::connect(&self.socket)
+            .context(format!("Failed to connect to {}", self.socket.display()))?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        write_line(&stream, request).context("Failed to send the request to tailor-fand")?;
+
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .context("Failed to read the response of tailor-fand")?;
+        serde_json::from_str(&line).context("Invalid response of tailor-fand")
+    }
+}
+
+fn write_line<T: Serialize>(mut stream: &UnixStream, value: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_system::FanControlMode;
+    use std::thread;
+
+    #[test]
+    fn test_set_profile_and_fan_state() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let state_dir = tempfile::TempDir::new().unwrap();
+        let server = FandServer::with_parts(
+            ProfileManager::with_config_dir(state_dir.path().join("state")).unwrap(),
+            fixture.controller(),
+            fixture.monitor(),
+        );
+        // The runtime directory is created if it's missing
+        let socket = state_dir.path().join("run/fand.sock");
+        // Without the group only the owner can connect
+        let listener = bind(&socket, "tailor-fand-test-missing").unwrap();
+        assert_eq!(
+            fs::metadata(&socket).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        thread::spawn(move || server.serve(listener));
+
+        let client = FandClient::new(&socket);
+        assert!(client.running().is_some());
+        assert_eq!(client.fan_state().unwrap().profile, "Default");
+
+        let mut profile = Profile::default_profile();
+        profile.name = "Quiet".to_string();
+        profile.is_default = false;
+        profile.locked = true;
+        profile.fan_control_mode = FanControlMode::Software;
+        client.set_profile(&profile).unwrap();
+        assert_eq!(client.fan_state().unwrap().profile, "Quiet");
+        // Sending it again updates the stored copy
+        client.set_profile(&profile).unwrap();
+
+        // The profile is validated before anything is applied
+        profile.keyboard_backlight.brightness = 150;
+        let err = client.set_profile(&profile).unwrap_err();
+        assert!(format!("{:#}", err).contains("Keyboard brightness"));
+        profile.keyboard_backlight.brightness = 50;
+
+        // So is a curve that lets the fans idle when it's hot
+        let mut curve = profile.fan_curves["fan1"].clone();
+        for point in &mut curve.points {
+            point.speed = 0;
+        }
+        curve.allow_fan_stop = true;
+        let mut unsafe_profile = profile.clone();
+        unsafe_profile.fan_curves.insert("fan1".to_string(), curve);
+        let err = client.set_profile(&unsafe_profile).unwrap_err();
+        assert!(format!("{:#}", err).contains("fan1"));
+
+        // Only the last few profiles are kept
+        for i in 0..MAX_STORED_PROFILES + 2 {
+            profile.name = format!("Profile {}", i);
+            client.set_profile(&profile).unwrap();
+        }
+        let state = client.fan_state().unwrap();
+        assert_eq!(
+            state.profile,
+            format!("Profile {}", MAX_STORED_PROFILES + 1)
+        );
+        let stored = ProfileManager::with_config_dir(state_dir.path().join("state")).unwrap();
+        assert_eq!(stored.get_profiles().len(), MAX_STORED_PROFILES);
+        assert!(stored.get_profiles().iter().any(|p| p.is_default));
+
+        client
+            .set_keyboard_color(&RGBColor { r: 255, g: 0, b: 0 })
+            .unwrap();
+        assert_eq!(
+            fixture
+                .read("/sys/class/leds/rgb:kbd_backlight/multi_intensity")
+                .trim(),
+            "255 0 0"
+        );
+
+        client.set_energy_performance_preference("power").unwrap();
+        assert_eq!(
+            fixture
+                .read("/sys/devices/system/cpu/cpu1/cpufreq/energy_performance_preference")
+                .trim(),
+            "power"
+        );
+        assert!(client.set_energy_performance_preference("turbo").is_err());
+        client.set_amd_prefcore(false).unwrap();
+        assert_eq!(
+            fixture
+                .read("/sys/devices/system/cpu/amd_pstate/prefcore")
+                .trim(),
+            "disabled"
+        );
+
+        // Turning the fan curves off hands the fans to the firmware
+        client.set_fan_daemon(true).unwrap();
+        client.set_fan_daemon(false).unwrap();
+        assert_eq!(
+            fixture.read("/sys/class/hwmon/hwmon2/pwm1_enable").trim(),
+            "2"
+        );
+
+        // Garbage gets an error instead of dropping the connection
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        stream.write_all(b"{\"Reboot\":null}\n").unwrap();
+        let mut response = String::new();
+        BufReader::new(&stream).read_line(&mut response).unwrap();
+        let response: FandResponse = serde_json::from_str(&response).unwrap();
+        assert!(matches!(response, FandResponse::Error(_)));
+
+        assert!(FandClient::new(&state_dir.path().join("missing.sock"))
+            .running()
+            .is_none());
+    }
+}
@@ -0,0 +1,195 @@
+//! Desktop-wide shortcut through the `org.freedesktop.portal.GlobalShortcuts`
+//! portal, so profiles can be switched without the window.
+//!
+//! Desktops without the portal (or without a portal backend implementing
+//! it) can't register the shortcut. Registering fails then and the caller
+//! logs it, nothing else changes. The trigger is only a preference, the
+//! desktop may ask the user to confirm or change it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use futures::channel::oneshot;
+use gtk::gio;
+use gtk::glib::{self, ToVariant, Variant, VariantDict, VariantTy};
+use relm4::gtk;
+
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+
+/// Id of the shortcut applying the next profile
+const NEXT_PROFILE_ID: &str = "next-profile";
+
+/// Open portal session with the bound shortcut. Dropping it closes the
+/// session, which releases the shortcut.
+#[derive(Debug)]
+pub struct GlobalShortcut {
+    connection: gio::DBusConnection,
+    session: String,
+    activated: Option<gio::SignalSubscriptionId>,
+}
+
+impl Drop for GlobalShortcut {
+    fn drop(&mut self) {
+        if let Some(id) = self.activated.take() {
+            self.connection.signal_unsubscribe(id);
+        }
+        self.connection.call(
+            Some(PORTAL_BUS_NAME),
+            &self.session,
+            "org.freedesktop.portal.Session",
+            "Close",
+            None,
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+            |_| {},
+        );
+    }
+}
+
+/// Bind the quick switch shortcut with `trigger` in the XDG shortcuts format,
+/// e.g. "CTRL+ALT+P". `on_activate` runs on the main thread whenever it's
+/// pressed.
+pub async fn bind_next_profile<F>(trigger: &str, on_activate: F) -> Result<GlobalShortcut>
+where
+    F: Fn() + 'static,
+{
+    let connection = gio::bus_get_future(gio::BusType::Session)
+        .await
+        .context("Failed to connect to the session bus")?;
+
+    let options = VariantDict::new(None);
+    options.insert("session_handle_token", "tailor");
+    let results = portal_request(&connection, "CreateSession", |token| {
+        options.insert("handle_token", token);
+        Variant::tuple_from_iter([options.end()])
+    })
+    .await?;
+    let session = results
+        .get("session_handle")
+        .and_then(|handle| handle.str().map(str::to_owned))
+        .context("Portal didn't return a session handle")?;
+
+    let shortcut = VariantDict::new(None);
+    shortcut.insert("description", "Switch to the next profile");
+    shortcut.insert("preferred_trigger", trigger);
+    let shortcuts = Variant::array_from_iter_with_type(
+        VariantTy::new("(sa{sv})").unwrap(),
+        [Variant::tuple_from_iter([
+            NEXT_PROFILE_ID.to_variant(),
+            shortcut.end(),
+        ])],
+    );
+    let session_path = glib::variant::ObjectPath::try_from(session.clone())
+        .context("Portal returned an invalid session handle")?;
+
+    let activated = {
+        let session = session.clone();
+        connection.signal_subscribe(
+            Some(PORTAL_BUS_NAME),
+            Some(SHORTCUTS_INTERFACE),
+            Some("Activated"),
+            Some(PORTAL_PATH),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |_, _, _, _, _, parameters| {
+                // (session_handle, shortcut_id, timestamp, options)
+                if parameters.child_value(0).str() == Some(session.as_str())
+                    && parameters.child_value(1).str() == Some(NEXT_PROFILE_ID)
+                {
+                    on_activate();
+                }
+            },
+        )
+    };
+    // From here on the session is closed again on errors
+    let global_shortcut = GlobalShortcut {
+        connection: connection.clone(),
+        session,
+        activated: Some(activated),
+    };
+
+    portal_request(&connection, "BindShortcuts", |token| {
+        let options = VariantDict::new(None);
+        options.insert("handle_token", token);
+        Variant::tuple_from_iter([
+            session_path.to_variant(),
+            shortcuts,
+            // No parent window, the shortcut belongs to the app
+            "".to_variant(),
+            options.end(),
+        ])
+    })
+    .await?;
+
+    tracing::info!("Bound the quick switch shortcut, preferring {trigger}");
+    Ok(global_shortcut)
+}
+
+/// Call `method` of the shortcuts portal and wait for the response of its
+/// request object. `args` gets the handle token to put into the options.
+async fn portal_request(
+    connection: &gio::DBusConnection,
+    method: &str,
+    args: impl FnOnce(&str) -> Variant,
+) -> Result<HashMap<String, Variant>> {
+    // The request path is known up front, so the response can't be missed
+    // by subscribing too late
+    let token = format!("tailor_{}", glib::random_int());
+    let sender = connection
+        .unique_name()
+        .context("Session bus connection has no name")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request_path = format!("{PORTAL_PATH}/request/{sender}/{token}");
+
+    let (response_tx, response_rx) = oneshot::channel();
+    let response_tx = Rc::new(RefCell::new(Some(response_tx)));
+    let subscription = connection.signal_subscribe(
+        Some(PORTAL_BUS_NAME),
+        Some("org.freedesktop.portal.Request"),
+        Some("Response"),
+        Some(&request_path),
+        None,
+        gio::DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            if let Some(tx) = response_tx.borrow_mut().take() {
+                let _ = tx.send(parameters.get::<(u32, HashMap<String, Variant>)>());
+            }
+        },
+    );
+
+    let call = connection
+        .call_future(
+            Some(PORTAL_BUS_NAME),
+            PORTAL_PATH,
+            SHORTCUTS_INTERFACE,
+            method,
+            Some(&args(&token)),
+            Some(VariantTy::new("(o)").unwrap()),
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await;
+    let response = match call {
+        Ok(_) => response_rx.await.ok().flatten(),
+        Err(err) => {
+            connection.signal_unsubscribe(subscription);
+            return Err(err).context(format!("Global shortcuts portal: {method} failed"));
+        }
+    };
+    connection.signal_unsubscribe(subscription);
+
+    match response {
+        Some((0, results)) => Ok(results),
+        // 1 means the user dismissed the dialog of the desktop
+        Some((1, _)) => anyhow::bail!("{method} was cancelled"),
+        Some((code, _)) => anyhow::bail!("{method} failed with response {code}"),
+        None => anyhow::bail!("Invalid response to {method}"),
+    }
+}
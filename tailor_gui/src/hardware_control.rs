@@ -1,69 +1,652 @@
 // src/hardware_control.rs
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use crate::profile_system::{Profile, FanCurve, CpuSettings, CpuPerformanceProfile};
-use crate::keyboard_control::KeyboardController;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::app_config::AppConfig;
+use crate::error::{self, HardwareResult};
+use crate::color_temperature::ColorTemperature;
+use crate::profile_system::{
+    Profile, FanCurve, FanControlMode, CpuSettings, CpuPerformanceProfile, KeyboardMode,
+    RGBColor, ScreenSettings,
+};
+use crate::keyboard_control::{perceptual_percentage, perceptual_raw, KeyboardController};
+use crate::hardware_monitor::{
+    gpu_hwmon_dirs, parse_gpu_fan_id, GpuMode, GpuPowerCap, HardwareMonitor, PendingGpuSwitch,
+};
+
+/// Total duration of a smooth brightness change
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// Number of brightness writes during a fade
+const FADE_STEPS: u32 = 10;
+
+/// Backlights of built-in panels, in the order they are preferred
+const PANEL_BACKLIGHTS: [&str; 3] = ["intel_backlight", "amdgpu_bl0", "acpi_video0"];
+
+/// PWM value of full speed unless the driver reports another one in
+/// `pwmN_max`, the hwmon ABI documents 0-255
+pub const DEFAULT_PWM_MAX: u32 = 255;
+
+/// PCIe Active State Power Management policy of the pcie_aspm module
+const ASPM_POLICY_PATH: &str = "/sys/module/pcie_aspm/parameters/policy";
+
+/// Choices of an attribute that lists them with the active one in brackets,
+/// e.g. "default performance [powersave] powersupersave". A single value
+/// without brackets counts as the active one.
+pub fn parse_bracketed_choice(content: &str) -> (Vec<String>, Option<String>) {
+    let mut current = None;
+    let choices: Vec<String> = content
+        .split_whitespace()
+        .map(|choice| match choice.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+            Some(active) => {
+                current = Some(active.to_string());
+                active.to_string()
+            }
+            None => choice.to_string(),
+        })
+        .collect();
+    if current.is_none() && choices.len() == 1 {
+        current = choices.first().cloned();
+    }
+    (choices, current)
+}
+
+/// PWM value for `percent` (0-100) of a fan whose full speed is `max`
+pub fn pwm_value(percent: u8, max: u32) -> u32 {
+    // Integer math, so 50% of 255 rounds up like it should
+    (percent.min(100) as u32 * max + 50) / 100
+}
+
+/// Percent (0-100) of a PWM `value` of a fan whose full speed is `max`,
+/// the reverse of [`pwm_value`]
+pub fn pwm_percent(value: u32, max: u32) -> u8 {
+    ((value.min(max) * 100 + max / 2) / max) as u8
+}
+
+/// Full speed value of the PWM attribute `pwm_path`, from `pwmN_max` if
+/// the driver has it and [`DEFAULT_PWM_MAX`] otherwise
+pub fn read_pwm_max(pwm_path: &Path) -> u32 {
+    let mut max_path = pwm_path.as_os_str().to_owned();
+    max_path.push("_max");
+    fs::read_to_string(max_path)
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_PWM_MAX)
+}
+
+/// Result of writing 0% to a fan, see [`HardwareController::probe_fan_stop`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FanStopProbe {
+    pub fan_id: String,
+    /// Speed measured after the fan had time to spin down, `None` without
+    /// an RPM sensor
+    pub rpm_at_zero: Option<u32>,
+}
+
+impl FanStopProbe {
+    /// E.g. "fan1: stops at 0%" or "fan1: keeps spinning at 1800 RPM at 0%"
+    pub fn summary(&self) -> String {
+        match self.rpm_at_zero {
+            Some(0) => format!("{}: stops at 0%", self.fan_id),
+            Some(rpm) => format!("{}: keeps spinning at {} RPM at 0%", self.fan_id, rpm),
+            None => format!("{}: unknown, no RPM sensor", self.fan_id),
+        }
+    }
+}
+
+/// Steps a brightness value to its target on a short-lived thread. Starting
+/// a new fade or setting a value directly cancels the running one, so rapid
+/// profile switches don't stack fades.
+#[derive(Default)]
+struct BrightnessFade {
+    generation: Arc<AtomicU64>,
+}
+
+impl BrightnessFade {
+    /// Stop a running fade, returns the new generation
+    fn cancel(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+    
+    fn start<F>(&self, from: u8, to: u8, set: F)
+    where
+        F: Fn(u8) -> Result<()> + Send + 'static,
+    {
+        let generation = self.cancel();
+        let current = Arc::clone(&self.generation);
+        
+        thread::spawn(move || {
+            for step in 1..=FADE_STEPS {
+                if current.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+                
+                let delta = (to as i32 - from as i32) * step as i32 / FADE_STEPS as i32;
+                if let Err(e) = set((from as i32 + delta) as u8) {
+                    eprintln!("Warning: Brightness fade stopped: {}", e);
+                    return;
+                }
+                
+                if step < FADE_STEPS {
+                    thread::sleep(FADE_DURATION / FADE_STEPS);
+                }
+            }
+        });
+    }
+}
+
+bitflags::bitflags! {
+    /// Parts of a profile [`HardwareController::apply_partial`] writes
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ApplyMask: u8 {
+        /// Backlight color, brightness, effect and timeout
+        const KEYBOARD = 1 << 0;
+        /// Governor, frequency limits, boost, SMT, EPP and preferred cores
+        const CPU = 1 << 1;
+        const FANS = 1 << 2;
+        /// Screen brightness
+        const SCREEN = 1 << 3;
+        /// PCIe ASPM policy and GPU power cap
+        const POWER = 1 << 4;
+        /// Night light of the desktop session, so it needs to be written
+        /// from the user's session rather than by a system service
+        const COLOR_TEMPERATURE = 1 << 5;
+    }
+}
+
+/// One-click battery charge limits, see
+/// [`HardwareController::set_charge_thresholds`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargePreset {
+    Full,
+    Balanced,
+    Travel,
+}
+
+impl ChargePreset {
+    pub const ALL: [ChargePreset; 3] = [ChargePreset::Full, ChargePreset::Balanced, ChargePreset::Travel];
+    
+    pub fn label(self) -> &'static str {
+        match self {
+            ChargePreset::Full => "Full (100%)",
+            ChargePreset::Balanced => "Balanced (80%)",
+            ChargePreset::Travel => "Travel (60%)",
+        }
+    }
+    
+    /// Start and end threshold in percent
+    pub fn thresholds(self) -> (u8, u8) {
+        match self {
+            ChargePreset::Full => (95, 100),
+            ChargePreset::Balanced => (75, 80),
+            ChargePreset::Travel => (55, 60),
+        }
+    }
+}
+
+/// cpufreq drivers with an energy performance preference (EPP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EppDriver {
+    IntelPstate,
+    AmdPstate,
+}
+
+impl EppDriver {
+    /// Detect the driver from `scaling_driver`. amd-pstate reports itself as
+    /// "amd-pstate" or "amd-pstate-epp" depending on the mode.
+    pub fn from_scaling_driver(driver: &str) -> Option<Self> {
+        if driver == "intel_pstate" {
+            Some(EppDriver::IntelPstate)
+        } else if driver.starts_with("amd-pstate") {
+            Some(EppDriver::AmdPstate)
+        } else {
+            None
+        }
+    }
+    
+    /// Name for labeling the EPP control
+    pub fn label(self) -> &'static str {
+        match self {
+            EppDriver::IntelPstate => "Intel P-State",
+            EppDriver::AmdPstate => "AMD P-State",
+        }
+    }
+}
+
+/// Governor and EPP a [`CpuPerformanceProfile`] maps to with the running
+/// cpufreq driver. Drivers in active mode pick frequencies themselves and
+/// only offer `powersave` and `performance`, the EPP tells them how eagerly
+/// to clock up:
+///
+/// | `scaling_driver`                  | PowerSave         | Balanced                        | Performance |
+/// |-----------------------------------|-------------------|---------------------------------|-------------|
+/// | `intel_pstate` (active)           | powersave + power | powersave + balance_performance | performance |
+/// | `amd-pstate-epp` (active)         | powersave + power | powersave + balance_performance | performance |
+/// | `intel_cpufreq` (passive)         | powersave         | schedutil                       | performance |
+/// | `amd-pstate` (passive or guided)  | powersave         | schedutil                       | performance |
+/// | `acpi-cpufreq` and others         | powersave         | schedutil                       | performance |
+///
+/// Where `schedutil` isn't available Balanced falls back to `ondemand`. The
+/// `performance` governor fixes the EPP to performance, so it has none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GovernorChoice {
+    pub governor: &'static str,
+    pub epp: Option<&'static str>,
+}
+
+impl GovernorChoice {
+    /// Choice for `profile` with `scaling_driver`, `available` being the
+    /// driver's `scaling_available_governors` (empty if unknown)
+    pub fn for_driver(profile: &CpuPerformanceProfile, scaling_driver: &str, available: &[&str]) -> Self {
+        let active = matches!(scaling_driver, "intel_pstate" | "amd-pstate-epp");
+        let (governor, epp) = match (profile, active) {
+            (CpuPerformanceProfile::PowerSave, true) => ("powersave", Some("power")),
+            (CpuPerformanceProfile::PowerSave, false) => ("powersave", None),
+            (CpuPerformanceProfile::Balanced, true) => ("powersave", Some("balance_performance")),
+            (CpuPerformanceProfile::Balanced, false) => {
+                if available.is_empty() || available.contains(&"schedutil") || !available.contains(&"ondemand") {
+                    ("schedutil", None)
+                } else {
+                    ("ondemand", None)
+                }
+            }
+            (CpuPerformanceProfile::Performance, _) => ("performance", None),
+        };
+        GovernorChoice { governor, epp }
+    }
+}
+
+/// What the running cpufreq driver supports for EPP
+#[derive(Debug, Clone, PartialEq)]
+pub struct EppSupport {
+    pub driver: EppDriver,
+    pub available: Vec<String>,
+    pub current: Option<String>,
+    /// amd-pstate preferred core ranking, `None` if not supported
+    pub prefcore: Option<bool>,
+}
+
+/// Frequencies the userspace governor can pin the CPU to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedFrequencySupport {
+    /// From `scaling_available_frequencies`, empty if the driver takes any
+    /// frequency within the hardware range
+    pub available_mhz: Vec<u32>,
+    pub min_mhz: Option<u32>,
+    pub max_mhz: Option<u32>,
+}
+
+impl FixedFrequencySupport {
+    /// Check that `scaling_setspeed` accepts `mhz`
+    pub fn check(&self, mhz: u32) -> Result<()> {
+        if !self.available_mhz.is_empty() {
+            if !self.available_mhz.contains(&mhz) {
+                let available: Vec<String> = self.available_mhz.iter().map(u32::to_string).collect();
+                anyhow::bail!("{} MHz is not available, choose one of {} MHz", mhz, available.join(", "));
+            }
+            return Ok(());
+        }
+        if self.min_mhz.is_some_and(|min| mhz < min) || self.max_mhz.is_some_and(|max| mhz > max) {
+            anyhow::bail!(
+                "{} MHz is outside the hardware range of {}-{} MHz",
+                mhz,
+                self.min_mhz.unwrap_or(0),
+                self.max_mhz.unwrap_or(u32::MAX)
+            );
+        }
+        Ok(())
+    }
+
+    /// Frequency closest to `mhz` that [`Self::check`] accepts
+    pub fn nearest(&self, mhz: u32) -> u32 {
+        if let Some(&nearest) = self.available_mhz.iter().min_by_key(|available| available.abs_diff(mhz)) {
+            return nearest;
+        }
+        let mhz = self.min_mhz.map_or(mhz, |min| mhz.max(min));
+        self.max_mhz.map_or(mhz, |max| mhz.min(max))
+    }
+}
+
+/// cpufreq state of one CPU
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuFreqState {
+    pub governor: Option<String>,
+    pub min_freq_khz: Option<u32>,
+    pub max_freq_khz: Option<u32>,
+}
+
+/// CPU state right after a profile was applied. The kernel may round or
+/// clamp the written values, so this is read back instead of derived from
+/// the profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedState {
+    pub cpus: Vec<CpuFreqState>,
+    pub boost: Option<bool>,
+}
+
+impl AppliedState {
+    /// Human readable differences to an older state
+    pub fn differences(&self, expected: &AppliedState) -> Vec<String> {
+        let mut differences = Vec::new();
+        
+        for (cpu, (live, expected)) in self.cpus.iter().zip(&expected.cpus).enumerate() {
+            if live.governor != expected.governor {
+                differences.push(format!(
+                    "CPU {} governor is {:?}, expected {:?}",
+                    cpu, live.governor, expected.governor
+                ));
+            }
+            if (live.min_freq_khz, live.max_freq_khz) != (expected.min_freq_khz, expected.max_freq_khz) {
+                differences.push(format!(
+                    "CPU {} frequency range is {:?}-{:?} kHz, expected {:?}-{:?} kHz",
+                    cpu, live.min_freq_khz, live.max_freq_khz,
+                    expected.min_freq_khz, expected.max_freq_khz
+                ));
+            }
+        }
+        if self.cpus.len() != expected.cpus.len() {
+            differences.push(format!(
+                "{} CPUs found, expected {}",
+                self.cpus.len(),
+                expected.cpus.len()
+            ));
+        }
+        if self.boost != expected.boost {
+            differences.push(format!("Boost is {:?}, expected {:?}", self.boost, expected.boost));
+        }
+        
+        differences
+    }
+}
+
+/// A directory with cpufreq attributes and the CPUs it controls
+struct CpufreqDir {
+    /// "policy0" or "CPU 3", for error messages
+    label: String,
+    path: PathBuf,
+    cpus: Vec<usize>,
+}
 
 /// Controller for applying hardware settings from profiles
 pub struct HardwareController {
+    /// Prefix for all sysfs/procfs paths, "/" except in tests
+    root: PathBuf,
     cpu_base_path: PathBuf,
     keyboard: Option<KeyboardController>,
+    smooth_brightness: AtomicBool,
+    brightness_all_displays: AtomicBool,
+    perceptual_brightness: AtomicBool,
+    screen_fade: BrightnessFade,
+    keyboard_fade: BrightnessFade,
+    color_temperature: ColorTemperature,
+    /// CPU settings of the last applied profile and the state they produced
+    applied_cpu: Mutex<Option<(CpuSettings, AppliedState)>>,
+    /// Set while maximum performance mode overrides the profile
+    max_performance: AtomicBool,
+    /// Full speed PWM value by pwm attribute, read on first use
+    pwm_max: Mutex<HashMap<PathBuf, u32>>,
+    /// Bumped whenever a profile writes the fans, see [`Self::fan_generation`]
+    fan_generation: AtomicU64,
 }
 
 impl HardwareController {
-    pub fn new() -> Result<Self> {
-        let cpu_base_path = PathBuf::from("/sys/devices/system/cpu");
+    pub fn new() -> HardwareResult<Self> {
+        let controller = Self::with_root(Path::new("/"))?;
+        let config = AppConfig::load();
+        controller.set_smooth_brightness(config.smooth_brightness);
+        controller.set_brightness_all_displays(config.brightness_all_displays);
+        controller.set_perceptual_brightness(config.perceptual_brightness);
+        Ok(controller)
+    }
+    
+    /// Controller for a sysfs tree mounted below `root` (for testing)
+    pub fn with_root(root: &Path) -> HardwareResult<Self> {
+        let cpu_base_path = root.join("sys/devices/system/cpu");
         
         // Keyboard controller is optional
-        let keyboard = KeyboardController::new().ok();
+        let keyboard = KeyboardController::with_path(root.join("sys/class/leds/rgb:kbd_backlight")).ok();
         
         Ok(HardwareController {
+            root: root.to_path_buf(),
             cpu_base_path,
             keyboard,
+            smooth_brightness: AtomicBool::new(false),
+            brightness_all_displays: AtomicBool::new(false),
+            perceptual_brightness: AtomicBool::new(false),
+            screen_fade: BrightnessFade::default(),
+            keyboard_fade: BrightnessFade::default(),
+            color_temperature: ColorTemperature::default(),
+            applied_cpu: Mutex::new(None),
+            max_performance: AtomicBool::new(false),
+            pwm_max: Mutex::new(HashMap::new()),
+            fan_generation: AtomicU64::new(0),
         })
     }
     
+    /// Resolve an absolute sysfs/procfs path below the root
+    fn sys_path(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+    
+    /// Fade screen and keyboard brightness instead of changing it at once
+    pub fn set_smooth_brightness(&self, enable: bool) {
+        self.smooth_brightness.store(enable, Ordering::Relaxed);
+    }
+    
+    fn smooth_brightness(&self) -> bool {
+        self.smooth_brightness.load(Ordering::Relaxed)
+    }
+    
+    /// Set the screen brightness of every display for profiles that don't
+    /// choose themselves
+    pub fn set_brightness_all_displays(&self, enable: bool) {
+        self.brightness_all_displays.store(enable, Ordering::Relaxed);
+    }
+    
+    /// Map screen and keyboard brightness percentages on a gamma curve
+    /// instead of linearly, see [`perceptual_raw`]
+    pub fn set_perceptual_brightness(&self, enable: bool) {
+        self.perceptual_brightness.store(enable, Ordering::Relaxed);
+    }
+    
+    fn perceptual_brightness(&self) -> bool {
+        self.perceptual_brightness.load(Ordering::Relaxed)
+    }
+    
     /// Apply all settings from a profile
-    pub fn apply_profile(&self, profile: &Profile) -> Result<()> {
-        println!("Applying profile: {}", profile.name);
+    pub fn apply_profile(&self, profile: &Profile) -> HardwareResult<()> {
+        self.apply_partial(profile, ApplyMask::all())
+    }
+    
+    /// Apply only the parts of a profile in `mask`, e.g. just its keyboard
+    /// color, and leave the rest of the hardware as it is
+    pub fn apply_partial(&self, profile: &Profile, mask: ApplyMask) -> HardwareResult<()> {
+        if mask == ApplyMask::all() {
+            println!("Applying profile: {}", profile.name);
+        } else {
+            println!("Applying {:?} of profile: {}", mask, profile.name);
+        }
         
         // Apply keyboard backlight
-        if let Err(e) = self.apply_keyboard_settings(profile) {
-            eprintln!("Warning: Failed to apply keyboard settings: {}", e);
+        if mask.contains(ApplyMask::KEYBOARD) {
+            if let Err(e) = self.apply_keyboard_settings(profile, HardwareMonitor::on_battery()) {
+                eprintln!("Warning: Failed to apply keyboard settings: {}", e);
+            }
         }
         
         // Apply fan curves
-        if let Err(e) = self.apply_fan_curves(profile) {
-            eprintln!("Warning: Failed to apply fan curves: {}", e);
+        if mask.contains(ApplyMask::FANS) {
+            if let Err(e) = self.apply_fan_curves(profile) {
+                eprintln!("Warning: Failed to apply fan curves: {}", e);
+            }
+            self.fan_generation.fetch_add(1, Ordering::Relaxed);
+        }
+        
+        // Apply CPU settings, holding the lock so reconciliation can't
+        // interleave with the new profile
+        if mask.contains(ApplyMask::CPU) {
+            let mut applied_cpu = self.applied_cpu.lock().unwrap();
+            self.max_performance.store(false, Ordering::Relaxed);
+            *applied_cpu = match self.apply_cpu_settings(&profile.cpu_settings) {
+                Ok(()) => self
+                    .read_applied_state()
+                    .ok()
+                    .map(|state| (profile.cpu_settings.clone(), state)),
+                Err(e) => {
+                    eprintln!("Warning: Failed to apply CPU settings: {}", e);
+                    // Re-applying would fail the same way on every check
+                    None
+                }
+            };
+        }
+        
+        if mask.contains(ApplyMask::SCREEN) {
+            if let Err(e) = self.apply_screen_brightness(&profile.screen_settings) {
+                eprintln!("Warning: Failed to apply screen brightness: {}", e);
+            }
         }
         
-        // Apply CPU settings
-        if let Err(e) = self.apply_cpu_settings(&profile.cpu_settings) {
-            eprintln!("Warning: Failed to apply CPU settings: {}", e);
+        if mask.contains(ApplyMask::COLOR_TEMPERATURE) {
+            // Shells out to gammastep/wlsunset/xrandr, so it doesn't block
+            self.color_temperature.apply(profile.screen_settings.color_temp_kelvin);
         }
         
-        // Apply screen brightness
-        if let Err(e) = self.apply_screen_brightness(profile.screen_settings.brightness) {
-            eprintln!("Warning: Failed to apply screen brightness: {}", e);
+        if mask.contains(ApplyMask::POWER) {
+            self.apply_power_settings(profile);
         }
         
         println!("Profile '{}' applied successfully", profile.name);
         Ok(())
     }
     
-    /// Apply keyboard backlight settings
-    fn apply_keyboard_settings(&self, profile: &Profile) -> Result<()> {
+    fn apply_power_settings(&self, profile: &Profile) {
+        // Kernels without pcie_aspm (or with ASPM built out) have nothing to set
+        if let Some(policy) = &profile.aspm_policy {
+            if self.aspm_policies().is_some() {
+                if let Err(e) = self.set_aspm_policy(policy) {
+                    eprintln!("Warning: Failed to set PCIe ASPM policy: {:#}", e);
+                }
+            }
+        }
+        
+        // Only amdgpu cards have a power cap, Intel and NVIDIA are skipped
+        if let Some(watts) = profile.gpu_power_cap_watts {
+            for cap in self.gpu_power_caps() {
+                match self.set_gpu_power_cap(&cap.card, u64::from(watts) * 1_000_000) {
+                    Ok(uw) => println!("  ✓ GPU power cap ({}): {} W", cap.card, uw / 1_000_000),
+                    Err(e) => eprintln!("Warning: Failed to set the GPU power cap: {:#}", e),
+                }
+            }
+        }
+    }
+    
+    /// Apply keyboard backlight settings for the current power source
+    pub fn apply_keyboard_settings(&self, profile: &Profile, on_battery: bool) -> HardwareResult<()> {
         if let Some(ref kbd) = self.keyboard {
-            let color = &profile.keyboard_backlight.color;
-            let brightness = profile.keyboard_backlight.brightness;
-            
-            kbd.set_color_and_brightness(color.r, color.g, color.b, brightness)
-                .context("Failed to set keyboard backlight")?;
-            
-            println!("  ✓ Keyboard: RGB({},{},{}) @ {}%", 
-                     color.r, color.g, color.b, brightness);
+            match profile.keyboard_backlight.mode {
+                KeyboardMode::Managed => {
+                    let color = &profile.keyboard_backlight.color;
+                    let brightness = profile.keyboard_backlight.brightness_for(on_battery);
+                    
+                    let perceptual = self.perceptual_brightness();
+                    if self.smooth_brightness() {
+                        let from = if perceptual {
+                            kbd.get_brightness_perceptual()
+                        } else {
+                            kbd.get_brightness()
+                        };
+                        kbd.set_color(color.r, color.g, color.b)
+                            .context("Failed to set keyboard backlight")?;
+                        let kbd = kbd.clone();
+                        self.keyboard_fade.start(from.unwrap_or(0), brightness, move |value| {
+                            if perceptual {
+                                kbd.set_brightness_perceptual(value)?;
+                            } else {
+                                kbd.set_brightness(value)?;
+                            }
+                            Ok(())
+                        });
+                    } else if perceptual {
+                        self.keyboard_fade.cancel();
+                        kbd.set_color(color.r, color.g, color.b)
+                            .and_then(|_| kbd.set_brightness_perceptual(brightness))
+                            .context("Failed to set keyboard backlight")?;
+                    } else {
+                        self.keyboard_fade.cancel();
+                        kbd.set_color_and_brightness(color.r, color.g, color.b, brightness)
+                            .context("Failed to set keyboard backlight")?;
+                    }
+                    
+                    println!("  ✓ Keyboard: RGB({},{},{}) @ {}%", 
+                             color.r, color.g, color.b, brightness);
+                    
+                    // Without an effect attribute the static color stays
+                    let effect_result = match &profile.keyboard_backlight.hw_effect {
+                        Some(effect) => kbd.set_hw_effect(effect).map(|_| {
+                            println!("  ✓ Keyboard effect: {}", effect);
+                        }),
+                        None => kbd.clear_hw_effect(),
+                    };
+                    if let Err(e) = effect_result {
+                        eprintln!("Warning: Keeping static keyboard color: {:#}", e);
+                    }
+                    
+                    // Keyboards without the attribute only have our own dimming
+                    if let Some(secs) = profile.keyboard_backlight.hw_timeout_secs {
+                        if kbd.has_hw_timeout() {
+                            match kbd.set_hw_timeout(secs) {
+                                Ok(()) => println!("  ✓ Keyboard timeout: {} s", secs),
+                                Err(e) => eprintln!("Warning: Failed to set keyboard timeout: {:#}", e),
+                            }
+                        }
+                    }
+                }
+                KeyboardMode::Off => {
+                    self.keyboard_fade.cancel();
+                    kbd.turn_off()
+                        .context("Failed to turn off keyboard backlight")?;
+                    println!("  ✓ Keyboard: off");
+                }
+                KeyboardMode::Unmanaged => {
+                    self.keyboard_fade.cancel();
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Whether the keyboard firmware has its own backlight timeout, the
+    /// timeout setting does nothing otherwise
+    pub fn has_keyboard_hw_timeout(&self) -> bool {
+        self.keyboard.as_ref().is_some_and(|kbd| kbd.has_hw_timeout())
+    }
+    
+    /// Switch the keyboard backlight off until the next
+    /// [`Self::apply_keyboard_settings`], e.g. while the screen is off.
+    /// Does nothing without a keyboard backlight.
+    pub fn turn_off_keyboard(&self) -> HardwareResult<()> {
+        if let Some(ref kbd) = self.keyboard {
+            self.keyboard_fade.cancel();
+            kbd.turn_off()
+                .context("Failed to turn off keyboard backlight")?;
+        }
+        Ok(())
+    }
+    
+    /// Write `color` unless the keyboard already shows it, for effects
+    /// updating it often. Does nothing without an RGB keyboard backlight.
+    pub fn update_keyboard_color(&self, color: &RGBColor) -> HardwareResult<()> {
+        if let Some(ref kbd) = self.keyboard {
+            if kbd.has_rgb_support() && kbd.get_color().ok() != Some((color.r, color.g, color.b)) {
+                kbd.set_color(color.r, color.g, color.b)?;
+            }
         }
         Ok(())
     }
@@ -71,19 +654,32 @@ impl HardwareController {
     /// Apply fan curves for all fans
     fn apply_fan_curves(&self, profile: &Profile) -> Result<()> {
         for (fan_id, curve) in &profile.fan_curves {
-            self.apply_single_fan_curve(fan_id, curve)
+            self.apply_single_fan_curve(fan_id, curve, profile.fan_control_mode)
                 .context(format!("Failed to apply fan curve for {}", fan_id))?;
         }
         Ok(())
     }
     
+    /// Changes whenever a profile wrote the fans, e.g. the hwmon mid-point
+    /// of its curves, so the fan daemon knows its own state is outdated
+    pub fn fan_generation(&self) -> u64 {
+        self.fan_generation.load(Ordering::Relaxed)
+    }
+    
     /// Apply a single fan curve
-    fn apply_single_fan_curve(&self, fan_id: &str, curve: &FanCurve) -> Result<()> {
+    fn apply_single_fan_curve(
+        &self,
+        fan_id: &str,
+        curve: &FanCurve,
+        mode: FanControlMode,
+    ) -> Result<()> {
         // Fan control via tuxedo_io or direct sysfs
         // This depends on the specific hardware interface available
         
-        // Try tuxedo_io method first
-        if let Ok(_) = self.apply_fan_curve_tuxedo_io(fan_id, curve) {
+        // Try tuxedo_io method first, it only takes hardware (8 point) curves
+        if mode == FanControlMode::Hardware
+            && self.apply_fan_curve_tuxedo_io(fan_id, curve).is_ok()
+        {
             println!("  ✓ Fan curve applied for {} (tuxedo_io)", fan_id);
             return Ok(());
         }
@@ -99,7 +695,7 @@ impl HardwareController {
     
     /// Apply fan curve via tuxedo_io interface
     fn apply_fan_curve_tuxedo_io(&self, fan_id: &str, curve: &FanCurve) -> Result<()> {
-        let tuxedo_io_path = Path::new("/sys/devices/platform/tuxedo_io");
+        let tuxedo_io_path = self.sys_path("/sys/devices/platform/tuxedo_io");
         
         if !tuxedo_io_path.exists() {
             anyhow::bail!("tuxedo_io interface not available");
@@ -118,7 +714,7 @@ impl HardwareController {
             if temp_path.exists() && speed_path.exists() {
                 fs::write(&temp_path, point.temp.to_string())
                     .context(format!("Failed to write temp point {}", idx))?;
-                fs::write(&speed_path, point.speed.to_string())
+                fs::write(&speed_path, curve.limit_speed(point.speed).to_string())
                     .context(format!("Failed to write speed point {}", idx))?;
             }
         }
@@ -126,48 +722,186 @@ impl HardwareController {
         Ok(())
     }
     
+    /// Whether fan curves can be applied on this model: tuxedo_io takes
+    /// hardware curves through sysfs or its ioctl device, hwmon PWM
+    /// software curves. Without either, curves are silently ignored by the
+    /// firmware.
+    pub fn fan_control_supported(&self) -> bool {
+        self.tuxedo_io_fan_control() || (1..=10).any(|fan| self.find_fan_pwm(&format!("fan{}", fan)).is_ok())
+    }
+    
+    fn tuxedo_io_fan_control(&self) -> bool {
+        let tuxedo_io_path = self.sys_path("/sys/devices/platform/tuxedo_io");
+        // Newer modules report the capability, older ones only have the
+        // curve points or just the ioctl device tailord uses
+        match fs::read_to_string(tuxedo_io_path.join("fan_control")) {
+            Ok(capability) => capability.trim() == "1",
+            Err(_) => {
+                (tuxedo_io_path.join("fan1_temp0").exists() && tuxedo_io_path.join("fan1_speed0").exists())
+                    || self.sys_path("/dev/tuxedo_io").exists()
+            }
+        }
+    }
+    
     /// Apply fan curve via hwmon interface (alternative method)
     fn apply_fan_curve_hwmon(&self, fan_id: &str, curve: &FanCurve) -> Result<()> {
-        // Some systems expose fan control via hwmon
-        let hwmon_base = Path::new("/sys/class/hwmon");
+        // Start with a fixed speed based on the middle of the curve, the
+        // fan daemon follows the curve from there
+        let mid_point = &curve.points[curve.points.len() / 2];
+        Ok(self.set_fan_speed(fan_id, curve.limit_speed(mid_point.speed))?)
+    }
+    
+    /// Check which PWM fans really stop at 0%, some drivers or ECs clamp
+    /// it to a minimum speed. Every fan is set to 0% at once and its RPM
+    /// read back after `settle`, then the previous mode and speed are
+    /// restored. Fans are briefly off, so only for diagnostics.
+    pub fn probe_fan_stop(&self, settle: Duration) -> Vec<FanStopProbe> {
+        // hwmon directory, PWM number and previous mode and duty cycle
+        let mut stopped = Vec::new();
+        for fan in 1..=10 {
+            let fan_id = format!("fan{}", fan);
+            let Ok((path, fan_num)) = self.find_fan_pwm(&fan_id) else {
+                continue;
+            };
+            let read = |name: String| fs::read_to_string(path.join(name)).ok();
+            let enable = read(format!("pwm{}_enable", fan_num));
+            let pwm = read(format!("pwm{}", fan_num));
+            match self.set_fan_speed(&fan_id, 0) {
+                Ok(()) => stopped.push((fan_id, path, fan_num, enable, pwm)),
+                Err(e) => eprintln!("Warning: Failed to stop {}: {:#}", fan_id, e),
+            }
+        }
+        if stopped.is_empty() {
+            return Vec::new();
+        }
         
+        thread::sleep(settle);
+        
+        stopped
+            .into_iter()
+            .map(|(fan_id, path, fan_num, enable, pwm)| {
+                let rpm_at_zero = fs::read_to_string(path.join(format!("fan{}_input", fan_num)))
+                    .ok()
+                    .and_then(|rpm| rpm.trim().parse().ok());
+                
+                // Duty cycle first, the mode may hand the fan back to the firmware
+                if let Some(pwm) = pwm {
+                    let _ = fs::write(path.join(format!("pwm{}", fan_num)), pwm.trim());
+                }
+                let enable = enable.unwrap_or_else(|| "2".to_string());
+                if let Err(e) = fs::write(path.join(format!("pwm{}_enable", fan_num)), enable.trim()) {
+                    eprintln!("Warning: Failed to restore the mode of {}: {}", fan_id, e);
+                }
+                FanStopProbe { fan_id, rpm_at_zero }
+            })
+            .collect()
+    }
+    
+    /// hwmon directory and PWM number controlling a fan. GPU fans
+    /// ("cardN_fanM") are looked up under their DRM card, chassis fans in
+    /// the hwmons that don't belong to a GPU.
+    fn find_fan_pwm(&self, fan_id: &str) -> Result<(PathBuf, usize)> {
+        let gpu_hwmons = gpu_hwmon_dirs(&self.sys_path("/sys/class/drm"));
+        let has_pwm = |path: &Path, fan_num: usize| {
+            path.join(format!("pwm{}_enable", fan_num)).exists()
+                && path.join(format!("pwm{}", fan_num)).exists()
+        };
+        
+        if let Some((card, fan_num)) = parse_gpu_fan_id(fan_id) {
+            return gpu_hwmons
+                .into_iter()
+                .find(|(owner, path)| owner == card && has_pwm(path, fan_num))
+                .map(|(_, path)| (path, fan_num))
+                .context(format!("{} has no controllable fan {}", card, fan_num));
+        }
+        
+        let hwmon_base = self.sys_path("/sys/class/hwmon");
         if !hwmon_base.exists() {
             anyhow::bail!("hwmon interface not available");
         }
         
-        // Search for fan control interface
-        for entry in fs::read_dir(hwmon_base)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            // Look for pwm_enable and pwm files
-            let fan_num: usize = fan_id.trim_start_matches("fan")
-                .parse()
-                .unwrap_or(1);
-            
-            let pwm_enable_path = path.join(format!("pwm{}_enable", fan_num));
-            let pwm_path = path.join(format!("pwm{}", fan_num));
-            
-            if pwm_enable_path.exists() && pwm_path.exists() {
-                // Set to manual control mode (1 = manual, 2 = automatic)
-                fs::write(&pwm_enable_path, "1")
-                    .context("Failed to set fan to manual mode")?;
-                
-                // For now, set a fixed speed based on the middle of the curve
-                // Full curve application would require a daemon monitoring temps
-                let mid_point = &curve.points[curve.points.len() / 2];
-                let pwm_value = (mid_point.speed as f32 * 2.55) as u8; // Convert 0-100 to 0-255
-                
-                fs::write(&pwm_path, pwm_value.to_string())
-                    .context("Failed to set fan speed")?;
-                
-                return Ok(());
+        let fan_num: usize = fan_id.trim_start_matches("fan")
+            .parse()
+            .unwrap_or(1);
+        let gpu_hwmon_set: Vec<PathBuf> = gpu_hwmons
+            .iter()
+            .filter_map(|(_, path)| path.canonicalize().ok())
+            .collect();
+        
+        for entry in fs::read_dir(&hwmon_base)? {
+            let path = entry?.path();
+            let is_gpu = path
+                .canonicalize()
+                .is_ok_and(|path| gpu_hwmon_set.contains(&path));
+            if !is_gpu && has_pwm(&path, fan_num) {
+                return Ok((path, fan_num));
             }
         }
         
         anyhow::bail!("No suitable hwmon interface found");
     }
     
+    /// Set a fan to a fixed speed (0-100%) via hwmon PWM
+    pub fn set_fan_speed(&self, fan_id: &str, percent: u8) -> HardwareResult<()> {
+        let (path, fan_num) = self.find_fan_pwm(fan_id)?;
+        
+        // Set to manual control mode (1 = manual, 2 = automatic)
+        fs::write(path.join(format!("pwm{}_enable", fan_num)), "1")
+            .context("Failed to set fan to manual mode")?;
+        
+        let pwm_path = path.join(format!("pwm{}", fan_num));
+        let value = pwm_value(percent, self.pwm_max(&pwm_path));
+        fs::write(&pwm_path, value.to_string())
+            .context("Failed to set fan speed")?;
+        
+        Ok(())
+    }
+    
+    /// Cached [`read_pwm_max`]
+    fn pwm_max(&self, pwm_path: &Path) -> u32 {
+        let mut cache = self.pwm_max.lock().unwrap();
+        *cache
+            .entry(pwm_path.to_path_buf())
+            .or_insert_with(|| read_pwm_max(pwm_path))
+    }
+    
+    /// Hand a single fan back to the firmware
+    pub fn set_fan_auto(&self, fan_id: &str) -> HardwareResult<()> {
+        let (path, fan_num) = self.find_fan_pwm(fan_id)?;
+        fs::write(path.join(format!("pwm{}_enable", fan_num)), "2")
+            .context("Failed to set fan to automatic mode")?;
+        Ok(())
+    }
+    
+    /// Hand all fans set to manual PWM control back to the firmware
+    pub fn restore_auto_fan_control(&self) -> HardwareResult<()> {
+        // GPU hwmons usually show up in /sys/class/hwmon as well, setting
+        // them twice is harmless
+        let mut hwmons: Vec<PathBuf> = gpu_hwmon_dirs(&self.sys_path("/sys/class/drm"))
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        if let Ok(entries) = fs::read_dir(self.sys_path("/sys/class/hwmon")) {
+            hwmons.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        
+        for path in hwmons {
+            for i in 1..=10 {
+                let pwm_enable_path = path.join(format!("pwm{}_enable", i));
+                let manual = fs::read_to_string(&pwm_enable_path)
+                    .map(|mode| mode.trim() == "1")
+                    .unwrap_or(false);
+                if manual {
+                    fs::write(&pwm_enable_path, "2")
+                        .context(format!("Failed to restore {}", pwm_enable_path.display()))?;
+                }
+            }
+        }
+        
+        println!("  ✓ Automatic fan control restored");
+        Ok(())
+    }
+    
     /// Apply CPU settings
     fn apply_cpu_settings(&self, settings: &CpuSettings) -> Result<()> {
         // Apply performance profile (governor)
@@ -182,55 +916,277 @@ impl HardwareController {
         // Apply SMT setting
         self.set_smt(settings.smt_enabled)?;
         
+        if let Some(ref epp) = settings.energy_performance_preference {
+            if let Err(e) = self.set_energy_performance_preference(epp) {
+                eprintln!("Warning: Failed to set energy performance preference: {}", e);
+            }
+        }
+        
+        if let Some(enable) = settings.amd_prefcore {
+            if let Err(e) = self.set_amd_prefcore(enable) {
+                eprintln!("Warning: Failed to set preferred core ranking: {}", e);
+            }
+        }
+        
         Ok(())
     }
     
-    /// Set CPU governor based on performance profile
-    fn set_cpu_governor(&self, settings: &CpuSettings) -> Result<()> {
-        let governor = match settings.performance_profile {
-            CpuPerformanceProfile::PowerSave => "powersave",
-            CpuPerformanceProfile::Balanced => "schedutil",
-            CpuPerformanceProfile::Performance => "performance",
+    /// Live governor, frequency limits and boost state
+    pub fn read_applied_state(&self) -> HardwareResult<AppliedState> {
+        let read = |path: PathBuf| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+        
+        let dirs = self.cpufreq_dirs()?;
+        let cpus = (0..self.get_cpu_count()?)
+            .map(|cpu| {
+                let cpufreq = match dirs.iter().find(|dir| dir.cpus.contains(&cpu)) {
+                    Some(dir) => dir.path.clone(),
+                    None => self.cpu_base_path.join(format!("cpu{}/cpufreq", cpu)),
+                };
+                CpuFreqState {
+                    governor: read(cpufreq.join("scaling_governor")),
+                    min_freq_khz: read_khz(&cpufreq.join("scaling_min_freq")),
+                    max_freq_khz: read_khz(&cpufreq.join("scaling_max_freq")),
+                }
+            })
+            .collect();
+        
+        let boost = match read(self.cpu_base_path.join("intel_pstate/no_turbo")) {
+            Some(no_turbo) => Some(no_turbo == "0"),
+            None => read(self.cpu_base_path.join("cpufreq/boost")).map(|boost| boost == "1"),
         };
         
-        let cpu_count = self.get_cpu_count()?;
+        Ok(AppliedState { cpus, boost })
+    }
+    
+    /// State recorded when the active profile was applied, `None` if its
+    /// CPU settings failed to apply
+    pub fn applied_state(&self) -> Option<AppliedState> {
+        self.applied_cpu.lock().unwrap().as_ref().map(|(_, state)| state.clone())
+    }
+    
+    /// Re-apply the CPU settings of the active profile if the live state
+    /// drifted from what was applied, e.g. because the firmware reset it
+    /// on resume. Returns whether anything was re-applied.
+    pub fn reconcile_cpu_settings(&self) -> HardwareResult<bool> {
+        let mut applied_cpu = self.applied_cpu.lock().unwrap();
+        if self.max_performance.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let Some((settings, expected)) = applied_cpu.as_mut() else {
+            return Ok(false);
+        };
         
+        let differences = self.read_applied_state()?.differences(expected);
+        if differences.is_empty() {
+            return Ok(false);
+        }
+        
+        println!("CPU settings drifted from the active profile, re-applying:");
+        for difference in &differences {
+            println!("  {}", difference);
+        }
+        self.apply_cpu_settings(settings)?;
+        *expected = self.read_applied_state()?;
+        Ok(true)
+    }
+    
+    /// Detect EPP support of the running cpufreq driver
+    pub fn epp_support(&self) -> Option<EppSupport> {
+        let cpufreq = self.cpu_base_path.join("cpu0/cpufreq");
+        let read_trimmed = |path: PathBuf| -> Option<String> {
+            fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+        };
+        
+        let driver = EppDriver::from_scaling_driver(&read_trimmed(cpufreq.join("scaling_driver"))?)?;
+        let available: Vec<String> = read_trimmed(cpufreq.join("energy_performance_available_preferences"))?
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        
+        let prefcore = match driver {
+            EppDriver::AmdPstate => read_trimmed(self.cpu_base_path.join("amd_pstate/prefcore"))
+                .map(|state| state == "enabled"),
+            EppDriver::IntelPstate => None,
+        };
+        
+        Some(EppSupport {
+            driver,
+            available,
+            current: read_trimmed(cpufreq.join("energy_performance_preference")),
+            prefcore,
+        })
+    }
+    
+    /// Set the energy performance preference on all CPUs
+    pub fn set_energy_performance_preference(&self, epp: &str) -> Result<()> {
+        let support = self.epp_support()
+            .context("The cpufreq driver has no energy performance preference")?;
+        if !support.available.iter().any(|available| available == epp) {
+            anyhow::bail!(
+                "{} doesn't support '{}', available: {}",
+                support.driver.label(),
+                epp,
+                support.available.join(", ")
+            );
+        }
+        
+        let cpu_count = self.get_cpu_count()?;
         for cpu in 0..cpu_count {
-            let governor_path = self.cpu_base_path
-                .join(format!("cpu{}/cpufreq/scaling_governor", cpu));
+            let epp_path = self.cpu_base_path
+                .join(format!("cpu{}/cpufreq/energy_performance_preference", cpu));
+            
+            // Offline CPUs have no cpufreq directory
+            if epp_path.exists() {
+                fs::write(&epp_path, epp)
+                    .context(format!("Failed to set EPP for CPU {}", cpu))?;
+            }
+        }
+        
+        println!("  ✓ EPP ({}): {}", support.driver.label(), epp);
+        Ok(())
+    }
+    
+    /// Enable or disable amd-pstate preferred core ranking. Most kernels only
+    /// allow this with the `amd_prefcore` boot parameter.
+    pub fn set_amd_prefcore(&self, enable: bool) -> HardwareResult<()> {
+        let prefcore_path = self.cpu_base_path.join("amd_pstate/prefcore");
+        
+        if !prefcore_path.exists() {
+            return Err(error::unsupported("Preferred core ranking is not supported").into());
+        }
+        
+        let value = if enable { "enabled" } else { "disabled" };
+        let current = fs::read_to_string(&prefcore_path).unwrap_or_default();
+        if current.trim() == value {
+            return Ok(());
+        }
+        
+        fs::write(&prefcore_path, value).context(
+            "Failed to change preferred core ranking, use the amd_prefcore kernel parameter instead",
+        )?;
+        println!("  ✓ Preferred cores: {}", value);
+        Ok(())
+    }
+    
+    /// What the userspace governor offers for pinning the frequency,
+    /// `None` if the driver has no userspace governor (intel_pstate or
+    /// amd-pstate in active mode)
+    pub fn fixed_frequency_support(&self) -> Option<FixedFrequencySupport> {
+        let cpufreq = self.cpufreq_dirs().ok()?.into_iter().next()?.path;
+        let governors = fs::read_to_string(cpufreq.join("scaling_available_governors")).ok()?;
+        if !governors.split_whitespace().any(|governor| governor == "userspace") {
+            return None;
+        }
+        
+        let mut available_mhz: Vec<u32> = fs::read_to_string(cpufreq.join("scaling_available_frequencies"))
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|khz| khz.parse::<u32>().ok())
+            .map(|khz| khz / 1000)
+            .collect();
+        available_mhz.sort_unstable();
+        
+        Some(FixedFrequencySupport {
+            available_mhz,
+            min_mhz: read_khz(&cpufreq.join("cpuinfo_min_freq")).map(|khz| khz / 1000),
+            max_mhz: read_khz(&cpufreq.join("cpuinfo_max_freq")).map(|khz| khz / 1000),
+        })
+    }
+    
+    /// Check a fixed frequency of a profile against the driver
+    fn check_fixed_frequency(&self, mhz: u32) -> Result<()> {
+        self.fixed_frequency_support()
+            .context("The cpufreq driver has no userspace governor")?
+            .check(mhz)
+    }
+    
+    /// Set CPU governor based on performance profile, or pin the frequency
+    /// with the userspace governor
+    fn set_cpu_governor(&self, settings: &CpuSettings) -> Result<()> {
+        // An unusable fixed frequency falls back to the profile's governor
+        let fixed_mhz = settings.fixed_freq_mhz.filter(|&mhz| match self.check_fixed_frequency(mhz) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Warning: Not pinning the CPU frequency: {:#}", e);
+                false
+            }
+        });
+        let choice = self.governor_choice(&settings.performance_profile);
+        let governor = if fixed_mhz.is_some() { "userspace" } else { choice.governor };
+        
+        for dir in self.cpufreq_dirs()? {
+            let governor_path = dir.path.join("scaling_governor");
             
             if governor_path.exists() {
                 fs::write(&governor_path, governor)
-                    .context(format!("Failed to set governor for CPU {}", cpu))?;
+                    .context(format!("Failed to set governor for {}", dir.label))?;
+                
+                if let Some(mhz) = fixed_mhz {
+                    fs::write(dir.path.join("scaling_setspeed"), (mhz * 1000).to_string())
+                        .context(format!("Failed to pin the frequency of {}", dir.label))?;
+                }
             }
         }
         
-        println!("  ✓ CPU Governor: {}", governor);
+        match fixed_mhz {
+            Some(mhz) => println!("  ✓ CPU Governor: {} at {} MHz", governor, mhz),
+            None => println!("  ✓ CPU Governor: {}", governor),
+        }
+        
+        // A preference of the profile is written afterwards and wins
+        if let Some(epp) = choice.epp.filter(|_| fixed_mhz.is_none()) {
+            if settings.energy_performance_preference.is_none() {
+                if let Err(e) = self.set_energy_performance_preference(epp) {
+                    eprintln!("Warning: Failed to set energy performance preference: {}", e);
+                }
+            }
+        }
         Ok(())
     }
     
+    /// Governor and EPP for `profile` with the running cpufreq driver
+    pub fn governor_choice(&self, profile: &CpuPerformanceProfile) -> GovernorChoice {
+        let cpufreq = self.cpu_base_path.join("cpu0/cpufreq");
+        let read = |name: &str| fs::read_to_string(cpufreq.join(name)).unwrap_or_default();
+        let driver = read("scaling_driver");
+        let available = read("scaling_available_governors");
+        GovernorChoice::for_driver(
+            profile,
+            driver.trim(),
+            &available.split_whitespace().collect::<Vec<_>>(),
+        )
+    }
+    
     /// Set CPU frequency limits
+    ///
+    /// Unset limits are reset to the hardware range, so leftovers from
+    /// [`Self::set_maximum_performance`] or an earlier profile don't linger.
     fn set_cpu_frequency_limits(&self, settings: &CpuSettings) -> Result<()> {
-        let cpu_count = self.get_cpu_count()?;
-        
-        for cpu in 0..cpu_count {
-            let cpu_path = self.cpu_base_path.join(format!("cpu{}/cpufreq", cpu));
+        for dir in self.cpufreq_dirs()? {
+            let cpu_path = dir.path;
             
-            if let Some(min_freq) = settings.min_freq_mhz {
+            let min_khz = match settings.min_freq_mhz {
+                Some(min_freq) => Some(min_freq * 1000),
+                None => read_khz(&cpu_path.join("cpuinfo_min_freq")),
+            };
+            let max_khz = match settings.max_freq_mhz {
+                Some(max_freq) => Some(max_freq * 1000),
+                None => read_khz(&cpu_path.join("cpuinfo_max_freq")),
+            };
+            
+            if let Some(freq_khz) = min_khz {
                 let min_path = cpu_path.join("scaling_min_freq");
                 if min_path.exists() {
-                    let freq_khz = min_freq * 1000;
                     fs::write(&min_path, freq_khz.to_string())
-                        .context(format!("Failed to set min freq for CPU {}", cpu))?;
+                        .context(format!("Failed to set min freq for {}", dir.label))?;
                 }
             }
             
-            if let Some(max_freq) = settings.max_freq_mhz {
+            if let Some(freq_khz) = max_khz {
                 let max_path = cpu_path.join("scaling_max_freq");
                 if max_path.exists() {
-                    let freq_khz = max_freq * 1000;
                     fs::write(&max_path, freq_khz.to_string())
-                        .context(format!("Failed to set max freq for CPU {}", cpu))?;
+                        .context(format!("Failed to set max freq for {}", dir.label))?;
                 }
             }
         }
@@ -246,20 +1202,20 @@ impl HardwareController {
     /// Enable or disable CPU boost
     fn set_cpu_boost(&self, enable: bool) -> Result<()> {
         // Intel boost
-        let intel_boost_path = Path::new("/sys/devices/system/cpu/intel_pstate/no_turbo");
+        let intel_boost_path = self.cpu_base_path.join("intel_pstate/no_turbo");
         if intel_boost_path.exists() {
             let value = if enable { "0" } else { "1" }; // Note: inverted logic (no_turbo)
-            fs::write(intel_boost_path, value)
+            fs::write(&intel_boost_path, value)
                 .context("Failed to set Intel turbo boost")?;
             println!("  ✓ CPU Boost (Intel): {}", if enable { "enabled" } else { "disabled" });
             return Ok(());
         }
         
         // AMD boost
-        let amd_boost_path = Path::new("/sys/devices/system/cpu/cpufreq/boost");
+        let amd_boost_path = self.cpu_base_path.join("cpufreq/boost");
         if amd_boost_path.exists() {
             let value = if enable { "1" } else { "0" };
-            fs::write(amd_boost_path, value)
+            fs::write(&amd_boost_path, value)
                 .context("Failed to set AMD boost")?;
             println!("  ✓ CPU Boost (AMD): {}", if enable { "enabled" } else { "disabled" });
             return Ok(());
@@ -282,71 +1238,143 @@ impl HardwareController {
     
     /// Enable or disable SMT (Simultaneous Multithreading / Hyperthreading)
     fn set_smt(&self, enable: bool) -> Result<()> {
-        let smt_path = Path::new("/sys/devices/system/cpu/smt/control");
+        let smt_path = self.cpu_base_path.join("smt/control");
         
         if !smt_path.exists() {
             return Ok(()); // SMT control not available, skip silently
         }
         
         let value = if enable { "on" } else { "off" };
-        fs::write(smt_path, value)
+        fs::write(&smt_path, value)
             .context("Failed to set SMT state")?;
         
         println!("  ✓ SMT/Hyperthreading: {}", if enable { "enabled" } else { "disabled" });
         Ok(())
     }
     
-    /// Apply screen brightness
-    fn apply_screen_brightness(&self, brightness: u8) -> Result<()> {
-        // Try common backlight paths
-        let backlight_paths = vec![
-            "/sys/class/backlight/intel_backlight",
-            "/sys/class/backlight/amdgpu_bl0",
-            "/sys/class/backlight/acpi_video0",
-        ];
-        
-        for base_path in backlight_paths {
-            let base = Path::new(base_path);
-            if base.exists() {
-                return self.set_backlight_brightness(base, brightness);
-            }
+    /// Apply screen brightness to the built-in panel, or every display
+    fn apply_screen_brightness(&self, settings: &ScreenSettings) -> Result<()> {
+        let all_displays = settings
+            .apply_to_all_displays
+            .unwrap_or_else(|| self.brightness_all_displays.load(Ordering::Relaxed));
+        let devices = self.backlight_devices(all_displays);
+        if devices.is_empty() {
+            anyhow::bail!("No backlight interface found");
         }
-        
-        anyhow::bail!("No backlight interface found")
+        self.set_backlight_brightness(devices, settings.brightness)
     }
     
-    /// Set brightness for a specific backlight device
-    fn set_backlight_brightness(&self, base_path: &Path, brightness: u8) -> Result<()> {
-        let max_brightness_path = base_path.join("max_brightness");
-        let brightness_path = base_path.join("brightness");
-        
-        let max_brightness: u32 = fs::read_to_string(&max_brightness_path)?
-            .trim()
-            .parse()
-            .context("Failed to parse max_brightness")?;
-        
-        let actual_brightness = ((brightness as f32 / 100.0) * max_brightness as f32) as u32;
+    /// Backlight devices to set, the built-in panel first. External
+    /// monitors show up as "ddcciN" with the ddcci driver loaded.
+    fn backlight_devices(&self, all_displays: bool) -> Vec<PathBuf> {
+        let mut devices: Vec<PathBuf> = fs::read_dir(self.sys_path("/sys/class/backlight"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        devices.sort();
         
-        fs::write(&brightness_path, actual_brightness.to_string())
-            .context("Failed to write brightness")?;
+        let rank = |path: &PathBuf| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            match PANEL_BACKLIGHTS.iter().position(|panel| *panel == name) {
+                Some(rank) => rank,
+                None if name.starts_with("ddcci") => PANEL_BACKLIGHTS.len() + 1,
+                None => PANEL_BACKLIGHTS.len(),
+            }
+        };
+        devices.sort_by_key(rank);
         
-        println!("  ✓ Screen brightness: {}%", brightness);
+        if !all_displays {
+            devices.truncate(1);
+        }
+        devices
+    }
+    
+    /// Set brightness for backlight devices, each scaled to its own maximum
+    fn set_backlight_brightness(&self, devices: Vec<PathBuf>, brightness: u8) -> Result<()> {
+        let displays = match devices.len() {
+            1 => String::new(),
+            count => format!(" on {} displays", count),
+        };
+        let perceptual = self.perceptual_brightness();
+        if self.smooth_brightness() {
+            let from = read_backlight_percent(&devices[0], perceptual).unwrap_or(brightness);
+            self.screen_fade.start(from, brightness, move |value| {
+                write_backlights_percent(&devices, value, perceptual)
+            });
+            println!("  ✓ Screen brightness: fading to {}%{}", brightness, displays);
+        } else {
+            self.screen_fade.cancel();
+            write_backlights_percent(&devices, brightness, perceptual)?;
+            println!("  ✓ Screen brightness: {}%{}", brightness, displays);
+        }
         Ok(())
     }
     
+    /// Restore neutral screen colors, e.g. on exit
+    pub fn reset_color_temperature(&self) {
+        self.color_temperature.reset();
+    }
+    
     /// Get number of CPUs
     fn get_cpu_count(&self) -> Result<usize> {
-        let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
+        let cpuinfo = fs::read_to_string(self.sys_path("/proc/cpuinfo"))?;
         let count = cpuinfo.lines()
             .filter(|line| line.starts_with("processor"))
             .count();
         Ok(count)
     }
     
-    /// Switch GPU using prime-select (NVIDIA Optimus)
-    pub fn switch_gpu(&self, use_discrete: bool) -> Result<()> {
+    /// Directories to write cpufreq settings to. CPUs sharing a clock are
+    /// grouped into one `cpufreq/policyN` by the kernel, writing each
+    /// policy once is enough and avoids redundant writes to siblings.
+    /// A policy's CPUs are its `related_cpus`, which unlike `affected_cpus`
+    /// includes offline ones. CPUs no policy lists (or all of them on
+    /// kernels without policy directories) are written one by one.
+    fn cpufreq_dirs(&self) -> Result<Vec<CpufreqDir>> {
+        let mut dirs = Vec::new();
+        
+        if let Ok(entries) = fs::read_dir(self.cpu_base_path.join("cpufreq")) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let Some(number) = name.strip_prefix("policy").and_then(|n| n.parse::<usize>().ok()) else {
+                    continue;
+                };
+                let cpus = fs::read_to_string(entry.path().join("related_cpus"))
+                    .or_else(|_| fs::read_to_string(entry.path().join("affected_cpus")))
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .filter_map(|cpu| cpu.parse().ok())
+                    .collect();
+                dirs.push((number, CpufreqDir { label: name, path: entry.path(), cpus }));
+            }
+        }
+        dirs.sort_by_key(|(number, _)| *number);
+        let mut dirs: Vec<CpufreqDir> = dirs.into_iter().map(|(_, dir)| dir).collect();
+        
+        for cpu in 0..self.get_cpu_count()? {
+            if !dirs.iter().any(|dir| dir.cpus.contains(&cpu)) {
+                dirs.push(CpufreqDir {
+                    label: format!("CPU {}", cpu),
+                    path: self.cpu_base_path.join(format!("cpu{}/cpufreq", cpu)),
+                    cpus: vec![cpu],
+                });
+            }
+        }
+        
+        Ok(dirs)
+    }
+    
+    /// Switch GPU using prime-select (NVIDIA Optimus). The switch is
+    /// pending until the next restart.
+    pub fn switch_gpu(&self, use_discrete: bool) -> HardwareResult<PendingGpuSwitch> {
         let gpu_mode = if use_discrete { "nvidia" } else { "intel" };
         
+        // The switch stays pending until the selection changes back then,
+        // which is no reason to report a switch that worked as failed
+        let boot_id = self.boot_id().unwrap_or_else(|e| {
+            eprintln!("Warning: Can't tell when the GPU switch takes effect: {}", e);
+            String::new()
+        });
+        
         let output = Command::new("prime-select")
             .arg(gpu_mode)
             .output()
@@ -354,26 +1382,166 @@ impl HardwareController {
         
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("prime-select failed: {}", stderr);
+            return Err(anyhow::anyhow!("prime-select failed: {}", stderr).into());
         }
         
         println!("  ✓ GPU switched to: {}", gpu_mode);
         println!("  ⚠ System restart required for GPU switch to take effect");
         
+        Ok(PendingGpuSwitch {
+            mode: if use_discrete { GpuMode::Discrete } else { GpuMode::Integrated },
+            boot_id,
+        })
+    }
+    
+    /// Id of the running boot, changes with every restart
+    pub fn boot_id(&self) -> HardwareResult<String> {
+        let path = self.sys_path("/proc/sys/kernel/random/boot_id");
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        Ok(content.trim().to_string())
+    }
+    
+    /// Power limits of the GPUs that have one, empty without amdgpu
+    pub fn gpu_power_caps(&self) -> Vec<GpuPowerCap> {
+        gpu_hwmon_dirs(&self.sys_path("/sys/class/drm"))
+            .iter()
+            .filter_map(|(card, hwmon)| GpuPowerCap::read(card, hwmon))
+            .collect()
+    }
+    
+    /// Limit the power of the GPU `card` (e.g. "card1"), clamped to the
+    /// range the driver allows. Returns the limit that was written.
+    pub fn set_gpu_power_cap(&self, card: &str, microwatts: u64) -> HardwareResult<u64> {
+        let (hwmon, cap) = gpu_hwmon_dirs(&self.sys_path("/sys/class/drm"))
+            .into_iter()
+            .filter(|(name, _)| name == card)
+            .find_map(|(name, hwmon)| GpuPowerCap::read(&name, &hwmon).map(|cap| (hwmon, cap)))
+            .context(format!("{} has no adjustable power cap", card))?;
+        
+        let value = microwatts.clamp(cap.min_uw, cap.max_uw);
+        let path = hwmon.join("power1_cap");
+        fs::write(&path, value.to_string())
+            .context(format!("Failed to write {}", path.display()))?;
+        Ok(value)
+    }
+    
+    /// PCIe ASPM policies the kernel accepts and the active one, `None`
+    /// without the pcie_aspm module parameter
+    pub fn aspm_policies(&self) -> Option<(Vec<String>, Option<String>)> {
+        let content = fs::read_to_string(self.sys_path(ASPM_POLICY_PATH)).ok()?;
+        Some(parse_bracketed_choice(&content))
+    }
+    
+    /// Set the PCIe ASPM policy, e.g. "powersupersave" on battery. Fails if
+    /// the kernel doesn't accept it or keeps the old policy.
+    pub fn set_aspm_policy(&self, policy: &str) -> HardwareResult<()> {
+        let (available, current) = self.aspm_policies()
+            .context("PCIe ASPM policy is not available on this kernel")?;
+        if !available.iter().any(|choice| choice == policy) {
+            return Err(error::invalid_input(format!(
+                "Unknown PCIe ASPM policy '{}', available: {}",
+                policy,
+                available.join(", ")
+            ))
+            .into());
+        }
+        if current.as_deref() == Some(policy) {
+            return Ok(());
+        }
+        
+        let path = self.sys_path(ASPM_POLICY_PATH);
+        fs::write(&path, policy).context(format!("Failed to write {}", path.display()))?;
+        
+        // The firmware can forbid ASPM, the kernel then keeps the old policy
+        let (_, applied) = self.aspm_policies().unwrap_or_default();
+        if applied.as_deref() != Some(policy) {
+            return Err(anyhow::anyhow!(
+                "PCIe ASPM policy is still '{}' after writing '{}'",
+                applied.as_deref().unwrap_or("unknown"),
+                policy
+            )
+            .into());
+        }
+        println!("  ✓ PCIe ASPM: {}", policy);
+        Ok(())
+    }
+    
+    /// Set the battery charge thresholds in percent: charging starts below
+    /// `start` and stops at `end`. Uses the standard power_supply attributes
+    /// of every battery, or the ones of tuxedo_io on older driver versions.
+    /// Models with only an end threshold ignore `start`.
+    pub fn set_charge_thresholds(&self, start: u8, end: u8) -> HardwareResult<()> {
+        if start >= end || end > 100 {
+            return Err(error::invalid_input(format!(
+                "Invalid charge thresholds {}-{}%",
+                start, end
+            ))
+            .into());
+        }
+        
+        let mut dirs: Vec<PathBuf> = fs::read_dir(self.sys_path("/sys/class/power_supply"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        dirs.retain(|path| {
+            fs::read_to_string(path.join("type")).is_ok_and(|t| t.trim() == "Battery")
+                && path.join("charge_control_end_threshold").exists()
+        });
+        dirs.sort();
+        if dirs.is_empty() {
+            let tuxedo_io_path = self.sys_path("/sys/devices/platform/tuxedo_io");
+            if tuxedo_io_path.join("charge_control_end_threshold").exists() {
+                dirs.push(tuxedo_io_path);
+            }
+        }
+        if dirs.is_empty() {
+            return Err(error::unsupported("Charge thresholds are not supported on this model").into());
+        }
+        
+        for dir in dirs {
+            let start_path = dir.join("charge_control_start_threshold");
+            let end_path = dir.join("charge_control_end_threshold");
+            let write = |path: &Path, value: u8| {
+                fs::write(path, value.to_string())
+                    .context(format!("Failed to write {}", path.display()))
+            };
+            
+            if !start_path.exists() {
+                write(&end_path, end)?;
+                continue;
+            }
+            // The driver rejects start >= end at any time, so pick the order
+            // that keeps the pair valid in between
+            let current_end: Option<u8> = fs::read_to_string(&end_path)
+                .ok()
+                .and_then(|value| value.trim().parse().ok());
+            if current_end.is_some_and(|current_end| start < current_end) {
+                write(&start_path, start)?;
+                write(&end_path, end)?;
+            } else {
+                write(&end_path, end)?;
+                write(&start_path, start)?;
+            }
+        }
+        
+        println!("  ✓ Charge thresholds: {}-{}%", start, end);
         Ok(())
     }
     
     /// Disable frequency limits (maximum performance mode for AMD)
-    pub fn set_maximum_performance(&self) -> Result<()> {
-        let cpu_count = self.get_cpu_count()?;
+    pub fn set_maximum_performance(&self) -> HardwareResult<()> {
+        // Keeps reconciliation from restoring the profile's limits
+        let _applied_cpu = self.applied_cpu.lock().unwrap();
+        self.max_performance.store(true, Ordering::Relaxed);
         
-        for cpu in 0..cpu_count {
-            let cpu_path = self.cpu_base_path.join(format!("cpu{}/cpufreq", cpu));
+        for dir in self.cpufreq_dirs()? {
+            let cpu_path = dir.path;
             
             // Read available frequencies
             let max_freq_path = cpu_path.join("cpuinfo_max_freq");
             if max_freq_path.exists() {
-                let max_freq_khz: u32 = fs::read_to_string(&max_freq_path)?
+                let max_freq_khz: u32 = fs::read_to_string(&max_freq_path)
+                    .context("Failed to read max frequency")?
                     .trim()
                     .parse()
                     .context("Failed to parse max frequency")?;
@@ -398,6 +1566,9 @@ impl HardwareController {
             max_freq_mhz: None,
             disable_boost: false,
             smt_enabled: true,
+            energy_performance_preference: None,
+            amd_prefcore: None,
+            fixed_freq_mhz: None,
         })?;
         
         // Enable boost
@@ -408,6 +1579,50 @@ impl HardwareController {
     }
 }
 
+fn read_max_backlight(base_path: &Path) -> Result<u32> {
+    fs::read_to_string(base_path.join("max_brightness"))?
+        .trim()
+        .parse()
+        .context("Failed to parse max_brightness")
+}
+
+fn read_backlight_percent(base_path: &Path, perceptual: bool) -> Option<u8> {
+    let max_brightness = read_max_backlight(base_path).ok().filter(|max| *max > 0)?;
+    let brightness: u32 = fs::read_to_string(base_path.join("brightness")).ok()?.trim().parse().ok()?;
+    if perceptual {
+        return Some(perceptual_percentage(brightness, max_brightness));
+    }
+    Some((brightness * 100 / max_brightness).min(100) as u8)
+}
+
+fn write_backlight_percent(base_path: &Path, brightness: u8, perceptual: bool) -> Result<()> {
+    let max_brightness = read_max_backlight(base_path)?;
+    let actual_brightness = if perceptual {
+        perceptual_raw(brightness, max_brightness)
+    } else {
+        ((brightness as f32 / 100.0) * max_brightness as f32) as u32
+    };
+    
+    fs::write(base_path.join("brightness"), actual_brightness.to_string())
+        .context("Failed to write brightness")
+}
+
+/// Write to every device, a monitor that was unplugged doesn't stop the others
+fn write_backlights_percent(devices: &[PathBuf], brightness: u8, perceptual: bool) -> Result<()> {
+    let mut result = Ok(());
+    for device in devices {
+        if let Err(e) = write_backlight_percent(device, brightness, perceptual) {
+            result = Err(e.context(format!("Failed to set brightness of {}", device.display())));
+        }
+    }
+    result
+}
+
+/// Read a frequency value (in kHz) from a cpufreq file
+fn read_khz(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
 /// Check if we have necessary permissions for hardware control
 pub fn check_permissions() -> Result<bool> {
     // Test write access to a common sysfs path
@@ -445,6 +1660,82 @@ mod tests {
         }
     }
     
+    fn controller_with_mock_keyboard(temp_dir: &tempfile::TempDir) -> HardwareController {
+        let kbd_path = temp_dir.path().join("rgb:kbd_backlight");
+        fs::create_dir_all(&kbd_path).unwrap();
+        fs::write(kbd_path.join("max_brightness"), "255").unwrap();
+        fs::write(kbd_path.join("brightness"), "128").unwrap();
+        fs::write(kbd_path.join("multi_intensity"), "255 255 255").unwrap();
+        
+        HardwareController {
+            root: temp_dir.path().to_path_buf(),
+            cpu_base_path: temp_dir.path().join("cpu"),
+            keyboard: Some(KeyboardController::with_path(kbd_path).unwrap()),
+            smooth_brightness: AtomicBool::new(false),
+            brightness_all_displays: AtomicBool::new(false),
+            perceptual_brightness: AtomicBool::new(false),
+            screen_fade: BrightnessFade::default(),
+            keyboard_fade: BrightnessFade::default(),
+            color_temperature: ColorTemperature::default(),
+            applied_cpu: Mutex::new(None),
+            max_performance: AtomicBool::new(false),
+            pwm_max: Mutex::new(HashMap::new()),
+            fan_generation: AtomicU64::new(0),
+        }
+    }
+    
+    #[test]
+    fn test_keyboard_fade() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let controller = controller_with_mock_keyboard(&temp_dir);
+        let kbd = controller.keyboard.as_ref().unwrap();
+        let mut profile = Profile::default_profile();
+        controller.set_smooth_brightness(true);
+        
+        profile.keyboard_backlight.brightness = 100;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        thread::sleep(FADE_DURATION * 2);
+        assert_eq!(kbd.get_brightness().unwrap(), 100);
+        
+        // An immediate change cancels the running fade
+        profile.keyboard_backlight.brightness = 0;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        controller.set_smooth_brightness(false);
+        profile.keyboard_backlight.brightness = 100;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        thread::sleep(FADE_DURATION * 2);
+        assert_eq!(kbd.get_brightness().unwrap(), 100);
+    }
+    
+    #[test]
+    fn test_keyboard_modes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let controller = controller_with_mock_keyboard(&temp_dir);
+        let kbd = controller.keyboard.as_ref().unwrap();
+        let mut profile = Profile::default_profile();
+        
+        profile.keyboard_backlight.mode = KeyboardMode::Unmanaged;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        assert_eq!(kbd.get_brightness().unwrap(), 50);
+        
+        profile.keyboard_backlight.mode = KeyboardMode::Off;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        assert_eq!(kbd.get_brightness().unwrap(), 0);
+        
+        profile.keyboard_backlight.mode = KeyboardMode::Managed;
+        profile.keyboard_backlight.color.g = 0;
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        assert_eq!(kbd.get_color().unwrap(), (255, 0, 255));
+        assert!(kbd.is_on().unwrap());
+        
+        // Dimmed on battery, back to normal on AC
+        profile.keyboard_backlight.battery_brightness = Some(0);
+        controller.apply_keyboard_settings(&profile, true).unwrap();
+        assert!(!kbd.is_on().unwrap());
+        controller.apply_keyboard_settings(&profile, false).unwrap();
+        assert!(kbd.is_on().unwrap());
+    }
+    
     #[test]
     fn test_profile_application() {
         if cfg!(target_os = "linux") {
@@ -456,4 +1747,476 @@ mod tests {
             }
         }
     }
+    
+    #[test]
+    fn test_apply_profile_to_fixture() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        let profile = Profile::default_profile();
+        
+        controller.apply_profile(&profile).unwrap();
+        
+        let curve = &profile.fan_curves["fan1"];
+        let last = curve.points.len() - 1;
+        assert_eq!(
+            fixture.read(&format!("/sys/devices/platform/tuxedo_io/fan1_temp{last}")),
+            curve.points[last].temp.to_string()
+        );
+        assert_eq!(fixture.read("/sys/class/leds/rgb:kbd_backlight/brightness"), "127");
+    }
+    
+    #[test]
+    fn test_apply_partial() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        fixture.write("/sys/class/backlight/intel_backlight/max_brightness", "1000\n");
+        fixture.write("/sys/class/backlight/intel_backlight/brightness", "100\n");
+        let controller = fixture.controller();
+        let mut profile = Profile::default_profile();
+        profile.keyboard_backlight.color = RGBColor { r: 255, g: 0, b: 0 };
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+        let governor = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor";
+        let fan_temp = "/sys/devices/platform/tuxedo_io/fan1_temp7";
+        let screen = "/sys/class/backlight/intel_backlight/brightness";
+        
+        controller.apply_partial(&profile, ApplyMask::KEYBOARD).unwrap();
+        assert_eq!(fixture.read("/sys/class/leds/rgb:kbd_backlight/multi_intensity"), "255 0 0");
+        // Masked out parts are left alone
+        assert_eq!(fixture.read(governor), "powersave\n");
+        assert_eq!(fixture.read(fan_temp), "0\n");
+        assert_eq!(fixture.read(screen), "100\n");
+        
+        controller.apply_partial(&profile, ApplyMask::CPU | ApplyMask::SCREEN).unwrap();
+        assert_eq!(fixture.read(governor), "performance");
+        assert_ne!(fixture.read(screen), "100\n");
+        assert_eq!(fixture.read(fan_temp), "0\n");
+        
+        controller.apply_partial(&profile, ApplyMask::FANS).unwrap();
+        assert_eq!(fixture.read(fan_temp), profile.fan_curves["fan1"].points[7].temp.to_string());
+    }
+    
+    #[test]
+    fn test_reconcile_cpu_settings() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        for cpu in 0..crate::test_fixtures::CPU_COUNT {
+            let cpufreq = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq");
+            fixture.write(&format!("{cpufreq}/scaling_min_freq"), "400000\n");
+            fixture.write(&format!("{cpufreq}/scaling_max_freq"), "4800000\n");
+        }
+        fixture.write("/sys/devices/system/cpu/cpufreq/boost", "1\n");
+        let controller = fixture.controller();
+        let governor = "/sys/devices/system/cpu/cpu1/cpufreq/scaling_governor";
+        
+        // Nothing applied yet
+        assert!(!controller.reconcile_cpu_settings().unwrap());
+        
+        let mut profile = Profile::default_profile();
+        profile.cpu_settings.max_freq_mhz = Some(3000);
+        controller.apply_profile(&profile).unwrap();
+        let applied = controller.applied_state().unwrap();
+        assert_eq!(applied.cpus[1].max_freq_khz, Some(3_000_000));
+        assert!(!controller.reconcile_cpu_settings().unwrap());
+        
+        // Firmware resets after resume
+        fixture.write(governor, "performance\n");
+        fixture.write("/sys/devices/system/cpu/cpu2/cpufreq/scaling_max_freq", "4800000\n");
+        assert_eq!(controller.read_applied_state().unwrap().differences(&applied).len(), 2);
+        assert!(controller.reconcile_cpu_settings().unwrap());
+        assert_eq!(controller.read_applied_state().unwrap(), applied);
+        
+        // Maximum performance isn't undone
+        controller.set_maximum_performance().unwrap();
+        fixture.write(governor, "powersave\n");
+        assert!(!controller.reconcile_cpu_settings().unwrap());
+        assert_eq!(fixture.read(governor), "powersave\n");
+    }
+    
+    #[test]
+    fn test_parse_bracketed_choice() {
+        let (choices, current) = parse_bracketed_choice("default performance [powersave] powersupersave\n");
+        assert_eq!(choices, ["default", "performance", "powersave", "powersupersave"]);
+        assert_eq!(current.as_deref(), Some("powersave"));
+        assert_eq!(parse_bracketed_choice("[default] performance").1.as_deref(), Some("default"));
+        assert_eq!(parse_bracketed_choice("default performance").1, None);
+        assert_eq!(parse_bracketed_choice("performance\n").1.as_deref(), Some("performance"));
+        assert_eq!(parse_bracketed_choice(""), (Vec::new(), None));
+    }
+    
+    #[test]
+    fn test_aspm_policy() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        let mut profile = Profile::default_profile();
+        profile.aspm_policy = Some("powersupersave".to_string());
+        
+        // Skipped without the module parameter
+        assert_eq!(controller.aspm_policies(), None);
+        assert!(controller.set_aspm_policy("powersave").is_err());
+        controller.apply_profile(&profile).unwrap();
+        
+        fixture.write(ASPM_POLICY_PATH, "[default] performance powersave powersupersave\n");
+        assert!(controller.set_aspm_policy("turbo").is_err());
+        controller.apply_profile(&profile).unwrap();
+        assert_eq!(fixture.read(ASPM_POLICY_PATH), "powersupersave");
+    }
+    
+    #[test]
+    fn test_governor_choice() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        let cpu0 = "/sys/devices/system/cpu/cpu0/cpufreq";
+        let set_driver = |driver: &str, governors: &str| {
+            for cpu in 0..crate::test_fixtures::CPU_COUNT {
+                let cpufreq = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq");
+                fixture.write(&format!("{cpufreq}/scaling_driver"), driver);
+                fixture.write(&format!("{cpufreq}/scaling_available_governors"), governors);
+                fixture.write(&format!("{cpufreq}/energy_performance_preference"), "default");
+            }
+        };
+        let applied = |profile: &Profile| {
+            controller.apply_profile(profile).unwrap();
+            (
+                fixture.read(&format!("{cpu0}/scaling_governor")),
+                fixture.read(&format!("{cpu0}/energy_performance_preference")),
+            )
+        };
+        let mut profile = Profile::default_profile();
+        
+        // Active mode: powersave with the EPP, not schedutil
+        for driver in ["amd-pstate-epp\n", "intel_pstate\n"] {
+            set_driver(driver, "performance powersave\n");
+            profile.cpu_settings.performance_profile = CpuPerformanceProfile::Balanced;
+            assert_eq!(applied(&profile), ("powersave".into(), "balance_performance".into()), "{driver}");
+            profile.cpu_settings.performance_profile = CpuPerformanceProfile::PowerSave;
+            assert_eq!(applied(&profile), ("powersave".into(), "power".into()), "{driver}");
+            // The performance governor implies the EPP
+            profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+            assert_eq!(applied(&profile).0, "performance", "{driver}");
+        }
+        
+        // The profile's own preference wins
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Balanced;
+        profile.cpu_settings.energy_performance_preference = Some("balance_power".to_string());
+        assert_eq!(applied(&profile).1, "balance_power");
+        profile.cpu_settings.energy_performance_preference = None;
+        
+        // Passive mode and generic drivers use the generic governors
+        for driver in ["intel_cpufreq\n", "amd-pstate\n", "acpi-cpufreq\n"] {
+            set_driver(driver, "conservative ondemand userspace powersave performance schedutil\n");
+            assert_eq!(applied(&profile), ("schedutil".into(), "default".into()), "{driver}");
+        }
+        set_driver("acpi-cpufreq\n", "ondemand powersave performance\n");
+        assert_eq!(applied(&profile).0, "ondemand");
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::PowerSave;
+        assert_eq!(applied(&profile).0, "powersave");
+    }
+    
+    #[test]
+    fn test_fixed_frequency() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        let mut profile = Profile::default_profile();
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+        profile.cpu_settings.fixed_freq_mhz = Some(2400);
+        
+        // amd-pstate-epp has no userspace governor, the profile's is kept
+        assert_eq!(controller.fixed_frequency_support(), None);
+        controller.apply_profile(&profile).unwrap();
+        let cpu0 = "/sys/devices/system/cpu/cpu0/cpufreq";
+        assert_eq!(fixture.read(&format!("{cpu0}/scaling_governor")), "performance");
+        
+        for cpu in 0..crate::test_fixtures::CPU_COUNT {
+            let cpufreq = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq");
+            fixture.write(&format!("{cpufreq}/scaling_available_governors"), "userspace schedutil performance\n");
+            fixture.write(&format!("{cpufreq}/scaling_available_frequencies"), "2800000 2400000 1800000\n");
+        }
+        let support = controller.fixed_frequency_support().unwrap();
+        assert_eq!(support.available_mhz, [1800, 2400, 2800]);
+        assert!(support.check(2400).is_ok());
+        assert!(support.check(2500).is_err());
+        assert_eq!(support.nearest(2500), 2400);
+        assert_eq!(support.nearest(5000), 2800);
+        
+        controller.apply_profile(&profile).unwrap();
+        assert_eq!(fixture.read(&format!("{cpu0}/scaling_governor")), "userspace");
+        assert_eq!(fixture.read(&format!("{cpu0}/scaling_setspeed")), "2400000");
+        
+        // Not in the list
+        profile.cpu_settings.fixed_freq_mhz = Some(2500);
+        controller.apply_profile(&profile).unwrap();
+        assert_eq!(fixture.read(&format!("{cpu0}/scaling_governor")), "performance");
+        
+        // Drivers without a list take anything in the hardware range
+        let support = FixedFrequencySupport { available_mhz: Vec::new(), ..support };
+        assert_eq!((support.min_mhz, support.max_mhz), (Some(400), Some(4800)));
+        assert!(support.check(2500).is_ok());
+        assert!(support.check(5000).is_err());
+        assert_eq!(support.nearest(2500), 2500);
+        assert_eq!((support.nearest(100), support.nearest(5000)), (400, 4800));
+    }
+    
+    #[test]
+    fn test_cpufreq_policies() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // cpu0/cpu1 and cpu2 share a policy each, cpu3 isn't claimed. cpu1
+        // is offline, so only `related_cpus` still lists it.
+        for (policy, related, affected) in [("policy0", "0 1\n", "0\n"), ("policy2", "2\n", "2\n")] {
+            let path = format!("/sys/devices/system/cpu/cpufreq/{policy}");
+            fixture.write(&format!("{path}/related_cpus"), related);
+            fixture.write(&format!("{path}/affected_cpus"), affected);
+            fixture.write(&format!("{path}/scaling_governor"), "powersave\n");
+            fixture.write(&format!("{path}/cpuinfo_min_freq"), "400000\n");
+            fixture.write(&format!("{path}/cpuinfo_max_freq"), "4800000\n");
+            fixture.write(&format!("{path}/scaling_min_freq"), "400000\n");
+            fixture.write(&format!("{path}/scaling_max_freq"), "4800000\n");
+        }
+        fixture.write("/sys/devices/system/cpu/cpu3/cpufreq/scaling_max_freq", "4800000\n");
+        let controller = fixture.controller();
+        
+        let mut profile = Profile::default_profile();
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+        profile.cpu_settings.max_freq_mhz = Some(3000);
+        controller.apply_profile(&profile).unwrap();
+        
+        let cpufreq = "/sys/devices/system/cpu/cpufreq";
+        assert_eq!(fixture.read(&format!("{cpufreq}/policy0/scaling_governor")), "performance");
+        assert_eq!(fixture.read(&format!("{cpufreq}/policy2/scaling_max_freq")), "3000000");
+        assert_eq!(fixture.read("/sys/devices/system/cpu/cpu3/cpufreq/scaling_governor"), "performance");
+        assert_eq!(fixture.read("/sys/devices/system/cpu/cpu3/cpufreq/scaling_max_freq"), "3000000");
+        // Covered CPUs aren't written one by one
+        assert_eq!(fixture.read("/sys/devices/system/cpu/cpu1/cpufreq/scaling_governor"), "powersave\n");
+        
+        // The state of each CPU is read from its policy
+        let applied = controller.applied_state().unwrap();
+        assert_eq!(applied.cpus[1].governor.as_deref(), Some("performance"));
+        assert_eq!(applied.cpus[1].max_freq_khz, Some(3_000_000));
+        assert!(!controller.reconcile_cpu_settings().unwrap());
+    }
+    
+    #[test]
+    fn test_gpu_power_cap() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        let gpu_hwmon = "/sys/class/drm/card0/device/hwmon/hwmon9";
+        
+        // Without power1_cap, like on Intel and NVIDIA
+        assert!(controller.gpu_power_caps().is_empty());
+        assert!(controller.set_gpu_power_cap("card0", 50_000_000).is_err());
+        
+        fixture.write(&format!("{gpu_hwmon}/power1_cap"), "80000000\n");
+        fixture.write(&format!("{gpu_hwmon}/power1_cap_min"), "30000000\n");
+        fixture.write(&format!("{gpu_hwmon}/power1_cap_max"), "100000000\n");
+        let caps = controller.gpu_power_caps();
+        assert_eq!(caps.len(), 1);
+        assert_eq!(caps[0].card, "card0");
+        assert_eq!(caps[0].cap_uw, 80_000_000);
+        
+        assert_eq!(controller.set_gpu_power_cap("card0", 50_000_000).unwrap(), 50_000_000);
+        assert_eq!(fixture.read(&format!("{gpu_hwmon}/power1_cap")), "50000000");
+        assert_eq!(controller.set_gpu_power_cap("card0", 500_000_000).unwrap(), 100_000_000);
+        assert_eq!(controller.set_gpu_power_cap("card0", 0).unwrap(), 30_000_000);
+        assert!(controller.set_gpu_power_cap("card1", 50_000_000).is_err());
+        
+        // The monitor reports it with the GPU
+        let stats = fixture.monitor().get_system_stats().unwrap();
+        assert_eq!(stats.gpus[0].power_cap.as_ref().unwrap().cap_uw, 30_000_000);
+    }
+    
+    #[test]
+    fn test_gpu_fan_speed() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let gpu_hwmon = "/sys/class/drm/card0/device/hwmon/hwmon9";
+        fixture.write(&format!("{gpu_hwmon}/pwm1"), "0\n");
+        fixture.write(&format!("{gpu_hwmon}/pwm1_enable"), "2\n");
+        std::os::unix::fs::symlink(
+            fixture.path(gpu_hwmon),
+            fixture.path("/sys/class/hwmon/hwmon9"),
+        )
+        .unwrap();
+        let controller = fixture.controller();
+        
+        controller.set_fan_speed("card0_fan1", 100).unwrap();
+        assert_eq!(fixture.read(&format!("{gpu_hwmon}/pwm1_enable")), "1");
+        assert_eq!(fixture.read(&format!("{gpu_hwmon}/pwm1")), "255");
+        
+        // Chassis fan1 is never looked up in the GPU hwmon
+        controller.set_fan_speed("fan1", 20).unwrap();
+        assert_eq!(fixture.read("/sys/class/hwmon/hwmon2/pwm1"), "51");
+        assert_eq!(fixture.read(&format!("{gpu_hwmon}/pwm1")), "255");
+        
+        assert!(controller.set_fan_speed("card0_fan2", 50).is_err());
+        
+        controller.restore_auto_fan_control().unwrap();
+        assert_eq!(fixture.read(&format!("{gpu_hwmon}/pwm1_enable")), "2");
+    }
+    
+    #[test]
+    fn test_pwm_scaling() {
+        assert_eq!(pwm_value(0, DEFAULT_PWM_MAX), 0);
+        assert_eq!(pwm_value(50, DEFAULT_PWM_MAX), 128);
+        assert_eq!(pwm_value(100, DEFAULT_PWM_MAX), 255);
+        assert_eq!(pwm_value(150, DEFAULT_PWM_MAX), 255);
+        assert_eq!(pwm_value(33, 100), 33);
+        assert_eq!(pwm_value(50, 7), 4);
+        assert_eq!(pwm_value(100, 7), 7);
+        
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let pwm = "/sys/class/hwmon/hwmon2/pwm1";
+        let controller = fixture.controller();
+        controller.set_fan_speed("fan1", 60).unwrap();
+        assert_eq!(fixture.read(pwm), "153");
+        
+        // A controller taking 0-100 gets percent, not 0-255
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        fixture.write(&format!("{pwm}_max"), "100\n");
+        let controller = fixture.controller();
+        controller.set_fan_speed("fan1", 60).unwrap();
+        assert_eq!(fixture.read(pwm), "60");
+        // Read once per fan
+        fixture.write(&format!("{pwm}_max"), "255\n");
+        controller.set_fan_speed("fan1", 100).unwrap();
+        assert_eq!(fixture.read(pwm), "100");
+        
+        // Nonsense falls back to the default
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        fixture.write(&format!("{pwm}_max"), "0\n");
+        let controller = fixture.controller();
+        controller.set_fan_speed("fan1", 100).unwrap();
+        assert_eq!(fixture.read(pwm), "255");
+    }
+    
+    #[test]
+    fn test_charge_thresholds() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        assert!(controller.set_charge_thresholds(80, 80).is_err());
+        // The fixture battery has no thresholds
+        assert!(controller.set_charge_thresholds(75, 80).is_err());
+        
+        fixture.write("/sys/devices/platform/tuxedo_io/charge_control_end_threshold", "100\n");
+        let (start, end) = ChargePreset::Travel.thresholds();
+        controller.set_charge_thresholds(start, end).unwrap();
+        assert_eq!(fixture.read("/sys/devices/platform/tuxedo_io/charge_control_end_threshold"), "60");
+        
+        // The standard attributes win over tuxedo_io
+        let battery = "/sys/class/power_supply/BAT0";
+        fixture.write(&format!("{battery}/charge_control_start_threshold"), "40\n");
+        fixture.write(&format!("{battery}/charge_control_end_threshold"), "50\n");
+        let (start, end) = ChargePreset::Balanced.thresholds();
+        controller.set_charge_thresholds(start, end).unwrap();
+        assert_eq!(fixture.read(&format!("{battery}/charge_control_start_threshold")), "75");
+        assert_eq!(fixture.read(&format!("{battery}/charge_control_end_threshold")), "80");
+        assert_eq!(fixture.read("/sys/devices/platform/tuxedo_io/charge_control_end_threshold"), "60");
+    }
+    
+    #[test]
+    fn test_brightness_all_displays() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        for (device, max) in [("ddcci5", "100"), ("intel_backlight", "96000")] {
+            fixture.write(&format!("/sys/class/backlight/{device}/max_brightness"), &format!("{max}\n"));
+            fixture.write(&format!("/sys/class/backlight/{device}/brightness"), "0\n");
+        }
+        let controller = fixture.controller();
+        let mut settings = Profile::default_profile().screen_settings;
+        settings.brightness = 50;
+        
+        // Only the built-in panel by default
+        controller.apply_screen_brightness(&settings).unwrap();
+        assert_eq!(fixture.read("/sys/class/backlight/intel_backlight/brightness"), "48000");
+        assert_eq!(fixture.read("/sys/class/backlight/ddcci5/brightness"), "0\n");
+        
+        // Scaled to each maximum
+        controller.set_brightness_all_displays(true);
+        settings.brightness = 25;
+        controller.apply_screen_brightness(&settings).unwrap();
+        assert_eq!(fixture.read("/sys/class/backlight/intel_backlight/brightness"), "24000");
+        assert_eq!(fixture.read("/sys/class/backlight/ddcci5/brightness"), "25");
+        
+        // A profile overrides the app setting
+        settings.apply_to_all_displays = Some(false);
+        settings.brightness = 100;
+        controller.apply_screen_brightness(&settings).unwrap();
+        assert_eq!(fixture.read("/sys/class/backlight/intel_backlight/brightness"), "96000");
+        assert_eq!(fixture.read("/sys/class/backlight/ddcci5/brightness"), "25");
+        
+        // Gamma curve instead of linear
+        controller.set_perceptual_brightness(true);
+        settings.brightness = 50;
+        controller.apply_screen_brightness(&settings).unwrap();
+        assert_eq!(fixture.read("/sys/class/backlight/intel_backlight/brightness"), "20893");
+    }
+    
+    #[test]
+    fn test_fan_control_supported() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        assert!(fixture.controller().fan_control_supported());
+        
+        // tuxedo_io reporting no fan control, hwmon PWM is still usable
+        fixture.write("/sys/devices/platform/tuxedo_io/fan_control", "0\n");
+        assert!(fixture.controller().fan_control_supported());
+        
+        std::fs::remove_file(fixture.path("/sys/class/hwmon/hwmon2/pwm1_enable")).unwrap();
+        assert!(!fixture.controller().fan_control_supported());
+        
+        fixture.write("/sys/devices/platform/tuxedo_io/fan_control", "1\n");
+        assert!(fixture.controller().fan_control_supported());
+        
+        // Older modules without the attribute
+        std::fs::remove_file(fixture.path("/sys/devices/platform/tuxedo_io/fan_control")).unwrap();
+        assert!(fixture.controller().fan_control_supported());
+        std::fs::remove_dir_all(fixture.path("/sys/devices/platform/tuxedo_io")).unwrap();
+        assert!(!fixture.controller().fan_control_supported());
+        
+        // Only the ioctl device
+        fixture.write("/dev/tuxedo_io", "");
+        assert!(fixture.controller().fan_control_supported());
+    }
+    
+    #[test]
+    fn test_probe_fan_stop() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let hwmon = "/sys/class/hwmon/hwmon2";
+        
+        // The fixture's fan reports 2400 RPM whatever is written
+        let probes = fixture.controller().probe_fan_stop(Duration::ZERO);
+        assert_eq!(
+            probes,
+            [FanStopProbe { fan_id: "fan1".to_string(), rpm_at_zero: Some(2400) }]
+        );
+        assert_eq!(probes[0].summary(), "fan1: keeps spinning at 2400 RPM at 0%");
+        
+        // Previous mode and speed are restored
+        assert_eq!(fixture.read(&format!("{hwmon}/pwm1")), "153");
+        assert_eq!(fixture.read(&format!("{hwmon}/pwm1_enable")), "2");
+        
+        fixture.write(&format!("{hwmon}/fan1_input"), "0\n");
+        let probes = fixture.controller().probe_fan_stop(Duration::ZERO);
+        assert_eq!(probes[0].summary(), "fan1: stops at 0%");
+    }
+    
+    #[test]
+    fn test_amd_pstate_epp() {
+        assert_eq!(EppDriver::from_scaling_driver("amd-pstate"), Some(EppDriver::AmdPstate));
+        assert_eq!(EppDriver::from_scaling_driver("intel_pstate"), Some(EppDriver::IntelPstate));
+        assert_eq!(EppDriver::from_scaling_driver("acpi-cpufreq"), None);
+        
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let controller = fixture.controller();
+        
+        let support = controller.epp_support().unwrap();
+        assert_eq!(support.driver, EppDriver::AmdPstate);
+        assert_eq!(support.available.len(), 5);
+        assert_eq!(support.current.as_deref(), Some("balance_performance"));
+        assert_eq!(support.prefcore, Some(true));
+        
+        controller.set_energy_performance_preference("power").unwrap();
+        assert_eq!(
+            fixture.read("/sys/devices/system/cpu/cpu3/cpufreq/energy_performance_preference"),
+            "power"
+        );
+        assert!(controller.set_energy_performance_preference("turbo").is_err());
+        
+        // Drivers without EPP aren't touched
+        fixture.write("/sys/devices/system/cpu/cpu0/cpufreq/scaling_driver", "acpi-cpufreq\n");
+        assert!(controller.epp_support().is_none());
+    }
 }
@@ -1,8 +1,18 @@
 // src/hardware_monitor.rs
+use crate::app_config::{AppConfig, PowerUnit, TemperatureUnit};
+use crate::hardware_control::{pwm_percent, read_pwm_max};
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct CpuCoreInfo {
@@ -15,8 +25,131 @@ pub struct CpuCoreInfo {
 #[derive(Debug, Clone)]
 pub struct CpuInfo {
     pub cores: Vec<CpuCoreInfo>,
+    pub median_frequency_mhz: u32,
+    pub median_load_percent: f32,
+    pub mean_load_percent: f32,
+    pub max_core_load: f32,
+    pub max_core_temp: Option<f32>,
     pub package_temp: Option<f32>,
+    /// Package and die sensors by label, e.g. "Tdie", "Tccd1" and "Tccd2"
+    /// on Ryzen or "Package id 0" per socket. `package_temp` is the
+    /// hottest of them unless a sensor is pinned.
+    pub die_temps: Vec<(String, f32)>,
     pub package_power_watts: Option<f32>,
+    /// Sustained power limit, see [`HardwareMonitor::get_cpu_tdp`]
+    pub tdp_watts: Option<f32>,
+    /// Power of the RAPL subzones by zone name ("core", "uncore", "dram").
+    /// Zones the CPU doesn't have are missing.
+    pub power_breakdown: HashMap<String, f32>,
+    /// cpufreq driver, e.g. "intel_pstate", "amd-pstate-epp" or "acpi-cpufreq"
+    pub scaling_driver: String,
+    /// Mode of intel_pstate/amd_pstate ("active", "passive", "guided")
+    pub pstate_status: Option<String>,
+    pub base_freq_mhz: Option<u32>,
+    /// Whether any core currently runs above `base_freq_mhz`, i.e. boost
+    /// (turbo) is engaged rather than only enabled
+    pub boost_active: bool,
+    /// CPUs that are present but offline, not part of `cores`
+    pub offline_cores: Vec<usize>,
+}
+
+impl CpuInfo {
+    /// Build CPU info from per-core readings, computing the summary values
+    pub fn from_cores(
+        cores: Vec<CpuCoreInfo>,
+        package_temp: Option<f32>,
+        package_power_watts: Option<f32>,
+    ) -> Self {
+        let mut frequencies: Vec<f32> = cores.iter().map(|c| c.frequency_mhz as f32).collect();
+        let mut loads: Vec<f32> = cores.iter().map(|c| c.load_percent).collect();
+
+        let mean_load_percent = if loads.is_empty() {
+            0.0
+        } else {
+            loads.iter().sum::<f32>() / loads.len() as f32
+        };
+
+        let max_core_load = loads.iter().copied().fold(0.0, f32::max);
+
+        let max_core_temp = cores
+            .iter()
+            .filter_map(|c| c.temperature)
+            .reduce(f32::max);
+
+        CpuInfo {
+            median_frequency_mhz: median(&mut frequencies).round() as u32,
+            median_load_percent: median(&mut loads),
+            mean_load_percent,
+            max_core_load,
+            max_core_temp,
+            cores,
+            package_temp,
+            die_temps: Vec::new(),
+            package_power_watts,
+            tdp_watts: None,
+            power_breakdown: HashMap::new(),
+            scaling_driver: String::new(),
+            pstate_status: None,
+            base_freq_mhz: None,
+            boost_active: false,
+            offline_cores: Vec::new(),
+        }
+    }
+    
+    /// Driver summary, e.g. "intel_pstate (active), base 2400 MHz"
+    pub fn driver_summary(&self) -> String {
+        let mut summary = if self.scaling_driver.is_empty() {
+            "No cpufreq driver".to_string()
+        } else {
+            self.scaling_driver.clone()
+        };
+        if let Some(status) = &self.pstate_status {
+            summary.push_str(&format!(" ({})", status));
+        }
+        if let Some(base) = self.base_freq_mhz {
+            summary.push_str(&format!(", base {} MHz", base));
+        }
+        summary
+    }
+
+    /// Package power with its breakdown, e.g. "15.2 W (core 9.8 W, uncore 1.1 W)",
+    /// or "15.2 W (51% of 30 W TDP, core 9.8 W, ...)" in [`PowerUnit::PercentOfTdp`]
+    pub fn power_summary(&self, unit: PowerUnit) -> Option<String> {
+        let package = self.package_power_watts?;
+        let mut zones: Vec<_> = self.power_breakdown.iter().collect();
+        zones.sort_by(|a, b| a.0.cmp(b.0));
+        let details: Vec<String> = unit
+            .tdp_share(package, self.tdp_watts)
+            .into_iter()
+            .chain(zones.iter().map(|(zone, watts)| format!("{} {:.1} W", zone, watts)))
+            .collect();
+        if details.is_empty() {
+            return Some(format!("{:.1} W", package));
+        }
+        Some(format!("{:.1} W ({})", package, details.join(", ")))
+    }
+
+    /// Short load summary, e.g. "Load: 23% avg / 91% peak"
+    pub fn load_summary(&self) -> String {
+        format!(
+            "Load: {:.0}% avg / {:.0}% peak",
+            self.mean_load_percent, self.max_core_load
+        )
+    }
+}
+
+/// Median of a list of values (average of the two middle values for even lengths)
+fn median(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Both indices point at the same element for odd lengths
+    let lower = values[(values.len() - 1) / 2];
+    let upper = values[values.len() / 2];
+    (lower + upper) / 2.0
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -25,6 +158,106 @@ pub enum GpuType {
     Discrete,
 }
 
+/// GPU mode selected with prime-select or EnvyControl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuMode {
+    Integrated,
+    /// Both GPUs are on, the dGPU renders on demand (PRIME offload)
+    Hybrid,
+    Discrete,
+}
+
+impl GpuMode {
+    /// Parse the output of `prime-select query` or `envycontrol --query`
+    pub fn parse(output: &str) -> Option<Self> {
+        // Older EnvyControl versions print "Current graphics mode is: hybrid"
+        match output.split_whitespace().last()?.to_lowercase().as_str() {
+            "nvidia" => Some(GpuMode::Discrete),
+            "intel" | "integrated" => Some(GpuMode::Integrated),
+            "on-demand" | "hybrid" => Some(GpuMode::Hybrid),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GpuMode::Integrated => "Integrated GPU",
+            GpuMode::Hybrid => "Hybrid graphics",
+            GpuMode::Discrete => "Discrete GPU",
+        }
+    }
+
+    /// Ask the installed GPU switching tool for the selected mode
+    pub fn query() -> Option<Self> {
+        [("prime-select", "query"), ("envycontrol", "--query")]
+            .iter()
+            .find_map(|(program, arg)| {
+                let output = Command::new(program).arg(arg).output().ok()?;
+                if !output.status.success() {
+                    return None;
+                }
+                Self::parse(&String::from_utf8_lossy(&output.stdout))
+            })
+    }
+}
+
+/// GPU switch waiting for a restart, kept in the config until it took effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingGpuSwitch {
+    pub mode: GpuMode,
+    /// Boot the switch was requested in, see /proc/sys/kernel/random/boot_id
+    pub boot_id: String,
+}
+
+impl PendingGpuSwitch {
+    /// Whether the switch no longer waits for a restart: the system was
+    /// restarted, or the selection was changed back in the meantime.
+    /// `selected` is what the switching tool reports, `None` if unknown.
+    pub fn is_settled(&self, boot_id: &str, selected: Option<GpuMode>) -> bool {
+        match selected {
+            Some(mode) if mode != self.mode => true,
+            _ => self.boot_id != boot_id,
+        }
+    }
+}
+
+/// Power state of the discrete GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DgpuState {
+    /// `power/runtime_status` is "active"
+    pub runtime_active: bool,
+    /// Runtime PM is disabled (`power/control` is "on"), so the GPU never suspends
+    pub always_on: bool,
+    /// A connected display is attached to the dGPU
+    pub drives_output: bool,
+}
+
+/// Decide which GPU is doing the work. Rules in order of precedence:
+///
+/// 1. Without a discrete GPU the integrated one is active.
+/// 2. If prime-select/EnvyControl selected a single GPU, that GPU is active.
+/// 3. A runtime suspended dGPU means the integrated GPU is active.
+/// 4. An awake dGPU that is allowed to suspend is in use, e.g. by a game
+///    using PRIME offload, so it is active.
+/// 5. A dGPU that never suspends is only active if it drives a display.
+pub(crate) fn resolve_active_gpu(mode: Option<GpuMode>, dgpu: Option<DgpuState>) -> GpuType {
+    let Some(dgpu) = dgpu else {
+        return GpuType::Integrated;
+    };
+
+    match mode {
+        Some(GpuMode::Discrete) => GpuType::Discrete,
+        Some(GpuMode::Integrated) => GpuType::Integrated,
+        Some(GpuMode::Hybrid) | None => {
+            if dgpu.runtime_active && (!dgpu.always_on || dgpu.drives_output) {
+                GpuType::Discrete
+            } else {
+                GpuType::Integrated
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GpuInfo {
     pub name: String,
@@ -33,14 +266,157 @@ pub struct GpuInfo {
     pub temperature: Option<f32>,
     pub load_percent: Option<f32>,
     pub power_watts: Option<f32>,
+    pub vram_used_mb: Option<u32>,
+    pub vram_total_mb: Option<u32>,
+    /// Ids of the fans attached to this GPU, empty if it has none
+    pub fan_ids: Vec<String>,
+    /// Adjustable power limit, only amdgpu has one
+    pub power_cap: Option<GpuPowerCap>,
+}
+
+/// Power limit of an amdgpu card, `power1_cap` in its hwmon directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuPowerCap {
+    /// DRM card, e.g. "card1"
+    pub card: String,
+    pub cap_uw: u64,
+    /// 0 if the driver doesn't report a lower bound
+    pub min_uw: u64,
+    pub max_uw: u64,
+}
+
+impl GpuPowerCap {
+    /// Read the limit of `card` from `hwmon`, `None` without `power1_cap`
+    pub(crate) fn read(card: &str, hwmon: &Path) -> Option<Self> {
+        let read_uw = |file: &str| -> Option<u64> {
+            fs::read_to_string(hwmon.join(file)).ok()?.trim().parse().ok()
+        };
+        Some(GpuPowerCap {
+            card: card.to_string(),
+            cap_uw: read_uw("power1_cap")?,
+            min_uw: read_uw("power1_cap_min").unwrap_or(0),
+            max_uw: read_uw("power1_cap_max")?,
+        })
+    }
+}
+
+impl GpuInfo {
+    /// Fraction of the VRAM in use (0.0 - 1.0), if the driver reports it
+    pub fn vram_usage(&self) -> Option<f32> {
+        match (self.vram_used_mb, self.vram_total_mb) {
+            (Some(used), Some(total)) if total > 0 => Some(used as f32 / total as f32),
+            _ => None,
+        }
+    }
+}
+
+/// VRAM usage as (used, total) in MB
+type VramInfo = (Option<u32>, Option<u32>);
+
+fn read_bytes_as_mb(path: &Path) -> Option<u32> {
+    let bytes: u64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some((bytes / (1024 * 1024)) as u32)
+}
+
+/// Id of a fan attached to a GPU, e.g. "card1_fan1". Chassis fans are
+/// plain "fanN".
+pub(crate) fn gpu_fan_id(card: &str, fan_num: usize) -> String {
+    format!("{}_fan{}", card, fan_num)
+}
+
+/// Split a GPU fan id into the DRM card and the fan number
+pub(crate) fn parse_gpu_fan_id(fan_id: &str) -> Option<(&str, usize)> {
+    let (card, fan) = fan_id.split_once('_')?;
+    let fan_num = fan.strip_prefix("fan")?.parse().ok()?;
+    card.starts_with("card").then_some((card, fan_num))
+}
+
+/// hwmon directories of the GPUs under `drm_path` (`/sys/class/drm`),
+/// together with the DRM card they belong to
+pub(crate) fn gpu_hwmon_dirs(drm_path: &Path) -> Vec<(String, PathBuf)> {
+    let mut dirs = Vec::new();
+    let Ok(entries) = fs::read_dir(drm_path) else {
+        return dirs;
+    };
+    
+    for entry in entries.flatten() {
+        let card = entry.file_name().to_string_lossy().into_owned();
+        // Skip connectors like card0-eDP-1
+        if !card.starts_with("card") || card.contains('-') {
+            continue;
+        }
+        if let Ok(hwmons) = fs::read_dir(entry.path().join("device/hwmon")) {
+            dirs.extend(hwmons.flatten().map(|hwmon| (card.clone(), hwmon.path())));
+        }
+    }
+    
+    dirs.sort();
+    dirs
 }
 
 #[derive(Debug, Clone)]
 pub struct FanInfo {
     pub fan_id: String,
     pub name: String,
+    /// Measured speed from `fanN_input`
     pub speed_rpm: Option<u32>,
-    pub speed_percent: Option<u8>,
+    /// Requested duty cycle from `pwmN`, scaled from 0-`pwmN_max`
+    pub requested_percent: Option<u8>,
+    /// DRM card (e.g. "card1") of the GPU the fan belongs to, `None` for
+    /// chassis fans
+    pub gpu: Option<String>,
+}
+
+impl FanInfo {
+    /// Name including the owning GPU, e.g. "GPU fan 1 (card1)"
+    pub fn display_name(&self) -> String {
+        match &self.gpu {
+            Some(card) => format!("{} ({})", self.name, card),
+            None => self.name.clone(),
+        }
+    }
+    
+    /// The fan should spin but doesn't, e.g. because it is seized or unplugged
+    pub fn is_stalled(&self) -> bool {
+        self.requested_percent.is_some_and(|percent| percent > 0) && self.speed_rpm == Some(0)
+    }
+
+    /// Speed summary, e.g. "requested 60% / 3200 RPM"
+    pub fn speed_summary(&self) -> String {
+        match (self.requested_percent, self.speed_rpm) {
+            (Some(percent), Some(rpm)) => format!("requested {}% / {} RPM", percent, rpm),
+            (Some(percent), None) => format!("requested {}%", percent),
+            (None, Some(rpm)) => format!("{} RPM", rpm),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatteryInfo {
+    pub name: String, // e.g. "BAT0"
+    pub charge_percent: Option<u8>,
+    pub status: Option<String>, // "Charging", "Discharging", "Full", ...
+    pub energy_full_wh: Option<f32>,
+    pub power_watts: Option<f32>,
+}
+
+impl BatteryInfo {
+    /// Charge summary, e.g. "BAT0: 73%, Discharging at 12.0 W"
+    pub fn summary(&self) -> String {
+        let charge = self
+            .charge_percent
+            .map_or("unknown".to_string(), |percent| format!("{}%", percent));
+        let mut summary = format!("{}: {}", self.name, charge);
+        if let Some(status) = &self.status {
+            summary.push_str(&format!(", {}", status));
+            // Full and idle batteries report 0 W
+            if let Some(watts) = self.power_watts.filter(|watts| *watts > 0.0) {
+                summary.push_str(&format!(" at {:.1} W", watts));
+            }
+        }
+        summary
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -48,13 +424,293 @@ pub struct SystemStats {
     pub cpu: CpuInfo,
     pub gpus: Vec<GpuInfo>,
     pub fans: Vec<FanInfo>,
+    pub batteries: Vec<BatteryInfo>,
     pub active_gpu: GpuType,
+    /// Sensor files that exist but couldn't be read or parsed, e.g.
+    /// "/sys/class/hwmon/hwmon2/temp1_input parse failed: abc". Missing
+    /// files aren't listed, most sensors simply don't exist everywhere.
+    pub warnings: Vec<String>,
+}
+
+impl SystemStats {
+    /// Combined charge of all batteries, weighted by their capacity
+    pub fn total_battery_percent(&self) -> Option<f32> {
+        aggregate_battery_percent(&self.batteries)
+    }
+
+    /// One line status for tooltips, e.g.
+    /// "CPU 64°C · GPU 52°C · Profile: Balanced". Missing temperatures are
+    /// left out.
+    pub fn status_summary(&self, unit: TemperatureUnit, profile: Option<&str>) -> String {
+        let cpu_temp = self.cpu.package_temp.or(self.cpu.max_core_temp);
+        let gpu_temp = self
+            .gpus
+            .iter()
+            .filter_map(|gpu| gpu.temperature)
+            .max_by(f32::total_cmp);
+
+        let mut parts = Vec::new();
+        if let Some(temp) = cpu_temp {
+            parts.push(format!("CPU {}", unit.format(temp)));
+        }
+        if let Some(temp) = gpu_temp {
+            parts.push(format!("GPU {}", unit.format(temp)));
+        }
+        if let Some(profile) = profile {
+            parts.push(format!("Profile: {}", profile));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Charge over several batteries. Falls back to a plain average if
+/// any battery doesn't report its capacity.
+pub(crate) fn aggregate_battery_percent(batteries: &[BatteryInfo]) -> Option<f32> {
+    let charged: Vec<(f32, Option<f32>)> = batteries
+        .iter()
+        .filter_map(|b| Some((b.charge_percent? as f32, b.energy_full_wh)))
+        .collect();
+    
+    if charged.is_empty() {
+        return None;
+    }
+    
+    let total_capacity: Option<f32> = charged.iter().map(|(_, capacity)| *capacity).sum();
+    match total_capacity {
+        Some(total) if total > 0.0 => Some(
+            charged
+                .iter()
+                .map(|(percent, capacity)| percent * capacity.unwrap_or(0.0))
+                .sum::<f32>()
+                / total,
+        ),
+        _ => Some(charged.iter().map(|(percent, _)| percent).sum::<f32>() / charged.len() as f32),
+    }
+}
+
+/// Model and firmware of the machine, from SMBIOS/DMI
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemInfo {
+    pub product_name: String,
+    pub manufacturer: String,
+    pub bios_version: String,
+    pub bios_date: String,
+    /// Embedded controller firmware, fan and thermal behavior depend on it
+    pub ec_firmware_version: String,
+}
+
+impl Default for SystemInfo {
+    fn default() -> Self {
+        let unknown = || "Unknown".to_string();
+        SystemInfo {
+            product_name: unknown(),
+            manufacturer: unknown(),
+            bios_version: unknown(),
+            bios_date: unknown(),
+            ec_firmware_version: unknown(),
+        }
+    }
+}
+
+impl SystemInfo {
+    /// Firmware summary, e.g. "BIOS 1.07.09 (05/17/2023), EC 1.14"
+    pub fn firmware_summary(&self) -> String {
+        format!(
+            "BIOS {} ({}), EC {}",
+            self.bios_version, self.bios_date, self.ec_firmware_version
+        )
+    }
+}
+
+/// One cache of the CPU, e.g. the L2 cache of each core
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheInfo {
+    pub level: u8,
+    /// "Data", "Instruction" or "Unified"
+    pub kind: String,
+    pub size_kib: u32,
+    /// Number of caches of this kind and size, one per group of CPUs
+    /// sharing it
+    pub instances: usize,
+}
+
+impl CacheInfo {
+    /// Name like lscpu uses it, e.g. "L1d" or "L3"
+    pub fn name(&self) -> String {
+        match self.kind.as_str() {
+            "Data" => format!("L{}d", self.level),
+            "Instruction" => format!("L{}i", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+
+    /// E.g. "L2: 8 × 1.25 MiB" or "L3: 16 MiB"
+    pub fn summary(&self) -> String {
+        let size = if self.size_kib >= 1024 {
+            let mib = format!("{:.2}", self.size_kib as f32 / 1024.0);
+            format!("{} MiB", mib.trim_end_matches('0').trim_end_matches('.'))
+        } else {
+            format!("{} KiB", self.size_kib)
+        };
+        if self.instances > 1 {
+            format!("{}: {} × {}", self.name(), self.instances, size)
+        } else {
+            format!("{}: {}", self.name(), size)
+        }
+    }
+}
+
+/// Cores, threads and caches of the CPU. Doesn't change while running,
+/// so the monitor reads it only once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuTopology {
+    pub packages: usize,
+    /// Logical CPUs (online ones only)
+    pub threads: usize,
+    /// Logical CPUs sharing a physical core, e.g. `[[0, 8], [1, 9], ...]`.
+    /// Without SMT each group has a single CPU.
+    pub sibling_groups: Vec<Vec<usize>>,
+    /// Sorted by level, L1 data before L1 instruction
+    pub caches: Vec<CacheInfo>,
+}
+
+impl CpuTopology {
+    pub fn cores(&self) -> usize {
+        self.sibling_groups.len()
+    }
+
+    /// E.g. "8 cores, 16 threads"
+    pub fn core_summary(&self) -> String {
+        format!("{} cores, {} threads", self.cores(), self.threads)
+    }
+
+    /// SMT siblings, e.g. "0+8, 1+9", `None` without SMT
+    pub fn siblings_summary(&self) -> Option<String> {
+        if self.sibling_groups.iter().all(|group| group.len() < 2) {
+            return None;
+        }
+        let groups: Vec<String> = self
+            .sibling_groups
+            .iter()
+            .map(|group| {
+                group.iter().map(usize::to_string).collect::<Vec<_>>().join("+")
+            })
+            .collect();
+        Some(groups.join(", "))
+    }
+}
+
+/// Parse a sysfs CPU list like "0-3,8,10-11"
+pub(crate) fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => cpus.extend(part.parse::<usize>().ok()),
+        }
+    }
+    cpus
+}
+
+/// Settings that rarely change and are therefore cached by the monitor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PowerSettings {
+    pub performance_profile: Option<String>,
+    pub charge_start_threshold: Option<u8>,
+    pub charge_end_threshold: Option<u8>,
+}
+
+const PERFORMANCE_PROFILE_PATHS: [&str; 2] = [
+    "/sys/devices/platform/tuxedo_io/performance_profile",
+    "/sys/firmware/acpi/platform_profile",
+];
+
+/// Attempts for reads on the polling hot path, keeps a poll fast even if
+/// every attempt fails
+const SYSFS_READ_ATTEMPTS: usize = 3;
+
+/// Delay before the first retry, doubled for every further attempt
+const SYSFS_RETRY_DELAY: Duration = Duration::from_millis(2);
+
+/// Read a sysfs file, retrying errors that only mean "try again"
+/// (EAGAIN/EBUSY/EINTR), as seen on `scaling_cur_freq` during
+/// frequency transitions
+pub(crate) fn read_sysfs_retry(path: &Path, attempts: usize) -> io::Result<String> {
+    retry_transient(attempts, || fs::read_to_string(path))
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    ) || err.raw_os_error() == Some(libc::EBUSY)
+}
+
+fn retry_transient<T, F>(attempts: usize, mut read: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut delay = SYSFS_RETRY_DELAY;
+    let mut attempt = 1;
+
+    loop {
+        match read() {
+            Err(err) if attempt < attempts && is_transient(&err) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Identifies a temperature sensor across reboots, the hwmon numbering
+/// itself isn't stable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TempSensorId {
+    /// hwmon chip name, e.g. "coretemp" or "acpitz"
+    pub chip: String,
+    /// Sensor label, or "tempN" if the chip provides none
+    pub label: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SensorDescriptor {
+    pub id: TempSensorId,
+    pub input_path: PathBuf,
+    pub temperature: Option<f32>,
 }
 
 pub struct HardwareMonitor {
+    /// Prefix for all sysfs/procfs paths, "/" except in tests
+    root: PathBuf,
     cpu_base_path: PathBuf,
     hwmon_paths: Vec<PathBuf>,
-    last_cpu_stats: Option<Vec<CpuStats>>,
+    last_cpu_stats: Option<HashMap<usize, CpuStats>>,
+    power_settings: Option<PowerSettings>,
+    cpu_temp_sensor: Option<TempSensorId>,
+    /// Queried once, a new mode only takes effect after a reboot
+    gpu_mode: OnceLock<Option<GpuMode>>,
+    /// Last energy counter of each RAPL zone, power is the delta over time
+    last_rapl: HashMap<PathBuf, RaplReading>,
+    /// Topology and the online CPUs it was read for, read again after a
+    /// CPU went on- or offline
+    topology: RefCell<Option<(Vec<usize>, CpuTopology)>>,
+    /// Failed sensor reads since the last [`Self::get_system_stats`]
+    read_warnings: RefCell<Vec<String>>,
+    /// Full speed value of each PWM attribute, see [`read_pwm_max`]
+    pwm_max: RefCell<HashMap<PathBuf, u32>>,
+}
+
+#[derive(Clone, Copy)]
+struct RaplReading {
+    energy_uj: u64,
+    time: Instant,
 }
 
 #[derive(Clone)]
@@ -70,18 +726,80 @@ struct CpuStats {
 
 impl HardwareMonitor {
     pub fn new() -> Result<Self> {
-        let cpu_base_path = PathBuf::from("/sys/devices/system/cpu");
-        let hwmon_paths = Self::discover_hwmon_paths()?;
+        let mut monitor = Self::with_root(Path::new("/"))?;
+        monitor.cpu_temp_sensor = AppConfig::load().cpu_temp_sensor;
+        Ok(monitor)
+    }
+    
+    /// Monitor for a sysfs tree mounted below `root` (for testing)
+    pub fn with_root(root: &Path) -> Result<Self> {
+        let cpu_base_path = root.join("sys/devices/system/cpu");
+        let hwmon_paths = Self::discover_hwmon_paths(&root.join("sys/class/hwmon"))?;
         
         Ok(HardwareMonitor {
+            root: root.to_path_buf(),
             cpu_base_path,
             hwmon_paths,
             last_cpu_stats: None,
+            power_settings: None,
+            cpu_temp_sensor: None,
+            gpu_mode: OnceLock::new(),
+            last_rapl: HashMap::new(),
+            topology: RefCell::new(None),
+            read_warnings: RefCell::new(Vec::new()),
+            pwm_max: RefCell::new(HashMap::new()),
         })
     }
     
-    fn discover_hwmon_paths() -> Result<Vec<PathBuf>> {
-        let hwmon_base = Path::new("/sys/class/hwmon");
+    /// Resolve an absolute sysfs/procfs path below the root
+    fn sys_path(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+    
+    /// Pin the sensor used for the CPU package temperature, `None` restores
+    /// the automatic detection
+    pub fn set_cpu_temp_sensor(&mut self, sensor: Option<TempSensorId>) {
+        self.cpu_temp_sensor = sensor;
+    }
+    
+    /// All temperature sensors of all hwmon chips with their current values
+    pub fn available_temp_sensors(&self) -> Vec<SensorDescriptor> {
+        let mut sensors = Vec::new();
+        
+        for hwmon_path in &self.hwmon_paths {
+            let Ok(chip) = fs::read_to_string(hwmon_path.join("name")) else {
+                continue;
+            };
+            let chip = chip.trim();
+            
+            for i in 1..=32 {
+                let input_path = hwmon_path.join(format!("temp{}_input", i));
+                if !input_path.exists() {
+                    continue;
+                }
+                
+                let label = fs::read_to_string(hwmon_path.join(format!("temp{}_label", i)))
+                    .map(|label| label.trim().to_string())
+                    .unwrap_or_else(|_| format!("temp{}", i));
+                let temperature = self
+                    .read_sensor::<i32>(&input_path)
+                    .map(|millidegrees| millidegrees as f32 / 1000.0);
+                
+                sensors.push(SensorDescriptor {
+                    id: TempSensorId {
+                        chip: chip.to_string(),
+                        label,
+                    },
+                    input_path,
+                    temperature,
+                });
+            }
+        }
+        
+        sensors
+    }
+    
+    fn discover_hwmon_paths(hwmon_base: &Path) -> Result<Vec<PathBuf>> {
         let mut paths = Vec::new();
         
         if !hwmon_base.exists() {
@@ -96,34 +814,344 @@ impl HardwareMonitor {
         Ok(paths)
     }
     
+    /// Model, BIOS and EC firmware. Missing values are "Unknown".
+    pub fn get_system_info(&self) -> SystemInfo {
+        let read = |path: PathBuf| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let dmi = |file: &str| read(self.sys_path("/sys/class/dmi/id").join(file));
+        let defaults = SystemInfo::default();
+        
+        // SMBIOS reports the EC release, some tuxedo_io versions export it too
+        let ec_firmware_version = dmi("ec_firmware_release")
+            .or_else(|| read(self.sys_path("/sys/devices/platform/tuxedo_io/fw_version")));
+        
+        SystemInfo {
+            product_name: dmi("product_name").unwrap_or(defaults.product_name),
+            manufacturer: dmi("sys_vendor").unwrap_or(defaults.manufacturer),
+            bios_version: dmi("bios_version").unwrap_or(defaults.bios_version),
+            bios_date: dmi("bios_date").unwrap_or(defaults.bios_date),
+            ec_firmware_version: ec_firmware_version.unwrap_or(defaults.ec_firmware_version),
+        }
+    }
+    
+    /// Cores, SMT siblings and caches of the online CPUs
+    pub fn cpu_topology(&self) -> CpuTopology {
+        let online = self.get_cpu_ids().map(|(online, _)| online).unwrap_or_default();
+        let mut cached = self.topology.borrow_mut();
+        match cached.as_ref() {
+            Some((cpus, topology)) if *cpus == online => topology.clone(),
+            _ => {
+                let topology = self.read_cpu_topology(&online);
+                *cached = Some((online, topology.clone()));
+                topology
+            }
+        }
+    }
+    
+    fn read_cpu_topology(&self, online: &[usize]) -> CpuTopology {
+        let read = |path: PathBuf| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+        
+        let mut packages = Vec::new();
+        let mut sibling_groups: Vec<Vec<usize>> = Vec::new();
+        // Size of each cache instance by (level, kind, CPUs sharing it). A
+        // hybrid CPU's P-cores and E-core clusters have L2s of their own size.
+        let mut cache_instances: HashMap<(u8, String, Vec<usize>), u32> = HashMap::new();
+        
+        for &cpu in online {
+            let cpu_path = self.cpu_base_path.join(format!("cpu{}", cpu));
+            
+            if let Some(package) = read(cpu_path.join("topology/physical_package_id")) {
+                if !packages.contains(&package) {
+                    packages.push(package);
+                }
+            }
+            
+            let mut siblings = read(cpu_path.join("topology/thread_siblings_list"))
+                .map(|list| parse_cpu_list(&list))
+                .filter(|siblings| siblings.contains(&cpu))
+                .unwrap_or_else(|| vec![cpu]);
+            siblings.sort_unstable();
+            if !sibling_groups.contains(&siblings) {
+                sibling_groups.push(siblings);
+            }
+            
+            let Ok(entries) = fs::read_dir(cpu_path.join("cache")) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let level = read(path.join("level")).and_then(|level| level.parse().ok());
+                let kind = read(path.join("type"));
+                // "48K", some architectures report "1M"
+                let size_kib = read(path.join("size")).and_then(|size| {
+                    match size.strip_suffix('M') {
+                        Some(mib) => mib.parse::<u32>().ok().map(|mib| mib * 1024),
+                        None => size.trim_end_matches('K').parse().ok(),
+                    }
+                });
+                let (Some(level), Some(kind), Some(size_kib)) = (level, kind, size_kib) else {
+                    continue;
+                };
+                let shared = read(path.join("shared_cpu_list"))
+                    .map(|list| parse_cpu_list(&list))
+                    .unwrap_or_else(|| vec![cpu]);
+                
+                cache_instances.insert((level, kind, shared), size_kib);
+            }
+        }
+        
+        sibling_groups.sort();
+        let mut instance_counts: HashMap<(u8, String, u32), usize> = HashMap::new();
+        for ((level, kind, _), size_kib) in cache_instances {
+            *instance_counts.entry((level, kind, size_kib)).or_default() += 1;
+        }
+        let mut caches: Vec<CacheInfo> = instance_counts
+            .into_iter()
+            .map(|((level, kind, size_kib), instances)| CacheInfo {
+                level,
+                kind,
+                size_kib,
+                instances,
+            })
+            .collect();
+        // "Data" < "Instruction" < "Unified"
+        caches.sort_by(|a, b| (a.level, &a.kind, a.size_kib).cmp(&(b.level, &b.kind, b.size_kib)));
+        
+        CpuTopology {
+            packages: packages.len().max(1),
+            threads: online.len(),
+            sibling_groups,
+            caches,
+        }
+    }
+    
+    /// Names of all discovered hwmon chips, e.g. "coretemp" or "amdgpu"
+    pub fn hwmon_chip_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .hwmon_paths
+            .iter()
+            .filter_map(|path| fs::read_to_string(path.join("name")).ok())
+            .map(|name| name.trim().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
     pub fn get_system_stats(&mut self) -> Result<SystemStats> {
+        self.read_warnings.borrow_mut().clear();
         Ok(SystemStats {
             cpu: self.get_cpu_info()?,
             gpus: self.get_gpu_info()?,
             fans: self.get_fan_info()?,
+            batteries: self.get_battery_info()?,
             active_gpu: self.get_active_gpu()?,
+            warnings: self.read_warnings.take(),
         })
     }
     
+    /// Read and parse a sensor value. A missing file only means the sensor
+    /// doesn't exist, but one that can't be read or parsed is recorded for
+    /// [`SystemStats::warnings`].
+    fn read_sensor<T: FromStr>(&self, path: &Path) -> Option<T> {
+        if !path.exists() {
+            return None;
+        }
+        let problem = match read_sysfs_retry(path, SYSFS_READ_ATTEMPTS) {
+            Ok(content) => match content.trim().parse() {
+                Ok(value) => return Some(value),
+                Err(_) => format!("parse failed: {}", content.trim()),
+            },
+            Err(e) => format!("read failed: {}", e),
+        };
+        
+        // Show paths like on a real system, also below a test root
+        let path = path.strip_prefix(&self.root).map_or(path.to_path_buf(), |p| Path::new("/").join(p));
+        let warning = format!("{} {}", path.display(), problem);
+        let mut warnings = self.read_warnings.borrow_mut();
+        // Some files are read more than once per poll
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+        None
+    }
+    
+    /// Performance profile and charge thresholds, cached until invalidated
+    pub fn get_power_settings(&mut self) -> PowerSettings {
+        if let Some(settings) = &self.power_settings {
+            return settings.clone();
+        }
+        let settings = Self::read_power_settings(|path| self.sys_path(path));
+        self.power_settings.insert(settings).clone()
+    }
+    
+    /// Drop the cached power settings, e.g. after another tool changed them
+    pub fn invalidate_power_settings(&mut self) {
+        self.power_settings = None;
+    }
+    
+    /// Measure the next CPU loads from now on, e.g. after stats weren't read
+    /// for a while. Otherwise the next loads are the average since the last
+    /// reading.
+    pub fn reset_load_baseline(&mut self) {
+        self.last_cpu_stats = self.read_cpu_stats().ok();
+    }
+    
+    /// Files backing [`PowerSettings`] that exist on this system
+    pub fn power_settings_paths() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = PERFORMANCE_PROFILE_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        
+        if let Some(battery) = Self::first_battery_path(Path::new("/sys/class/power_supply")) {
+            paths.push(battery.join("charge_control_start_threshold"));
+            paths.push(battery.join("charge_control_end_threshold"));
+        }
+        
+        paths.retain(|path| path.exists());
+        paths
+    }
+    
+    /// `online` files of all AC adapters
+    pub fn ac_online_paths() -> Vec<PathBuf> {
+        Self::find_ac_online_paths(Path::new("/sys/class/power_supply"))
+    }
+    
+    fn find_ac_online_paths(power_supply_path: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(power_supply_path) else {
+            return Vec::new();
+        };
+        
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                fs::read_to_string(path.join("type"))
+                    .map(|t| t.trim() == "Mains")
+                    .unwrap_or(false)
+            })
+            .map(|path| path.join("online"))
+            .filter(|path| path.exists())
+            .collect();
+        paths.sort();
+        paths
+    }
+    
+    /// Whether the system runs on battery. Systems without an AC adapter
+    /// (desktops) never do.
+    pub fn on_battery() -> bool {
+        Self::is_on_battery(&Self::ac_online_paths())
+    }
+    
+    fn is_on_battery(ac_online_paths: &[PathBuf]) -> bool {
+        !ac_online_paths.is_empty()
+            && !ac_online_paths.iter().any(|path| {
+                fs::read_to_string(path)
+                    .map(|online| online.trim() == "1")
+                    .unwrap_or(false)
+            })
+    }
+    
+    /// `dpms` files of all connected displays
+    pub fn display_dpms_paths() -> Vec<PathBuf> {
+        Self::find_display_dpms_paths(Path::new("/sys/class/drm"))
+    }
+    
+    fn find_display_dpms_paths(drm_path: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(drm_path) else {
+            return Vec::new();
+        };
+        
+        // Connectors are named like "card1-eDP-1", cards themselves have no dash
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.contains('-'))
+                    && fs::read_to_string(path.join("status"))
+                        .map(|status| status.trim() == "connected")
+                        .unwrap_or(false)
+            })
+            .map(|path| path.join("dpms"))
+            .filter(|path| path.exists())
+            .collect();
+        paths.sort();
+        paths
+    }
+    
+    /// Whether every connected display is powered off by DPMS, e.g. after
+    /// the idle timeout. False without any display found.
+    pub fn screens_off(dpms_paths: &[PathBuf]) -> bool {
+        !dpms_paths.is_empty()
+            && dpms_paths.iter().all(|path| {
+                fs::read_to_string(path)
+                    .map(|dpms| dpms.trim() == "Off")
+                    .unwrap_or(false)
+            })
+    }
+    
+    /// `sys_path` maps the absolute paths below the monitor's root
+    fn read_power_settings(sys_path: impl Fn(&str) -> PathBuf) -> PowerSettings {
+        let read_trimmed = |path: &Path| -> Option<String> {
+            fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+        };
+        
+        let performance_profile = PERFORMANCE_PROFILE_PATHS
+            .iter()
+            .find_map(|path| read_trimmed(&sys_path(path)));
+        
+        let battery = Self::first_battery_path(&sys_path("/sys/class/power_supply"));
+        let tuxedo_io_path = sys_path("/sys/devices/platform/tuxedo_io");
+        // Older tuxedo_io versions have the thresholds instead of the battery
+        let read_threshold = |file: &str| -> Option<u8> {
+            battery
+                .as_ref()
+                .and_then(|battery| read_trimmed(&battery.join(file)))
+                .or_else(|| read_trimmed(&tuxedo_io_path.join(file)))?
+                .parse()
+                .ok()
+        };
+        
+        PowerSettings {
+            performance_profile,
+            charge_start_threshold: read_threshold("charge_control_start_threshold"),
+            charge_end_threshold: read_threshold("charge_control_end_threshold"),
+        }
+    }
+    
+    fn first_battery_path(power_supply_path: &Path) -> Option<PathBuf> {
+        let mut batteries: Vec<PathBuf> = fs::read_dir(power_supply_path)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                fs::read_to_string(path.join("type"))
+                    .map(|t| t.trim() == "Battery")
+                    .unwrap_or(false)
+            })
+            .collect();
+        batteries.sort();
+        batteries.into_iter().next()
+    }
+    
     fn get_cpu_info(&mut self) -> Result<CpuInfo> {
-        let cpu_count = self.get_cpu_count()?;
+        let (online, offline) = self.get_cpu_ids()?;
         let mut cores = Vec::new();
         
         // Read new CPU stats
         let current_stats = self.read_cpu_stats()?;
         
-        for core_id in 0..cpu_count {
+        for core_id in online {
             let frequency = self.read_cpu_frequency(core_id).unwrap_or(0);
             
             // Calculate load if we have previous stats
-            let load = if let Some(ref last_stats) = self.last_cpu_stats {
-                if core_id < last_stats.len() && core_id < current_stats.len() {
-                    Self::calculate_cpu_load(&last_stats[core_id], &current_stats[core_id])
-                } else {
-                    0.0
-                }
-            } else {
-                0.0
+            let load = match (&self.last_cpu_stats, current_stats.get(&core_id)) {
+                (Some(last_stats), Some(current)) => last_stats
+                    .get(&core_id)
+                    .map(|last| Self::calculate_cpu_load(last, current))
+                    .unwrap_or(0.0),
+                _ => 0.0,
             };
             
             cores.push(CpuCoreInfo {
@@ -145,32 +1173,62 @@ impl HardwareMonitor {
             }
         }
         
-        Ok(CpuInfo {
+        let die_temps = self.get_die_temperatures();
+        let mut info = CpuInfo::from_cores(
             cores,
-            package_temp: self.get_package_temperature()?,
-            package_power_watts: self.get_cpu_power()?,
-        })
+            self.get_package_temperature(&die_temps)?,
+            self.get_cpu_power()?,
+        );
+        info.die_temps = die_temps;
+        info.power_breakdown = self.get_rapl_breakdown();
+        info.tdp_watts = self.get_cpu_tdp();
+        self.read_cpufreq_driver_info(&mut info);
+        info.offline_cores = offline;
+        Ok(info)
     }
     
-    fn get_cpu_count(&self) -> Result<usize> {
-        let mut count = 0;
+    /// IDs of the online and offline CPUs. cpu0 usually has no `online`
+    /// file because it can't be taken offline.
+    fn get_cpu_ids(&self) -> Result<(Vec<usize>, Vec<usize>)> {
+        let mut online = Vec::new();
+        let mut offline = Vec::new();
         
-        while self.cpu_base_path.join(format!("cpu{}", count)).exists() {
-            count += 1;
+        if let Ok(entries) = fs::read_dir(&self.cpu_base_path) {
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let Some(id) = name
+                    .to_str()
+                    .and_then(|name| name.strip_prefix("cpu"))
+                    .and_then(|id| id.parse::<usize>().ok())
+                else {
+                    // cpufreq, cpuidle, smt, ...
+                    continue;
+                };
+                
+                let is_online = fs::read_to_string(entry.path().join("online"))
+                    .map(|state| state.trim() == "1")
+                    .unwrap_or(true);
+                if is_online {
+                    online.push(id);
+                } else {
+                    offline.push(id);
+                }
+            }
         }
         
-        // Subtract 1 because cpu0 exists but we also have cpuidle, cpufreq, etc.
-        if count > 0 {
-            count -= 1; // Adjust for non-CPU entries
+        // Without cpu directories in sysfs, /proc/cpuinfo lists the online CPUs
+        if online.is_empty() {
+            let cpuinfo = fs::read_to_string(self.sys_path("/proc/cpuinfo"))?;
+            online = cpuinfo
+                .lines()
+                .filter(|line| line.starts_with("processor"))
+                .filter_map(|line| line.split(':').nth(1)?.trim().parse().ok())
+                .collect();
         }
         
-        // More reliable method: check /proc/cpuinfo
-        let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
-        let processor_count = cpuinfo.lines()
-            .filter(|line| line.starts_with("processor"))
-            .count();
-        
-        Ok(processor_count)
+        online.sort_unstable();
+        offline.sort_unstable();
+        Ok((online, offline))
     }
     
     fn read_cpu_frequency(&self, core_id: usize) -> Result<u32> {
@@ -178,27 +1236,52 @@ impl HardwareMonitor {
             .join(format!("cpu{}", core_id))
             .join("cpufreq/scaling_cur_freq");
         
-        if !freq_path.exists() {
-            anyhow::bail!("Frequency info not available");
-        }
-        
-        let freq_khz: u32 = fs::read_to_string(freq_path)?
-            .trim()
-            .parse()
-            .context("Failed to parse frequency")?;
+        let freq_khz: u32 = self
+            .read_sensor(&freq_path)
+            .context("Frequency info not available")?;
         
         Ok(freq_khz / 1000) // Convert to MHz
     }
     
-    fn read_cpu_stats(&self) -> Result<Vec<CpuStats>> {
-        let stat_content = fs::read_to_string("/proc/stat")?;
-        let mut stats = Vec::new();
+    /// Fill in the cpufreq driver, its mode and the base clock
+    fn read_cpufreq_driver_info(&self, info: &mut CpuInfo) {
+        let cpufreq = self.cpu_base_path.join("cpu0/cpufreq");
+        let read_trimmed = |path: PathBuf| -> Option<String> {
+            fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+        };
+        
+        info.scaling_driver = read_trimmed(cpufreq.join("scaling_driver")).unwrap_or_default();
+        info.pstate_status = ["intel_pstate", "amd_pstate"]
+            .iter()
+            .find_map(|driver| read_trimmed(self.cpu_base_path.join(driver).join("status")));
+        // base_frequency only exists with intel_pstate and the nominal clock
+        // with amd-pstate, whose cpuinfo_max_freq is the boost clock.
+        // Otherwise the highest non-boost frequency is the best guess.
+        info.base_freq_mhz = ["base_frequency", "amd_pstate_nominal_freq", "cpuinfo_max_freq"]
+            .iter()
+            .find_map(|file| read_trimmed(cpufreq.join(file))?.parse::<u32>().ok())
+            .map(|khz| khz / 1000);
+        // AMD (and acpi-cpufreq) switch boost off in cpufreq/boost
+        let boost_enabled = read_trimmed(self.cpu_base_path.join("cpufreq/boost"))
+            .is_none_or(|boost| boost != "0");
+        info.boost_active = boost_enabled && info
+            .base_freq_mhz
+            .is_some_and(|base| info.cores.iter().any(|core| core.frequency_mhz > base));
+    }
+    
+    /// Per-CPU counters by CPU id, offline CPUs are missing from /proc/stat
+    fn read_cpu_stats(&self) -> Result<HashMap<usize, CpuStats>> {
+        let stat_content = fs::read_to_string(self.sys_path("/proc/stat"))?;
+        let mut stats = HashMap::new();
         
         for line in stat_content.lines() {
             if line.starts_with("cpu") && !line.starts_with("cpu ") {
                 let parts: Vec<&str> = line.split_whitespace().collect();
+                let Ok(id) = parts[0].trim_start_matches("cpu").parse::<usize>() else {
+                    continue;
+                };
                 if parts.len() >= 8 {
-                    stats.push(CpuStats {
+                    stats.insert(id, CpuStats {
                         user: parts[1].parse().unwrap_or(0),
                         nice: parts[2].parse().unwrap_or(0),
                         system: parts[3].parse().unwrap_or(0),
@@ -259,10 +1342,8 @@ impl HardwareMonitor {
                                     if let Some(core_num) = label.split_whitespace()
                                         .find_map(|s| s.parse::<usize>().ok()) {
                                         
-                                        if let Ok(temp_str) = fs::read_to_string(&temp_input_path) {
-                                            if let Ok(temp_millidegrees) = temp_str.trim().parse::<i32>() {
-                                                temps.insert(core_num, temp_millidegrees as f32 / 1000.0);
-                                            }
+                                        if let Some(temp_millidegrees) = self.read_sensor::<i32>(&temp_input_path) {
+                                            temps.insert(core_num, temp_millidegrees as f32 / 1000.0);
                                         }
                                     }
                                 }
@@ -276,7 +1357,39 @@ impl HardwareMonitor {
         Ok(temps)
     }
     
-    fn get_package_temperature(&self) -> Result<Option<f32>> {
+    /// Package temperature, the hottest of `die_temps` as read by
+    /// [`Self::get_die_temperatures`]
+    fn get_package_temperature(&self, die_temps: &[(String, f32)]) -> Result<Option<f32>> {
+        if let Some(pinned) = &self.cpu_temp_sensor {
+            let temperature = self
+                .available_temp_sensors()
+                .into_iter()
+                .find(|sensor| &sensor.id == pinned)
+                .and_then(|sensor| sensor.temperature);
+            if temperature.is_some() {
+                return Ok(temperature);
+            }
+        }
+        
+        // A chiplet or a socket can run much hotter than the others
+        if let Some(hottest) = die_temps.iter().map(|(_, temp)| *temp).reduce(f32::max) {
+            return Ok(Some(hottest));
+        }
+        
+        // Last resort for unknown CPU sensors: the ACPI thermal zone
+        Ok(self
+            .available_temp_sensors()
+            .into_iter()
+            .find(|sensor| sensor.id.chip == "acpitz")
+            .and_then(|sensor| sensor.temperature))
+    }
+    
+    /// Every package and die sensor of the CPU chips: "Package id N" of
+    /// coretemp, "Tdie" and "TccdN" of k10temp and zenpower. Sensors that
+    /// can't be read are left out.
+    fn get_die_temperatures(&self) -> Vec<(String, f32)> {
+        let mut temps = Vec::new();
+        
         for hwmon_path in &self.hwmon_paths {
             let name_path = hwmon_path.join("name");
             if let Ok(name) = fs::read_to_string(&name_path) {
@@ -285,20 +1398,20 @@ impl HardwareMonitor {
                 if name.contains("coretemp") || name.contains("k10temp") || 
                    name.contains("zenpower") {
                     
-                    // Look for package temperature
                     for i in 1..=32 {
                         let temp_label_path = hwmon_path.join(format!("temp{}_label", i));
                         let temp_input_path = hwmon_path.join(format!("temp{}_input", i));
                         
                         if temp_input_path.exists() {
                             if let Ok(label) = fs::read_to_string(&temp_label_path) {
-                                let label = label.trim().to_lowercase();
+                                let label = label.trim();
+                                let lower = label.to_lowercase();
                                 
-                                if label.contains("package") || label.contains("tdie") {
-                                    if let Ok(temp_str) = fs::read_to_string(&temp_input_path) {
-                                        if let Ok(temp) = temp_str.trim().parse::<i32>() {
-                                            return Ok(Some(temp as f32 / 1000.0));
-                                        }
+                                if lower.contains("package") || lower.contains("tdie")
+                                    || lower.starts_with("tccd")
+                                {
+                                    if let Some(temp) = self.read_sensor::<i32>(&temp_input_path) {
+                                        temps.push((label.to_string(), temp as f32 / 1000.0));
                                     }
                                 }
                             }
@@ -308,20 +1421,16 @@ impl HardwareMonitor {
             }
         }
         
-        Ok(None)
+        temps
     }
     
-    fn get_cpu_power(&self) -> Result<Option<f32>> {
+    fn get_cpu_power(&mut self) -> Result<Option<f32>> {
         // Try to read from RAPL (Running Average Power Limit)
-        let rapl_path = Path::new("/sys/class/powercap/intel-rapl/intel-rapl:0");
+        let rapl_path = self.sys_path("/sys/class/powercap/intel-rapl/intel-rapl:0");
         
-        if rapl_path.exists() {
-            let energy_path = rapl_path.join("energy_uj");
-            if energy_path.exists() {
-                // This would need to be calculated over time
-                // For now, return None as it requires state tracking
-                return Ok(None);
-            }
+        if rapl_path.join("energy_uj").exists() {
+            // None on the first reading, power needs two of them
+            return Ok(self.read_rapl_power(&rapl_path));
         }
         
         // AMD alternative
@@ -345,6 +1454,86 @@ impl HardwareMonitor {
         Ok(None)
     }
     
+    /// Power of the package subzones (core, uncore, and dram if it is one)
+    /// and of a separate top-level dram zone
+    fn get_rapl_breakdown(&mut self) -> HashMap<String, f32> {
+        let powercap = self.sys_path("/sys/class/powercap/intel-rapl");
+        let mut zones = Vec::new();
+        
+        if let Ok(entries) = fs::read_dir(powercap.join("intel-rapl:0")) {
+            zones.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.file_name().is_some_and(|name| {
+                        name.to_string_lossy().starts_with("intel-rapl:0:")
+                    })),
+            );
+        }
+        // Servers have dram as its own zone next to the packages
+        if let Ok(entries) = fs::read_dir(&powercap) {
+            zones.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+                fs::read_to_string(path.join("name")).is_ok_and(|name| name.trim() == "dram")
+            }));
+        }
+        
+        let mut breakdown = HashMap::new();
+        for zone in zones {
+            let Ok(name) = fs::read_to_string(zone.join("name")) else {
+                continue;
+            };
+            if let Some(watts) = self.read_rapl_power(&zone) {
+                breakdown.insert(name.trim().to_string(), watts);
+            }
+        }
+        breakdown
+    }
+    
+    /// Average power of a RAPL zone since the last call for it
+    fn read_rapl_power(&mut self, zone: &Path) -> Option<f32> {
+        let energy_uj: u64 = read_sysfs_retry(&zone.join("energy_uj"), SYSFS_READ_ATTEMPTS)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let reading = RaplReading {
+            energy_uj,
+            time: Instant::now(),
+        };
+        let last = self.last_rapl.insert(zone.to_path_buf(), reading)?;
+        
+        let elapsed = reading.time.duration_since(last.time).as_secs_f32();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        
+        // The counter wraps at max_energy_range_uj
+        let delta_uj = if energy_uj >= last.energy_uj {
+            energy_uj - last.energy_uj
+        } else {
+            let range: u64 = fs::read_to_string(zone.join("max_energy_range_uj"))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()?;
+            range.checked_sub(last.energy_uj)? + energy_uj
+        };
+        
+        Some(delta_uj as f32 / 1_000_000.0 / elapsed)
+    }
+    
+    /// Sustained CPU power limit (PL1) from RAPL, a good stand-in for the TDP
+    pub fn get_cpu_tdp(&self) -> Option<f32> {
+        let rapl_path = self.sys_path("/sys/class/powercap/intel-rapl/intel-rapl:0");
+        
+        ["constraint_0_power_limit_uw", "constraint_0_max_power_uw"]
+            .iter()
+            .filter_map(|file| fs::read_to_string(rapl_path.join(file)).ok())
+            .filter_map(|value| value.trim().parse::<u64>().ok())
+            .find(|&uw| uw > 0)
+            .map(|uw| uw as f32 / 1_000_000.0)
+    }
+    
     fn get_gpu_info(&self) -> Result<Vec<GpuInfo>> {
         let mut gpus = Vec::new();
         
@@ -362,7 +1551,7 @@ impl HardwareMonitor {
     
     fn detect_amd_gpus(&self) -> Result<Vec<GpuInfo>> {
         let mut gpus = Vec::new();
-        let drm_path = Path::new("/sys/class/drm");
+        let drm_path = self.sys_path("/sys/class/drm");
         
         if !drm_path.exists() {
             return Ok(gpus);
@@ -382,6 +1571,8 @@ impl HardwareMonitor {
                             let gpu_name = self.read_gpu_name(&device_path)
                                 .unwrap_or_else(|_| "AMD GPU".to_string());
                             
+                            let (vram_used_mb, vram_total_mb) = Self::read_amd_vram(&device_path);
+                            
                             let gpu_type = if gpu_name.to_lowercase().contains("radeon") &&
                                             gpu_name.to_lowercase().contains("graphics") {
                                 GpuType::Integrated
@@ -396,6 +1587,13 @@ impl HardwareMonitor {
                                 temperature: self.read_amd_gpu_temp(&device_path).ok(),
                                 load_percent: self.read_amd_gpu_load(&device_path).ok(),
                                 power_watts: self.read_amd_gpu_power(&device_path).ok(),
+                                vram_used_mb,
+                                vram_total_mb,
+                                fan_ids: self.read_gpu_fans(&device_path, name)
+                                    .into_iter()
+                                    .map(|fan| fan.fan_id)
+                                    .collect(),
+                                power_cap: Self::read_amd_power_cap(&device_path, name),
                             });
                         }
                     }
@@ -408,7 +1606,7 @@ impl HardwareMonitor {
     
     fn detect_intel_gpus(&self) -> Result<Vec<GpuInfo>> {
         let mut gpus = Vec::new();
-        let drm_path = Path::new("/sys/class/drm");
+        let drm_path = self.sys_path("/sys/class/drm");
         
         if !drm_path.exists() {
             return Ok(gpus);
@@ -426,6 +1624,7 @@ impl HardwareMonitor {
                         if vendor.trim() == "0x8086" { // Intel vendor ID
                             let gpu_name = self.read_gpu_name(&device_path)
                                 .unwrap_or_else(|_| "Intel GPU".to_string());
+                            let (vram_used_mb, vram_total_mb) = Self::read_intel_vram(&path);
                             
                             gpus.push(GpuInfo {
                                 name: gpu_name,
@@ -434,6 +1633,10 @@ impl HardwareMonitor {
                                 temperature: None,
                                 load_percent: None,
                                 power_watts: None,
+                                vram_used_mb,
+                                vram_total_mb,
+                                fan_ids: Vec::new(),
+                                power_cap: None,
                             });
                         }
                     }
@@ -495,8 +1698,9 @@ impl HardwareMonitor {
                 let temp_input = entry.path().join("temp1_input");
                 
                 if temp_input.exists() {
-                    let temp_str = fs::read_to_string(temp_input)?;
-                    let temp_millidegrees: i32 = temp_str.trim().parse()?;
+                    let temp_millidegrees: i32 = self
+                        .read_sensor(&temp_input)
+                        .context("Could not read GPU temperature")?;
                     return Ok(temp_millidegrees as f32 / 1000.0);
                 }
             }
@@ -508,9 +1712,8 @@ impl HardwareMonitor {
     fn read_amd_gpu_load(&self, device_path: &Path) -> Result<f32> {
         let load_path = device_path.join("gpu_busy_percent");
         
-        if load_path.exists() {
-            let load_str = fs::read_to_string(load_path)?;
-            return Ok(load_str.trim().parse()?);
+        if let Some(load) = self.read_sensor(&load_path) {
+            return Ok(load);
         }
         
         anyhow::bail!("Could not read GPU load")
@@ -525,7 +1728,7 @@ impl HardwareMonitor {
                 let power_input = entry.path().join("power1_average");
                 
                 if power_input.exists() {
-                    let power_str = fs::read_to_string(power_input)?;
+                    let power_str = read_sysfs_retry(&power_input, SYSFS_READ_ATTEMPTS)?;
                     let power_uw: u64 = power_str.trim().parse()?;
                     return Ok(power_uw as f32 / 1_000_000.0);
                 }
@@ -535,6 +1738,29 @@ impl HardwareMonitor {
         anyhow::bail!("Could not read GPU power")
     }
     
+    fn read_amd_power_cap(device_path: &Path, card: &str) -> Option<GpuPowerCap> {
+        fs::read_dir(device_path.join("hwmon"))
+            .ok()?
+            .flatten()
+            .find_map(|hwmon| GpuPowerCap::read(card, &hwmon.path()))
+    }
+    
+    /// amdgpu reports VRAM in bytes below the PCI device
+    fn read_amd_vram(device_path: &Path) -> VramInfo {
+        (
+            read_bytes_as_mb(&device_path.join("mem_info_vram_used")),
+            read_bytes_as_mb(&device_path.join("mem_info_vram_total")),
+        )
+    }
+    
+    /// i915 only reports local memory for discrete cards, on the DRM card
+    fn read_intel_vram(card_path: &Path) -> VramInfo {
+        let total = read_bytes_as_mb(&card_path.join("lmem_total_bytes"));
+        let available = read_bytes_as_mb(&card_path.join("lmem_avail_bytes"));
+        let used = total.zip(available).map(|(total, available)| total.saturating_sub(available));
+        (used, total)
+    }
+    
     fn read_intel_gpu_freq(&self, device_path: &Path) -> Result<u32> {
         // Intel GPU frequency reading - simplified
         Ok(0)
@@ -542,50 +1768,203 @@ impl HardwareMonitor {
     
     fn get_fan_info(&self) -> Result<Vec<FanInfo>> {
         let mut fans = Vec::new();
+        let gpu_hwmons = gpu_hwmon_dirs(&self.sys_path("/sys/class/drm"));
+        let gpu_hwmon_set: Vec<PathBuf> = gpu_hwmons
+            .iter()
+            .filter_map(|(_, path)| path.canonicalize().ok())
+            .collect();
         
         for hwmon_path in &self.hwmon_paths {
-            for i in 1..=10 {
-                let fan_input_path = hwmon_path.join(format!("fan{}_input", i));
-                
-                if fan_input_path.exists() {
-                    let rpm = fs::read_to_string(&fan_input_path)
-                        .ok()
-                        .and_then(|s| s.trim().parse().ok());
-                    
-                    let label = fs::read_to_string(hwmon_path.join(format!("fan{}_label", i)))
-                        .unwrap_or_else(|_| format!("Fan {}", i));
-                    
-                    fans.push(FanInfo {
-                        fan_id: format!("fan{}", i),
-                        name: label.trim().to_string(),
-                        speed_rpm: rpm,
-                        speed_percent: None, // Would need fan max to calculate
-                    });
-                }
+            // GPU hwmons are listed in /sys/class/hwmon too, but their fans
+            // are reported per GPU below
+            let is_gpu = hwmon_path
+                .canonicalize()
+                .is_ok_and(|path| gpu_hwmon_set.contains(&path));
+            if !is_gpu {
+                fans.extend(self.read_hwmon_fans(hwmon_path, None));
             }
         }
         
+        for (card, hwmon_path) in &gpu_hwmons {
+            fans.extend(self.read_hwmon_fans(hwmon_path, Some(card)));
+        }
+        
         Ok(fans)
     }
     
+    /// Fans of the GPU with the given PCI device directory
+    fn read_gpu_fans(&self, device_path: &Path, card: &str) -> Vec<FanInfo> {
+        let Ok(entries) = fs::read_dir(device_path.join("hwmon")) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .flat_map(|entry| self.read_hwmon_fans(&entry.path(), Some(card)))
+            .collect()
+    }
+    
+    fn read_hwmon_fans(&self, hwmon_path: &Path, gpu: Option<&str>) -> Vec<FanInfo> {
+        let mut fans = Vec::new();
+        
+        for i in 1..=10 {
+            let fan_input_path = hwmon_path.join(format!("fan{}_input", i));
+            let pwm_path = hwmon_path.join(format!("pwm{}", i));
+            
+            // Some drivers only expose one of the two files
+            if !fan_input_path.exists() && !pwm_path.exists() {
+                continue;
+            }
+            
+            let rpm = self.read_sensor(&fan_input_path);
+            
+            let requested_percent = self.read_sensor::<u32>(&pwm_path).map(|pwm| {
+                let max = *self
+                    .pwm_max
+                    .borrow_mut()
+                    .entry(pwm_path.clone())
+                    .or_insert_with(|| read_pwm_max(&pwm_path));
+                pwm_percent(pwm, max)
+            });
+            
+            let (fan_id, default_label) = match gpu {
+                Some(card) => (gpu_fan_id(card, i), format!("GPU fan {}", i)),
+                None => (format!("fan{}", i), format!("Fan {}", i)),
+            };
+            let label = fs::read_to_string(hwmon_path.join(format!("fan{}_label", i)))
+                .unwrap_or(default_label);
+            
+            fans.push(FanInfo {
+                fan_id,
+                name: label.trim().to_string(),
+                speed_rpm: rpm,
+                requested_percent,
+                gpu: gpu.map(str::to_string),
+            });
+        }
+        
+        fans
+    }
+    
+    fn get_battery_info(&self) -> Result<Vec<BatteryInfo>> {
+        Self::read_batteries(&self.sys_path("/sys/class/power_supply"))
+    }
+    
+    fn read_batteries(power_supply_path: &Path) -> Result<Vec<BatteryInfo>> {
+        let mut batteries = Vec::new();
+        
+        if !power_supply_path.exists() {
+            return Ok(batteries);
+        }
+        
+        for entry in fs::read_dir(power_supply_path)? {
+            let path = entry?.path();
+            
+            let is_battery = fs::read_to_string(path.join("type"))
+                .map(|t| t.trim() == "Battery")
+                .unwrap_or(false);
+            if !is_battery {
+                continue;
+            }
+            
+            let read_value = |file: &str| -> Option<f32> {
+                fs::read_to_string(path.join(file)).ok()?.trim().parse().ok()
+            };
+            
+            // Capacity is reported either as energy (µWh) or as charge (µAh)
+            let energy_full_wh = read_value("energy_full")
+                .map(|uwh| uwh / 1_000_000.0)
+                .or_else(|| {
+                    let charge_full = read_value("charge_full")?;
+                    let voltage = read_value("voltage_min_design")?;
+                    Some(charge_full * voltage / 1e12)
+                });
+            
+            let power_watts = read_value("power_now")
+                .map(|uw| uw / 1_000_000.0)
+                .or_else(|| {
+                    let current = read_value("current_now")?;
+                    let voltage = read_value("voltage_now")?;
+                    Some(current * voltage / 1e12)
+                });
+            
+            batteries.push(BatteryInfo {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                charge_percent: read_value("capacity").map(|c| c.clamp(0.0, 100.0) as u8),
+                status: fs::read_to_string(path.join("status"))
+                    .ok()
+                    .map(|s| s.trim().to_string()),
+                energy_full_wh,
+                power_watts,
+            });
+        }
+        
+        batteries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(batteries)
+    }
+    
+    /// Active GPU, see [`resolve_active_gpu`] for the rules
     fn get_active_gpu(&self) -> Result<GpuType> {
-        // Check prime-select status
-        let prime_select_output = std::process::Command::new("prime-select")
-            .arg("query")
-            .output();
+        let mode = *self.gpu_mode.get_or_init(GpuMode::query);
+        Ok(resolve_active_gpu(mode, self.read_dgpu_state()))
+    }
+    
+    /// Find the discrete GPU and read its power state. The DRM card is
+    /// preferred because it shows attached displays, PCI devices cover the
+    /// proprietary NVIDIA driver without modesetting.
+    fn read_dgpu_state(&self) -> Option<DgpuState> {
+        let read = |path: PathBuf| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+        let power_state = |device: &Path, drives_output: bool| DgpuState {
+            runtime_active: read(device.join("power/runtime_status")).as_deref() == Some("active"),
+            always_on: read(device.join("power/control")).as_deref() == Some("on"),
+            drives_output,
+        };
+        
+        let drm_path = self.sys_path("/sys/class/drm");
+        let entries: Vec<PathBuf> = fs::read_dir(&drm_path)
+            .map(|dir| dir.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
         
-        if let Ok(output) = prime_select_output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        for card in &entries {
+            let Some(name) = card.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !name.starts_with("card") || name.contains('-') {
+                continue;
+            }
             
-            if stdout.contains("nvidia") {
-                return Ok(GpuType::Discrete);
-            } else if stdout.contains("intel") || stdout.contains("amd") {
-                return Ok(GpuType::Integrated);
+            let device = card.join("device");
+            // The iGPU is the boot VGA device, 3D controllers have no boot_vga
+            let discrete = read(device.join("vendor")).as_deref() == Some("0x10de")
+                || read(device.join("boot_vga")).as_deref() == Some("0");
+            if !discrete {
+                continue;
+            }
+            
+            let connector_prefix = format!("{}-", name);
+            let drives_output = entries.iter().any(|connector| {
+                connector
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&connector_prefix))
+                    && read(connector.join("status")).as_deref() == Some("connected")
+            });
+            return Some(power_state(&device, drives_output));
+        }
+        
+        let pci_path = self.sys_path("/sys/bus/pci/devices");
+        for entry in fs::read_dir(pci_path).ok()?.flatten() {
+            let device = entry.path();
+            let is_nvidia_display = read(device.join("vendor")).as_deref() == Some("0x10de")
+                && read(device.join("class")).is_some_and(|class| class.starts_with("0x03"));
+            if is_nvidia_display {
+                return Some(power_state(&device, false));
             }
         }
         
-        // Fallback: assume integrated
-        Ok(GpuType::Integrated)
+        None
     }
 }
 
@@ -593,6 +1972,39 @@ impl HardwareMonitor {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_retry_transient_failure() {
+        let mut calls = 0;
+        let result = retry_transient(SYSFS_READ_ATTEMPTS, || {
+            calls += 1;
+            if calls == 1 {
+                Err(io::Error::from_raw_os_error(libc::EBUSY))
+            } else {
+                Ok("2400000\n".to_string())
+            }
+        });
+        assert_eq!(result.unwrap(), "2400000\n");
+        assert_eq!(calls, 2);
+
+        // Give up after the configured number of attempts
+        let mut calls = 0;
+        let result: io::Result<String> = retry_transient(SYSFS_READ_ATTEMPTS, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::WouldBlock))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, SYSFS_READ_ATTEMPTS);
+
+        // Permanent errors are not retried
+        let mut calls = 0;
+        let result: io::Result<String> = retry_transient(SYSFS_READ_ATTEMPTS, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
     #[test]
     fn test_hardware_monitor_creation() {
         // This test will only work on Linux systems with proper sysfs
@@ -601,4 +2013,724 @@ mod tests {
             // Don't assert success as it depends on system configuration
         }
     }
+
+    fn core(core_id: usize, frequency_mhz: u32, load_percent: f32, temperature: Option<f32>) -> CpuCoreInfo {
+        CpuCoreInfo {
+            core_id,
+            frequency_mhz,
+            load_percent,
+            temperature,
+        }
+    }
+
+    #[test]
+    fn test_cpu_summary_values() {
+        let cores = vec![
+            core(0, 1000, 10.0, Some(50.0)),
+            core(1, 3000, 91.0, Some(72.5)),
+            core(2, 2000, 20.0, None),
+            core(3, 1500, 15.0, Some(61.0)),
+        ];
+
+        let info = CpuInfo::from_cores(cores, None, None);
+
+        assert_eq!(info.median_frequency_mhz, 1750);
+        assert!((info.median_load_percent - 17.5).abs() < f32::EPSILON);
+        assert!((info.mean_load_percent - 34.0).abs() < f32::EPSILON);
+        assert!((info.max_core_load - 91.0).abs() < f32::EPSILON);
+        assert_eq!(info.max_core_temp, Some(72.5));
+        assert_eq!(info.load_summary(), "Load: 34% avg / 91% peak");
+    }
+
+    fn battery(name: &str, charge_percent: Option<u8>, energy_full_wh: Option<f32>) -> BatteryInfo {
+        BatteryInfo {
+            name: name.to_string(),
+            charge_percent,
+            status: None,
+            energy_full_wh,
+            power_watts: None,
+        }
+    }
+
+    #[test]
+    fn test_status_summary() {
+        let gpu = |temperature: Option<f32>| GpuInfo {
+            name: "GPU".to_string(),
+            gpu_type: GpuType::Discrete,
+            frequency_mhz: None,
+            temperature,
+            load_percent: None,
+            power_watts: None,
+            vram_used_mb: None,
+            vram_total_mb: None,
+            fan_ids: Vec::new(),
+            power_cap: None,
+        };
+        let mut stats = SystemStats {
+            cpu: CpuInfo::from_cores(Vec::new(), Some(64.4), None),
+            gpus: vec![gpu(Some(48.0)), gpu(Some(52.0))],
+            fans: Vec::new(),
+            batteries: Vec::new(),
+            active_gpu: GpuType::Discrete,
+            warnings: Vec::new(),
+        };
+        assert_eq!(
+            stats.status_summary(TemperatureUnit::Celsius, Some("Balanced")),
+            "CPU 64°C · GPU 52°C · Profile: Balanced"
+        );
+
+        stats.gpus = vec![gpu(None)];
+        assert_eq!(stats.status_summary(TemperatureUnit::Fahrenheit, None), "CPU 148°F");
+        stats.cpu.package_temp = None;
+        assert_eq!(stats.status_summary(TemperatureUnit::Celsius, Some("Quiet")), "Profile: Quiet");
+    }
+
+    #[test]
+    fn test_power_settings() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // Older tuxedo_io versions have the thresholds instead of the battery
+        fixture.write("/sys/devices/platform/tuxedo_io/charge_control_end_threshold", "80\n");
+        let mut monitor = fixture.monitor();
+        let settings = monitor.get_power_settings();
+        assert_eq!(settings.charge_start_threshold, None);
+        assert_eq!(settings.charge_end_threshold, Some(80));
+
+        // The battery wins once the cache is dropped
+        fixture.write("/sys/class/power_supply/BAT0/charge_control_start_threshold", "55\n");
+        fixture.write("/sys/class/power_supply/BAT0/charge_control_end_threshold", "60\n");
+        assert_eq!(monitor.get_power_settings().charge_end_threshold, Some(80));
+        monitor.invalidate_power_settings();
+        let settings = monitor.get_power_settings();
+        assert_eq!((settings.charge_start_threshold, settings.charge_end_threshold), (Some(55), Some(60)));
+    }
+
+    #[test]
+    fn test_battery_aggregate() {
+        assert_eq!(aggregate_battery_percent(&[]), None);
+
+        // A single battery reports its own charge
+        let single = [battery("BAT0", Some(73), Some(50.0))];
+        assert_eq!(aggregate_battery_percent(&single), Some(73.0));
+
+        // Weighted by capacity
+        let dual = [
+            battery("BAT0", Some(100), Some(60.0)),
+            battery("BAT1", Some(40), Some(20.0)),
+        ];
+        assert_eq!(aggregate_battery_percent(&dual), Some(85.0));
+
+        // Plain average without capacity info
+        let unknown = [battery("BAT0", Some(100), None), battery("BAT1", Some(40), Some(20.0))];
+        assert_eq!(aggregate_battery_percent(&unknown), Some(70.0));
+    }
+
+    #[test]
+    fn test_battery_summary() {
+        let mut info = battery("BAT0", Some(73), Some(50.0));
+        assert_eq!(info.summary(), "BAT0: 73%");
+
+        info.status = Some("Discharging".to_string());
+        info.power_watts = Some(12.04);
+        assert_eq!(info.summary(), "BAT0: 73%, Discharging at 12.0 W");
+
+        info.status = Some("Full".to_string());
+        info.power_watts = Some(0.0);
+        info.charge_percent = None;
+        assert_eq!(info.summary(), "BAT0: unknown, Full");
+    }
+
+    #[test]
+    fn test_read_batteries() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (name, kind, capacity) in [("BAT1", "Battery", "40"), ("AC", "Mains", ""), ("BAT0", "Battery", "90")] {
+            let dir = temp_dir.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("type"), format!("{kind}\n")).unwrap();
+            fs::write(dir.join("capacity"), capacity).unwrap();
+            fs::write(dir.join("energy_full"), "40000000").unwrap();
+        }
+
+        let batteries = HardwareMonitor::read_batteries(temp_dir.path()).unwrap();
+        let names: Vec<&str> = batteries.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["BAT0", "BAT1"]);
+        assert_eq!(batteries[0].charge_percent, Some(90));
+        assert_eq!(batteries[0].energy_full_wh, Some(40.0));
+        assert_eq!(aggregate_battery_percent(&batteries), Some(65.0));
+    }
+
+    #[test]
+    fn test_cpu_summary_without_cores() {
+        let info = CpuInfo::from_cores(Vec::new(), None, None);
+
+        assert_eq!(info.median_frequency_mhz, 0);
+        assert_eq!(info.mean_load_percent, 0.0);
+        assert_eq!(info.max_core_load, 0.0);
+        assert_eq!(info.max_core_temp, None);
+    }
+
+    #[test]
+    fn test_pinned_temp_sensor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut hwmon_paths = Vec::new();
+        for (dir, chip, label, millidegrees) in [
+            ("hwmon0", "acpitz", None, "48000"),
+            ("hwmon1", "nct6687", Some("CPU"), "61500"),
+        ] {
+            let path = temp_dir.path().join(dir);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("name"), format!("{chip}\n")).unwrap();
+            fs::write(path.join("temp1_input"), millidegrees).unwrap();
+            if let Some(label) = label {
+                fs::write(path.join("temp1_label"), label).unwrap();
+            }
+            hwmon_paths.push(path);
+        }
+
+        let mut monitor = HardwareMonitor {
+            root: temp_dir.path().to_path_buf(),
+            cpu_base_path: temp_dir.path().to_path_buf(),
+            hwmon_paths,
+            last_cpu_stats: None,
+            power_settings: None,
+            cpu_temp_sensor: None,
+            gpu_mode: OnceLock::new(),
+            last_rapl: HashMap::new(),
+            topology: RefCell::new(None),
+            read_warnings: RefCell::new(Vec::new()),
+            pwm_max: RefCell::new(HashMap::new()),
+        };
+
+        let sensors = monitor.available_temp_sensors();
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].id.label, "temp1");
+        assert_eq!(sensors[1].temperature, Some(61.5));
+
+        // Without a known CPU chip the ACPI thermal zone is used
+        assert_eq!(monitor.get_package_temperature(&[]).unwrap(), Some(48.0));
+
+        monitor.set_cpu_temp_sensor(Some(sensors[1].id.clone()));
+        assert_eq!(monitor.get_package_temperature(&[]).unwrap(), Some(61.5));
+    }
+
+    #[test]
+    fn test_read_vram() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path();
+        assert_eq!(HardwareMonitor::read_amd_vram(path), (None, None));
+
+        fs::write(path.join("mem_info_vram_used"), "1073741824\n").unwrap();
+        fs::write(path.join("mem_info_vram_total"), "4294967296\n").unwrap();
+        assert_eq!(HardwareMonitor::read_amd_vram(path), (Some(1024), Some(4096)));
+
+        fs::write(path.join("lmem_total_bytes"), "8589934592").unwrap();
+        fs::write(path.join("lmem_avail_bytes"), "6442450944").unwrap();
+        assert_eq!(HardwareMonitor::read_intel_vram(path), (Some(2048), Some(8192)));
+    }
+
+    #[test]
+    fn test_power_source() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(!HardwareMonitor::is_on_battery(&[]));
+
+        for (name, kind) in [("ADP1", "Mains"), ("BAT0", "Battery")] {
+            let dir = temp_dir.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("type"), format!("{kind}\n")).unwrap();
+            fs::write(dir.join("online"), "1\n").unwrap();
+        }
+
+        let paths = HardwareMonitor::find_ac_online_paths(temp_dir.path());
+        assert_eq!(paths, [temp_dir.path().join("ADP1/online")]);
+        assert!(!HardwareMonitor::is_on_battery(&paths));
+
+        fs::write(&paths[0], "0\n").unwrap();
+        assert!(HardwareMonitor::is_on_battery(&paths));
+    }
+
+    #[test]
+    fn test_screens_off() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(!HardwareMonitor::screens_off(&[]));
+
+        for (name, status) in [("card1-eDP-1", "connected"), ("card1-HDMI-A-1", "disconnected")] {
+            let dir = temp_dir.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("status"), format!("{status}\n")).unwrap();
+            fs::write(dir.join("dpms"), "On\n").unwrap();
+        }
+        fs::create_dir_all(temp_dir.path().join("card1")).unwrap();
+
+        let paths = HardwareMonitor::find_display_dpms_paths(temp_dir.path());
+        assert_eq!(paths, [temp_dir.path().join("card1-eDP-1/dpms")]);
+        assert!(!HardwareMonitor::screens_off(&paths));
+
+        fs::write(&paths[0], "Off\n").unwrap();
+        assert!(HardwareMonitor::screens_off(&paths));
+    }
+
+    #[test]
+    fn test_cpufreq_driver_info() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let monitor = HardwareMonitor {
+            root: temp_dir.path().to_path_buf(),
+            cpu_base_path: temp_dir.path().to_path_buf(),
+            hwmon_paths: Vec::new(),
+            last_cpu_stats: None,
+            power_settings: None,
+            cpu_temp_sensor: None,
+            gpu_mode: OnceLock::new(),
+            last_rapl: HashMap::new(),
+            topology: RefCell::new(None),
+            read_warnings: RefCell::new(Vec::new()),
+            pwm_max: RefCell::new(HashMap::new()),
+        };
+
+        let mut info = CpuInfo::from_cores(Vec::new(), None, None);
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert_eq!(info.driver_summary(), "No cpufreq driver");
+
+        let cpufreq = temp_dir.path().join("cpu0/cpufreq");
+        fs::create_dir_all(&cpufreq).unwrap();
+        fs::create_dir_all(temp_dir.path().join("intel_pstate")).unwrap();
+        fs::write(cpufreq.join("scaling_driver"), "intel_pstate\n").unwrap();
+        fs::write(cpufreq.join("cpuinfo_max_freq"), "4700000\n").unwrap();
+        fs::write(cpufreq.join("base_frequency"), "2400000\n").unwrap();
+        fs::write(temp_dir.path().join("intel_pstate/status"), "active\n").unwrap();
+
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert_eq!(info.base_freq_mhz, Some(2400));
+        assert_eq!(info.driver_summary(), "intel_pstate (active), base 2400 MHz");
+        assert!(!info.boost_active);
+
+        let core = |core_id, frequency_mhz| CpuCoreInfo {
+            core_id,
+            frequency_mhz,
+            load_percent: 50.0,
+            temperature: None,
+        };
+        info.cores = vec![core(0, 1800), core(1, 2400)];
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert!(!info.boost_active);
+
+        // One core above base is enough
+        info.cores.push(core(2, 4100));
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert!(info.boost_active);
+
+        // Without base_frequency the highest non-boost clock is the reference
+        fs::remove_file(cpufreq.join("base_frequency")).unwrap();
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert_eq!(info.base_freq_mhz, Some(4700));
+        assert!(!info.boost_active);
+
+        // amd-pstate reports the boost clock as cpuinfo_max_freq
+        fs::write(cpufreq.join("scaling_driver"), "amd-pstate-epp\n").unwrap();
+        fs::write(cpufreq.join("cpuinfo_max_freq"), "5100000\n").unwrap();
+        fs::write(cpufreq.join("amd_pstate_nominal_freq"), "3800000\n").unwrap();
+        fs::create_dir_all(temp_dir.path().join("cpufreq")).unwrap();
+        fs::write(temp_dir.path().join("cpufreq/boost"), "1\n").unwrap();
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert_eq!(info.base_freq_mhz, Some(3800));
+        assert!(info.boost_active);
+
+        // No boost while it's switched off
+        fs::write(temp_dir.path().join("cpufreq/boost"), "0\n").unwrap();
+        monitor.read_cpufreq_driver_info(&mut info);
+        assert!(!info.boost_active);
+    }
+
+    #[test]
+    fn test_system_info() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let info = fixture.monitor().get_system_info();
+        assert_eq!(info.product_name, "InfinityBook Pro 14 Gen9");
+        assert_eq!(info.manufacturer, "TUXEDO");
+        assert_eq!(info.firmware_summary(), "BIOS N.1.07A09 (05/17/2024), EC Unknown");
+
+        fixture.write("/sys/devices/platform/tuxedo_io/fw_version", "1.14\n");
+        assert_eq!(fixture.monitor().get_system_info().ec_firmware_version, "1.14");
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let info = HardwareMonitor::with_root(temp_dir.path()).unwrap().get_system_info();
+        assert_eq!(info, SystemInfo::default());
+    }
+
+    #[test]
+    fn test_gpu_mode_parse() {
+        assert_eq!(GpuMode::parse("on-demand\n"), Some(GpuMode::Hybrid));
+        assert_eq!(GpuMode::parse("nvidia\n"), Some(GpuMode::Discrete));
+        assert_eq!(GpuMode::parse("integrated\n"), Some(GpuMode::Integrated));
+        assert_eq!(
+            GpuMode::parse("Current graphics mode is: hybrid\n"),
+            Some(GpuMode::Hybrid)
+        );
+        assert_eq!(GpuMode::parse(""), None);
+    }
+
+    #[test]
+    fn test_pending_gpu_switch() {
+        let pending = PendingGpuSwitch {
+            mode: GpuMode::Discrete,
+            boot_id: "boot-1".to_string(),
+        };
+
+        // prime-select reports the new mode right away, the restart is missing
+        assert!(!pending.is_settled("boot-1", Some(GpuMode::Discrete)));
+        assert!(!pending.is_settled("boot-1", None));
+        assert!(pending.is_settled("boot-2", Some(GpuMode::Discrete)));
+        // Switched back before restarting
+        assert!(pending.is_settled("boot-1", Some(GpuMode::Integrated)));
+    }
+
+    #[test]
+    fn test_resolve_active_gpu() {
+        let dgpu = |runtime_active, always_on, drives_output| {
+            Some(DgpuState {
+                runtime_active,
+                always_on,
+                drives_output,
+            })
+        };
+
+        assert_eq!(resolve_active_gpu(Some(GpuMode::Discrete), None), GpuType::Integrated);
+        assert_eq!(
+            resolve_active_gpu(Some(GpuMode::Discrete), dgpu(false, false, false)),
+            GpuType::Discrete
+        );
+        assert_eq!(
+            resolve_active_gpu(Some(GpuMode::Integrated), dgpu(true, false, false)),
+            GpuType::Integrated
+        );
+
+        // Hybrid: a suspended dGPU is idle, an awake one is rendering
+        let hybrid = Some(GpuMode::Hybrid);
+        assert_eq!(resolve_active_gpu(hybrid, dgpu(false, false, false)), GpuType::Integrated);
+        assert_eq!(resolve_active_gpu(hybrid, dgpu(true, false, false)), GpuType::Discrete);
+        // Without runtime PM the dGPU is always awake, only outputs count
+        assert_eq!(resolve_active_gpu(None, dgpu(true, true, false)), GpuType::Integrated);
+        assert_eq!(resolve_active_gpu(None, dgpu(true, true, true)), GpuType::Discrete);
+    }
+
+    #[test]
+    fn test_active_gpu_from_fixture() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let monitor = fixture.monitor();
+        monitor.gpu_mode.set(Some(GpuMode::Hybrid)).unwrap();
+
+        // The AMD iGPU alone
+        assert_eq!(monitor.read_dgpu_state(), None);
+        assert_eq!(monitor.get_active_gpu().unwrap(), GpuType::Integrated);
+
+        // NVIDIA dGPU without DRM, found on the PCI bus
+        let nvidia = "/sys/bus/pci/devices/0000:01:00.0";
+        fixture.write(&format!("{nvidia}/vendor"), "0x10de\n");
+        fixture.write(&format!("{nvidia}/class"), "0x030200\n");
+        fixture.write(&format!("{nvidia}/power/control"), "auto\n");
+        fixture.write(&format!("{nvidia}/power/runtime_status"), "suspended\n");
+        assert_eq!(monitor.get_active_gpu().unwrap(), GpuType::Integrated);
+
+        fixture.write(&format!("{nvidia}/power/runtime_status"), "active\n");
+        assert_eq!(monitor.get_active_gpu().unwrap(), GpuType::Discrete);
+    }
+
+    #[test]
+    fn test_system_stats_from_fixture() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let mut monitor = fixture.monitor();
+
+        let stats = monitor.get_system_stats().unwrap();
+        assert_eq!(stats.cpu.cores.len(), crate::test_fixtures::CPU_COUNT);
+        assert_eq!(stats.cpu.cores[1].frequency_mhz, 2100);
+        assert_eq!(stats.cpu.package_temp, Some(61.5));
+        assert_eq!(stats.cpu.scaling_driver, "amd-pstate-epp");
+
+        assert_eq!(stats.gpus.len(), 1);
+        let gpu = &stats.gpus[0];
+        assert_eq!(gpu.frequency_mhz, Some(1800));
+        assert_eq!(gpu.temperature, Some(48.0));
+        assert_eq!(gpu.vram_total_mb, Some(2048));
+
+        assert_eq!(stats.fans.len(), 1);
+        assert_eq!(stats.fans[0].speed_rpm, Some(2400));
+        assert_eq!(stats.fans[0].requested_percent, Some(60));
+
+        assert_eq!(stats.batteries.len(), 1);
+        assert_eq!(stats.total_battery_percent(), Some(80.0));
+    }
+
+    #[test]
+    fn test_die_temperatures() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // Ryzen with two chiplets, the second one busy
+        fixture.write("/sys/class/hwmon/hwmon0/temp3_label", "Tccd1\n");
+        fixture.write("/sys/class/hwmon/hwmon0/temp3_input", "58000\n");
+        fixture.write("/sys/class/hwmon/hwmon0/temp4_label", "Tccd2\n");
+        fixture.write("/sys/class/hwmon/hwmon0/temp4_input", "66250\n");
+        // Broken reading
+        fixture.write("/sys/class/hwmon/hwmon0/temp5_label", "Tccd3\n");
+        fixture.write("/sys/class/hwmon/hwmon0/temp5_input", "\n");
+        let mut monitor = fixture.monitor();
+
+        let cpu = monitor.get_system_stats().unwrap().cpu;
+        assert_eq!(
+            cpu.die_temps,
+            [
+                ("Tdie".to_string(), 61.5),
+                ("Tccd1".to_string(), 58.0),
+                ("Tccd2".to_string(), 66.25),
+            ]
+        );
+        // The hottest die, not the first sensor
+        assert_eq!(cpu.package_temp, Some(66.25));
+    }
+
+    #[test]
+    fn test_fan_requested_and_measured_speed() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // Seized fan and a fan that only reports its duty cycle
+        fixture.write("/sys/class/hwmon/hwmon2/fan1_input", "0\n");
+        fixture.write("/sys/class/hwmon/hwmon2/pwm2", "255\n");
+        let mut monitor = fixture.monitor();
+
+        let fans = monitor.get_system_stats().unwrap().fans;
+        assert_eq!(fans.len(), 2);
+
+        assert!(fans[0].is_stalled());
+        assert_eq!(fans[0].speed_summary(), "requested 60% / 0 RPM");
+
+        assert_eq!(fans[1].speed_rpm, None);
+        assert_eq!(fans[1].requested_percent, Some(100));
+        assert!(!fans[1].is_stalled());
+        assert_eq!(fans[1].speed_summary(), "requested 100%");
+
+        // Scaled by the driver's full speed like writes
+        fixture.write("/sys/class/hwmon/hwmon2/pwm2_max", "100\n");
+        fixture.write("/sys/class/hwmon/hwmon2/pwm2", "40\n");
+        let fans = fixture.monitor().get_system_stats().unwrap().fans;
+        assert_eq!(fans[1].requested_percent, Some(40));
+    }
+
+    #[test]
+    fn test_gpu_fans() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let gpu_hwmon = "/sys/class/drm/card0/device/hwmon/hwmon9";
+        fixture.write(&format!("{gpu_hwmon}/fan1_input"), "1800\n");
+        fixture.write(&format!("{gpu_hwmon}/pwm1"), "102\n");
+        // Like in real sysfs the GPU hwmon is also listed in /sys/class/hwmon
+        std::os::unix::fs::symlink(
+            fixture.path(gpu_hwmon),
+            fixture.path("/sys/class/hwmon/hwmon9"),
+        )
+        .unwrap();
+        let mut monitor = fixture.monitor();
+        let stats = monitor.get_system_stats().unwrap();
+
+        let gpu_fans: Vec<_> = stats.fans.iter().filter(|fan| fan.gpu.is_some()).collect();
+        assert_eq!(gpu_fans.len(), 1);
+        assert_eq!(gpu_fans[0].fan_id, "card0_fan1");
+        assert_eq!(gpu_fans[0].display_name(), "GPU fan 1 (card0)");
+        assert_eq!(gpu_fans[0].requested_percent, Some(40));
+        assert_eq!(stats.gpus[0].fan_ids, ["card0_fan1"]);
+
+        // Not reported a second time as chassis fan
+        assert_eq!(stats.fans.len(), 2);
+        assert_eq!(parse_gpu_fan_id("card0_fan1"), Some(("card0", 1)));
+        assert_eq!(parse_gpu_fan_id("fan1"), None);
+    }
+
+    #[test]
+    fn test_sensor_warnings() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let mut monitor = fixture.monitor();
+        assert_eq!(monitor.get_system_stats().unwrap().warnings, Vec::<String>::new());
+
+        // A garbled reading is reported, a missing sensor is not
+        fixture.write("/sys/class/hwmon/hwmon0/temp2_input", "abc\n");
+        fixture.write("/sys/class/hwmon/hwmon0/temp3_label", "Tccd1\n");
+        let stats = monitor.get_system_stats().unwrap();
+        assert_eq!(stats.warnings, ["/sys/class/hwmon/hwmon0/temp2_input parse failed: abc"]);
+
+        // Warnings are per poll
+        fixture.write("/sys/class/hwmon/hwmon0/temp2_input", "61500\n");
+        assert!(monitor.get_system_stats().unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), [5]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn test_cpu_topology() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // Two cores with two threads each, siblings are numbered apart
+        for (cpu, siblings) in [(0, "0,2"), (1, "1,3"), (2, "0,2"), (3, "1,3")] {
+            let path = format!("/sys/devices/system/cpu/cpu{cpu}");
+            fixture.write(&format!("{path}/topology/physical_package_id"), "0\n");
+            fixture.write(&format!("{path}/topology/thread_siblings_list"), siblings);
+            for (index, level, kind, size) in [
+                (0, "1", "Data", "48K"),
+                (1, "1", "Instruction", "32K"),
+                (2, "2", "Unified", "1280K"),
+            ] {
+                let cache = format!("{path}/cache/index{index}");
+                fixture.write(&format!("{cache}/level"), level);
+                fixture.write(&format!("{cache}/type"), kind);
+                fixture.write(&format!("{cache}/size"), size);
+                fixture.write(&format!("{cache}/shared_cpu_list"), siblings);
+            }
+            fixture.write(&format!("{path}/cache/index3/level"), "3");
+            fixture.write(&format!("{path}/cache/index3/type"), "Unified");
+            fixture.write(&format!("{path}/cache/index3/size"), "16384K");
+            fixture.write(&format!("{path}/cache/index3/shared_cpu_list"), "0-3");
+        }
+
+        let topology = fixture.monitor().cpu_topology();
+        assert_eq!(topology.sibling_groups, [vec![0, 2], vec![1, 3]]);
+        assert_eq!(topology.core_summary(), "2 cores, 4 threads");
+        assert_eq!(topology.siblings_summary().as_deref(), Some("0+2, 1+3"));
+        assert_eq!(topology.packages, 1);
+
+        let caches: Vec<String> = topology.caches.iter().map(CacheInfo::summary).collect();
+        assert_eq!(caches, ["L1d: 2 × 48 KiB", "L1i: 2 × 32 KiB", "L2: 2 × 1.25 MiB", "L3: 16 MiB"]);
+    }
+
+    #[test]
+    fn test_cpu_topology_hybrid() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        // Two P-cores with an L2 each, two E-cores sharing a bigger one
+        for (cpu, shared, size) in [(0, "0", "1280K"), (1, "1", "1280K"), (2, "2-3", "2048K"), (3, "2-3", "2048K")] {
+            let cache = format!("/sys/devices/system/cpu/cpu{cpu}/cache/index2");
+            fixture.write(&format!("{cache}/level"), "2");
+            fixture.write(&format!("{cache}/type"), "Unified");
+            fixture.write(&format!("{cache}/size"), size);
+            fixture.write(&format!("{cache}/shared_cpu_list"), shared);
+        }
+        let monitor = fixture.monitor();
+
+        let caches: Vec<String> = monitor.cpu_topology().caches.iter().map(CacheInfo::summary).collect();
+        assert_eq!(caches, ["L2: 2 × 1.25 MiB", "L2: 2 MiB"]);
+
+        // Taking CPUs offline changes the topology
+        fixture.write("/sys/devices/system/cpu/cpu0/online", "0\n");
+        let topology = monitor.cpu_topology();
+        assert_eq!(topology.threads, 3);
+        let caches: Vec<String> = topology.caches.iter().map(CacheInfo::summary).collect();
+        assert_eq!(caches, ["L2: 1.25 MiB", "L2: 2 MiB"]);
+    }
+
+    #[test]
+    fn test_cpu_topology_without_smt() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let topology = fixture.monitor().cpu_topology();
+        assert_eq!(topology.cores(), crate::test_fixtures::CPU_COUNT);
+        assert_eq!(topology.siblings_summary(), None);
+        assert!(topology.caches.is_empty());
+    }
+
+    #[test]
+    fn test_rapl_power() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let package = "/sys/class/powercap/intel-rapl/intel-rapl:0";
+        fixture.write(&format!("{package}/name"), "package-0\n");
+        fixture.write(&format!("{package}/energy_uj"), "1000000\n");
+        fixture.write(&format!("{package}/max_energy_range_uj"), "262143328850\n");
+        fixture.write(&format!("{package}/intel-rapl:0:0/name"), "core\n");
+        fixture.write(&format!("{package}/intel-rapl:0:0/energy_uj"), "500000\n");
+        // Zone without a readable counter
+        fixture.write(&format!("{package}/intel-rapl:0:1/name"), "uncore\n");
+        let mut monitor = fixture.monitor();
+
+        // A single reading has no power yet
+        let start = Instant::now();
+        let cpu = monitor.get_system_stats().unwrap().cpu;
+        assert_eq!(cpu.package_power_watts, None);
+        assert!(cpu.power_breakdown.is_empty());
+
+        thread::sleep(Duration::from_millis(100));
+        fixture.write(&format!("{package}/energy_uj"), "3000000\n");
+        fixture.write(&format!("{package}/intel-rapl:0:0/energy_uj"), "1500000\n");
+        let cpu = monitor.get_system_stats().unwrap().cpu;
+        let elapsed = start.elapsed().as_secs_f32();
+
+        // 2 J and 1 J in at least 100ms, but no longer than the test took.
+        // Each zone is timed on its own, so they only agree roughly.
+        let package_watts = cpu.package_power_watts.unwrap();
+        assert!(package_watts >= 2.0 / elapsed && package_watts <= 20.0, "{package_watts} W");
+        let core_watts = cpu.power_breakdown["core"];
+        assert!((core_watts * 2.0 / package_watts - 1.0).abs() < 0.05, "{core_watts} W");
+        assert!(!cpu.power_breakdown.contains_key("uncore"));
+        assert!(cpu.power_summary(PowerUnit::Watts).unwrap().contains("(core "));
+        // Without a power limit the TDP is unknown
+        assert_eq!(cpu.tdp_watts, None);
+        let summary = cpu.power_summary(PowerUnit::PercentOfTdp).unwrap();
+        assert!(!summary.contains("TDP"));
+        
+        fixture.write(&format!("{package}/constraint_0_power_limit_uw"), "28000000\n");
+        let cpu = monitor.get_system_stats().unwrap().cpu;
+        assert_eq!(cpu.tdp_watts, Some(28.0));
+        let summary = cpu.power_summary(PowerUnit::PercentOfTdp).unwrap();
+        assert!(summary.contains("% of 28 W TDP, core "), "{}", summary);
+    }
+
+    #[test]
+    fn test_offline_cores() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        fixture.write("/sys/devices/system/cpu/cpu2/online", "0\n");
+        fixture.write(
+            "/proc/stat",
+            "cpu  300 0 150 3000 0 0 0\n\
+             cpu0 100 0 50 1000 0 0 0\n\
+             cpu1 100 0 50 1000 0 0 0\n\
+             cpu3 100 0 50 1000 0 0 0\n",
+        );
+        let mut monitor = fixture.monitor();
+
+        assert_eq!(monitor.get_cpu_ids().unwrap(), (vec![0, 1, 3], vec![2]));
+
+        // Loads of the CPUs after the offline one must not shift
+        monitor.get_cpu_info().unwrap();
+        fixture.write(
+            "/proc/stat",
+            "cpu  400 0 150 3300 0 0 0\n\
+             cpu0 100 0 50 1100 0 0 0\n\
+             cpu1 100 0 50 1100 0 0 0\n\
+             cpu3 200 0 50 1100 0 0 0\n",
+        );
+        let info = monitor.get_cpu_info().unwrap();
+        let ids: Vec<usize> = info.cores.iter().map(|core| core.core_id).collect();
+        assert_eq!(ids, [0, 1, 3]);
+        assert_eq!(info.cores[1].load_percent, 0.0);
+        assert_eq!(info.cores[2].load_percent, 50.0);
+        assert_eq!(info.offline_cores, [2]);
+    }
+
+    #[test]
+    fn test_reset_load_baseline() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let mut monitor = fixture.monitor();
+        let stat = |busy: u64, idle: u64| {
+            (0..crate::test_fixtures::CPU_COUNT)
+                .map(|cpu| format!("cpu{cpu} {busy} 0 50 {idle} 0 0 0\n"))
+                .collect::<String>()
+        };
+        monitor.get_cpu_info().unwrap();
+
+        // Busy while nobody read the stats, idle since the reset
+        fixture.write("/proc/stat", &stat(1100, 1000));
+        monitor.reset_load_baseline();
+        fixture.write("/proc/stat", &stat(1100, 1100));
+        let info = monitor.get_cpu_info().unwrap();
+        assert!(info.cores.iter().all(|core| core.load_percent == 0.0));
+
+        // Without a reset the load covers both
+        let mut monitor = fixture.monitor();
+        fixture.write("/proc/stat", &stat(100, 1000));
+        monitor.get_cpu_info().unwrap();
+        fixture.write("/proc/stat", &stat(1100, 1100));
+        let info = monitor.get_cpu_info().unwrap();
+        assert!(info.cores.iter().all(|core| core.load_percent > 90.0));
+    }
 }
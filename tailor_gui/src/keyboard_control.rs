@@ -1,10 +1,58 @@
 // src/keyboard_control.rs
 use anyhow::{Context, Result};
+use crate::error::{self, HardwareResult};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Attributes that select a firmware effect, relative to the LED device.
+/// tuxedo_keyboard has `mode` on its platform device, the LED's parent.
+const HW_EFFECT_ATTRIBUTES: [&str; 3] = ["effect", "mode", "device/mode"];
+
+/// Effects of tuxedo_keyboard's numeric `mode` attribute, by value
+const TUXEDO_KEYBOARD_MODES: [&str; 8] = [
+    "static", "breathe", "cycle", "dance", "flash", "random_color", "tempo", "wave",
+];
+
+/// Backlight timeout of the keyboard firmware in seconds, exposed by
+/// tuxedo_io on some Clevo ECs
+const HW_TIMEOUT_ATTRIBUTES: [&str; 2] = ["kbd_backlight_timeout", "device/kbd_backlight_timeout"];
+
+/// Exponent of the perceptual brightness curve, roughly how the eye
+/// responds to light
+pub const PERCEPTUAL_GAMMA: f32 = 2.2;
+
+/// Raw value for `percentage` of `max` on the perceptual curve, so equal
+/// steps look equally far apart. Anything above 0% stays lit.
+pub fn perceptual_raw(percentage: u8, max: u32) -> u32 {
+    if percentage == 0 {
+        return 0;
+    }
+    let fraction = (percentage.min(100) as f32 / 100.0).powf(PERCEPTUAL_GAMMA);
+    let raw = (max as f32 * fraction).round() as u32;
+    raw.max(1).min(max)
+}
+
+/// Percentage of a raw value on the perceptual curve, the inverse of
+/// [`perceptual_raw`]
+pub fn perceptual_percentage(raw: u32, max: u32) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    let fraction = (raw.min(max) as f32 / max as f32).powf(1.0 / PERCEPTUAL_GAMMA);
+    (fraction * 100.0).round() as u8
+}
+
+/// A firmware-driven keyboard effect
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HwEffect {
+    name: String,
+    /// What is written to the attribute to select the effect
+    value: String,
+}
+
 /// Controller for Clevo RGB keyboard backlight
 /// Interfaces with /sys/class/leds/rgb:kbd_backlight/
+#[derive(Clone)]
 pub struct KeyboardController {
     base_path: PathBuf,
     max_brightness: u8,
@@ -12,15 +60,16 @@ pub struct KeyboardController {
 
 impl KeyboardController {
     /// Create a new keyboard controller
-    pub fn new() -> Result<Self> {
+    pub fn new() -> HardwareResult<Self> {
         let base_path = PathBuf::from("/sys/class/leds/rgb:kbd_backlight");
         
         if !base_path.exists() {
-            anyhow::bail!(
+            return Err(error::unsupported(format!(
                 "Keyboard backlight interface not found at {}. \
                  Is the keyboard RGB driver loaded?",
                 base_path.display()
-            );
+            ))
+            .into());
         }
         
         // Read max brightness
@@ -33,7 +82,7 @@ impl KeyboardController {
     }
     
     /// Create controller with custom path (for testing)
-    pub fn with_path(path: PathBuf) -> Result<Self> {
+    pub fn with_path(path: PathBuf) -> HardwareResult<Self> {
         let max_brightness = Self::read_max_brightness(&path)?;
         Ok(KeyboardController {
             base_path: path,
@@ -51,15 +100,25 @@ impl KeyboardController {
             .context("Failed to parse max_brightness")
     }
     
-    /// Get current brightness (0-100%)
-    pub fn get_brightness(&self) -> Result<u8> {
+    fn read_raw_brightness(&self) -> Result<u8> {
         let brightness_path = self.base_path.join("brightness");
         let content = fs::read_to_string(&brightness_path)
             .context("Failed to read brightness")?;
         
-        let raw_brightness: u8 = content.trim()
+        content.trim()
             .parse()
-            .context("Failed to parse brightness")?;
+            .context("Failed to parse brightness")
+    }
+    
+    fn write_raw_brightness(&self, raw_value: u8) -> Result<()> {
+        let brightness_path = self.base_path.join("brightness");
+        fs::write(&brightness_path, raw_value.to_string())
+            .context("Failed to write brightness")
+    }
+    
+    /// Get current brightness (0-100%)
+    pub fn get_brightness(&self) -> HardwareResult<u8> {
+        let raw_brightness = self.read_raw_brightness()?;
         
         // Convert from raw value to percentage
         let percentage = if self.max_brightness > 0 {
@@ -72,27 +131,50 @@ impl KeyboardController {
     }
     
     /// Set brightness (0-100%)
-    pub fn set_brightness(&self, percentage: u8) -> Result<()> {
+    pub fn set_brightness(&self, percentage: u8) -> HardwareResult<()> {
         if percentage > 100 {
-            anyhow::bail!("Brightness percentage must be 0-100, got {}", percentage);
+            return Err(error::invalid_input(format!(
+                "Brightness percentage must be 0-100, got {}",
+                percentage
+            ))
+            .into());
         }
         
         // Convert percentage to raw value
         let raw_value = ((percentage as f32 / 100.0) * self.max_brightness as f32) as u8;
+        Ok(self.write_raw_brightness(raw_value)?)
+    }
+    
+    /// Get current brightness (0-100%) on the perceptual curve
+    pub fn get_brightness_perceptual(&self) -> HardwareResult<u8> {
+        let raw_brightness = self.read_raw_brightness()?;
+        Ok(perceptual_percentage(raw_brightness as u32, self.max_brightness as u32))
+    }
+    
+    /// Set brightness (0-100%) on the perceptual curve, low percentages
+    /// get finer steps than with [`Self::set_brightness`]
+    pub fn set_brightness_perceptual(&self, percentage: u8) -> HardwareResult<()> {
+        if percentage > 100 {
+            return Err(error::invalid_input(format!(
+                "Brightness percentage must be 0-100, got {}",
+                percentage
+            ))
+            .into());
+        }
         
-        let brightness_path = self.base_path.join("brightness");
-        fs::write(&brightness_path, raw_value.to_string())
-            .context("Failed to write brightness")?;
-        
-        Ok(())
+        let raw_value = perceptual_raw(percentage, self.max_brightness as u32);
+        Ok(self.write_raw_brightness(raw_value as u8)?)
     }
     
     /// Get current RGB color
-    pub fn get_color(&self) -> Result<(u8, u8, u8)> {
+    pub fn get_color(&self) -> HardwareResult<(u8, u8, u8)> {
         let multi_intensity_path = self.base_path.join("multi_intensity");
         
         if !multi_intensity_path.exists() {
-            anyhow::bail!("RGB color control not available (multi_intensity missing)");
+            return Err(error::unsupported(
+                "RGB color control not available (multi_intensity missing)",
+            )
+            .into());
         }
         
         let content = fs::read_to_string(&multi_intensity_path)
@@ -102,7 +184,7 @@ impl KeyboardController {
         let parts: Vec<&str> = content.trim().split_whitespace().collect();
         
         if parts.len() != 3 {
-            anyhow::bail!("Invalid multi_intensity format: {}", content);
+            return Err(error::parse(format!("Invalid multi_intensity format: {}", content)).into());
         }
         
         let r = parts[0].parse().context("Failed to parse red value")?;
@@ -113,11 +195,14 @@ impl KeyboardController {
     }
     
     /// Set RGB color (0-255 per channel)
-    pub fn set_color(&self, r: u8, g: u8, b: u8) -> Result<()> {
+    pub fn set_color(&self, r: u8, g: u8, b: u8) -> HardwareResult<()> {
         let multi_intensity_path = self.base_path.join("multi_intensity");
         
         if !multi_intensity_path.exists() {
-            anyhow::bail!("RGB color control not available (multi_intensity missing)");
+            return Err(error::unsupported(
+                "RGB color control not available (multi_intensity missing)",
+            )
+            .into());
         }
         
         let color_str = format!("{} {} {}", r, g, b);
@@ -128,7 +213,7 @@ impl KeyboardController {
     }
     
     /// Set both color and brightness in one operation
-    pub fn set_color_and_brightness(&self, r: u8, g: u8, b: u8, brightness: u8) -> Result<()> {
+    pub fn set_color_and_brightness(&self, r: u8, g: u8, b: u8, brightness: u8) -> HardwareResult<()> {
         self.set_color(r, g, b)?;
         self.set_brightness(brightness)?;
         Ok(())
@@ -145,14 +230,152 @@ impl KeyboardController {
     }
     
     /// Turn off keyboard backlight
-    pub fn turn_off(&self) -> Result<()> {
+    pub fn turn_off(&self) -> HardwareResult<()> {
         self.set_brightness(0)
     }
     
     /// Check if keyboard backlight is currently on
-    pub fn is_on(&self) -> Result<bool> {
+    pub fn is_on(&self) -> HardwareResult<bool> {
         Ok(self.get_brightness()? > 0)
     }
+    
+    /// Names of the effects the keyboard firmware can run on its own,
+    /// e.g. "breathe" or "wave". Empty if there's no effect attribute.
+    pub fn list_hw_effects(&self) -> Vec<String> {
+        self.hw_effect_attribute()
+            .map(|(_, effects)| effects.into_iter().map(|effect| effect.name).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Let the firmware run the effect `name`
+    pub fn set_hw_effect(&self, name: &str) -> HardwareResult<()> {
+        let (path, effects) = self
+            .hw_effect_attribute()
+            .ok_or_else(|| error::unsupported("Keyboard has no hardware effects"))?;
+        
+        let Some(effect) = effects.iter().find(|effect| effect.name == name) else {
+            let names: Vec<&str> = effects.iter().map(|effect| effect.name.as_str()).collect();
+            return Err(error::invalid_input(format!(
+                "Unknown keyboard effect '{}', supported are: {}",
+                name,
+                names.join(", ")
+            ))
+            .into());
+        };
+        
+        fs::write(&path, &effect.value)
+            .context(format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+    
+    /// Switch a running firmware effect back to the static color, if
+    /// the keyboard has effects at all
+    pub fn clear_hw_effect(&self) -> HardwareResult<()> {
+        let static_effect = self
+            .list_hw_effects()
+            .into_iter()
+            .find(|name| matches!(name.as_str(), "static" | "custom" | "none"));
+        match static_effect {
+            Some(name) => self.set_hw_effect(&name),
+            None => Ok(()),
+        }
+    }
+    
+    /// Whether the keyboard firmware has its own backlight timeout
+    pub fn has_hw_timeout(&self) -> bool {
+        self.hw_timeout_attribute().is_some()
+    }
+    
+    /// Seconds until the firmware turns the backlight off, 0 if it never does
+    pub fn get_hw_timeout(&self) -> HardwareResult<u32> {
+        let path = self
+            .hw_timeout_attribute()
+            .ok_or_else(|| error::unsupported("Keyboard has no firmware backlight timeout"))?;
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let timeout = content.trim()
+            .parse()
+            .context("Failed to parse the backlight timeout")?;
+        Ok(timeout)
+    }
+    
+    /// Let the firmware turn the backlight off after `secs` without input,
+    /// 0 keeps it on
+    pub fn set_hw_timeout(&self, secs: u32) -> HardwareResult<()> {
+        let path = self
+            .hw_timeout_attribute()
+            .ok_or_else(|| error::unsupported("Keyboard has no firmware backlight timeout"))?;
+        fs::write(&path, secs.to_string())
+            .context(format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+    
+    fn hw_timeout_attribute(&self) -> Option<PathBuf> {
+        HW_TIMEOUT_ATTRIBUTES
+            .iter()
+            .map(|attribute| self.base_path.join(attribute))
+            .find(|path| path.is_file())
+    }
+    
+    /// The effect attribute and the values it accepts
+    fn hw_effect_attribute(&self) -> Option<(PathBuf, Vec<HwEffect>)> {
+        HW_EFFECT_ATTRIBUTES.iter().find_map(|attribute| {
+            let path = self.base_path.join(attribute);
+            let current = fs::read_to_string(&path).ok()?;
+            let effects = Self::accepted_effects(&path, current.trim());
+            (!effects.is_empty()).then_some((path, effects))
+        })
+    }
+    
+    /// Values accepted by an effect attribute. Drivers list them in a
+    /// sibling file like `mode_available`, or in the attribute itself
+    /// with the active one in brackets ("static [breathe] wave").
+    fn accepted_effects(path: &Path, current: &str) -> Vec<HwEffect> {
+        let by_name = |names: &str| -> Vec<HwEffect> {
+            names
+                .split_whitespace()
+                .map(|name| name.trim_matches(|c| c == '[' || c == ']'))
+                .map(|name| HwEffect {
+                    name: name.to_string(),
+                    value: name.to_string(),
+                })
+                .collect()
+        };
+        
+        let attribute = path.file_name().unwrap_or_default().to_string_lossy();
+        let available = [
+            format!("{}_available", attribute),
+            format!("available_{}s", attribute),
+        ];
+        if let Some(names) = available
+            .iter()
+            .find_map(|file| fs::read_to_string(path.with_file_name(file)).ok())
+        {
+            return by_name(&names);
+        }
+        
+        if current.contains('[') {
+            return by_name(current);
+        }
+        
+        // tuxedo_keyboard takes the index of the mode
+        let is_tuxedo_keyboard = path
+            .parent()
+            .and_then(|device| device.canonicalize().ok())
+            .is_some_and(|device| device.ends_with("tuxedo_keyboard"));
+        if is_tuxedo_keyboard && current.parse::<usize>().is_ok() {
+            return TUXEDO_KEYBOARD_MODES
+                .iter()
+                .enumerate()
+                .map(|(index, name)| HwEffect {
+                    name: name.to_string(),
+                    value: index.to_string(),
+                })
+                .collect();
+        }
+        
+        Vec::new()
+    }
 }
 
 /// Helper function to check if keyboard backlight is available on the system
@@ -182,6 +405,7 @@ pub fn list_led_devices() -> Result<Vec<String>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::HardwareError;
     use std::io::Write;
     use tempfile::TempDir;
 
@@ -228,6 +452,51 @@ mod tests {
         assert_eq!(controller.get_brightness().unwrap(), 0);
     }
     
+    #[test]
+    fn test_error_kinds() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = KeyboardController::with_path(temp_dir.path().join("missing"));
+        assert!(matches!(missing.err(), Some(HardwareError::Unsupported(_))));
+        
+        let kbd_path = create_mock_keyboard_sysfs(&temp_dir);
+        let controller = KeyboardController::with_path(kbd_path.clone()).unwrap();
+        assert!(matches!(controller.set_brightness(101), Err(HardwareError::InvalidInput(_))));
+        assert!(matches!(controller.set_hw_timeout(60), Err(HardwareError::Unsupported(_))));
+        
+        fs::write(kbd_path.join("brightness"), "bright").unwrap();
+        assert!(matches!(controller.get_brightness(), Err(HardwareError::Parse(_))));
+        fs::remove_file(kbd_path.join("multi_intensity")).unwrap();
+        assert!(matches!(controller.get_color(), Err(HardwareError::Unsupported(_))));
+    }
+    
+    #[test]
+    fn test_perceptual_brightness() {
+        // Endpoints match the linear mapping
+        assert_eq!(perceptual_raw(0, 255), 0);
+        assert_eq!(perceptual_raw(100, 255), 255);
+        assert_eq!(perceptual_raw(100, 0), 0);
+        // Monotonic, and dimmer than linear in between
+        for max in [1, 3, 255, 1000, 120000] {
+            for pct in 1..=100 {
+                assert!(perceptual_raw(pct, max) >= perceptual_raw(pct - 1, max));
+                assert!(perceptual_raw(pct, max) >= 1);
+            }
+        }
+        assert_eq!(perceptual_raw(50, 255), 55);
+        // Reading back gives the percentage that was set
+        for pct in 0..=100 {
+            assert_eq!(perceptual_percentage(perceptual_raw(pct, 120000), 120000), pct);
+        }
+        
+        let temp_dir = TempDir::new().unwrap();
+        let kbd_path = create_mock_keyboard_sysfs(&temp_dir);
+        let controller = KeyboardController::with_path(kbd_path.clone()).unwrap();
+        controller.set_brightness_perceptual(50).unwrap();
+        assert_eq!(fs::read_to_string(kbd_path.join("brightness")).unwrap(), "55");
+        assert_eq!(controller.get_brightness_perceptual().unwrap(), 50);
+        assert!(controller.set_brightness_perceptual(101).is_err());
+    }
+    
     #[test]
     fn test_color_operations() {
         let temp_dir = TempDir::new().unwrap();
@@ -255,6 +524,65 @@ mod tests {
         assert!(controller.set_brightness(101).is_err());
     }
     
+    #[test]
+    fn test_hw_effects() {
+        let temp_dir = TempDir::new().unwrap();
+        let kbd_path = create_mock_keyboard_sysfs(&temp_dir);
+        let controller = KeyboardController::with_path(kbd_path.clone()).unwrap();
+        
+        // No effect attribute: nothing to list, clearing is a no-op
+        assert!(controller.list_hw_effects().is_empty());
+        assert!(controller.set_hw_effect("wave").is_err());
+        assert!(controller.clear_hw_effect().is_ok());
+        
+        // Selector format with the active effect in brackets
+        fs::write(kbd_path.join("effect"), "[static] breathe wave\n").unwrap();
+        assert_eq!(controller.list_hw_effects(), ["static", "breathe", "wave"]);
+        controller.set_hw_effect("wave").unwrap();
+        assert_eq!(fs::read_to_string(kbd_path.join("effect")).unwrap(), "wave");
+        // Like the kernel, report the list again
+        fs::write(kbd_path.join("effect"), "static breathe [wave]\n").unwrap();
+        let err = controller.set_hw_effect("rainbow").unwrap_err();
+        assert!(err.to_string().contains("static, breathe, wave"));
+    }
+    
+    #[test]
+    fn test_hw_timeout() {
+        let temp_dir = TempDir::new().unwrap();
+        let kbd_path = create_mock_keyboard_sysfs(&temp_dir);
+        let controller = KeyboardController::with_path(kbd_path.clone()).unwrap();
+        
+        assert!(!controller.has_hw_timeout());
+        assert!(controller.get_hw_timeout().is_err());
+        assert!(controller.set_hw_timeout(30).is_err());
+        
+        // tuxedo_io puts it on the platform device
+        fs::create_dir_all(kbd_path.join("device")).unwrap();
+        fs::write(kbd_path.join("device/kbd_backlight_timeout"), "0\n").unwrap();
+        assert!(controller.has_hw_timeout());
+        assert_eq!(controller.get_hw_timeout().unwrap(), 0);
+        controller.set_hw_timeout(30).unwrap();
+        assert_eq!(controller.get_hw_timeout().unwrap(), 30);
+    }
+    
+    #[test]
+    fn test_tuxedo_keyboard_modes() {
+        let temp_dir = TempDir::new().unwrap();
+        let platform = temp_dir.path().join("tuxedo_keyboard");
+        let kbd_path = platform.join("leds/rgb:kbd_backlight");
+        fs::create_dir_all(&kbd_path).unwrap();
+        fs::write(kbd_path.join("max_brightness"), "255").unwrap();
+        std::os::unix::fs::symlink(&platform, kbd_path.join("device")).unwrap();
+        fs::write(platform.join("mode"), "0\n").unwrap();
+        let controller = KeyboardController::with_path(kbd_path).unwrap();
+        
+        assert_eq!(controller.list_hw_effects().len(), TUXEDO_KEYBOARD_MODES.len());
+        controller.set_hw_effect("breathe").unwrap();
+        assert_eq!(fs::read_to_string(platform.join("mode")).unwrap(), "1");
+        controller.clear_hw_effect().unwrap();
+        assert_eq!(fs::read_to_string(platform.join("mode")).unwrap(), "0");
+    }
+    
     #[test]
     fn test_rgb_support_check() {
         let temp_dir = TempDir::new().unwrap();
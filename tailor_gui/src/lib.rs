@@ -0,0 +1,48 @@
+//! Hardware control of the Tailor GUI without GTK.
+//!
+//! The GUI is one frontend for these modules, they can also drive a custom
+//! frontend or a headless service. `examples/embed_controller.rs` shows the
+//! typical use:
+//!
+//! - [`profile_controller::ProfileController`] applies and manages profiles
+//! - [`hardware_monitor::HardwareMonitor`] reads temperatures, loads and fans
+//! - [`fan_daemon::FanDaemon`] runs software fan curves in the background
+//! - [`profile_controller::ProfileBuilder`] creates profiles in code
+//! - [`fand`] runs fan control as a privileged daemon for an unprivileged
+//!   frontend
+//!
+//! [`prelude`] has everything needed for that, the modules stay public
+//! for less common types.
+
+pub mod app_config;
+pub mod color_temperature;
+pub mod diagnostics;
+pub mod error;
+pub mod fan_daemon;
+pub mod fand;
+pub mod hardware_control;
+pub mod hardware_monitor;
+pub mod keyboard_control;
+pub mod profile_controller;
+pub mod profile_system;
+pub mod stats_broadcaster;
+pub mod sysfs_watcher;
+pub mod thermal_history;
+pub mod threshold_monitor;
+pub mod user_service;
+
+#[cfg(test)]
+mod test_fixtures;
+
+/// Commonly used types, `use tailor_gui::prelude::*;`
+pub mod prelude {
+    pub use crate::error::{HardwareError, HardwareResult};
+    pub use crate::fan_daemon::FanDaemon;
+    pub use crate::hardware_control::{check_permissions, ApplyMask, HardwareController};
+    pub use crate::hardware_monitor::{HardwareMonitor, SystemStats};
+    pub use crate::keyboard_control::{is_keyboard_backlight_available, KeyboardController};
+    pub use crate::profile_controller::{ProfileBuilder, ProfileController};
+    pub use crate::profile_system::{
+        CpuPerformanceProfile, FanControlMode, KeyboardMode, Profile, ProfileManager,
+    };
+}
@@ -3,56 +3,174 @@
 mod app;
 pub mod components;
 mod config;
+mod global_shortcuts;
 mod modals;
+mod notifications;
 mod setup;
+mod tray;
 pub mod state;
 pub mod templates;
 pub mod util;
+mod window_placement;
 
-// Phase 1 modules (already added)
-pub mod profile_system;
-pub mod hardware_monitor;
-pub mod keyboard_control;
-
-// NEW - Phase 2 modules
-pub mod hardware_control;
-pub mod profile_controller;
+// Hardware control lives in the library, so other frontends can use it
+pub use tailor_gui::{
+    app_config, color_temperature, diagnostics, error, fan_daemon, fand, hardware_control,
+    hardware_monitor, keyboard_control, profile_controller, profile_system, stats_broadcaster,
+    sysfs_watcher, thermal_history, threshold_monitor, user_service,
+};
 
 use app::App;
 use clap::Parser;
+use gtk::glib;
 use gtk::prelude::ApplicationExt;
 use relm4::actions::{AccelsPlus, RelmAction, RelmActionGroup};
-use relm4::{gtk, main_application, RelmApp};
+use relm4::{adw, gtk, main_application, RelmApp};
 use setup::setup;
+use state::profile_controller;
 
+use crate::app_config::AppConfig;
 use crate::config::APP_ID;
 
 relm4::new_action_group!(AppActionGroup, "app");
 relm4::new_stateless_action!(QuitAction, AppActionGroup, "quit");
+relm4::new_stateless_action!(MaxPerformanceAction, AppActionGroup, "max-performance");
+relm4::new_stateless_action!(RestoreProfileAction, AppActionGroup, "restore-profile");
 
 /// Tailord GUI (part of tuxedo-rs)
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct CliArgs {}
+struct CliArgs {
+    /// Apply the profile with this name on startup
+    #[arg(long, value_name = "PROFILE")]
+    apply: Option<String>,
+
+    /// Exit after applying the profile instead of opening the window
+    #[arg(long, requires = "apply")]
+    no_gui: bool,
+
+    /// Print a system report for bug reports and exit
+    #[arg(long, conflicts_with = "apply")]
+    diagnostics: bool,
+
+    /// Include whether the fans stop at 0% in the report. Briefly stops
+    /// the fans and needs root.
+    #[arg(long, requires = "diagnostics")]
+    probe_fan_stop: bool,
+
+    /// Print the JSON Schema of profiles, for editing them by hand, and exit
+    #[arg(long, conflicts_with_all = ["apply", "diagnostics"])]
+    profile_schema: bool,
+
+    /// Load all CPU cores for this many seconds with the active profile,
+    /// then print peak temperatures, fan speeds and whether the CPU throttled
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["apply", "diagnostics", "profile_schema"])]
+    thermal_test: Option<u64>,
+
+    /// Check a hand-edited profile file (one profile or a list of them),
+    /// print what's wrong and exit, nonzero if anything is
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["apply", "diagnostics", "profile_schema", "thermal_test"])]
+    validate: Option<std::path::PathBuf>,
+}
 
 fn main() {
-    let _ = CliArgs::parse();
-    run_app()
-}
+    let args = CliArgs::parse();
 
-fn run_app() {
     // Enable logging
     tracing_subscriber::fmt()
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::FULL)
         .with_max_level(tracing::Level::INFO)
         .init();
 
+    // Works without a display server, GTK is never initialized here
+    if args.diagnostics {
+        let mut report = diagnostics::collect();
+        if args.probe_fan_stop {
+            report.probe_fan_stop();
+        }
+        println!("{report}");
+        return;
+    }
+    if args.profile_schema {
+        println!("{}", profile_system::json_schema());
+        return;
+    }
+    if let Some(path) = &args.validate {
+        let code =
+            profile_system::run_validate(path, &mut std::io::stdout(), &mut std::io::stderr());
+        std::process::exit(code);
+    }
+    if let Some(seconds) = args.thermal_test {
+        println!("Loading all CPU cores for {seconds}s, press Ctrl+C to stop");
+        cancel_on_sigint();
+        match diagnostics::run_thermal_test(std::time::Duration::from_secs(seconds), &CANCEL) {
+            Ok(report) => println!("{report}"),
+            Err(err) => {
+                eprintln!("Thermal test failed: {err:#}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(name) = &args.apply {
+        // Runs before GTK is initialized, so it also works without a display
+        let result = profile_controller()
+            .ok_or_else(|| anyhow::anyhow!("Profile controller is not available"))
+            .and_then(|controller| controller.apply_profile_by_name(name));
+
+        if let Err(err) = result {
+            eprintln!("Failed to apply profile '{name}': {err:#}");
+            if error::HardwareError::is_permission_denied_in(&err) {
+                eprintln!("Changing hardware settings needs root, try again with sudo");
+            }
+            std::process::exit(1);
+        }
+    }
+
+    // The application was never registered, so there's no instance to release
+    if args.no_gui {
+        return;
+    }
+
+    // A profile given on the command line wins over the configured one
+    run_app(args.apply.is_none())
+}
+
+fn run_app(apply_startup_profile: bool) {
     setup();
 
     let app = main_application();
     app.set_application_id(Some(APP_ID));
     app.set_resource_base_path(Some("/com/github/aaronerhardt/Tailor/"));
 
+    if apply_startup_profile {
+        // Activation happens again whenever the app is launched a second time,
+        // only apply the profile for the first one
+        let applied = std::cell::Cell::new(false);
+        app.connect_activate(move |_| {
+            if applied.replace(true) {
+                return;
+            }
+            let Some(name) = AppConfig::load().startup_profile else {
+                return;
+            };
+            if let Some(controller) = profile_controller() {
+                if let Err(err) = controller.apply_startup_profile(&name) {
+                    tracing::error!("Failed to apply startup profile: {err:#}");
+                }
+            }
+        });
+    }
+
+    // Every way of quitting ends here, including the signal handlers
+    app.connect_shutdown(|_| {
+        if let Some(controller) = profile_controller() {
+            controller.shutdown();
+        }
+    });
+    setup_signal_handlers(&app);
+
     let quit_action = {
         let app = app.clone();
         RelmAction::<QuitAction>::new_stateless(move |_| {
@@ -60,8 +178,26 @@ fn run_app() {
         })
     };
 
+    let max_performance_action = RelmAction::<MaxPerformanceAction>::new_stateless(|_| {
+        if let Some(controller) = profile_controller() {
+            if let Err(err) = controller.enable_maximum_performance() {
+                tracing::error!("Failed to enable maximum performance: {err:#}");
+            }
+        }
+    });
+
+    let restore_profile_action = RelmAction::<RestoreProfileAction>::new_stateless(|_| {
+        if let Some(controller) = profile_controller() {
+            if let Err(err) = controller.restore_active_profile() {
+                tracing::error!("Failed to restore profile settings: {err:#}");
+            }
+        }
+    });
+
     let mut actions = RelmActionGroup::<AppActionGroup>::new();
     actions.add_action(quit_action);
+    actions.add_action(max_performance_action);
+    actions.add_action(restore_profile_action);
     actions.register_for_main_application();
 
     app.set_accelerators_for_action::<QuitAction>(&["<Control>q"]);
@@ -71,3 +207,32 @@ fn run_app() {
     let app = RelmApp::from_app(app).visible_on_activate(false);
     app.run::<App>(());
 }
+
+/// Set by the first Ctrl+C of the thermal test, see [`cancel_on_sigint`]
+static CANCEL: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Let Ctrl+C stop the thermal test and still print the report. GTK isn't
+/// running yet, so a plain signal handler. A second Ctrl+C kills as usual.
+fn cancel_on_sigint() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        CANCEL.store(true, std::sync::atomic::Ordering::Relaxed);
+        // SAFETY: signal() is async-signal-safe
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+    }
+    let handler: extern "C" fn(libc::c_int) = on_sigint;
+    // SAFETY: the handler only touches an atomic and calls signal()
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+}
+
+/// Quit cleanly on SIGTERM (e.g. logout) and SIGINT, so fans aren't left
+/// at a fixed speed when the app is killed
+fn setup_signal_handlers(app: &adw::Application) {
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        let app = app.clone();
+        glib::unix_signal_add_local(signal, move || {
+            tracing::info!("Received signal {signal}, shutting down");
+            app.quit();
+            glib::ControlFlow::Break
+        });
+    }
+}
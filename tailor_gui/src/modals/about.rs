@@ -3,6 +3,7 @@ use relm4::{adw, gtk, ComponentParts, ComponentSender, SimpleComponent};
 
 // use gettextrs::gettext;
 use crate::config::{APP_ID, VERSION};
+use crate::diagnostics;
 
 pub struct AboutDialog {}
 
@@ -23,6 +24,7 @@ impl SimpleComponent for AboutDialog {
             .modal(true)
             .developers(vec!["Aaron Erhardt"])
             .artists(vec!["Aaron Erhardt"])
+            .debug_info_filename("tailor-diagnostics.txt")
             .build()
     }
 
@@ -39,6 +41,8 @@ impl SimpleComponent for AboutDialog {
     }
 
     fn update_view(&self, dialog: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+        // Probe again on every open, drivers might have been loaded meanwhile
+        dialog.set_debug_info(&diagnostics::collect().to_string());
         dialog.present();
     }
 }
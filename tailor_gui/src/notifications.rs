@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use gtk::gio;
+use gtk::prelude::ApplicationExt;
+use relm4::{gtk, main_application};
+
+use crate::app_config::NotificationBackend;
+use crate::config::APP_ID;
+
+/// Sends desktop notifications through the configured backend
+pub struct Notifier {
+    backend: NotificationBackend,
+    /// Server ids of the last libnotify notification by our id, a new one
+    /// replaces it
+    libnotify_ids: Arc<Mutex<HashMap<&'static str, u32>>>,
+}
+
+impl Notifier {
+    /// Resolve `NotificationBackend::Auto` by probing. Must be called after
+    /// the application was registered.
+    pub fn new(configured: NotificationBackend) -> Self {
+        let backend = match configured {
+            NotificationBackend::Auto => Self::probe(),
+            backend => backend,
+        };
+        tracing::info!("Using {} for notifications", backend.label());
+        Self {
+            backend,
+            libnotify_ids: Arc::default(),
+        }
+    }
+
+    /// GNotification needs a registered app with an installed desktop
+    /// file, otherwise notifications are silently dropped on many desktops.
+    /// Fall back to libnotify if a notification daemon is running.
+    fn probe() -> NotificationBackend {
+        let desktop_file = format!("{APP_ID}.desktop");
+        let gnotification_works = main_application().is_registered()
+            && gio::DesktopAppInfo::new(&desktop_file).is_some();
+
+        if !gnotification_works && notify_rust::get_server_information().is_ok() {
+            NotificationBackend::Libnotify
+        } else {
+            NotificationBackend::GNotification
+        }
+    }
+
+    /// Show a notification, replacing the previous one with the same `id`
+    pub fn notify(&self, id: &'static str, title: &str, body: &str) {
+        match self.backend {
+            NotificationBackend::Libnotify => {
+                let (title, body) = (title.to_owned(), body.to_owned());
+                let ids = Arc::clone(&self.libnotify_ids);
+                // Talks to D-Bus synchronously, so keep it off the main thread
+                std::thread::spawn(move || {
+                    let mut notification = notify_rust::Notification::new();
+                    notification.appname("Tailor").icon(APP_ID).summary(&title).body(&body);
+                    if let Some(&server_id) = ids.lock().unwrap().get(id) {
+                        notification.id(server_id);
+                    }
+                    match notification.show() {
+                        Ok(handle) => {
+                            ids.lock().unwrap().insert(id, handle.id());
+                        }
+                        Err(err) => tracing::warn!("Failed to send notification: {err}"),
+                    }
+                });
+            }
+            NotificationBackend::GNotification | NotificationBackend::Auto => {
+                let notification = gio::Notification::new(title);
+                notification.set_body(Some(body));
+                main_application().send_notification(Some(id), &notification);
+            }
+        }
+    }
+}
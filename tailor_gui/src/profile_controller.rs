@@ -1,11 +1,211 @@
 // src/profile_controller.rs
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use crate::profile_system::{ProfileManager, Profile};
-use crate::hardware_monitor::HardwareMonitor;
-use crate::hardware_control::HardwareController;
+use std::time::{Duration, Instant, SystemTime};
+use crate::app_config::{AppConfig, DailyWindow};
+use crate::profile_system::{
+    FanCurve, FieldChange, KeyboardEffect, KeyboardMode, MatchKind, ProfileManager, Profile,
+    TriggerMatch,
+};
+use crate::hardware_monitor::{GpuMode, HardwareMonitor};
+use crate::hardware_control::{check_permissions, ApplyMask, HardwareController};
+use crate::fan_daemon::FanDaemon;
+use crate::fand::{self, FandClient};
+use crate::sysfs_watcher::SysfsWatcher;
+use crate::threshold_monitor::{ThresholdAction, ThresholdEvent, ThresholdRule, ThresholdWatcher};
+use crate::stats_broadcaster::{StatsBroadcaster, StatsSubscription};
+
+/// Span the CPU load of the cooling warnings is averaged over
+const LOAD_WINDOW: Duration = Duration::from_secs(60);
+
+/// How often the drift watcher wakes up to check for a resume or stop
+const DRIFT_TICK: Duration = Duration::from_secs(1);
+
+/// Time the wall clock must be ahead of the monotonic clock, which stops
+/// during suspend, to count as a resume
+const RESUME_GAP: Duration = Duration::from_secs(5);
+
+/// Re-applies CPU settings that drifted from the active profile. The
+/// thread stops when this handle is dropped.
+pub struct DriftWatcher {
+    running: Arc<AtomicBool>,
+}
+
+impl DriftWatcher {
+    /// Compare the live governor, frequency limits and boost with the
+    /// active profile every `interval` and right after a resume, and
+    /// re-apply them if they drifted. Skipped in maximum performance mode
+    /// and while `fand` runs, which does its own checks.
+    pub fn spawn(
+        hardware_controller: Arc<HardwareController>,
+        interval: Duration,
+        fand: Option<FandClient>,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = Arc::clone(&running);
+        
+        thread::spawn(move || {
+            let mut elapsed = Duration::ZERO;
+            let mut last_tick = (Instant::now(), SystemTime::now());
+            
+            while thread_running.load(Ordering::Relaxed) {
+                thread::sleep(DRIFT_TICK);
+                
+                let now = (Instant::now(), SystemTime::now());
+                let monotonic = now.0 - last_tick.0;
+                let wall = now.1.duration_since(last_tick.1).unwrap_or_default();
+                last_tick = now;
+                
+                elapsed += monotonic;
+                let resumed = wall > monotonic + RESUME_GAP;
+                if elapsed < interval && !resumed {
+                    continue;
+                }
+                elapsed = Duration::ZERO;
+                
+                if fand.as_ref().and_then(FandClient::running).is_some() {
+                    continue;
+                }
+                if let Err(e) = hardware_controller.reconcile_cpu_settings() {
+                    eprintln!("Warning: Failed to check CPU settings for drift: {:#}", e);
+                }
+            }
+        });
+        
+        DriftWatcher { running }
+    }
+}
+
+impl Drop for DriftWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Runs the software keyboard effect of the active profile with the stats
+/// of a broadcaster. Unsubscribes when dropped.
+pub struct KeyboardEffectWatcher {
+    _subscription: StatsSubscription,
+}
+
+/// How often app monitoring looks for trigger apps
+const APP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Polls in a row without a heartbeat before app monitoring counts as hung
+const APP_MISSED_HEARTBEATS: u32 = 3;
+
+/// Thread polling for trigger apps. A panicking poll is logged and retried
+/// on the next one, the thread stops when this handle is dropped.
+struct AppMonitor {
+    running: Arc<AtomicBool>,
+    heartbeat: Arc<Mutex<Instant>>,
+    interval: Duration,
+    handle: thread::JoinHandle<()>,
+}
+
+impl AppMonitor {
+    fn spawn<P>(interval: Duration, mut poll: P) -> Self
+    where
+        P: FnMut() + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let heartbeat = Arc::new(Mutex::new(Instant::now()));
+        let thread_running = Arc::clone(&running);
+        let thread_heartbeat = Arc::clone(&heartbeat);
+        
+        let handle = thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                *thread_heartbeat.lock().unwrap() = Instant::now();
+                if panic::catch_unwind(AssertUnwindSafe(&mut poll)).is_err() {
+                    eprintln!("Warning: App monitoring failed, retrying on the next poll");
+                }
+                thread::sleep(interval);
+            }
+        });
+        
+        AppMonitor { running, heartbeat, interval, handle }
+    }
+    
+    /// Whether the thread runs and polled recently
+    fn is_alive(&self) -> bool {
+        !self.handle.is_finished()
+            && self.heartbeat.lock().unwrap().elapsed() < self.interval * APP_MISSED_HEARTBEATS
+    }
+}
+
+impl Drop for AppMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Profile change decided by [`AutoSwitcher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoSwitch {
+    Apply(usize),
+    RestoreDefault,
+}
+
+/// Decides when app monitoring switches profiles, so launchers starting
+/// and exiting helper processes don't make the profile flap. An
+/// auto-applied profile stays for at least `dwell`, and the default comes
+/// back only after the trigger app was gone for `dwell`.
+pub struct AutoSwitcher {
+    dwell: Duration,
+    /// Auto-applied profile and when it was applied
+    current: Option<(usize, Instant)>,
+    /// Since when the current profile isn't triggered any more
+    gone_since: Option<Instant>,
+}
+
+impl AutoSwitcher {
+    pub fn new(dwell: Duration) -> Self {
+        AutoSwitcher {
+            dwell,
+            current: None,
+            gone_since: None,
+        }
+    }
+    
+    /// Feed the profile the running apps trigger (`None` if no trigger
+    /// app runs) and get the switch to make, if any
+    pub fn update(&mut self, wanted: Option<usize>, now: Instant) -> Option<AutoSwitch> {
+        let Some((current, applied_at)) = self.current else {
+            let index = wanted?;
+            self.current = Some((index, now));
+            return Some(AutoSwitch::Apply(index));
+        };
+        
+        if wanted == Some(current) {
+            self.gone_since = None;
+            return None;
+        }
+        let gone_since = *self.gone_since.get_or_insert(now);
+        if now.duration_since(applied_at) < self.dwell {
+            return None;
+        }
+        
+        match wanted {
+            Some(index) => {
+                self.current = Some((index, now));
+                self.gone_since = None;
+                Some(AutoSwitch::Apply(index))
+            }
+            None if now.duration_since(gone_since) >= self.dwell => {
+                self.current = None;
+                self.gone_since = None;
+                Some(AutoSwitch::RestoreDefault)
+            }
+            None => None,
+        }
+    }
+}
 
 /// High-level controller that manages profile application and monitoring
 pub struct ProfileController {
@@ -13,26 +213,130 @@ pub struct ProfileController {
     hardware_controller: Arc<HardwareController>,
     hardware_monitor: Arc<Mutex<HardwareMonitor>>,
     monitoring_enabled: Arc<Mutex<bool>>,
+    app_monitor: Mutex<Option<AppMonitor>>,
+    fan_daemon: FanDaemon,
+    /// Privileged daemon applying profiles and running the fans, used
+    /// instead of the in-process parts when running as a user and it runs
+    fand: Option<FandClient>,
+    /// See [`AppConfig::quiet_hours`]
+    quiet_hours: Arc<Mutex<Option<DailyWindow>>>,
+    /// CPU loads of the last [`LOAD_WINDOW`], see [`Self::watch_load`]
+    recent_loads: Arc<Mutex<VecDeque<(Instant, f32)>>>,
 }
 
 impl ProfileController {
     pub fn new() -> Result<Self> {
+        let profile_manager = Arc::new(Mutex::new(ProfileManager::new()?));
+        let hardware_controller = Arc::new(HardwareController::new()?);
+        let hardware_monitor = Arc::new(Mutex::new(HardwareMonitor::new()?));
+        let config = AppConfig::load();
+        // A monitor of its own, so a tick never waits for the UI's reads
+        let fan_daemon = FanDaemon::new(
+            Arc::clone(&profile_manager),
+            Arc::clone(&hardware_controller),
+            Arc::new(Mutex::new(HardwareMonitor::new()?)),
+        )
+        .with_cpu_temp_offset(config.fan_cpu_temp_offset());
+        
+        // As root the hardware is controlled in-process
+        let fand = if check_permissions().unwrap_or(false) {
+            None
+        } else {
+            Some(FandClient::new(Path::new(fand::SOCKET_PATH)))
+        };
+        if fand.as_ref().and_then(FandClient::running).is_some() {
+            println!("Applying profiles through tailor-fand");
+        }
+        
         Ok(ProfileController {
-            profile_manager: Arc::new(Mutex::new(ProfileManager::new()?)),
-            hardware_controller: Arc::new(HardwareController::new()?),
-            hardware_monitor: Arc::new(Mutex::new(HardwareMonitor::new()?)),
+            profile_manager,
+            hardware_controller,
+            hardware_monitor,
             monitoring_enabled: Arc::new(Mutex::new(false)),
+            app_monitor: Mutex::new(None),
+            fan_daemon,
+            fand,
+            quiet_hours: Arc::new(Mutex::new(config.quiet_hours)),
+            recent_loads: Arc::new(Mutex::new(VecDeque::new())),
         })
     }
     
-    /// Apply a profile by index
+    /// Apply a profile by index. It only becomes the active profile once it
+    /// was written, a failed apply keeps the previous one active.
     pub fn apply_profile(&self, profile_index: usize) -> Result<()> {
-        let mut mgr = self.profile_manager.lock().unwrap();
-        mgr.set_active_profile(profile_index)?;
-        let profile = mgr.get_active_profile().clone();
-        drop(mgr); // Release lock
+        let profile = self.profile_manager.lock().unwrap()
+            .get_profiles()
+            .get(profile_index)
+            .cloned()
+            .context("Profile index out of bounds")?;
         
-        self.hardware_controller.apply_profile(&profile)
+        for warning in self.cooling_warnings(&profile) {
+            eprintln!("Warning: {}", warning);
+        }
+        
+        activate(&self.profile_manager, self.fand.as_ref(), &self.hardware_controller, &profile)
+    }
+    
+    /// Write only the `mask` parts of the profile named `name`, e.g. just
+    /// its keyboard color. The active profile stays the same.
+    pub fn apply_partial(&self, name: &str, mask: ApplyMask) -> Result<()> {
+        if self.fand().is_some() {
+            anyhow::bail!("tailor-fand only applies whole profiles");
+        }
+        let profile = self.profile_manager.lock().unwrap()
+            .get_profiles()
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .context(format!("Profile '{}' not found", name))?;
+        Ok(self.hardware_controller.apply_partial(&profile, mask)?)
+    }
+    
+    /// Write `profile` to the hardware, through tailor-fand if it runs
+    fn apply_to_hardware(&self, profile: &Profile) -> Result<()> {
+        apply_with(self.fand.as_ref(), &self.hardware_controller, profile)
+    }
+    
+    /// Whether profiles are applied through the privileged tailor-fand
+    pub fn uses_fand(&self) -> bool {
+        self.fand().is_some()
+    }
+    
+    /// The tailor-fand client if the daemon runs right now
+    fn fand(&self) -> Option<&FandClient> {
+        self.fand.as_ref().and_then(FandClient::running)
+    }
+    
+    /// Advisory warnings for fan curves that may be too slow for the CPU
+    /// load of the last minute. None without readings in that time, a
+    /// single reading could be any spike.
+    pub fn cooling_warnings(&self, profile: &Profile) -> Vec<String> {
+        let mut warnings: Vec<String> = profile
+            .fan_curves
+            .iter()
+            .filter_map(|(fan_id, curve)| {
+                self.curve_cooling_warning(curve)
+                    .map(|warning| format!("{}: {}", fan_id, warning))
+            })
+            .collect();
+        warnings.sort();
+        warnings
+    }
+    
+    /// Warning of [`Self::cooling_warnings`] for a single curve, e.g. one
+    /// that's being edited
+    pub fn curve_cooling_warning(&self, curve: &FanCurve) -> Option<String> {
+        let load = average_load(&self.recent_loads.lock().unwrap(), Instant::now())?;
+        let tdp = self.hardware_monitor.lock().unwrap().get_cpu_tdp();
+        curve.cooling_warning(load, tdp)
+    }
+    
+    /// Apply the profile after the active one, wrapping around to the first.
+    /// Returns the applied profile.
+    pub fn apply_next_profile(&self) -> Result<Profile> {
+        let index = self.profile_manager.lock().unwrap().next_profile_index();
+        self.apply_profile(index)?;
+        Ok(self.get_active_profile())
     }
     
     /// Apply a profile by name
@@ -47,6 +351,27 @@ impl ProfileController {
         self.apply_profile(profile_index)
     }
     
+    /// Apply the profile configured to be used on startup. Falls back to the
+    /// default profile if it no longer exists.
+    pub fn apply_startup_profile(&self, name: &str) -> Result<()> {
+        let mgr = self.profile_manager.lock().unwrap();
+        let profile_index = match mgr.get_profiles().iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                eprintln!("Warning: Startup profile '{}' not found, using the default profile", name);
+                mgr.default_profile_index()
+            }
+        };
+        drop(mgr);
+        
+        self.apply_profile(profile_index)
+    }
+    
+    /// When the profile `name` was last applied, `None` if never
+    pub fn last_applied(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.profile_manager.lock().unwrap().last_applied(name)
+    }
+    
     /// Get the currently active profile
     pub fn get_active_profile(&self) -> Profile {
         let mgr = self.profile_manager.lock().unwrap();
@@ -59,22 +384,55 @@ impl ProfileController {
         mgr.get_profiles().to_vec()
     }
     
+    /// Whether profile changes are saved, see [`ProfileManager::is_writable`]
+    pub fn profiles_writable(&self) -> bool {
+        self.profile_manager.lock().unwrap().is_writable()
+    }
+    
     /// Add a new profile
     pub fn add_profile(&self, profile: Profile) -> Result<()> {
         let mut mgr = self.profile_manager.lock().unwrap();
-        mgr.add_profile(profile)
+        Ok(mgr.add_profile(profile)?)
+    }
+    
+    /// Changes `profile` would make to the stored profile at `index`, for
+    /// confirming an edit before it is saved
+    pub fn profile_changes(&self, index: usize, profile: &Profile) -> Result<Vec<FieldChange>> {
+        let mgr = self.profile_manager.lock().unwrap();
+        let stored = mgr.get_profiles().get(index).context("Profile index out of bounds")?;
+        Ok(profile.diff(stored))
     }
     
     /// Update an existing profile
     pub fn update_profile(&self, index: usize, profile: Profile) -> Result<()> {
         let mut mgr = self.profile_manager.lock().unwrap();
-        mgr.update_profile(index, profile)
+        Ok(mgr.update_profile(index, profile)?)
     }
     
     /// Delete a profile
     pub fn delete_profile(&self, index: usize) -> Result<()> {
         let mut mgr = self.profile_manager.lock().unwrap();
-        mgr.delete_profile(index)
+        Ok(mgr.delete_profile(index)?)
+    }
+    
+    /// Lock or unlock the profile named `name` against edits and deletion
+    pub fn set_profile_locked(&self, name: &str, locked: bool) -> Result<()> {
+        let mut mgr = self.profile_manager.lock().unwrap();
+        let index = mgr.get_profiles()
+            .iter()
+            .position(|p| p.name == name)
+            .context(format!("Profile '{}' not found", name))?;
+        Ok(mgr.set_locked(index, locked)?)
+    }
+    
+    /// Remove all profiles except the default one and apply it
+    pub fn reset_profiles(&self) -> Result<()> {
+        let mut mgr = self.profile_manager.lock().unwrap();
+        mgr.reset_to_default()?;
+        let profile = mgr.get_active_profile().clone();
+        drop(mgr);
+        
+        self.apply_to_hardware(&profile)
     }
     
     /// Get current hardware statistics
@@ -83,14 +441,478 @@ impl ProfileController {
         monitor.get_system_stats()
     }
     
-    /// Switch GPU (requires restart)
+    /// Model and firmware versions of the machine
+    pub fn get_system_info(&self) -> crate::hardware_monitor::SystemInfo {
+        self.hardware_monitor.lock().unwrap().get_system_info()
+    }
+    
+    /// All temperature sensors, e.g. to pick the one a fan curve follows
+    pub fn available_temp_sensors(&self) -> Vec<crate::hardware_monitor::SensorDescriptor> {
+        self.hardware_monitor.lock().unwrap().available_temp_sensors()
+    }
+    
+    /// Cores, SMT siblings and caches of the CPU
+    pub fn cpu_topology(&self) -> crate::hardware_monitor::CpuTopology {
+        self.hardware_monitor.lock().unwrap().cpu_topology()
+    }
+    
+    /// Watch the performance profile and charge threshold files for changes
+    /// made by other tools. Cached values are dropped and `on_change` is
+    /// called right away, so the UI doesn't have to wait for the next tick.
+    pub fn watch_external_changes<F>(&self, on_change: F) -> SysfsWatcher
+    where
+        F: Fn() + Send + 'static,
+    {
+        let hardware_monitor = Arc::clone(&self.hardware_monitor);
+        SysfsWatcher::spawn(HardwareMonitor::power_settings_paths(), move |_| {
+            hardware_monitor.lock().unwrap().invalidate_power_settings();
+            on_change();
+        })
+    }
+    
+    /// Watch the profiles file for edits made outside the GUI, e.g. by the
+    /// CLI, and reload it. `on_reload` receives the new profiles; if the file
+    /// can't be parsed the previous profiles stay active.
+    pub fn watch_profiles<F>(&self, on_reload: F) -> SysfsWatcher
+    where
+        F: Fn(Vec<Profile>) + Send + 'static,
+    {
+        let profile_manager = Arc::clone(&self.profile_manager);
+        let profiles_file = profile_manager.lock().unwrap().profiles_file();
+        
+        SysfsWatcher::spawn(vec![profiles_file], move |_| {
+            let mut mgr = profile_manager.lock().unwrap();
+            match mgr.reload_if_changed() {
+                Ok(true) => {
+                    let profiles = mgr.get_profiles().to_vec();
+                    drop(mgr);
+                    on_reload(profiles);
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("Warning: Keeping previous profiles: {:#}", e),
+            }
+        })
+    }
+    
+    /// Re-apply the keyboard brightness of the active profile whenever the
+    /// AC adapter is plugged in or out. Only fires on transitions, so manual
+    /// brightness changes stick until the next one.
+    pub fn watch_power_source(&self) -> SysfsWatcher {
+        let profile_manager = Arc::clone(&self.profile_manager);
+        let hardware_controller = Arc::clone(&self.hardware_controller);
+        let fand = self.fand.clone();
+        
+        // sysfs doesn't emit inotify events for `online`, so poll it
+        SysfsWatcher::spawn_polling(HardwareMonitor::ac_online_paths(), move |_| {
+            let profile = profile_manager.lock().unwrap().get_active_profile().clone();
+            if profile.keyboard_backlight.battery_brightness.is_none() {
+                return;
+            }
+            
+            let on_battery = HardwareMonitor::on_battery();
+            let result = match fand.as_ref().and_then(FandClient::running) {
+                Some(fand) => fand.apply_keyboard(on_battery),
+                None => hardware_controller.apply_keyboard_settings(&profile, on_battery).map_err(Into::into),
+            };
+            if let Err(e) = result {
+                eprintln!("Warning: Failed to apply keyboard settings: {}", e);
+            }
+        })
+    }
+    
+    /// Turn the keyboard backlight off while DPMS has every display off,
+    /// and restore the active profile's keyboard settings when one comes
+    /// back on. Profiles that don't manage the keyboard are left alone.
+    pub fn watch_screen_power(&self) -> SysfsWatcher {
+        let profile_manager = Arc::clone(&self.profile_manager);
+        let hardware_controller = Arc::clone(&self.hardware_controller);
+        let fand = self.fand.clone();
+        let dpms_paths = HardwareMonitor::display_dpms_paths();
+        let screens_off = AtomicBool::new(HardwareMonitor::screens_off(&dpms_paths));
+        
+        // Like `online`, `dpms` doesn't emit inotify events
+        SysfsWatcher::spawn_polling(dpms_paths.clone(), move |_| {
+            let off = HardwareMonitor::screens_off(&dpms_paths);
+            if screens_off.swap(off, Ordering::Relaxed) == off {
+                return;
+            }
+            
+            // Read on every change, the profile may have switched meanwhile
+            let profile = profile_manager.lock().unwrap().get_active_profile().clone();
+            if profile.keyboard_backlight.mode != KeyboardMode::Managed {
+                return;
+            }
+            
+            let on_battery = HardwareMonitor::on_battery();
+            let result = match (fand.as_ref().and_then(FandClient::running), off) {
+                (Some(fand), true) => fand.turn_off_keyboard(),
+                (Some(fand), false) => fand.apply_keyboard(on_battery),
+                (None, true) => hardware_controller.turn_off_keyboard().map_err(Into::into),
+                (None, false) => hardware_controller.apply_keyboard_settings(&profile, on_battery).map_err(Into::into),
+            };
+            if let Err(e) = result {
+                eprintln!("Warning: Failed to update keyboard backlight for the screen: {}", e);
+            }
+        })
+    }
+    
+    /// Read hardware stats every `interval` on one thread, for every view
+    /// and watcher subscribing to it
+    pub fn stats_broadcaster(&self, interval: Duration) -> StatsBroadcaster {
+        let hardware_monitor = Arc::clone(&self.hardware_monitor);
+        StatsBroadcaster::spawn(interval, move || {
+            hardware_monitor.lock().unwrap().get_system_stats()
+        })
+    }
+    
+    /// Keep the CPU loads for [`Self::cooling_warnings`], read by
+    /// `broadcaster` while `wanted` returns true
+    pub fn watch_load<W>(&self, broadcaster: &StatsBroadcaster, wanted: W) -> StatsSubscription
+    where
+        W: Fn() -> bool + Send + 'static,
+    {
+        let recent_loads = Arc::clone(&self.recent_loads);
+        broadcaster.subscribe_while(wanted, move |stats| {
+            let now = Instant::now();
+            let mut loads = recent_loads.lock().unwrap();
+            loads.push_back((now, stats.cpu.mean_load_percent));
+            while loads.front().is_some_and(|(at, _)| now.duration_since(*at) > LOAD_WINDOW) {
+                loads.pop_front();
+            }
+        })
+    }
+    
+    /// Update the keyboard color with every reading of `broadcaster` while
+    /// the active profile has a [`KeyboardEffect`]. Profiles without one
+    /// or that don't manage the keyboard are left alone and cause no
+    /// readings. During the quiet hours the effect pauses at a dim static
+    /// color.
+    pub fn watch_keyboard_effect(
+        &self,
+        broadcaster: &StatsBroadcaster,
+    ) -> KeyboardEffectWatcher {
+        let profile_manager = Arc::clone(&self.profile_manager);
+        let hardware_controller = Arc::clone(&self.hardware_controller);
+        let fand = self.fand.clone();
+        let quiet_hours = Arc::clone(&self.quiet_hours);
+        let failing = AtomicBool::new(false);
+        
+        // Checked before every reading, the profile may have switched
+        // meanwhile. Without an effect nothing is read for it.
+        let wanted_profiles = Arc::clone(&profile_manager);
+        let wanted = move || active_keyboard_effect(&wanted_profiles).is_some();
+        let subscription = broadcaster.subscribe_while(wanted, move |stats| {
+            let Some(effect) = active_keyboard_effect(&profile_manager) else {
+                return;
+            };
+            let color = if in_quiet_hours(&quiet_hours) {
+                effect.quiet_color()
+            } else {
+                // Without a reading the last color stays
+                let Some(temp) = stats.cpu.package_temp.or(stats.cpu.max_core_temp).filter(|t| t.is_finite()) else {
+                    return;
+                };
+                effect.color_at(temp)
+            };
+            
+            let result = match fand.as_ref().and_then(FandClient::running) {
+                Some(fand) => fand.set_keyboard_color(&color),
+                None => hardware_controller.update_keyboard_color(&color).map_err(Into::into),
+            };
+            match result {
+                Ok(()) => failing.store(false, Ordering::Relaxed),
+                // It would fail the same way with every reading
+                Err(e) => {
+                    if !failing.swap(true, Ordering::Relaxed) {
+                        eprintln!("Warning: Failed to update the keyboard effect: {}", e);
+                    }
+                }
+            }
+        });
+        KeyboardEffectWatcher { _subscription: subscription }
+    }
+    
+    /// Call `on_event` when a rule starts to match the stats of
+    /// `broadcaster`. Rules with `ApplyProfile` switch the profile first.
+    pub fn watch_thresholds<F>(
+        &self,
+        broadcaster: &StatsBroadcaster,
+        rules: Vec<ThresholdRule>,
+        on_event: F,
+    ) -> ThresholdWatcher
+    where
+        F: Fn(ThresholdEvent) + Send + 'static,
+    {
+        let profile_manager = Arc::clone(&self.profile_manager);
+        let hardware_controller = Arc::clone(&self.hardware_controller);
+        let fand = self.fand.clone();
+        
+        ThresholdWatcher::subscribe(
+            broadcaster,
+            rules,
+            move |event| {
+                if let ThresholdAction::ApplyProfile(name) = &event.rule.action {
+                    let mut mgr = profile_manager.lock().unwrap();
+                    let result = mgr
+                        .get_profiles()
+                        .iter()
+                        .position(|p| &p.name == name)
+                        .context(format!("Profile '{}' not found", name))
+                        .and_then(|index| Ok(mgr.set_active_profile(index)?))
+                        .map(|_| mgr.get_active_profile().clone());
+                    drop(mgr);
+                    
+                    match result.and_then(|profile| apply_with(fand.as_ref(), &hardware_controller, &profile)) {
+                        Ok(()) => mark_applied(&profile_manager, name),
+                        Err(e) => eprintln!("Warning: Failed to apply profile '{}': {}", name, e),
+                    }
+                }
+                on_event(event);
+            },
+        )
+    }
+    
+    /// Re-apply CPU settings that drifted from the active profile, see
+    /// [`DriftWatcher::spawn`]
+    pub fn watch_drift(&self, interval: Duration) -> DriftWatcher {
+        DriftWatcher::spawn(Arc::clone(&self.hardware_controller), interval, self.fand.clone())
+    }
+    
+    /// Fade brightness changes instead of applying them at once
+    pub fn set_smooth_brightness(&self, enable: bool) {
+        self.hardware_controller.set_smooth_brightness(enable);
+    }
+    
+    /// Default for profiles that don't choose the displays themselves
+    pub fn set_brightness_all_displays(&self, enable: bool) {
+        self.hardware_controller.set_brightness_all_displays(enable);
+    }
+    
+    /// Map brightness percentages on a gamma curve instead of linearly
+    pub fn set_perceptual_brightness(&self, enable: bool) {
+        self.hardware_controller.set_perceptual_brightness(enable);
+    }
+    
+    /// Pause keyboard effects and profile switch notifications during
+    /// `window`, see [`AppConfig::quiet_hours`]
+    pub fn set_quiet_hours(&self, window: Option<DailyWindow>) {
+        *self.quiet_hours.lock().unwrap() = window;
+    }
+    
+    /// Whether the quiet hours are on right now
+    pub fn in_quiet_hours(&self) -> bool {
+        in_quiet_hours(&self.quiet_hours)
+    }
+    
+    /// EPP support of the CPU driver, for labeling the EPP control
+    pub fn epp_support(&self) -> Option<crate::hardware_control::EppSupport> {
+        self.hardware_controller.epp_support()
+    }
+    
+    /// Frequencies a profile can pin the CPU to, see
+    /// [`HardwareController::fixed_frequency_support`]
+    pub fn fixed_frequency_support(&self) -> Option<crate::hardware_control::FixedFrequencySupport> {
+        self.hardware_controller.fixed_frequency_support()
+    }
+    
+    /// Set the energy performance preference until the next profile with
+    /// one is applied
+    pub fn set_energy_performance_preference(&self, epp: &str) -> Result<()> {
+        match self.fand() {
+            Some(fand) => fand.set_energy_performance_preference(epp),
+            None => self.hardware_controller.set_energy_performance_preference(epp),
+        }
+    }
+    
+    /// Turn amd-pstate preferred core ranking on or off, see
+    /// [`HardwareController::set_amd_prefcore`]
+    pub fn set_amd_prefcore(&self, enabled: bool) -> Result<()> {
+        match self.fand() {
+            Some(fand) => fand.set_amd_prefcore(enabled),
+            None => Ok(self.hardware_controller.set_amd_prefcore(enabled)?),
+        }
+    }
+    
+    /// Set the battery charge thresholds of a preset. Returns the power
+    /// settings read back afterwards.
+    pub fn apply_charge_preset(
+        &self,
+        preset: crate::hardware_control::ChargePreset,
+    ) -> Result<crate::hardware_monitor::PowerSettings> {
+        let (start, end) = preset.thresholds();
+        match self.fand() {
+            Some(fand) => fand.set_charge_thresholds(start, end)?,
+            None => self.hardware_controller.set_charge_thresholds(start, end)?,
+        }
+        let mut monitor = self.hardware_monitor.lock().unwrap();
+        monitor.invalidate_power_settings();
+        Ok(monitor.get_power_settings())
+    }
+    
+    /// Read the cached power settings, e.g. the charge thresholds
+    pub fn power_settings(&self) -> crate::hardware_monitor::PowerSettings {
+        self.hardware_monitor.lock().unwrap().get_power_settings()
+    }
+    
+    /// Switch GPU (requires restart). The switch is remembered in the
+    /// config until [`Self::pending_gpu_switch`] sees it took effect.
     pub fn switch_gpu(&self, use_discrete: bool) -> Result<()> {
-        self.hardware_controller.switch_gpu(use_discrete)
+        let pending = self.hardware_controller.switch_gpu(use_discrete)?;
+        let mut config = AppConfig::load();
+        config.pending_gpu_switch = Some(pending);
+        config.save().context("Failed to remember the pending GPU switch")
+    }
+    
+    /// GPU mode that becomes active with the next restart, `None` if no
+    /// switch is pending. Forgets the switch once it took effect or the
+    /// selection was changed back.
+    pub fn pending_gpu_switch(&self) -> Option<GpuMode> {
+        let mut config = AppConfig::load();
+        let pending = config.pending_gpu_switch.clone()?;
+        let Ok(boot_id) = self.hardware_controller.boot_id() else {
+            return Some(pending.mode);
+        };
+        if !pending.is_settled(&boot_id, GpuMode::query()) {
+            return Some(pending.mode);
+        }
+        
+        config.pending_gpu_switch = None;
+        if let Err(e) = config.save() {
+            eprintln!("Warning: Failed to clear the pending GPU switch: {:#}", e);
+        }
+        None
     }
     
     /// Enable maximum performance mode
     pub fn enable_maximum_performance(&self) -> Result<()> {
-        self.hardware_controller.set_maximum_performance()
+        Ok(self.hardware_controller.set_maximum_performance()?)
+    }
+    
+    /// Push the active profile to the hardware again, e.g. after the
+    /// firmware reset settings on resume or another tool changed them.
+    /// Software fan speeds are written again on the next fan daemon tick.
+    /// Returns the applied profile.
+    pub fn reapply_active_profile(&self) -> Result<Profile> {
+        let index = self.profile_manager.lock().unwrap().active_profile_index();
+        self.apply_profile(index)?;
+        self.fan_daemon.force_refresh();
+        Ok(self.get_active_profile())
+    }
+    
+    /// Re-apply the active profile, e.g. to leave maximum performance mode
+    pub fn restore_active_profile(&self) -> Result<()> {
+        self.apply_to_hardware(&self.get_active_profile())
+    }
+    
+    /// Whether fan curves can be applied, see
+    /// [`HardwareController::fan_control_supported`]
+    pub fn fan_control_supported(&self) -> bool {
+        self.hardware_controller.fan_control_supported()
+    }
+    
+    /// Report failed profile writes, e.g. in the UI, see
+    /// [`ProfileManager::set_save_error_handler`]
+    pub fn on_save_error(&self, handler: impl Fn(&str) + Send + 'static) {
+        self.profile_manager.lock().unwrap().set_save_error_handler(handler);
+    }
+    
+    /// Whether the keyboard firmware has its own backlight timeout, see
+    /// [`HardwareController::has_keyboard_hw_timeout`]
+    pub fn has_keyboard_hw_timeout(&self) -> bool {
+        self.hardware_controller.has_keyboard_hw_timeout()
+    }
+    
+    /// Run software fan curves of the active profile in the background
+    pub fn start_fan_daemon(&self) {
+        // tailor-fand runs them
+        if let Some(fand) = self.fand() {
+            if let Err(e) = fand.set_fan_daemon(true) {
+                eprintln!("Warning: {:#}", e);
+            }
+            return;
+        }
+        if !self.hardware_controller.fan_control_supported() {
+            eprintln!("Warning: No writable fan control found, software fan curves are disabled");
+            return;
+        }
+        self.fan_daemon.start();
+    }
+    
+    /// Stop the fan daemon and restore automatic fan control
+    pub fn stop_fan_daemon(&self) {
+        if let Some(fand) = self.fand() {
+            if let Err(e) = fand.set_fan_daemon(false) {
+                eprintln!("Warning: {:#}", e);
+            }
+            return;
+        }
+        self.fan_daemon.stop();
+    }
+    
+    /// Start or stop the fan daemon, see [`AppConfig::fan_daemon_enabled`]
+    pub fn set_fan_daemon_enabled(&self, enabled: bool) {
+        if enabled {
+            self.start_fan_daemon();
+        } else {
+            self.stop_fan_daemon();
+        }
+    }
+    
+    /// Stop all background work and hand the fans back to the firmware.
+    /// Called on every exit path, so fans aren't left at a fixed speed.
+    pub fn shutdown(&self) {
+        *self.monitoring_enabled.lock().unwrap() = false;
+        self.app_monitor.lock().unwrap().take();
+        self.fan_daemon.stop();
+        
+        // tailor-fand keeps running the curves of the last profile
+        if self.fand().is_none() {
+            // Software profiles set fans to manual even without the daemon
+            if let Err(e) = self.hardware_controller.restore_auto_fan_control() {
+                eprintln!("Warning: Failed to restore automatic fan control: {}", e);
+            }
+        }
+        self.hardware_controller.reset_color_temperature();
+    }
+    
+    /// Speeds the fan daemon set last, by fan id
+    pub fn fan_targets(&self) -> HashMap<String, u8> {
+        match self.fand() {
+            Some(fand) => fand.fan_state().map(|state| state.targets).unwrap_or_else(|e| {
+                eprintln!("Warning: {:#}", e);
+                HashMap::new()
+            }),
+            None => self.fan_daemon.last_targets().lock().unwrap().clone(),
+        }
+    }
+    
+    /// Pin a fan to a speed or return it to its curve, see
+    /// [`FanDaemon::set_manual_override`]
+    pub fn set_fan_override(&self, fan_id: &str, speed: Option<u8>) -> Result<()> {
+        if self.fand().is_some() {
+            anyhow::bail!("Fans can't be pinned while tailor-fand controls them");
+        }
+        self.fan_daemon.set_manual_override(fan_id, speed)
+    }
+    
+    /// Power limits of the GPUs that have one, see
+    /// [`HardwareController::gpu_power_caps`]
+    pub fn gpu_power_caps(&self) -> Vec<crate::hardware_monitor::GpuPowerCap> {
+        self.hardware_controller.gpu_power_caps()
+    }
+    
+    /// Limit the power of a GPU until the next profile with a GPU power cap
+    /// is applied. Returns the limit the driver got.
+    pub fn set_gpu_power_cap(&self, card: &str, microwatts: u64) -> Result<u64> {
+        match self.fand() {
+            Some(fand) => fand.set_gpu_power_cap(card, microwatts),
+            None => Ok(self.hardware_controller.set_gpu_power_cap(card, microwatts)?),
+        }
+    }
+    
+    /// Pinned fans and their speeds, by fan id
+    pub fn fan_overrides(&self) -> HashMap<String, u8> {
+        self.fan_daemon.manual_overrides()
     }
     
     /// Start monitoring for application-triggered profile switching
@@ -102,93 +924,229 @@ impl ProfileController {
         *enabled = true;
         drop(enabled);
         
+        *self.app_monitor.lock().unwrap() = Some(self.spawn_app_monitor());
+        println!("Application monitoring started");
+        Ok(())
+    }
+    
+    fn spawn_app_monitor(&self) -> AppMonitor {
         let profile_manager = Arc::clone(&self.profile_manager);
         let hardware_controller = Arc::clone(&self.hardware_controller);
-        let monitoring_enabled = Arc::clone(&self.monitoring_enabled);
-        
-        thread::spawn(move || {
-            let mut last_detected_app = String::new();
-            
-            loop {
-                // Check if monitoring is still enabled
-                {
-                    let enabled = monitoring_enabled.lock().unwrap();
-                    if !*enabled {
-                        break;
-                    }
-                }
-                
-                // Get running processes
-                if let Ok(current_app) = detect_running_apps() {
-                    if current_app != last_detected_app {
-                        // Check if any profile should be triggered
-                        let mgr = profile_manager.lock().unwrap();
-                        if let Some(profile_index) = mgr.find_profile_for_app(&current_app) {
-                            let profile = mgr.get_profiles()[profile_index].clone();
-                            drop(mgr);
-                            
-                            println!("Auto-switching to profile '{}' for app: {}", 
-                                     profile.name, current_app);
-                            
-                            if let Err(e) = hardware_controller.apply_profile(&profile) {
-                                eprintln!("Failed to apply profile: {}", e);
-                            }
-                            
-                            last_detected_app = current_app;
-                        }
-                    }
-                }
-                
-                thread::sleep(Duration::from_secs(5)); // Check every 5 seconds
-            }
-        });
+        let fand = self.fand.clone();
+        let dwell = Duration::from_secs(AppConfig::load().auto_switch_dwell_secs.into());
+        let mut switcher = AutoSwitcher::new(dwell);
         
-        println!("Application monitoring started");
-        Ok(())
+        AppMonitor::spawn(APP_POLL_INTERVAL, move || {
+            let Ok(running) = running_process_names(Path::new("/proc")) else {
+                return;
+            };
+            auto_switch(
+                &profile_manager,
+                fand.as_ref(),
+                &hardware_controller,
+                &mut switcher,
+                &running,
+                Instant::now(),
+            );
+        })
+    }
+    
+    /// Whether app monitoring is enabled and its thread is polling
+    pub fn app_monitoring_alive(&self) -> bool {
+        *self.monitoring_enabled.lock().unwrap()
+            && self.app_monitor.lock().unwrap().as_ref().is_some_and(AppMonitor::is_alive)
+    }
+    
+    /// Health check for app monitoring: restart the thread if it died or
+    /// hangs while monitoring is enabled. Returns whether it was restarted.
+    pub fn check_app_monitoring(&self) -> bool {
+        if !*self.monitoring_enabled.lock().unwrap() {
+            return false;
+        }
+        if self.app_monitor.lock().unwrap().as_ref().is_some_and(AppMonitor::is_alive) {
+            return false;
+        }
+        eprintln!("Warning: Application monitoring stopped responding, restarting it");
+        if self.profile_manager.is_poisoned() {
+            // Polls only read the profiles, they are still intact
+            self.profile_manager.clear_poison();
+        }
+        let app_monitor = self.spawn_app_monitor();
+        // Dropping the old handle stops a hung thread once it wakes up
+        *self.app_monitor.lock().unwrap() = Some(app_monitor);
+        true
+    }
+    
+    /// Whether app monitoring was started, see [`Self::app_monitoring_alive`]
+    pub fn app_monitoring_enabled(&self) -> bool {
+        *self.monitoring_enabled.lock().unwrap()
+    }
+    
+    /// Whether any profile switches automatically for an app
+    pub fn has_trigger_apps(&self) -> bool {
+        self.profile_manager
+            .lock()
+            .unwrap()
+            .get_profiles()
+            .iter()
+            .any(|profile| !profile.trigger_apps.is_empty())
     }
     
     /// Stop monitoring for application-triggered profile switching
     pub fn stop_app_monitoring(&self) {
         let mut enabled = self.monitoring_enabled.lock().unwrap();
         *enabled = false;
+        self.app_monitor.lock().unwrap().take();
         println!("Application monitoring stopped");
     }
 }
 
-/// Detect running applications (Steam, Lutris, etc.)
-fn detect_running_apps() -> Result<String> {
-    // Read /proc to find running processes
-    let proc_path = std::path::Path::new("/proc");
+fn in_quiet_hours(quiet_hours: &Mutex<Option<DailyWindow>>) -> bool {
+    quiet_hours
+        .lock()
+        .unwrap()
+        .is_some_and(|window| window.contains(Local::now().time()))
+}
+
+/// Mean of the loads read within [`LOAD_WINDOW`] before `now`, `None`
+/// without any
+fn average_load(loads: &VecDeque<(Instant, f32)>, now: Instant) -> Option<f32> {
+    let recent: Vec<f32> = loads
+        .iter()
+        .filter(|(at, _)| now.duration_since(*at) <= LOAD_WINDOW)
+        .map(|(_, load)| *load)
+        .collect();
+    if recent.is_empty() {
+        return None;
+    }
+    Some(recent.iter().sum::<f32>() / recent.len() as f32)
+}
+
+/// Remember that the profile `name` was applied, after it was written
+/// successfully
+fn mark_applied(profile_manager: &Mutex<ProfileManager>, name: &str) {
+    if let Err(e) = profile_manager.lock().unwrap().mark_applied(name) {
+        eprintln!("Warning: {:#}", e);
+    }
+}
+
+/// Write `profile` to the hardware, then make it the active profile and
+/// remember when it was applied. The fan daemon and the watchers follow
+/// the active profile, so it must not change before the write succeeded.
+fn activate(
+    profile_manager: &Mutex<ProfileManager>,
+    fand: Option<&FandClient>,
+    hardware_controller: &HardwareController,
+    profile: &Profile,
+) -> Result<()> {
+    apply_with(fand, hardware_controller, profile)?;
+    
+    let mut mgr = profile_manager.lock().unwrap();
+    // The profiles may have been reloaded while applying
+    let index = mgr.get_profiles()
+        .iter()
+        .position(|p| p.name == profile.name)
+        .context(format!("Profile '{}' not found", profile.name))?;
+    mgr.set_active_profile(index)?;
+    drop(mgr);
+    
+    mark_applied(profile_manager, &profile.name);
+    Ok(())
+}
+
+/// One poll of app monitoring: switch to the profile one of the `running`
+/// processes triggers, or back to the default once it is gone
+fn auto_switch(
+    profile_manager: &Mutex<ProfileManager>,
+    fand: Option<&FandClient>,
+    hardware_controller: &HardwareController,
+    switcher: &mut AutoSwitcher,
+    running: &[String],
+    now: Instant,
+) {
+    // Locked only for lookups, so a panicking poll can't poison the
+    // profiles for the rest of the app
+    let triggered = profile_manager.lock().unwrap().find_profile_for_apps(running);
+    
+    let index = match switcher.update(triggered.map(|(index, _)| index), now) {
+        Some(AutoSwitch::Apply(index)) => {
+            let app = triggered.map_or("", |(_, app)| app);
+            println!("Auto-switching to profile {} for app: {}", index, app);
+            index
+        }
+        Some(AutoSwitch::RestoreDefault) => {
+            println!("Trigger app is gone, restoring the default profile");
+            profile_manager.lock().unwrap().default_profile_index()
+        }
+        None => return,
+    };
+    
+    let Some(profile) = profile_manager.lock().unwrap().get_profiles().get(index).cloned() else {
+        return;
+    };
+    if let Err(e) = activate(profile_manager, fand, hardware_controller, &profile) {
+        eprintln!("Failed to apply profile: {}", e);
+    }
+}
+
+/// Keyboard effect of the active profile, if it manages the keyboard
+fn active_keyboard_effect(profile_manager: &Mutex<ProfileManager>) -> Option<KeyboardEffect> {
+    let mgr = profile_manager.lock().unwrap();
+    let backlight = &mgr.get_active_profile().keyboard_backlight;
+    backlight.effect.clone().filter(|_| backlight.mode == KeyboardMode::Managed)
+}
+
+/// Apply `profile` through tailor-fand if it runs, in-process otherwise.
+/// The color temperature is always set here, in the user's session.
+fn apply_with(
+    fand: Option<&FandClient>,
+    hardware_controller: &HardwareController,
+    profile: &Profile,
+) -> Result<()> {
+    match fand.and_then(FandClient::running) {
+        Some(fand) => {
+            fand.set_profile(profile)?;
+            Ok(hardware_controller.apply_partial(profile, ApplyMask::COLOR_TEMPERATURE)?)
+        }
+        None => Ok(hardware_controller.apply_profile(profile)?),
+    }
+}
+
+/// Names of the running processes under `proc_path`, for matching trigger
+/// apps. Each process contributes its `comm` and the file name of its
+/// argv[0], `comm` is cut to 15 characters and launchers rename it.
+fn running_process_names(proc_path: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
     
     for entry in std::fs::read_dir(proc_path)? {
-        let entry = entry?;
-        let path = entry.path();
+        let path = entry?.path();
         
         // Only check numeric directories (PIDs)
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if name.chars().all(|c| c.is_numeric()) {
-                // Read cmdline
-                let cmdline_path = path.join("cmdline");
-                if let Ok(cmdline) = std::fs::read_to_string(&cmdline_path) {
-                    let cmdline_lower = cmdline.to_lowercase();
-                    
-                    // Check for known gaming apps
-                    if cmdline_lower.contains("steam") {
-                        return Ok("steam".to_string());
-                    }
-                    if cmdline_lower.contains("lutris") {
-                        return Ok("lutris".to_string());
-                    }
-                    if cmdline_lower.contains("gamemode") {
-                        return Ok("gamemode".to_string());
-                    }
-                    // Add more apps as needed
-                }
+        let is_pid = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        
+        // Processes may exit while they are read
+        if let Ok(comm) = std::fs::read_to_string(path.join("comm")) {
+            names.push(comm.trim_end().to_string());
+        }
+        if let Ok(cmdline) = std::fs::read(path.join("cmdline")) {
+            let argv0 = cmdline.split(|&byte| byte == 0).next().unwrap_or_default();
+            let argv0 = String::from_utf8_lossy(argv0);
+            if let Some(executable) = Path::new(argv0.as_ref()).file_name().and_then(|n| n.to_str()) {
+                names.push(executable.to_string());
             }
         }
     }
     
-    Ok(String::new())
+    names.retain(|name| !name.is_empty());
+    names.sort();
+    names.dedup();
+    Ok(names)
 }
 
 /// Builder for creating profiles easily
@@ -217,6 +1175,38 @@ impl ProfileBuilder {
         self
     }
     
+    pub fn keyboard_battery_brightness(mut self, brightness: u8) -> Self {
+        self.profile.keyboard_backlight.battery_brightness = Some(brightness);
+        self
+    }
+    
+    pub fn keyboard_mode(mut self, mode: crate::profile_system::KeyboardMode) -> Self {
+        self.profile.keyboard_backlight.mode = mode;
+        self
+    }
+    
+    pub fn keyboard_hw_effect(mut self, effect: &str) -> Self {
+        self.profile.keyboard_backlight.hw_effect = Some(effect.to_string());
+        self
+    }
+    
+    /// Let the keyboard firmware turn the backlight off after `secs`,
+    /// only on keyboards with a firmware timeout
+    pub fn keyboard_hw_timeout(mut self, secs: u32) -> Self {
+        self.profile.keyboard_backlight.hw_timeout_secs = Some(secs);
+        self
+    }
+    
+    pub fn keyboard_effect(mut self, effect: KeyboardEffect) -> Self {
+        self.profile.keyboard_backlight.effect = Some(effect);
+        self
+    }
+    
+    pub fn energy_performance_preference(mut self, epp: &str) -> Self {
+        self.profile.cpu_settings.energy_performance_preference = Some(epp.to_string());
+        self
+    }
+    
     pub fn cpu_performance(mut self, profile: crate::profile_system::CpuPerformanceProfile) -> Self {
         self.profile.cpu_settings.performance_profile = profile;
         self
@@ -228,6 +1218,13 @@ impl ProfileBuilder {
         self
     }
     
+    /// Pin the CPU to `mhz` with the userspace governor, see
+    /// [`crate::profile_system::CpuSettings::fixed_freq_mhz`]
+    pub fn cpu_fixed_frequency(mut self, mhz: u32) -> Self {
+        self.profile.cpu_settings.fixed_freq_mhz = Some(mhz);
+        self
+    }
+    
     pub fn disable_boost(mut self, disable: bool) -> Self {
         self.profile.cpu_settings.disable_boost = disable;
         self
@@ -238,14 +1235,78 @@ impl ProfileBuilder {
         self
     }
     
+    /// PCIe ASPM policy, e.g. "powersupersave"
+    pub fn aspm_policy(mut self, policy: &str) -> Self {
+        self.profile.aspm_policy = Some(policy.to_string());
+        self
+    }
+    
+    /// Power limit in watts for amdgpu cards, e.g. to keep a quiet profile quiet
+    pub fn gpu_power_cap(mut self, watts: u32) -> Self {
+        self.profile.gpu_power_cap_watts = Some(watts);
+        self
+    }
+    
+    pub fn fan_control_mode(mut self, mode: crate::profile_system::FanControlMode) -> Self {
+        self.profile.fan_control_mode = mode;
+        self
+    }
+    
+    /// Let the curve of `fan_id` follow another sensor (software mode)
+    pub fn fan_temp_source(mut self, fan_id: &str, source: crate::profile_system::TempSource) -> Self {
+        if let Some(curve) = self.profile.fan_curves.get_mut(fan_id) {
+            curve.temp_source = source;
+        }
+        self
+    }
+    
+    /// Let the fan of `fan_id` stop where its curve reaches 0%
+    pub fn fan_allow_stop(mut self, fan_id: &str, allow: bool) -> Self {
+        if let Some(curve) = self.profile.fan_curves.get_mut(fan_id) {
+            curve.allow_fan_stop = allow;
+        }
+        self
+    }
+    
+    /// Choose how the curve of `fan_id` interpolates between points (software mode)
+    pub fn fan_interpolation(mut self, fan_id: &str, interpolation: crate::profile_system::Interpolation) -> Self {
+        if let Some(curve) = self.profile.fan_curves.get_mut(fan_id) {
+            curve.interpolation = interpolation;
+        }
+        self
+    }
+    
     pub fn screen_brightness(mut self, brightness: u8) -> Self {
         self.profile.screen_settings.brightness = brightness;
         self
     }
     
+    pub fn color_temperature(mut self, kelvin: u32) -> Self {
+        self.profile.screen_settings.color_temp_kelvin = Some(kelvin);
+        self
+    }
+    
+    /// Override whether the brightness applies to every display
+    pub fn screen_all_displays(mut self, all: bool) -> Self {
+        self.profile.screen_settings.apply_to_all_displays = Some(all);
+        self
+    }
+    
+    /// Switch to the profile while one of `apps` runs, matching them
+    /// anywhere in the app name
     pub fn auto_switch_for_apps(mut self, apps: Vec<String>) -> Self {
         self.profile.auto_switch_enabled = true;
-        self.profile.trigger_apps = apps;
+        self.profile.trigger_apps = apps
+            .into_iter()
+            .map(|app| TriggerMatch::new(app, MatchKind::Substring))
+            .collect();
+        self
+    }
+    
+    /// Also switch to the profile while an app matching `trigger` runs
+    pub fn auto_switch_trigger(mut self, trigger: TriggerMatch) -> Self {
+        self.profile.auto_switch_enabled = true;
+        self.profile.trigger_apps.push(trigger);
         self
     }
     
@@ -271,4 +1332,185 @@ mod tests {
         assert_eq!(profile.keyboard_backlight.color.r, 255);
         assert!(profile.auto_switch_enabled);
     }
+    
+    #[test]
+    fn test_average_load() {
+        let start = Instant::now();
+        let now = start + Duration::from_secs(90);
+        let at = |secs_ago: u64| now - Duration::from_secs(secs_ago);
+        assert_eq!(average_load(&VecDeque::new(), now), None);
+        
+        // A spike doesn't dominate, readings out of the window don't count
+        let loads = VecDeque::from([(at(90), 100.0), (at(40), 20.0), (at(20), 20.0), (at(0), 80.0)]);
+        assert_eq!(average_load(&loads, now), Some(40.0));
+        assert_eq!(average_load(&loads, now + LOAD_WINDOW * 2), None);
+    }
+    
+    #[test]
+    fn test_app_monitor_survives_panic() {
+        let interval = Duration::from_millis(10);
+        let polls = Arc::new(Mutex::new(0));
+        let thread_polls = Arc::clone(&polls);
+        let monitor = AppMonitor::spawn(interval, move || {
+            let mut polls = thread_polls.lock().unwrap();
+            *polls += 1;
+            if *polls == 1 {
+                drop(polls);
+                panic!("/proc entry vanished");
+            }
+        });
+        
+        thread::sleep(interval * 10);
+        assert!(*polls.lock().unwrap() > 1);
+        assert!(monitor.is_alive());
+        drop(monitor);
+    }
+    
+    #[test]
+    fn test_app_monitor_hang() {
+        let interval = Duration::from_millis(10);
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let blocked = Mutex::new(blocked);
+        let monitor = AppMonitor::spawn(interval, move || {
+            let _ = blocked.lock().unwrap().recv();
+        });
+        
+        // No heartbeat while the poll blocks
+        thread::sleep(interval * (APP_MISSED_HEARTBEATS + 2));
+        assert!(!monitor.is_alive());
+        
+        drop(release);
+        drop(monitor);
+    }
+    
+    #[test]
+    fn test_auto_switch_dwell() {
+        let dwell = Duration::from_secs(30);
+        let mut switcher = AutoSwitcher::new(dwell);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        
+        // A launcher appears, exits and its game starts a moment later
+        let presence = [
+            (0, Some(1), Some(AutoSwitch::Apply(1))),
+            (5, None, None),
+            (10, Some(1), None),
+            (15, None, None),
+            // Gone for 30s, but counting restarts when the app reappeared
+            (40, None, None),
+            (45, None, Some(AutoSwitch::RestoreDefault)),
+            (50, None, None),
+        ];
+        for (secs, wanted, expected) in presence {
+            assert_eq!(switcher.update(wanted, at(secs)), expected, "at {secs}s");
+        }
+    }
+    
+    #[test]
+    fn test_auto_switch_activates_profile() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let config_dir = tempfile::TempDir::new().unwrap();
+        let mut mgr = ProfileManager::with_config_dir(config_dir.path().to_path_buf()).unwrap();
+        mgr.add_profile(ProfileBuilder::new("Gaming").auto_switch_for_apps(vec!["steam".to_string()]).build())
+            .unwrap();
+        let profile_manager = Mutex::new(mgr);
+        let controller = fixture.controller();
+        let mut switcher = AutoSwitcher::new(Duration::ZERO);
+        let start = Instant::now();
+        let active = || profile_manager.lock().unwrap().get_active_profile().name.clone();
+        
+        let running = vec!["bash".to_string(), "steam".to_string()];
+        auto_switch(&profile_manager, None, &controller, &mut switcher, &running, start);
+        assert_eq!(active(), "Gaming");
+        assert!(profile_manager.lock().unwrap().last_applied("Gaming").is_some());
+        
+        auto_switch(&profile_manager, None, &controller, &mut switcher, &[], start + Duration::from_secs(1));
+        assert_eq!(active(), "Default");
+    }
+    
+    #[test]
+    fn test_trigger_matches_process_names() {
+        let proc_dir = tempfile::TempDir::new().unwrap();
+        let add_process = |pid: &str, comm: &str, cmdline: &[u8]| {
+            let dir = proc_dir.path().join(pid);
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+            std::fs::write(dir.join("cmdline"), cmdline).unwrap();
+        };
+        add_process("1", "systemd", b"/sbin/init\0splash\0");
+        add_process("812", "dota2", b"/home/user/.steam/dota 2 beta/game/bin/linuxsteamrt64/dota2\0-novid\0");
+        // comm is cut to 15 characters
+        add_process("990", "witcher3-launch", b"/opt/games/witcher3-launcher\0");
+        // Kernel threads have no command line
+        add_process("2", "kthreadd", b"");
+        std::fs::create_dir(proc_dir.path().join("self")).unwrap();
+        
+        let running = running_process_names(proc_dir.path()).unwrap();
+        assert_eq!(
+            running,
+            ["dota2", "init", "kthreadd", "systemd", "witcher3-launch", "witcher3-launcher"]
+        );
+        
+        let config_dir = tempfile::TempDir::new().unwrap();
+        let mut mgr = ProfileManager::with_config_dir(config_dir.path().to_path_buf()).unwrap();
+        let mut gaming = ProfileBuilder::new("Gaming").build();
+        gaming.auto_switch_enabled = true;
+        gaming.trigger_apps = vec![TriggerMatch::new("dota2", MatchKind::Exact)];
+        mgr.add_profile(gaming).unwrap();
+        let mut rpg = ProfileBuilder::new("RPG").build();
+        rpg.auto_switch_enabled = true;
+        rpg.trigger_apps = vec![TriggerMatch::new("^witcher[0-9]-launcher$", MatchKind::Regex)];
+        mgr.add_profile(rpg).unwrap();
+        
+        assert_eq!(mgr.find_profile_for_apps(&running), Some((1, "dota2")));
+        let without_dota: Vec<String> = running.into_iter().filter(|name| name != "dota2").collect();
+        assert_eq!(mgr.find_profile_for_apps(&without_dota), Some((2, "witcher3-launcher")));
+        assert_eq!(mgr.find_profile_for_apps(&[]), None);
+    }
+    
+    #[test]
+    fn test_failed_apply_keeps_active_profile() {
+        let fixture = crate::test_fixtures::SysfsFixture::new();
+        let config_dir = tempfile::TempDir::new().unwrap();
+        let server = crate::fand::FandServer::with_parts(
+            ProfileManager::with_config_dir(config_dir.path().join("fand")).unwrap(),
+            fixture.controller(),
+            fixture.monitor(),
+        );
+        let socket = config_dir.path().join("fand.sock");
+        let listener = fand::bind(&socket, "tailor-fand-test-missing").unwrap();
+        thread::spawn(move || server.serve(listener));
+        let client = FandClient::new(&socket);
+        
+        let mut mgr = ProfileManager::with_config_dir(config_dir.path().join("gui")).unwrap();
+        mgr.add_profile(ProfileBuilder::new("Broken").build()).unwrap();
+        let profile_manager = Mutex::new(mgr);
+        // tailor-fand validates it and refuses
+        let mut broken = profile_manager.lock().unwrap().get_profiles()[1].clone();
+        broken.keyboard_backlight.brightness = 150;
+        
+        let controller = fixture.controller();
+        assert!(activate(&profile_manager, Some(&client), &controller, &broken).is_err());
+        let mgr = profile_manager.lock().unwrap();
+        assert_eq!(mgr.get_active_profile().name, "Default");
+        assert!(mgr.last_applied("Broken").is_none());
+    }
+    
+    #[test]
+    fn test_auto_switch_minimum_dwell() {
+        let mut switcher = AutoSwitcher::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        
+        assert_eq!(switcher.update(Some(1), at(0)), Some(AutoSwitch::Apply(1)));
+        // Another trigger app has to wait until the first profile dwelled
+        assert_eq!(switcher.update(Some(2), at(10)), None);
+        assert_eq!(switcher.update(Some(2), at(30)), Some(AutoSwitch::Apply(2)));
+        
+        // Without a dwell time every change switches right away
+        let mut switcher = AutoSwitcher::new(Duration::ZERO);
+        assert_eq!(switcher.update(Some(1), at(0)), Some(AutoSwitch::Apply(1)));
+        assert_eq!(switcher.update(None, at(5)), Some(AutoSwitch::RestoreDefault));
+        assert_eq!(switcher.update(None, at(10)), None);
+    }
 }
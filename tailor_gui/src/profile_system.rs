@@ -1,239 +1,1790 @@
 // src/profile_system.rs
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use anyhow::{Context, Result};
+use crate::color_temperature::COLOR_TEMP_RANGE;
+use crate::error::{self, HardwareError, HardwareResult};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct RGBColor {
     pub r: u8,
     pub g: u8,
     pub b: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl RGBColor {
+    /// Hue in degrees, saturation and value 0-1
+    fn to_hsv(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+    
+    fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        let sector = (hue / 60.0).rem_euclid(6.0);
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + value - chroma) * 255.0).round() as u8;
+        RGBColor { r: channel(r), g: channel(g), b: channel(b) }
+    }
+}
+
+impl std::fmt::Display for RGBColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FanCurvePoint {
     pub temp: u8,      // Temperature in Celsius
+    #[schemars(range(max = 100))]
     pub speed: u8,     // Fan speed percentage (0-100)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Number of points the tuxedo_io driver expects for a fan curve
+pub const FAN_CURVE_POINTS: usize = 8;
+
+/// Smallest number of points for a software-interpolated fan curve
+pub const MIN_SOFTWARE_CURVE_POINTS: usize = 2;
+
+/// Largest number of points for a software-interpolated fan curve
+pub const MAX_SOFTWARE_CURVE_POINTS: usize = 16;
+
+/// Slowest speed (percent) written for curves without
+/// [`FanCurve::allow_fan_stop`], some fans stall or stop below it
+pub const MIN_SPINNING_SPEED: u8 = 15;
+
+/// Hottest temperature (°C) [`FanCurve::is_unsafe`] checks, around the
+/// point where CPUs shut down
+const MAX_SAFETY_CHECK_TEMP: u8 = 105;
+
+/// Temperature band a CPU usually settles in under sustained load
+const SUSTAINED_LOAD_TEMPS: RangeInclusive<u8> = 70..=90;
+
+/// CPU load (percent) above which the cooling check considers the system busy
+pub const HIGH_LOAD_PERCENT: f32 = 80.0;
+
+/// Temperatures a CPU passes through in everyday use, from idle to busy
+const EVERYDAY_TEMPS: [u8; 4] = [50, 60, 70, 80];
+
+/// Brightness of the cold color keyboard effects rest at in quiet hours
+const QUIET_EFFECT_DIM: f32 = 0.2;
+
+/// How the fan curves of a profile are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum FanControlMode {
+    /// Curve points are written to the tuxedo_io driver
+    #[default]
+    Hardware,
+    /// Curve is interpolated in software and written to hwmon PWM
+    Software,
+}
+
+impl FanControlMode {
+    /// Allowed number of fan curve points in this mode
+    pub fn point_count(self) -> RangeInclusive<usize> {
+        match self {
+            FanControlMode::Hardware => FAN_CURVE_POINTS..=FAN_CURVE_POINTS,
+            FanControlMode::Software => MIN_SOFTWARE_CURVE_POINTS..=MAX_SOFTWARE_CURVE_POINTS,
+        }
+    }
+}
+
+/// Temperature a fan curve follows (software mode only, the EC picks its
+/// own sensor for hardware curves)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum TempSource {
+    /// CPU package temperature for `fan1`, the GPU for the other fans
+    #[default]
+    Auto,
+    /// Any hwmon sensor, e.g. a VRM or SSD. Identified like
+    /// [`crate::hardware_monitor::TempSensorId`].
+    Hwmon { chip: String, label: String },
+}
+
+impl TempSource {
+    /// E.g. "nvme Composite", "automatic" for `Auto`
+    pub fn label(&self) -> String {
+        match self {
+            TempSource::Auto => "automatic".to_string(),
+            TempSource::Hwmon { chip, label } => format!("{} {}", chip, label),
+        }
+    }
+}
+
+/// How the fan speed between two curve points is chosen (software mode
+/// only, the EC interpolates hardware curves itself)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum Interpolation {
+    /// Ramp linearly from one point to the next
+    #[default]
+    Linear,
+    /// Hold a point's speed until the next point's temperature is reached,
+    /// fewer speed changes at mid-range temperatures
+    Step,
+    /// Smooth monotone cubic through the points. It never leaves the range
+    /// of the two neighboring points, so there's no overshoot, only softer
+    /// speed changes around the points.
+    Spline,
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 3] = [Interpolation::Linear, Interpolation::Step, Interpolation::Spline];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Interpolation::Linear => "linear",
+            Interpolation::Step => "stepped",
+            Interpolation::Spline => "smooth",
+        }
+    }
+}
+
+/// When a fan curve counts as unsafe: curves may be quiet, but not
+/// stop cooling once the CPU is hot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanSafetyPolicy {
+    /// Temperature (°C) from which on the fan has to cool
+    pub danger_temp: u8,
+    /// Slowest speed (percent) that counts as cooling at and above
+    /// `danger_temp`
+    pub min_speed: u8,
+}
+
+impl Default for FanSafetyPolicy {
+    fn default() -> Self {
+        FanSafetyPolicy {
+            danger_temp: 70,
+            min_speed: MIN_SPINNING_SPEED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FanCurve {
-    pub points: Vec<FanCurvePoint>, // Should have exactly 8 points
+    pub points: Vec<FanCurvePoint>, // 8 points for hardware control, 2-16 in software
+    /// Below this temperature the firmware controls the fan (software mode
+    /// only). `None` keeps the fan under manual control all the time.
+    #[serde(default)]
+    pub firmware_below: Option<u8>,
+    #[serde(default)]
+    pub temp_source: TempSource,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// Let the fan stop (0%) where the curve says so. Without it the fan
+    /// keeps spinning at [`MIN_SPINNING_SPEED`] or faster. Whether the
+    /// hardware honors 0% is model specific, see
+    /// [`crate::hardware_control::HardwareController::probe_fan_stop`].
+    /// Curves saved before the floor existed run as they were saved.
+    #[serde(default = "saved_without_floor")]
+    pub allow_fan_stop: bool,
+}
+
+fn saved_without_floor() -> bool {
+    true
 }
 
 impl FanCurve {
+    /// Validate a curve for the tuxedo_io hardware path (exactly 8 points)
     pub fn validate(&self) -> Result<()> {
-        if self.points.len() != 8 {
-            anyhow::bail!("Fan curve must have exactly 8 points");
+        self.validate_points(FanControlMode::Hardware.point_count())
+    }
+    
+    /// Validate a curve, allowing the given number of points
+    pub fn validate_points(&self, count: RangeInclusive<usize>) -> Result<()> {
+        if !count.contains(&self.points.len()) {
+            if count.start() == count.end() {
+                anyhow::bail!("Fan curve must have exactly {} points", count.start());
+            }
+            anyhow::bail!(
+                "Fan curve must have between {} and {} points",
+                count.start(),
+                count.end()
+            );
         }
         
-        // Check that temperatures are in ascending order
+        // Check that temperatures are in ascending order. Points are
+        // numbered from 1, as people count them in the file.
         for i in 1..self.points.len() {
             if self.points[i].temp <= self.points[i - 1].temp {
-                anyhow::bail!("Fan curve temperatures must be in ascending order");
+                anyhow::bail!(
+                    "Fan curve temperatures must be in ascending order, point {} ({}°C) isn't hotter than point {} ({}°C)",
+                    i + 1,
+                    self.points[i].temp,
+                    i,
+                    self.points[i - 1].temp
+                );
             }
         }
         
         // Validate ranges
-        for point in &self.points {
+        for (i, point) in self.points.iter().enumerate() {
             if point.speed > 100 {
-                anyhow::bail!("Fan speed must be 0-100%");
+                anyhow::bail!("Fan speed must be 0-100%, point {} has {}%", i + 1, point.speed);
             }
         }
         
         Ok(())
     }
+
+    /// Calculate the fan speed for a temperature, interpolating between the
+    /// curve points as set by `interpolation`
+    pub fn calculate_fan_speed(&self, temp: f32) -> u8 {
+        self.speed_at(temp).round() as u8
+    }
+    
+    /// Unrounded fan speed for a temperature, e.g. for drawing the curve
+    pub fn speed_at(&self, temp: f32) -> f32 {
+        let points = self.ordered_points();
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 100.0,
+        };
+        
+        if temp <= first.temp as f32 {
+            return first.speed as f32;
+        }
+        if temp >= last.temp as f32 {
+            return last.speed as f32;
+        }
+        
+        for (index, window) in points.windows(2).enumerate() {
+            let (lower, upper) = (&window[0], &window[1]);
+            if self.interpolation == Interpolation::Step {
+                if temp < upper.temp as f32 {
+                    return lower.speed as f32;
+                }
+                continue;
+            }
+            if temp <= upper.temp as f32 {
+                let temp_range = upper.temp as f32 - lower.temp as f32;
+                let ratio = (temp - lower.temp as f32) / temp_range;
+                if self.interpolation == Interpolation::Spline {
+                    return Self::spline_speed(&points, index, ratio);
+                }
+                let speed_range = upper.speed as f32 - lower.speed as f32;
+                return lower.speed as f32 + speed_range * ratio;
+            }
+        }
+        
+        last.speed as f32
+    }
+    
+    /// Points by ascending temperature, of equal temperatures only the
+    /// highest speed. Validated curves already are, the others (e.g. from
+    /// a hand-edited file) get a sorted copy, so the interpolation never
+    /// sees a segment without width.
+    fn ordered_points(&self) -> Cow<'_, [FanCurvePoint]> {
+        if self.points.windows(2).all(|window| window[0].temp < window[1].temp) {
+            return Cow::Borrowed(&self.points);
+        }
+        
+        let mut points = self.points.clone();
+        // Higher speed first, dedup keeps the first one
+        points.sort_by(|a, b| a.temp.cmp(&b.temp).then(b.speed.cmp(&a.speed)));
+        points.dedup_by_key(|point| point.temp);
+        Cow::Owned(points)
+    }
+    
+    /// Cubic Hermite between point `index` and the next one at `ratio`
+    /// (0-1) of the way. The tangents are the weighted harmonic mean of the
+    /// neighboring slopes (Fritsch-Butland), which keeps the curve monotone
+    /// between the points.
+    fn spline_speed(points: &[FanCurvePoint], index: usize, ratio: f32) -> f32 {
+        let (lower, upper) = (&points[index], &points[index + 1]);
+        let width = upper.temp as f32 - lower.temp as f32;
+        let (t2, t3) = (ratio * ratio, ratio * ratio * ratio);
+        
+        let speed = (2.0 * t3 - 3.0 * t2 + 1.0) * lower.speed as f32
+            + (t3 - 2.0 * t2 + ratio) * width * Self::spline_tangent(points, index)
+            + (-2.0 * t3 + 3.0 * t2) * upper.speed as f32
+            + (t3 - t2) * width * Self::spline_tangent(points, index + 1);
+        
+        // Rounding errors aside, the tangents already guarantee this
+        let (low, high) = if lower.speed <= upper.speed {
+            (lower.speed, upper.speed)
+        } else {
+            (upper.speed, lower.speed)
+        };
+        speed.clamp(low as f32, high as f32)
+    }
+    
+    /// Slope of the spline at point `index` in percent per °C
+    fn spline_tangent(points: &[FanCurvePoint], index: usize) -> f32 {
+        let slope = |index: usize| {
+            let (lower, upper) = (&points[index], &points[index + 1]);
+            (upper.speed as f32 - lower.speed as f32) / (upper.temp as f32 - lower.temp as f32)
+        };
+        // The end points continue the slope of their only segment
+        if index == 0 {
+            return slope(0);
+        }
+        if index == points.len() - 1 {
+            return slope(index - 1);
+        }
+        
+        let (before, after) = (slope(index - 1), slope(index));
+        // Flat or turning around, a tangent would overshoot
+        if before * after <= 0.0 {
+            return 0.0;
+        }
+        let width_before = points[index].temp as f32 - points[index - 1].temp as f32;
+        let width_after = points[index + 1].temp as f32 - points[index].temp as f32;
+        let weight_before = 2.0 * width_after + width_before;
+        let weight_after = width_after + 2.0 * width_before;
+        (weight_before + weight_after) / (weight_before / before + weight_after / after)
+    }
+    
+    /// Speed to write for `temp`: the curve's speed raised to
+    /// [`MIN_SPINNING_SPEED`] unless the fan may stop
+    pub fn target_speed(&self, temp: f32) -> u8 {
+        self.limit_speed(self.calculate_fan_speed(temp))
+    }
+
+    /// Apply the [`MIN_SPINNING_SPEED`] floor to `speed` unless the fan may stop
+    pub fn limit_speed(&self, speed: u8) -> u8 {
+        if self.allow_fan_stop {
+            speed
+        } else {
+            speed.max(MIN_SPINNING_SPEED)
+        }
+    }
+    
+    /// Whether the curve keeps the fan slower than `policy.min_speed` at
+    /// or above `policy.danger_temp`. Such curves pass [`Self::validate`],
+    /// so editors should ask before saving them.
+    pub fn is_unsafe(&self, policy: &FanSafetyPolicy) -> bool {
+        (policy.danger_temp..=MAX_SAFETY_CHECK_TEMP)
+            .any(|temp| self.calculate_fan_speed(temp as f32) < policy.min_speed)
+    }
+    
+    /// Lowest fan speed the curve provides while the CPU is under sustained load
+    pub fn estimate_min_cooling(&self) -> u8 {
+        SUSTAINED_LOAD_TEMPS
+            .map(|temp| self.calculate_fan_speed(temp as f32))
+            .min()
+            .unwrap_or(0)
+    }
+    
+    /// Advisory check whether the curve may cause throttling at the current
+    /// load. Chips with a higher TDP need more airflow; without a known TDP
+    /// a mid-range requirement is assumed.
+    pub fn cooling_warning(&self, cpu_load_percent: f32, tdp_watts: Option<f32>) -> Option<String> {
+        if cpu_load_percent < HIGH_LOAD_PERCENT {
+            return None;
+        }
+        
+        let required = tdp_watts
+            .map(|tdp| (20.0 + tdp * 0.6).clamp(20.0, 80.0) as u8)
+            .unwrap_or(40);
+        let provided = self.estimate_min_cooling();
+        
+        (provided < required).then(|| {
+            format!(
+                "Fans only reach {}% under sustained load (about {}% recommended), \
+                 the CPU may throttle",
+                provided, required
+            )
+        })
+    }
+    
+    /// Turn an invalid curve into a valid one by clamping, sorting and
+    /// padding/trimming the points to `count`. Returns a description of every fix.
+    pub fn repair(&mut self, count: RangeInclusive<usize>) -> Vec<String> {
+        let (min_points, max_points) = (*count.start(), *count.end());
+        let mut fixes = Vec::new();
+
+        for point in &mut self.points {
+            if point.speed > 100 {
+                fixes.push(format!("clamped speed {}% at {}°C to 100%", point.speed, point.temp));
+                point.speed = 100;
+            }
+        }
+
+        if self.points.windows(2).any(|w| w[1].temp < w[0].temp) {
+            self.points.sort_by_key(|p| p.temp);
+            fixes.push("sorted points by temperature".to_string());
+        }
+
+        // Keep the faster point if two share a temperature
+        let len_before = self.points.len();
+        self.points.dedup_by(|next, prev| {
+            if next.temp == prev.temp {
+                prev.speed = prev.speed.max(next.speed);
+                true
+            } else {
+                false
+            }
+        });
+        if self.points.len() != len_before {
+            fixes.push(format!(
+                "merged {} points with duplicate temperatures",
+                len_before - self.points.len()
+            ));
+        }
+
+        if self.points.len() > max_points {
+            // Sample evenly so the first and last point are always kept
+            let len = self.points.len();
+            self.points = (0..max_points)
+                .map(|i| self.points[i * (len - 1) / (max_points - 1)].clone())
+                .collect();
+            fixes.push(format!("trimmed {} points to {}", len, max_points));
+        }
+
+        if self.points.len() < min_points {
+            let len = self.points.len();
+            let last = self.points.last().cloned();
+            let padded = last.filter(|last| {
+                last.temp as usize + 5 * (min_points - len) <= u8::MAX as usize
+            });
+
+            if let Some(last) = padded {
+                // Extend the curve above the hottest point at the same speed
+                for i in 1..=(min_points - len) {
+                    self.points.push(FanCurvePoint {
+                        temp: last.temp + 5 * i as u8,
+                        speed: last.speed,
+                    });
+                }
+                fixes.push(format!("padded {} points to {}", len, min_points));
+            } else {
+                self.points = Profile::default_fan_curve().points;
+                fixes.push("replaced unusable curve with the default curve".to_string());
+            }
+        }
+
+        fixes
+    }
+}
+
+/// What a profile does with the keyboard backlight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum KeyboardMode {
+    /// Write the profile's color and brightness
+    #[default]
+    Managed,
+    /// Actively turn the backlight off
+    Off,
+    /// Leave the backlight as it is
+    Unmanaged,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KeyboardBacklight {
+    #[serde(default)]
+    pub mode: KeyboardMode,
     pub color: RGBColor,
+    #[schemars(range(max = 100))]
     pub brightness: u8, // 0-100
+    /// Brightness while running on battery, `None` keeps `brightness`
+    #[serde(default)]
+    #[schemars(range(max = 100))]
+    pub battery_brightness: Option<u8>,
+    /// Effect run by the keyboard firmware (e.g. "wave"), `None` for the
+    /// static color
+    #[serde(default)]
+    pub hw_effect: Option<String>,
+    /// Seconds until the keyboard firmware turns the backlight off, 0
+    /// keeps it on. `None` leaves the firmware setting alone.
+    #[serde(default)]
+    pub hw_timeout_secs: Option<u32>,
+    /// Software effect, e.g. a color following the CPU temperature.
+    /// Replaces `color` while running.
+    #[serde(default)]
+    pub effect: Option<KeyboardEffect>,
+}
+
+/// Effect Tailor runs itself by rewriting the keyboard color
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum KeyboardEffect {
+    /// Follow the CPU package temperature, from `cold` at `min_temp` to
+    /// `hot` at `max_temp` (°C). Blends through the hues in between, so
+    /// blue to red passes green.
+    TempReactive {
+        cold: RGBColor,
+        hot: RGBColor,
+        min_temp: f32,
+        max_temp: f32,
+    },
+}
+
+impl KeyboardEffect {
+    /// Color at `temp`, temperatures outside the range get the color of
+    /// its end
+    pub fn color_at(&self, temp: f32) -> RGBColor {
+        let KeyboardEffect::TempReactive { cold, hot, min_temp, max_temp } = self;
+        let ratio = if max_temp > min_temp {
+            ((temp - min_temp) / (max_temp - min_temp)).clamp(0.0, 1.0)
+        } else if temp >= *max_temp {
+            1.0
+        } else {
+            0.0
+        };
+        
+        let (cold_hue, cold_saturation, cold_value) = cold.to_hsv();
+        let (hot_hue, hot_saturation, hot_value) = hot.to_hsv();
+        // White and grays have no hue, only the saturation changes then
+        let cold_hue = if cold_saturation == 0.0 { hot_hue } else { cold_hue };
+        let hot_hue = if hot_saturation == 0.0 { cold_hue } else { hot_hue };
+        let lerp = |from: f32, to: f32| from + (to - from) * ratio;
+        
+        RGBColor::from_hsv(
+            lerp(cold_hue, hot_hue),
+            lerp(cold_saturation, hot_saturation),
+            lerp(cold_value, hot_value),
+        )
+    }
+    
+    /// Static color while quiet hours pause the effect, its cold color dimmed
+    pub fn quiet_color(&self) -> RGBColor {
+        let KeyboardEffect::TempReactive { cold, .. } = self;
+        let (hue, saturation, value) = cold.to_hsv();
+        RGBColor::from_hsv(hue, saturation, value * QUIET_EFFECT_DIM)
+    }
+}
+
+impl std::fmt::Display for KeyboardEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let KeyboardEffect::TempReactive { cold, hot, min_temp, max_temp } = self;
+        write!(f, "{} at {}°C to {} at {}°C", cold, min_temp, hot, max_temp)
+    }
+}
+
+impl KeyboardBacklight {
+    pub fn brightness_for(&self, on_battery: bool) -> u8 {
+        match self.battery_brightness {
+            Some(brightness) if on_battery => brightness,
+            _ => self.brightness,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum CpuPerformanceProfile {
     PowerSave,
     Balanced,
     Performance,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CpuSettings {
     pub performance_profile: CpuPerformanceProfile,
     pub min_freq_mhz: Option<u32>,
     pub max_freq_mhz: Option<u32>,
     pub disable_boost: bool,
     pub smt_enabled: bool, // Hyperthreading/SMT
+    /// Energy performance preference for intel_pstate/amd-pstate,
+    /// e.g. "balance_power". `None` leaves it alone.
+    #[serde(default)]
+    pub energy_performance_preference: Option<String>,
+    /// Preferred core ranking of amd-pstate, `None` leaves it alone
+    #[serde(default)]
+    pub amd_prefcore: Option<bool>,
+    /// Pin every core to this frequency with the userspace governor, e.g.
+    /// for benchmarks. Replaces the governor of `performance_profile`
+    /// where the driver supports it.
+    #[serde(default)]
+    pub fixed_freq_mhz: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScreenSettings {
+    #[schemars(range(max = 100))]
     pub brightness: u8, // 0-100
     pub auto_brightness: bool,
+    /// Screen color temperature, e.g. 4000 for a warm "reading" profile.
+    /// `None` leaves the colors neutral.
+    #[serde(default)]
+    pub color_temp_kelvin: Option<u32>,
+    /// Set the brightness of every display instead of only the built-in
+    /// one. `None` follows [`crate::app_config::AppConfig::brightness_all_displays`].
+    #[serde(default)]
+    pub apply_to_all_displays: Option<bool>,
+}
+
+/// How a trigger is compared with the name of a running app, ignoring case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// The executable name, a path matches by its file name
+    Exact,
+    /// Anywhere in the name
+    #[default]
+    Substring,
+    Regex,
+}
+
+impl MatchKind {
+    pub const ALL: [MatchKind; 3] = [MatchKind::Exact, MatchKind::Substring, MatchKind::Regex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchKind::Exact => "Exact name",
+            MatchKind::Substring => "Contains",
+            MatchKind::Regex => "Regular expression",
+        }
+    }
+}
+
+/// App that switches to a profile while it runs. Profiles from before
+/// match kinds stored plain strings, which are read as substring matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "TriggerMatchRepr")]
+pub struct TriggerMatch {
+    pub pattern: String,
+    pub kind: MatchKind,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum TriggerMatchRepr {
+    Name(String),
+    Match {
+        pattern: String,
+        #[serde(default)]
+        kind: MatchKind,
+    },
+}
+
+impl From<TriggerMatchRepr> for TriggerMatch {
+    fn from(repr: TriggerMatchRepr) -> Self {
+        match repr {
+            TriggerMatchRepr::Name(pattern) => TriggerMatch::new(pattern, MatchKind::Substring),
+            TriggerMatchRepr::Match { pattern, kind } => TriggerMatch::new(pattern, kind),
+        }
+    }
+}
+
+impl TriggerMatch {
+    pub fn new(pattern: impl Into<String>, kind: MatchKind) -> Self {
+        TriggerMatch { pattern: pattern.into(), kind }
+    }
+
+    /// Check that a regex pattern compiles, the other kinds always do
+    pub fn validate(&self) -> Result<()> {
+        if self.kind == MatchKind::Regex {
+            self.regex()?;
+        }
+        Ok(())
+    }
+
+    fn regex(&self) -> Result<regex::Regex> {
+        regex::RegexBuilder::new(&self.pattern)
+            .case_insensitive(true)
+            .build()
+            .context(format!("Invalid trigger pattern '{}'", self.pattern))
+    }
+
+    /// Whether the app named `app_name` triggers the profile. Regexes that
+    /// don't compile match nothing.
+    pub fn matches(&self, app_name: &str) -> bool {
+        match self.kind {
+            MatchKind::Exact => {
+                let executable = Path::new(app_name)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or(app_name);
+                !self.pattern.is_empty() && executable.eq_ignore_ascii_case(&self.pattern)
+            }
+            MatchKind::Substring => {
+                !self.pattern.is_empty()
+                    && app_name.to_lowercase().contains(&self.pattern.to_lowercase())
+            }
+            MatchKind::Regex => self.regex().is_ok_and(|regex| regex.is_match(app_name)),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Display for TriggerMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            MatchKind::Substring => write!(f, "{}", self.pattern),
+            MatchKind::Exact => write!(f, "{} (exact)", self.pattern),
+            MatchKind::Regex => write!(f, "{} (regex)", self.pattern),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Profile {
     pub name: String,
     pub is_default: bool,
+    /// Protects the profile from edits and deletion until it's unlocked
+    #[serde(default)]
+    pub locked: bool,
     
     // Hardware settings
     pub keyboard_backlight: KeyboardBacklight,
     pub fan_curves: HashMap<String, FanCurve>, // fan_id -> curve
+    #[serde(default)]
+    pub fan_control_mode: FanControlMode,
     pub cpu_settings: CpuSettings,
     pub screen_settings: ScreenSettings,
+    /// PCIe ASPM policy, e.g. "powersupersave" to save power on battery.
+    /// `None` leaves it alone.
+    #[serde(default)]
+    pub aspm_policy: Option<String>,
+    /// Power limit in watts for GPUs that have one (amdgpu), clamped to what
+    /// the driver allows. `None` leaves the limit alone.
+    #[serde(default)]
+    pub gpu_power_cap_watts: Option<u32>,
     
     // Auto-switching rules
     pub auto_switch_enabled: bool,
-    pub trigger_apps: Vec<String>, // App names/executables that trigger this profile
+    pub trigger_apps: Vec<TriggerMatch>,
 }
 
-impl Profile {
-    pub fn default_profile() -> Self {
-        let mut fan_curves = HashMap::new();
-        
-        // Default fan curve with 8 points
-        let default_curve = FanCurve {
-            points: vec![
-                FanCurvePoint { temp: 40, speed: 30 },
-                FanCurvePoint { temp: 50, speed: 40 },
-                FanCurvePoint { temp: 60, speed: 50 },
-                FanCurvePoint { temp: 65, speed: 60 },
-                FanCurvePoint { temp: 70, speed: 70 },
-                FanCurvePoint { temp: 75, speed: 80 },
-                FanCurvePoint { temp: 80, speed: 90 },
-                FanCurvePoint { temp: 85, speed: 100 },
-            ],
-        };
-        
-        fan_curves.insert("fan1".to_string(), default_curve.clone());
-        fan_curves.insert("fan2".to_string(), default_curve);
-        
-        Profile {
-            name: "Default".to_string(),
-            is_default: true,
-            keyboard_backlight: KeyboardBacklight {
-                color: RGBColor { r: 255, g: 255, b: 255 },
-                brightness: 50,
-            },
-            fan_curves,
-            cpu_settings: CpuSettings {
-                performance_profile: CpuPerformanceProfile::Balanced,
-                min_freq_mhz: None,
-                max_freq_mhz: None,
-                disable_boost: false,
-                smt_enabled: true,
-            },
-            screen_settings: ScreenSettings {
-                brightness: 70,
-                auto_brightness: false,
-            },
-            auto_switch_enabled: false,
-            trigger_apps: Vec::new(),
+/// One setting that differs between two versions of a profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Human readable name, e.g. "Keyboard brightness"
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl std::fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} → {}", self.field, self.old, self.new)
+    }
+}
+
+/// Rough loudness of a profile's fans, see [`Profile::noise_hint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NoiseLevel {
+    Quiet,
+    Moderate,
+    Loud,
+}
+
+impl NoiseLevel {
+    /// Advisory estimate how loud fans following `curves` get, from the
+    /// average speed of the most aggressive curve at everyday temperatures.
+    /// Without curves the firmware decides, which is usually moderate.
+    pub fn from_curves<'a>(curves: impl IntoIterator<Item = &'a FanCurve>) -> Self {
+        let average_speed = curves
+            .into_iter()
+            .map(|curve| {
+                let total: u32 = EVERYDAY_TEMPS
+                    .iter()
+                    .map(|&temp| curve.calculate_fan_speed(temp as f32) as u32)
+                    .sum();
+                total / EVERYDAY_TEMPS.len() as u32
+            })
+            .max();
+
+        match average_speed {
+            Some(speed) if speed < 45 => NoiseLevel::Quiet,
+            Some(speed) if speed >= 70 => NoiseLevel::Loud,
+            _ => NoiseLevel::Moderate,
         }
     }
-    
-    pub fn validate(&self) -> Result<()> {
-        // Validate fan curves
-        for (fan_id, curve) in &self.fan_curves {
-            curve.validate()
-                .context(format!("Invalid fan curve for {}", fan_id))?;
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NoiseLevel::Quiet => "quiet",
+            NoiseLevel::Moderate => "moderate noise",
+            NoiseLevel::Loud => "loud",
         }
-        
-        // Validate brightness values
-        if self.keyboard_backlight.brightness > 100 {
-            anyhow::bail!("Keyboard brightness must be 0-100");
+    }
+}
+
+/// Rough power draw of a profile's CPU settings, see [`Profile::power_hint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PowerLevel {
+    Saving,
+    Balanced,
+    Performance,
+}
+
+impl PowerLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerLevel::Saving => "power saving",
+            PowerLevel::Balanced => "balanced power",
+            PowerLevel::Performance => "high power",
         }
-        if self.screen_settings.brightness > 100 {
-            anyhow::bail!("Screen brightness must be 0-100");
+    }
+
+    /// Power draw of a firmware (ODM) performance profile tailord sets,
+    /// `None` for names the TUXEDO drivers don't use
+    pub fn from_odm_profile(name: &str) -> Option<Self> {
+        match name {
+            "quiet" | "power_save" | "power_saving" => Some(PowerLevel::Saving),
+            "enthusiast" | "entertainment" => Some(PowerLevel::Balanced),
+            "performance" | "overboost" => Some(PowerLevel::Performance),
+            _ => None,
         }
-        
-        Ok(())
     }
 }
 
-pub struct ProfileManager {
-    profiles: Vec<Profile>,
-    active_profile_index: usize,
-    config_dir: PathBuf,
+impl CpuPerformanceProfile {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CpuPerformanceProfile::PowerSave => "power save",
+            CpuPerformanceProfile::Balanced => "balanced",
+            CpuPerformanceProfile::Performance => "performance",
+        }
+    }
 }
 
-impl ProfileManager {
-    pub fn new() -> Result<Self> {
-        let config_dir = Self::get_config_dir()?;
-        fs::create_dir_all(&config_dir)
-            .context("Failed to create config directory")?;
-        
-        let mut manager = ProfileManager {
-            profiles: Vec::new(),
-            active_profile_index: 0,
-            config_dir,
-        };
-        
-        manager.load_profiles()?;
-        
-        // Ensure at least one profile exists
-        if manager.profiles.is_empty() {
-            manager.profiles.push(Profile::default_profile());
-            manager.save_profiles()?;
+/// Collects changes, values are compared by their text
+struct DiffBuilder(Vec<FieldChange>);
+
+impl DiffBuilder {
+    fn field(&mut self, field: impl Into<String>, old: String, new: String) {
+        if old != new {
+            self.0.push(FieldChange { field: field.into(), old, new });
         }
-        
-        Ok(manager)
+    }
+}
+
+fn describe_option<T: std::fmt::Display>(value: Option<T>, none: &str) -> String {
+    value.map_or_else(|| none.to_string(), |value| value.to_string())
+}
+
+fn describe_switch(enabled: bool) -> String {
+    if enabled { "on" } else { "off" }.to_string()
+}
+
+fn describe_triggers(triggers: &[TriggerMatch]) -> String {
+    triggers.iter().map(TriggerMatch::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn describe_curve(curve: Option<&FanCurve>) -> String {
+    let Some(curve) = curve else {
+        return "none".to_string();
+    };
+    let mut text = curve
+        .points
+        .iter()
+        .map(|point| format!("{}°C {}%", point.temp, point.speed))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Some(floor) = curve.firmware_below {
+        text.push_str(&format!(" (firmware below {}°C)", floor));
+    }
+    if curve.temp_source != TempSource::Auto {
+        text.push_str(&format!(" following {}", curve.temp_source.label()));
+    }
+    if curve.interpolation != Interpolation::Linear {
+        text.push_str(&format!(", {}", curve.interpolation.label()));
+    }
+    if curve.allow_fan_stop {
+        text.push_str(", fan may stop");
+    }
+    text
+}
+
+/// JSON Schema of a profile as stored in profiles.json, for editors and
+/// tools writing profiles
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(Profile);
+    serde_json::to_string_pretty(&schema).expect("schema serializes")
+}
+
+/// Check a hand-edited file with one profile or a list of them, like
+/// profiles.json, before the GUI loads it. Fails if the file isn't a valid
+/// profile file at all, otherwise returns one message per invalid profile,
+/// none if all are fine.
+pub fn validate_profile_file(path: &Path) -> Result<Vec<String>> {
+    let content =
+        fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+
+    // The shape the schema describes, serde names the offending field and
+    // its line
+    let profiles: Vec<Profile> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content)
+    } else {
+        serde_json::from_str(&content).map(|profile| vec![profile])
+    }
+    .context(format!("{} isn't a valid profile file", path.display()))?;
+
+    let mut problems = Vec::new();
+    let mut names = HashSet::new();
+    for profile in &profiles {
+        if let Err(e) = validate_profile_name(&profile.name).and_then(|_| profile.validate()) {
+            problems.push(format!("Profile '{}': {:#}", profile.name, e));
+        }
+        if !names.insert(profile.name.as_str()) {
+            problems.push(format!("Profile '{}': The name is used more than once", profile.name));
+        }
+    }
+    Ok(problems)
+}
+
+/// `--validate`: check the profile file at `path` like
+/// [`validate_profile_file`], report the outcome and return the exit code,
+/// 1 if anything is wrong
+pub fn run_validate(path: &Path, stdout: &mut impl io::Write, stderr: &mut impl io::Write) -> i32 {
+    // Nothing to do about failing to print
+    match validate_profile_file(path) {
+        Ok(problems) if problems.is_empty() => {
+            let _ = writeln!(stdout, "{} is valid", path.display());
+            0
+        }
+        Ok(problems) => {
+            for problem in problems {
+                let _ = writeln!(stderr, "{problem}");
+            }
+            1
+        }
+        Err(err) => {
+            let _ = writeln!(stderr, "{err:#}");
+            1
+        }
+    }
+}
+
+/// Profile of TUXEDO Control Center, as in its `/etc/tcc/profiles` or an
+/// export. Only the parts that map onto [`Profile`] are parsed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccProfile {
+    name: String,
+    #[serde(default)]
+    display: Option<TccDisplay>,
+    #[serde(default)]
+    cpu: Option<TccCpu>,
+    #[serde(default)]
+    fan: Option<TccFan>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccDisplay {
+    brightness: Option<u8>,
+    #[serde(default)]
+    use_brightness: bool,
+    #[serde(default)]
+    use_ref_rate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccCpu {
+    online_cores: Option<u32>,
+    #[serde(default)]
+    use_max_perf_gov: bool,
+    /// kHz like cpufreq
+    scaling_min_frequency: Option<u32>,
+    scaling_max_frequency: Option<u32>,
+    governor: Option<String>,
+    energy_performance_preference: Option<String>,
+    #[serde(default)]
+    no_turbo: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TccFan {
+    #[serde(default)]
+    use_control: bool,
+    fan_profile: Option<String>,
+    #[serde(default)]
+    minimum_fanspeed: u8,
+    maximum_fanspeed: Option<u8>,
+    #[serde(default)]
+    offset_fanspeed: i16,
+    custom_fan_curve: Option<TccFanCurve>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TccFanCurve {
+    #[serde(default, rename = "tableCPU")]
+    table_cpu: Vec<FanCurvePoint>,
+    #[serde(default, rename = "tableGPU")]
+    table_gpu: Vec<FanCurvePoint>,
+}
+
+/// Parts of a TCC profile that are understood, everything else is dropped
+const TCC_PROFILE_FIELDS: [&str; 6] = ["id", "name", "description", "display", "cpu", "fan"];
+
+/// Read profiles exported from TUXEDO Control Center, a list or a single
+/// profile. Fan tables become 8 point hardware curves, the CPU governor
+/// settings the closest performance profile. Settings without an
+/// equivalent, e.g. webcam or ODM power limits, are dropped with a warning.
+pub fn import_tcc(path: &Path) -> Result<Vec<Profile>> {
+    let content = fs::read_to_string(path)
+        .context(format!("Failed to read {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .context("Failed to parse TCC profiles")?;
+    let entries = match json {
+        serde_json::Value::Array(entries) => entries,
+        entry => vec![entry],
+    };
+    
+    entries
+        .into_iter()
+        .map(|entry| {
+            let dropped: Vec<String> = entry
+                .as_object()
+                .map(|fields| {
+                    fields
+                        .keys()
+                        .filter(|key| !TCC_PROFILE_FIELDS.contains(&key.as_str()))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            let tcc: TccProfile = serde_json::from_value(entry)
+                .context("Failed to parse TCC profile")?;
+            let mut warnings: Vec<String> = dropped
+                .into_iter()
+                .map(|field| format!("'{}' has no equivalent", field))
+                .collect();
+            
+            let profile = tcc_to_profile(tcc, &mut warnings);
+            for warning in warnings {
+                eprintln!("Warning: TCC profile '{}': {}", profile.name, warning);
+            }
+            validate_profile_name(&profile.name)?;
+            profile.validate()
+                .context(format!("Imported profile '{}' is invalid", profile.name))?;
+            Ok(profile)
+        })
+        .collect()
+}
+
+fn tcc_to_profile(tcc: TccProfile, warnings: &mut Vec<String>) -> Profile {
+    let mut profile = Profile::default_profile();
+    profile.name = tcc.name;
+    profile.is_default = false;
+    
+    if let Some(display) = tcc.display {
+        if display.use_brightness {
+            if let Some(brightness) = display.brightness {
+                profile.screen_settings.brightness = brightness.min(100);
+            }
+        }
+        if display.use_ref_rate {
+            warnings.push("refresh rate is not imported".to_string());
+        }
+    }
+    
+    if let Some(cpu) = tcc.cpu {
+        let settings = &mut profile.cpu_settings;
+        // intel_pstate and amd-pstate only offer "powersave", the EPP
+        // tells how eager it is
+        let epp = cpu.energy_performance_preference.filter(|epp| epp != "default");
+        settings.performance_profile = if cpu.use_max_perf_gov
+            || cpu.governor.as_deref() == Some("performance")
+        {
+            CpuPerformanceProfile::Performance
+        } else {
+            match epp.as_deref() {
+                Some("power") => CpuPerformanceProfile::PowerSave,
+                Some("performance") => CpuPerformanceProfile::Performance,
+                _ => CpuPerformanceProfile::Balanced,
+            }
+        };
+        settings.energy_performance_preference = epp;
+        settings.min_freq_mhz = cpu.scaling_min_frequency.map(|khz| khz / 1000);
+        settings.max_freq_mhz = cpu.scaling_max_frequency.map(|khz| khz / 1000);
+        settings.disable_boost = cpu.no_turbo;
+        if cpu.online_cores.is_some() {
+            warnings.push("number of online cores is not imported".to_string());
+        }
+    }
+    
+    match tcc.fan {
+        Some(fan) if fan.use_control => match fan.custom_fan_curve {
+            Some(custom) => {
+                // TCC's CPU table drives the chassis fan, the GPU table the second fan
+                let max = fan.maximum_fanspeed.unwrap_or(100).min(100) as i16;
+                let min = (fan.minimum_fanspeed as i16).min(max);
+                let limits = |speed: u8| (speed as i16 + fan.offset_fanspeed).clamp(min, max) as u8;
+                for (fan_id, table) in [("fan1", custom.table_cpu), ("fan2", custom.table_gpu)] {
+                    match resample_fan_table(table, limits) {
+                        Some(curve) => {
+                            profile.fan_curves.insert(fan_id.to_string(), curve);
+                        }
+                        None => warnings.push(format!(
+                            "fan table for {} is unusable, using the default curve",
+                            fan_id
+                        )),
+                    }
+                }
+            }
+            None => warnings.push(format!(
+                "built-in fan profile '{}' is not imported, using the default curves",
+                fan.fan_profile.as_deref().unwrap_or("unknown")
+            )),
+        },
+        Some(_) => warnings.push("fan control is off in TCC, using the default curves".to_string()),
+        None => {}
+    }
+    
+    profile
+}
+
+/// Turn a TCC fan table (any number of points) into a hardware curve
+/// with [`FAN_CURVE_POINTS`] points spread evenly over its temperatures.
+/// `None` if the table spans too few degrees for that.
+fn resample_fan_table(
+    mut table: Vec<FanCurvePoint>,
+    limits: impl Fn(u8) -> u8,
+) -> Option<FanCurve> {
+    table.sort_by_key(|point| point.temp);
+    let (first, last) = (table.first()?.temp, table.last()?.temp);
+    let span = last.checked_sub(first)? as usize;
+    if span < FAN_CURVE_POINTS - 1 {
+        return None;
+    }
+    
+    let source = FanCurve { points: table, ..Profile::default_fan_curve() };
+    let points = (0..FAN_CURVE_POINTS)
+        .map(|i| {
+            let step = span as f32 * i as f32 / (FAN_CURVE_POINTS - 1) as f32;
+            let temp = first + step.round() as u8;
+            FanCurvePoint {
+                temp,
+                speed: limits(source.calculate_fan_speed(temp as f32).min(100)),
+            }
+        })
+        .collect();
+    Some(FanCurve { points, ..Profile::default_fan_curve() })
+}
+
+/// Check that a profile name can be used as a file name, e.g. for exports
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        anyhow::bail!("Profile name must not be empty");
+    }
+    if name.contains(['/', '\\']) {
+        anyhow::bail!("Profile name must not contain '/' or '\\'");
+    }
+    if name.chars().any(char::is_control) {
+        anyhow::bail!("Profile name must not contain control characters");
+    }
+    if name.trim_matches('.').is_empty() {
+        anyhow::bail!("Profile name must not consist of dots only");
+    }
+    Ok(())
+}
+
+impl Profile {
+    /// File name (without extension) derived from the profile name. Path
+    /// separators and control characters are replaced, unicode is kept.
+    pub fn sanitized_filename(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c == '/' || c == '\\' || c.is_control() { '_' } else { c })
+            .collect();
+        // Leading dots would hide the file, trailing ones confuse extensions
+        let name = name.trim().trim_matches('.');
+        
+        if name.is_empty() {
+            "profile".to_string()
+        } else {
+            name.to_string()
+        }
+    }
+    
+    /// Default fan curve with 8 points
+    pub fn default_fan_curve() -> FanCurve {
+        FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 40, speed: 30 },
+                FanCurvePoint { temp: 50, speed: 40 },
+                FanCurvePoint { temp: 60, speed: 50 },
+                FanCurvePoint { temp: 65, speed: 60 },
+                FanCurvePoint { temp: 70, speed: 70 },
+                FanCurvePoint { temp: 75, speed: 80 },
+                FanCurvePoint { temp: 80, speed: 90 },
+                FanCurvePoint { temp: 85, speed: 100 },
+            ],
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: false,
+        }
+    }
+
+    pub fn default_profile() -> Self {
+        let mut fan_curves = HashMap::new();
+        let default_curve = Self::default_fan_curve();
+        
+        fan_curves.insert("fan1".to_string(), default_curve.clone());
+        fan_curves.insert("fan2".to_string(), default_curve);
+        
+        Profile {
+            name: "Default".to_string(),
+            is_default: true,
+            locked: false,
+            keyboard_backlight: KeyboardBacklight {
+                mode: KeyboardMode::Managed,
+                color: RGBColor { r: 255, g: 255, b: 255 },
+                brightness: 50,
+                battery_brightness: None,
+                hw_effect: None,
+                hw_timeout_secs: None,
+                effect: None,
+            },
+            fan_curves,
+            fan_control_mode: FanControlMode::Hardware,
+            cpu_settings: CpuSettings {
+                performance_profile: CpuPerformanceProfile::Balanced,
+                min_freq_mhz: None,
+                max_freq_mhz: None,
+                disable_boost: false,
+                smt_enabled: true,
+                energy_performance_preference: None,
+                amd_prefcore: None,
+                fixed_freq_mhz: None,
+            },
+            screen_settings: ScreenSettings {
+                brightness: 70,
+                auto_brightness: false,
+                color_temp_kelvin: None,
+                apply_to_all_displays: None,
+            },
+            aspm_policy: None,
+            gpu_power_cap_watts: None,
+            auto_switch_enabled: false,
+            trigger_apps: Vec::new(),
+        }
+    }
+    
+    /// Settings that differ from `other`, e.g. the stored version of an
+    /// edited profile. Old values are taken from `other`, new ones from
+    /// `self`.
+    pub fn diff(&self, other: &Profile) -> Vec<FieldChange> {
+        let mut diff = DiffBuilder(Vec::new());
+        
+        // General
+        diff.field("Name", other.name.clone(), self.name.clone());
+        diff.field("Default", describe_switch(other.is_default), describe_switch(self.is_default));
+        diff.field(
+            "Auto switching",
+            describe_switch(other.auto_switch_enabled),
+            describe_switch(self.auto_switch_enabled),
+        );
+        diff.field(
+            "Trigger apps",
+            describe_triggers(&other.trigger_apps),
+            describe_triggers(&self.trigger_apps),
+        );
+        
+        // Keyboard
+        let (old, new) = (&other.keyboard_backlight, &self.keyboard_backlight);
+        diff.field("Keyboard mode", format!("{:?}", old.mode), format!("{:?}", new.mode));
+        diff.field(
+            "Keyboard color",
+            format!("#{:02x}{:02x}{:02x}", old.color.r, old.color.g, old.color.b),
+            format!("#{:02x}{:02x}{:02x}", new.color.r, new.color.g, new.color.b),
+        );
+        diff.field("Keyboard brightness", old.brightness.to_string(), new.brightness.to_string());
+        diff.field(
+            "Keyboard brightness on battery",
+            describe_option(old.battery_brightness, "same"),
+            describe_option(new.battery_brightness, "same"),
+        );
+        diff.field(
+            "Keyboard effect",
+            describe_option(old.hw_effect.as_deref(), "static"),
+            describe_option(new.hw_effect.as_deref(), "static"),
+        );
+        diff.field(
+            "Keyboard timeout",
+            describe_option(old.hw_timeout_secs.map(|secs| format!("{} s", secs)), "unchanged"),
+            describe_option(new.hw_timeout_secs.map(|secs| format!("{} s", secs)), "unchanged"),
+        );
+        diff.field(
+            "Keyboard software effect",
+            describe_option(old.effect.as_ref(), "none"),
+            describe_option(new.effect.as_ref(), "none"),
+        );
+        
+        // Fans
+        diff.field(
+            "Fan control",
+            format!("{:?}", other.fan_control_mode),
+            format!("{:?}", self.fan_control_mode),
+        );
+        let mut fan_ids: Vec<&String> = other.fan_curves.keys().chain(self.fan_curves.keys()).collect();
+        fan_ids.sort();
+        fan_ids.dedup();
+        for fan_id in fan_ids {
+            diff.field(
+                format!("Fan curve {}", fan_id),
+                describe_curve(other.fan_curves.get(fan_id)),
+                describe_curve(self.fan_curves.get(fan_id)),
+            );
+        }
+        
+        // CPU
+        let (old, new) = (&other.cpu_settings, &self.cpu_settings);
+        diff.field(
+            "Governor",
+            old.performance_profile.label().to_string(),
+            new.performance_profile.label().to_string(),
+        );
+        diff.field(
+            "Fixed frequency",
+            describe_option(old.fixed_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "off"),
+            describe_option(new.fixed_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "off"),
+        );
+        diff.field(
+            "Minimum frequency",
+            describe_option(old.min_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "hardware limit"),
+            describe_option(new.min_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "hardware limit"),
+        );
+        diff.field(
+            "Maximum frequency",
+            describe_option(old.max_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "hardware limit"),
+            describe_option(new.max_freq_mhz.map(|mhz| format!("{} MHz", mhz)), "hardware limit"),
+        );
+        diff.field("Boost", describe_switch(!old.disable_boost), describe_switch(!new.disable_boost));
+        diff.field("SMT", describe_switch(old.smt_enabled), describe_switch(new.smt_enabled));
+        diff.field(
+            "Energy preference",
+            describe_option(old.energy_performance_preference.as_deref(), "unchanged"),
+            describe_option(new.energy_performance_preference.as_deref(), "unchanged"),
+        );
+        diff.field(
+            "Preferred cores",
+            describe_option(old.amd_prefcore.map(describe_switch), "unchanged"),
+            describe_option(new.amd_prefcore.map(describe_switch), "unchanged"),
+        );
+        
+        // Screen
+        let (old, new) = (&other.screen_settings, &self.screen_settings);
+        diff.field("Screen brightness", old.brightness.to_string(), new.brightness.to_string());
+        diff.field(
+            "Automatic brightness",
+            describe_switch(old.auto_brightness),
+            describe_switch(new.auto_brightness),
+        );
+        diff.field(
+            "Color temperature",
+            describe_option(old.color_temp_kelvin.map(|k| format!("{}K", k)), "neutral"),
+            describe_option(new.color_temp_kelvin.map(|k| format!("{}K", k)), "neutral"),
+        );
+        diff.field(
+            "Brightness on all displays",
+            describe_option(old.apply_to_all_displays.map(describe_switch), "app setting"),
+            describe_option(new.apply_to_all_displays.map(describe_switch), "app setting"),
+        );
+        
+        diff.field(
+            "PCIe power policy",
+            describe_option(other.aspm_policy.as_deref(), "unchanged"),
+            describe_option(self.aspm_policy.as_deref(), "unchanged"),
+        );
+        diff.field(
+            "GPU power cap",
+            describe_option(other.gpu_power_cap_watts.map(|watts| format!("{} W", watts)), "unchanged"),
+            describe_option(self.gpu_power_cap_watts.map(|watts| format!("{} W", watts)), "unchanged"),
+        );
+        
+        diff.0
+    }
+    
+    pub fn validate(&self) -> Result<()> {
+        // Validate fan curves
+        for (fan_id, curve) in &self.fan_curves {
+            curve.validate_points(self.fan_control_mode.point_count())
+                .context(format!("Invalid fan curve for {}", fan_id))?;
+        }
+        
+        for trigger in &self.trigger_apps {
+            trigger.validate()?;
+        }
+        
+        // Validate brightness values
+        if self.keyboard_backlight.brightness > 100
+            || self.keyboard_backlight.battery_brightness.is_some_and(|b| b > 100)
+        {
+            anyhow::bail!("Keyboard brightness must be 0-100");
+        }
+        if let Some(KeyboardEffect::TempReactive { min_temp, max_temp, .. }) =
+            &self.keyboard_backlight.effect
+        {
+            if !(min_temp.is_finite() && max_temp.is_finite() && min_temp < max_temp) {
+                anyhow::bail!("Keyboard effect needs a lower minimum than maximum temperature");
+            }
+        }
+        if self.screen_settings.brightness > 100 {
+            anyhow::bail!("Screen brightness must be 0-100");
+        }
+        if let Some(kelvin) = self.screen_settings.color_temp_kelvin {
+            if !COLOR_TEMP_RANGE.contains(&kelvin) {
+                anyhow::bail!(
+                    "Color temperature must be {}-{}K",
+                    COLOR_TEMP_RANGE.start(),
+                    COLOR_TEMP_RANGE.end()
+                );
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Advisory estimate how loud the fans get, see [`NoiseLevel::from_curves`]
+    pub fn noise_hint(&self) -> NoiseLevel {
+        NoiseLevel::from_curves(self.fan_curves.values())
+    }
+
+    /// Advisory estimate of the power draw from the governor profile, boost,
+    /// frequency limit and energy performance preference
+    pub fn power_hint(&self) -> PowerLevel {
+        let cpu = &self.cpu_settings;
+        let mut score: i32 = match cpu.performance_profile {
+            CpuPerformanceProfile::PowerSave => -1,
+            CpuPerformanceProfile::Balanced => 0,
+            CpuPerformanceProfile::Performance => 1,
+        };
+        if cpu.disable_boost {
+            score -= 1;
+        }
+        if cpu.max_freq_mhz.is_some() {
+            score -= 1;
+        }
+        match cpu.energy_performance_preference.as_deref() {
+            Some("performance") => score += 1,
+            Some("balance_power" | "power") => score -= 1,
+            _ => {}
+        }
+
+        match score {
+            ..=-1 => PowerLevel::Saving,
+            0 => PowerLevel::Balanced,
+            _ => PowerLevel::Performance,
+        }
+    }
+}
+
+pub struct ProfileManager {
+    profiles: Vec<Profile>,
+    active_profile_index: usize,
+    config_dir: PathBuf,
+    /// Modification time of the profiles file as of our last load or save
+    synced_mtime: Option<SystemTime>,
+    /// When each profile was last applied, by name. Stored in its own file
+    /// so applying a profile doesn't rewrite (and reload) the profiles.
+    last_applied: HashMap<String, DateTime<Utc>>,
+    /// Set once the config dir turned out to be read-only, changes are
+    /// only kept in memory from then on
+    read_only: bool,
+    /// Told about every failed write, e.g. to show it in the UI. Writes
+    /// after applying a profile have no caller that could show them.
+    on_save_error: Option<SaveErrorHandler>,
+}
+
+type SaveErrorHandler = Box<dyn Fn(&str) + Send>;
+
+/// Shown when profiles can't be saved, e.g. in a config dir managed by an admin
+pub const READ_ONLY_WARNING: &str = "Profiles are read-only, changes won't persist";
+
+/// How long ago a profile was applied, e.g. "Applied 5 minutes ago"
+pub fn format_last_applied(applied: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(applied);
+    let plural = |count: i64, unit: &str| {
+        format!("Applied {} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+    };
+    
+    // Timestamps from the future (clock changes) count as just now
+    if elapsed.num_minutes() < 1 {
+        "Applied just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        plural(elapsed.num_minutes(), "minute")
+    } else if elapsed.num_days() < 1 {
+        plural(elapsed.num_hours(), "hour")
+    } else {
+        plural(elapsed.num_days(), "day")
+    }
+}
+
+impl ProfileManager {
+    pub fn new() -> HardwareResult<Self> {
+        Self::with_config_dir(Self::get_config_dir()?)
+    }
+    
+    /// Manager for the profiles stored in `config_dir`
+    pub fn with_config_dir(config_dir: PathBuf) -> HardwareResult<Self> {
+        // A missing dir that can't be created is handled like a read-only one
+        if let Err(e) = fs::create_dir_all(&config_dir) {
+            eprintln!("Warning: Failed to create {}: {}", config_dir.display(), e);
+        }
+        
+        let mut manager = ProfileManager {
+            profiles: Vec::new(),
+            active_profile_index: 0,
+            config_dir,
+            synced_mtime: None,
+            last_applied: HashMap::new(),
+            read_only: false,
+            on_save_error: None,
+        };
+        manager.read_only = !manager.is_writable();
+        if manager.read_only {
+            eprintln!("Warning: {}", READ_ONLY_WARNING);
+        }
+        
+        manager.load_profiles()?;
+        manager.last_applied = manager.load_last_applied().unwrap_or_else(|e| {
+            eprintln!("Warning: Forgetting when profiles were applied: {:#}", e);
+            HashMap::new()
+        });
+        
+        // Ensure at least one profile exists. It's saved again with the
+        // next change, a full disk shouldn't keep the app from starting.
+        if manager.profiles.is_empty() {
+            manager.profiles.push(Profile::default_profile());
+            if let Err(e) = manager.save_profiles() {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+        
+        Ok(manager)
     }
     
-    fn get_config_dir() -> Result<PathBuf> {
+    pub fn get_config_dir() -> HardwareResult<PathBuf> {
         let home = std::env::var("HOME")
             .context("HOME environment variable not set")?;
         Ok(PathBuf::from(home).join(".config/tuxedo-control"))
     }
     
-    fn profiles_file(&self) -> PathBuf {
+    pub fn profiles_file(&self) -> PathBuf {
         self.config_dir.join("profiles.json")
     }
     
-    pub fn load_profiles(&mut self) -> Result<()> {
+    fn last_applied_file(&self) -> PathBuf {
+        self.config_dir.join("last_applied.json")
+    }
+    
+    fn load_last_applied(&self) -> Result<HashMap<String, DateTime<Utc>>> {
+        let path = self.last_applied_file();
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse apply times")
+    }
+    
+    fn save_last_applied(&mut self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.last_applied)
+            .context("Failed to serialize apply times")?;
+        self.write_file(&self.last_applied_file(), &content)
+    }
+    
+    /// Whether changes can be saved. Opens the profiles file for appending,
+    /// or creates and removes a probe file in the config dir before the
+    /// first save. False once a write was denied.
+    pub fn is_writable(&self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let profiles_file = self.profiles_file();
+        if profiles_file.exists() {
+            return fs::OpenOptions::new().append(true).open(&profiles_file).is_ok();
+        }
+        let probe = self.config_dir.join(".write_test");
+        let writable = fs::write(&probe, "").is_ok();
+        let _ = fs::remove_file(&probe);
+        writable
+    }
+    
+    /// Call `handler` with the message of every failed write, see
+    /// [`Self::write_file`]
+    pub fn set_save_error_handler(&mut self, handler: impl Fn(&str) + Send + 'static) {
+        self.on_save_error = Some(Box::new(handler));
+    }
+    
+    /// Write a file of the config dir. A denied write switches to in-memory
+    /// mode, so edits keep working for the session and only that write
+    /// fails. Other failures, e.g. a full disk, fail every write.
+    fn write_file(&mut self, path: &Path, content: &str) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let Err(e) = fs::write(path, content) else {
+            return Ok(());
+        };
+        
+        let denied = matches!(
+            e.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        );
+        let message = if denied {
+            self.read_only = true;
+            format!("{} ({})", READ_ONLY_WARNING, path.display())
+        } else {
+            format!("Failed to save {}", path.display())
+        };
+        let err = anyhow::Error::new(e).context(message);
+        eprintln!("Warning: {:#}", err);
+        if let Some(handler) = &self.on_save_error {
+            handler(&format!("{:#}", err));
+        }
+        Err(err)
+    }
+    
+    /// Remember that the profile `name` was applied now
+    pub fn mark_applied(&mut self, name: &str) -> HardwareResult<()> {
+        self.last_applied.insert(name.to_string(), Utc::now());
+        Ok(self.save_last_applied()?)
+    }
+    
+    /// When the profile `name` was last applied, `None` if never
+    pub fn last_applied(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.last_applied.get(name).copied()
+    }
+    
+    fn profiles_mtime(&self) -> Option<SystemTime> {
+        fs::metadata(self.profiles_file()).and_then(|m| m.modified()).ok()
+    }
+    
+    /// Load the profiles file. On errors the previously loaded profiles
+    /// are kept.
+    pub fn load_profiles(&mut self) -> HardwareResult<()> {
         let profiles_file = self.profiles_file();
         
         if !profiles_file.exists() {
             return Ok(());
         }
         
+        let mtime = self.profiles_mtime();
         let content = fs::read_to_string(&profiles_file)
             .context("Failed to read profiles file")?;
         
-        self.profiles = serde_json::from_str(&content)
+        let mut profiles: Vec<Profile> = serde_json::from_str(&content)
             .context("Failed to parse profiles")?;
         
-        // Validate all profiles
-        for profile in &self.profiles {
+        // Validate all profiles, repairing broken fan curves instead of
+        // discarding the whole file over a single bad hand-edit
+        for profile in &mut profiles {
+            if profile.validate().is_err() {
+                Self::repair_fan_curves(profile);
+            }
             profile.validate()
                 .context(format!("Invalid profile: {}", profile.name))?;
         }
+        Self::rename_duplicates(&mut profiles);
         
+        // Stay on the same profile if it still exists after a reload
+        let active_name = self.profiles.get(self.active_profile_index).map(|p| p.name.clone());
+        self.active_profile_index = active_name
+            .and_then(|name| profiles.iter().position(|p| p.name == name))
+            .unwrap_or(0);
+        
+        self.profiles = profiles;
+        self.synced_mtime = mtime;
         Ok(())
     }
     
-    pub fn save_profiles(&self) -> Result<()> {
+    /// Reload the profiles if the file was changed by someone else, e.g. the
+    /// CLI. Returns whether the profiles were reloaded.
+    pub fn reload_if_changed(&mut self) -> HardwareResult<bool> {
+        let mtime = self.profiles_mtime();
+        if mtime.is_none() || mtime == self.synced_mtime {
+            // Deleted, or the change was our own save
+            return Ok(false);
+        }
+        
+        self.load_profiles()?;
+        if self.profiles.is_empty() {
+            self.profiles.push(Profile::default_profile());
+            eprintln!("Warning: Profiles file is empty, using the default profile");
+        }
+        Ok(true)
+    }
+    
+    fn repair_fan_curves(profile: &mut Profile) {
+        let count = profile.fan_control_mode.point_count();
+        for (fan_id, curve) in &mut profile.fan_curves {
+            if curve.validate_points(count.clone()).is_ok() {
+                continue;
+            }
+            
+            for fix in curve.repair(count.clone()) {
+                eprintln!("Warning: Profile '{}', {}: {}", profile.name, fan_id, fix);
+            }
+        }
+    }
+    
+    /// Give later profiles sharing a name a numbered one ("Gaming (2)"), so
+    /// lookups by name can't pick the wrong profile. Only renamed in memory,
+    /// the next save writes the new names.
+    fn rename_duplicates(profiles: &mut [Profile]) {
+        let mut taken: HashSet<String> = HashSet::new();
+        for index in 0..profiles.len() {
+            if taken.insert(profiles[index].name.clone()) {
+                continue;
+            }
+            
+            // Skip numbers a later profile already uses
+            let name = &profiles[index].name;
+            let new_name = (2..)
+                .map(|n| format!("{} ({})", name, n))
+                .find(|candidate| {
+                    !taken.contains(candidate) && !profiles.iter().any(|p| &p.name == candidate)
+                })
+                .expect("a free name exists");
+            eprintln!("Warning: Duplicate profile name '{}', renamed to '{}'", name, new_name);
+            taken.insert(new_name.clone());
+            profiles[index].name = new_name;
+        }
+    }
+    
+    pub fn save_profiles(&mut self) -> HardwareResult<()> {
         let profiles_file = self.profiles_file();
         let content = serde_json::to_string_pretty(&self.profiles)
             .context("Failed to serialize profiles")?;
         
-        fs::write(&profiles_file, content)
-            .context("Failed to write profiles file")?;
+        self.write_file(&profiles_file, &content)?;
         
+        // Don't treat our own write as an external change
+        self.synced_mtime = self.profiles_mtime();
         Ok(())
     }
     
-    pub fn add_profile(&mut self, mut profile: Profile) -> Result<()> {
+    pub fn add_profile(&mut self, mut profile: Profile) -> HardwareResult<()> {
+        validate_profile_name(&profile.name).map_err(HardwareError::InvalidInput)?;
         profile.validate()
-            .context("Profile validation failed")?;
+            .map_err(|e| HardwareError::InvalidInput(e.context("Profile validation failed")))?;
         
         // Ensure unique name
         if self.profiles.iter().any(|p| p.name == profile.name) {
-            anyhow::bail!("Profile with name '{}' already exists", profile.name);
+            return Err(error::invalid_input(format!(
+                "Profile with name '{}' already exists",
+                profile.name
+            ))
+            .into());
         }
         
         self.profiles.push(profile);
@@ -241,29 +1792,40 @@ impl ProfileManager {
         Ok(())
     }
     
-    pub fn update_profile(&mut self, index: usize, profile: Profile) -> Result<()> {
+    pub fn update_profile(&mut self, index: usize, profile: Profile) -> HardwareResult<()> {
         if index >= self.profiles.len() {
-            anyhow::bail!("Profile index out of bounds");
+            return Err(error::invalid_input("Profile index out of bounds").into());
         }
         
+        self.check_unlocked(index)?;
+        validate_profile_name(&profile.name).map_err(HardwareError::InvalidInput)?;
         profile.validate()
-            .context("Profile validation failed")?;
+            .map_err(|e| HardwareError::InvalidInput(e.context("Profile validation failed")))?;
         
-        self.profiles[index] = profile;
+        let old_name = std::mem::replace(&mut self.profiles[index], profile).name;
         self.save_profiles()?;
+        
+        let new_name = &self.profiles[index].name;
+        if *new_name != old_name {
+            if let Some(applied) = self.last_applied.remove(&old_name) {
+                self.last_applied.insert(new_name.clone(), applied);
+                self.save_last_applied()?;
+            }
+        }
         Ok(())
     }
     
-    pub fn delete_profile(&mut self, index: usize) -> Result<()> {
+    pub fn delete_profile(&mut self, index: usize) -> HardwareResult<()> {
         if index >= self.profiles.len() {
-            anyhow::bail!("Profile index out of bounds");
+            return Err(error::invalid_input("Profile index out of bounds").into());
         }
         
         if self.profiles[index].is_default {
-            anyhow::bail!("Cannot delete default profile");
+            return Err(error::invalid_input("Cannot delete default profile").into());
         }
+        self.check_unlocked(index)?;
         
-        self.profiles.remove(index);
+        let removed = self.profiles.remove(index);
         
         // Adjust active profile index if needed
         if self.active_profile_index >= self.profiles.len() {
@@ -271,37 +1833,97 @@ impl ProfileManager {
         }
         
         self.save_profiles()?;
+        if self.last_applied.remove(&removed.name).is_some() {
+            self.save_last_applied()?;
+        }
         Ok(())
     }
     
-    pub fn set_active_profile(&mut self, index: usize) -> Result<()> {
-        if index >= self.profiles.len() {
-            anyhow::bail!("Profile index out of bounds");
+    /// Lock or unlock the profile at `index`, see [`Profile::locked`]
+    pub fn set_locked(&mut self, index: usize, locked: bool) -> HardwareResult<()> {
+        let profile = self
+            .profiles
+            .get_mut(index)
+            .ok_or_else(|| error::invalid_input("Profile index out of bounds"))?;
+        if profile.locked != locked {
+            profile.locked = locked;
+            self.save_profiles()?;
         }
-        
-        self.active_profile_index = index;
         Ok(())
     }
     
-    pub fn get_active_profile(&self) -> &Profile {
-        &self.profiles[self.active_profile_index]
-    }
+    fn check_unlocked(&self, index: usize) -> Result<()> {
+        let profile = &self.profiles[index];
+        if profile.locked {
+            return Err(error::invalid_input(format!(
+                "Profile '{}' is locked, unlock it first",
+                profile.name
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Remove all profiles except the default one. Like on first start, a
+    /// fresh default profile is created if none is marked as default.
+    pub fn reset_to_default(&mut self) -> HardwareResult<()> {
+        let default = self
+            .profiles
+            .iter()
+            .find(|p| p.is_default)
+            .cloned()
+            .unwrap_or_else(Profile::default_profile);
+        
+        self.last_applied.retain(|name, _| *name == default.name);
+        self.profiles = vec![default];
+        self.active_profile_index = 0;
+        self.save_profiles()?;
+        Ok(self.save_last_applied()?)
+    }
+    
+    pub fn set_active_profile(&mut self, index: usize) -> HardwareResult<()> {
+        if index >= self.profiles.len() {
+            return Err(error::invalid_input("Profile index out of bounds").into());
+        }
+        
+        self.active_profile_index = index;
+        Ok(())
+    }
+    
+    pub fn get_active_profile(&self) -> &Profile {
+        &self.profiles[self.active_profile_index]
+    }
+    
+    pub fn active_profile_index(&self) -> usize {
+        self.active_profile_index
+    }
+    
+    /// Index of the profile after the active one, wrapping around
+    pub fn next_profile_index(&self) -> usize {
+        (self.active_profile_index + 1) % self.profiles.len()
+    }
+    
+    /// Index of the profile marked as default, or the first one
+    pub fn default_profile_index(&self) -> usize {
+        self.profiles.iter().position(|p| p.is_default).unwrap_or(0)
+    }
     
     pub fn get_profiles(&self) -> &[Profile] {
         &self.profiles
     }
     
-    pub fn find_profile_for_app(&self, app_name: &str) -> Option<usize> {
+    /// First profile one of the running `app_names` triggers, with the
+    /// name of the app that triggered it
+    pub fn find_profile_for_apps<'a>(&self, app_names: &'a [String]) -> Option<(usize, &'a str)> {
         self.profiles
             .iter()
             .enumerate()
-            .find(|(_, profile)| {
-                profile.auto_switch_enabled && 
-                profile.trigger_apps.iter().any(|trigger| {
-                    app_name.to_lowercase().contains(&trigger.to_lowercase())
-                })
+            .filter(|(_, profile)| profile.auto_switch_enabled)
+            .find_map(|(index, profile)| {
+                app_names
+                    .iter()
+                    .find(|app| profile.trigger_apps.iter().any(|trigger| trigger.matches(app)))
+                    .map(|app| (index, app.as_str()))
             })
-            .map(|(index, _)| index)
     }
 }
 
@@ -322,6 +1944,10 @@ mod tests {
                 FanCurvePoint { temp: 80, speed: 90 },
                 FanCurvePoint { temp: 85, speed: 100 },
             ],
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: false,
         };
         
         assert!(curve.validate().is_ok());
@@ -333,7 +1959,1042 @@ mod tests {
     
     #[test]
     fn test_profile_validation() {
-        let profile = Profile::default_profile();
+        let mut profile = Profile::default_profile();
+        assert!(profile.validate().is_ok());
+        
+        profile.screen_settings.color_temp_kelvin = Some(4000);
+        assert!(profile.validate().is_ok());
+        profile.screen_settings.color_temp_kelvin = Some(40);
+        assert!(profile.validate().is_err());
+    }
+    
+    #[test]
+    fn test_fan_curve_repair() {
+        let mut curve = FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 60, speed: 50 },
+                FanCurvePoint { temp: 40, speed: 30 },
+                FanCurvePoint { temp: 60, speed: 70 },
+                FanCurvePoint { temp: 80, speed: 120 },
+            ],
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: false,
+        };
+        assert!(curve.validate().is_err());
+        
+        let fixes = curve.repair(FanControlMode::Hardware.point_count());
+        assert!(!fixes.is_empty());
+        assert!(curve.validate().is_ok());
+        
+        assert_eq!(curve.points[0].temp, 40);
+        assert_eq!(curve.points[1].temp, 60);
+        assert_eq!(curve.points[1].speed, 70);
+        assert_eq!(curve.points[2].speed, 100);
+        assert_eq!(curve.points[7].temp, 105);
+        
+        // Valid curves are left untouched
+        assert!(curve.repair(FanControlMode::Hardware.point_count()).is_empty());
+    }
+    
+    #[test]
+    fn test_fan_curve_repair_trims_evenly() {
+        let mut curve = FanCurve {
+            points: (0..12)
+                .map(|i| FanCurvePoint { temp: 30 + i * 5, speed: i * 8 })
+                .collect(),
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: false,
+        };
+        
+        curve.repair(FanControlMode::Hardware.point_count());
+        assert!(curve.validate().is_ok());
+        assert_eq!(curve.points.first().unwrap().temp, 30);
+        assert_eq!(curve.points.last().unwrap().temp, 85);
+    }
+    
+    #[test]
+    fn test_fan_curve_point_count_modes() {
+        let mut curve = Profile::default_fan_curve();
+        curve.points.push(FanCurvePoint { temp: 95, speed: 100 });
+        
+        // Hardware control needs exactly 8 points
+        assert!(curve.validate().is_err());
+        assert!(curve.validate_points(FanControlMode::Hardware.point_count()).is_err());
+        
+        // Software control accepts 2-16 points
+        let software = FanControlMode::Software.point_count();
+        assert!(curve.validate_points(software.clone()).is_ok());
+        
+        curve.points.truncate(2);
+        assert!(curve.validate_points(software.clone()).is_ok());
+        
+        curve.points.truncate(1);
+        assert!(curve.validate_points(software.clone()).is_err());
+        
+        curve.points = (0..17)
+            .map(|i| FanCurvePoint { temp: 20 + i * 4, speed: i * 5 })
+            .collect();
+        assert!(curve.validate_points(software).is_err());
+    }
+    
+    #[test]
+    fn test_software_profile_validation() {
+        let mut profile = Profile::default_profile();
+        profile.fan_curves.get_mut("fan1").unwrap().points.truncate(4);
+        assert!(profile.validate().is_err());
+        
+        profile.fan_control_mode = FanControlMode::Software;
+        assert!(profile.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_calculate_fan_speed() {
+        let curve = Profile::default_fan_curve();
+        
+        assert_eq!(curve.calculate_fan_speed(20.0), 30);
+        assert_eq!(curve.calculate_fan_speed(45.0), 35);
+        assert_eq!(curve.calculate_fan_speed(65.0), 60);
+        assert_eq!(curve.calculate_fan_speed(67.5), 65);
+        assert_eq!(curve.calculate_fan_speed(99.0), 100);
+    }
+    
+    #[test]
+    fn test_calculate_fan_speed_step() {
+        let linear = Profile::default_fan_curve();
+        let mut step = linear.clone();
+        step.interpolation = Interpolation::Step;
+        
+        // (temperature, linear, step)
+        let expected = [
+            (20.0, 30, 30),
+            (40.0, 30, 30),
+            (45.0, 35, 30),
+            (49.9, 40, 30),
+            (50.0, 40, 40),
+            (67.5, 65, 60),
+            (84.0, 98, 90),
+            (85.0, 100, 100),
+            (99.0, 100, 100),
+        ];
+        for (temp, linear_speed, step_speed) in expected {
+            assert_eq!(linear.calculate_fan_speed(temp), linear_speed, "linear at {temp}°C");
+            assert_eq!(step.calculate_fan_speed(temp), step_speed, "step at {temp}°C");
+        }
+    }
+    
+    #[test]
+    fn test_calculate_fan_speed_spline() {
+        let linear = Profile::default_fan_curve();
+        let mut spline = linear.clone();
+        spline.interpolation = Interpolation::Spline;
+        
+        // Goes through every point and stays flat outside of them
+        for point in &linear.points {
+            assert_eq!(spline.calculate_fan_speed(point.temp as f32), point.speed);
+        }
+        assert_eq!(spline.calculate_fan_speed(0.0), linear.points[0].speed);
+        assert_eq!(spline.calculate_fan_speed(120.0), 100);
+        
+        // Monotone and within the neighboring points everywhere
+        let mut previous = 0.0;
+        for tenth in 0..=1000 {
+            let temp = tenth as f32 / 10.0;
+            let speed = spline.speed_at(temp);
+            assert!(speed >= previous, "dips at {temp}°C");
+            previous = speed;
+            
+            if let Some(index) = linear.points.windows(2).position(|w| temp <= w[1].temp as f32) {
+                let (lower, upper) = (&linear.points[index], &linear.points[index + 1]);
+                if temp >= lower.temp as f32 {
+                    let range = lower.speed as f32..=upper.speed as f32;
+                    assert!(range.contains(&speed), "overshoots at {temp}°C");
+                }
+            }
+        }
+        
+        // A plateau stays flat instead of bulging between its points
+        let plateau = FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 40, speed: 20 },
+                FanCurvePoint { temp: 50, speed: 50 },
+                FanCurvePoint { temp: 60, speed: 50 },
+                FanCurvePoint { temp: 70, speed: 100 },
+            ],
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Spline,
+            allow_fan_stop: true,
+        };
+        for temp in 50..=60 {
+            assert_eq!(plateau.speed_at(temp as f32), 50.0, "at {temp}°C");
+        }
+        // Eases into the plateau instead of the linear kinks (44% and 60%)
+        assert!(plateau.speed_at(48.0) > 44.0 && plateau.speed_at(48.0) < 50.0);
+        assert!(plateau.speed_at(62.0) > 50.0 && plateau.speed_at(62.0) < 60.0);
+        
+        // Two points are a straight line
+        let mut line = plateau.clone();
+        line.points.truncate(2);
+        assert_eq!(line.calculate_fan_speed(45.0), 35);
+    }
+    
+    #[test]
+    fn test_calculate_fan_speed_unordered() {
+        let point = |temp, speed| FanCurvePoint { temp, speed };
+        let mut curve = Profile::default_fan_curve();
+        // Duplicate 60°C and out of order
+        curve.points = vec![point(60, 50), point(40, 30), point(60, 70), point(80, 100)];
+        
+        for interpolation in [Interpolation::Linear, Interpolation::Step, Interpolation::Spline] {
+            curve.interpolation = interpolation;
+            for temp in 0..=110 {
+                let speed = curve.speed_at(temp as f32);
+                assert!((30.0..=100.0).contains(&speed), "{interpolation:?} at {temp}°C: {speed}");
+            }
+            // Equal temperatures take the higher speed
+            assert_eq!(curve.calculate_fan_speed(60.0), 70, "{interpolation:?}");
+            assert_eq!(curve.calculate_fan_speed(30.0), 30, "{interpolation:?}");
+        }
+        
+        curve.interpolation = Interpolation::Linear;
+        assert_eq!(curve.calculate_fan_speed(50.0), 50);
+        assert_eq!(curve.calculate_fan_speed(70.0), 85);
+        
+        // All at one temperature
+        curve.points = vec![point(60, 40), point(60, 80)];
+        assert_eq!(curve.calculate_fan_speed(59.0), 80);
+        assert_eq!(curve.calculate_fan_speed(61.0), 80);
+    }
+    
+    #[test]
+    fn test_fan_stop() {
+        let mut curve = FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 50, speed: 0 },
+                FanCurvePoint { temp: 60, speed: 10 },
+                FanCurvePoint { temp: 80, speed: 80 },
+            ],
+            ..Profile::default_fan_curve()
+        };
+        
+        // The fan keeps spinning unless it may stop
+        assert_eq!(curve.target_speed(40.0), MIN_SPINNING_SPEED);
+        assert_eq!(curve.target_speed(60.0), MIN_SPINNING_SPEED);
+        assert_eq!(curve.target_speed(70.0), 45);
+        
+        curve.allow_fan_stop = true;
+        assert_eq!(curve.target_speed(40.0), 0);
+        assert_eq!(curve.target_speed(60.0), 10);
+        assert_eq!(curve.target_speed(70.0), 45);
+        
+        // Older profiles keep the speeds they were saved with
+        let json = r#"{"points": [{"temp": 50, "speed": 0}, {"temp": 80, "speed": 80}]}"#;
+        let curve: FanCurve = serde_json::from_str(json).unwrap();
+        assert_eq!(curve.target_speed(40.0), 0);
+    }
+    
+    #[test]
+    fn test_cooling_warning() {
+        let default_curve = Profile::default_fan_curve();
+        assert_eq!(default_curve.estimate_min_cooling(), 70);
+        
+        let silent_curve = FanCurve {
+            points: (0..8)
+                .map(|i| FanCurvePoint { temp: 30 + i * 10, speed: if i < 7 { 0 } else { 20 } })
+                .collect(),
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: false,
+        };
+        assert_eq!(silent_curve.estimate_min_cooling(), 0);
+        
+        // Stepped curves hold the lower point's speed up to the next point
+        let ramp = |interpolation| FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 60, speed: 30 },
+                FanCurvePoint { temp: 80, speed: 70 },
+                FanCurvePoint { temp: 90, speed: 100 },
+            ],
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation,
+            allow_fan_stop: false,
+        };
+        assert_eq!(ramp(Interpolation::Linear).estimate_min_cooling(), 50);
+        assert_eq!(ramp(Interpolation::Step).estimate_min_cooling(), 30);
+        assert!(ramp(Interpolation::Linear).cooling_warning(95.0, Some(45.0)).is_none());
+        assert!(ramp(Interpolation::Step).cooling_warning(95.0, Some(45.0)).is_some());
+        
+        // Only warn while the CPU is busy
+        assert!(silent_curve.cooling_warning(20.0, Some(45.0)).is_none());
+        assert!(silent_curve.cooling_warning(95.0, Some(45.0)).is_some());
+        assert!(silent_curve.cooling_warning(95.0, None).is_some());
+        assert!(default_curve.cooling_warning(95.0, Some(45.0)).is_none());
+    }
+    
+    #[test]
+    fn test_unsafe_fan_curve() {
+        let curve = |points: &[(u8, u8)]| FanCurve {
+            points: points
+                .iter()
+                .map(|&(temp, speed)| FanCurvePoint { temp, speed })
+                .collect(),
+            firmware_below: None,
+            temp_source: TempSource::Auto,
+            interpolation: Interpolation::Linear,
+            allow_fan_stop: true,
+        };
+        let policy = FanSafetyPolicy::default();
+        
+        assert!(!Profile::default_fan_curve().is_unsafe(&policy));
+        // Silent while cool, but spinning up in time
+        assert!(!curve(&[(30, 0), (60, 0), (70, 40), (90, 100)]).is_unsafe(&policy));
+        // Every point at 0%, still a valid curve
+        let stopped = curve(&[(30, 0), (50, 0), (70, 0), (90, 0)]);
+        assert!(stopped.validate_points(FanControlMode::Software.point_count()).is_ok());
+        assert!(stopped.is_unsafe(&policy));
+        // Spinning up too late
+        assert!(curve(&[(30, 0), (80, 0), (90, 100)]).is_unsafe(&policy));
+        // Slowing down again at the top
+        assert!(curve(&[(30, 20), (80, 60), (100, 0)]).is_unsafe(&policy));
+        
+        // A stricter policy flags quiet curves as well
+        let strict = FanSafetyPolicy { danger_temp: 60, min_speed: 50 };
+        assert!(!Profile::default_fan_curve().is_unsafe(&strict));
+        assert!(curve(&[(30, 0), (60, 0), (70, 40), (90, 100)]).is_unsafe(&strict));
+    }
+    
+    #[test]
+    fn test_noise_and_power_hints() {
+        let mut profile = Profile::default_profile();
+        assert_eq!(profile.noise_hint(), NoiseLevel::Moderate);
+        assert_eq!(profile.power_hint(), PowerLevel::Balanced);
+
+        // Silent office profile
+        let quiet_curve = FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 60, speed: 0 },
+                FanCurvePoint { temp: 80, speed: 40 },
+                FanCurvePoint { temp: 95, speed: 100 },
+            ],
+            ..Profile::default_fan_curve()
+        };
+        profile.fan_curves.insert("fan1".to_string(), quiet_curve.clone());
+        profile.fan_curves.insert("fan2".to_string(), quiet_curve);
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::PowerSave;
+        profile.cpu_settings.disable_boost = true;
+        assert_eq!(profile.noise_hint(), NoiseLevel::Quiet);
+        assert_eq!(profile.power_hint(), PowerLevel::Saving);
+
+        // One aggressive fan makes the profile loud
+        let loud_curve = FanCurve {
+            points: vec![
+                FanCurvePoint { temp: 40, speed: 60 },
+                FanCurvePoint { temp: 70, speed: 100 },
+            ],
+            ..Profile::default_fan_curve()
+        };
+        profile.fan_curves.insert("fan2".to_string(), loud_curve);
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+        profile.cpu_settings.disable_boost = false;
+        profile.cpu_settings.energy_performance_preference = Some("performance".to_string());
+        assert_eq!(profile.noise_hint(), NoiseLevel::Loud);
+        assert_eq!(profile.power_hint(), PowerLevel::Performance);
+
+        // A frequency cap pulls a performance governor back to balanced
+        profile.cpu_settings.energy_performance_preference = None;
+        profile.cpu_settings.max_freq_mhz = Some(2000);
+        assert_eq!(profile.power_hint(), PowerLevel::Balanced);
+
+        // tailord profiles only have the firmware's performance profile
+        assert_eq!(PowerLevel::from_odm_profile("power_save"), Some(PowerLevel::Saving));
+        assert_eq!(PowerLevel::from_odm_profile("entertainment"), Some(PowerLevel::Balanced));
+        assert_eq!(PowerLevel::from_odm_profile("overboost"), Some(PowerLevel::Performance));
+        assert_eq!(PowerLevel::from_odm_profile("custom"), None);
+
+        // Without curves the firmware decides
+        profile.fan_curves.clear();
+        assert_eq!(profile.noise_hint(), NoiseLevel::Moderate);
+    }
+
+    #[test]
+    fn test_keyboard_mode_defaults_to_managed() {
+        let json = r#"{"color": {"r": 1, "g": 2, "b": 3}, "brightness": 40}"#;
+        let backlight: KeyboardBacklight = serde_json::from_str(json).unwrap();
+        assert_eq!(backlight.mode, KeyboardMode::Managed);
+        
+        let mut profile = Profile::default_profile();
+        profile.keyboard_backlight.mode = KeyboardMode::Unmanaged;
+        let json = serde_json::to_string(&profile).unwrap();
+        let profile: Profile = serde_json::from_str(&json).unwrap();
+        assert_eq!(profile.keyboard_backlight.mode, KeyboardMode::Unmanaged);
+    }
+    
+    #[test]
+    fn test_keyboard_temp_effect() {
+        let effect = KeyboardEffect::TempReactive {
+            cold: RGBColor { r: 0, g: 0, b: 255 },
+            hot: RGBColor { r: 255, g: 0, b: 0 },
+            min_temp: 40.0,
+            max_temp: 90.0,
+        };
+        assert_eq!(effect.color_at(40.0), RGBColor { r: 0, g: 0, b: 255 });
+        // Blue to red passes green
+        assert_eq!(effect.color_at(65.0), RGBColor { r: 0, g: 255, b: 0 });
+        assert_eq!(effect.color_at(90.0), RGBColor { r: 255, g: 0, b: 0 });
+        // Clamped to the range
+        assert_eq!(effect.color_at(20.0), effect.color_at(40.0));
+        assert_eq!(effect.color_at(105.0), effect.color_at(90.0));
+        assert_eq!(effect.quiet_color(), RGBColor { r: 0, g: 0, b: 51 });
+        
+        // White only loses saturation on the way to red
+        let from_white = KeyboardEffect::TempReactive {
+            cold: RGBColor { r: 255, g: 255, b: 255 },
+            hot: RGBColor { r: 255, g: 0, b: 0 },
+            min_temp: 40.0,
+            max_temp: 90.0,
+        };
+        assert_eq!(from_white.color_at(65.0), RGBColor { r: 255, g: 128, b: 128 });
+        
+        let mut profile = Profile::default_profile();
+        profile.keyboard_backlight.effect = Some(effect);
         assert!(profile.validate().is_ok());
+        let json = serde_json::to_string(&profile).unwrap();
+        let loaded: Profile = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.keyboard_backlight.effect, profile.keyboard_backlight.effect);
+        
+        profile.keyboard_backlight.effect = Some(KeyboardEffect::TempReactive {
+            cold: RGBColor { r: 0, g: 0, b: 255 },
+            hot: RGBColor { r: 255, g: 0, b: 0 },
+            min_temp: 90.0,
+            max_temp: 40.0,
+        });
+        assert!(profile.validate().is_err());
+    }
+    
+    #[test]
+    fn test_load_profiles_repairs_curves() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager {
+            profiles: Vec::new(),
+            active_profile_index: 0,
+            config_dir: temp_dir.path().to_path_buf(),
+            synced_mtime: None,
+            last_applied: HashMap::new(),
+            read_only: false,
+            on_save_error: None,
+        };
+        
+        let mut profile = Profile::default_profile();
+        let curve = profile.fan_curves.get_mut("fan1").unwrap();
+        curve.points.truncate(3);
+        curve.points.swap(0, 2);
+        fs::write(
+            manager.profiles_file(),
+            serde_json::to_string(&vec![profile]).unwrap(),
+        )
+        .unwrap();
+        
+        manager.load_profiles().unwrap();
+        assert_eq!(manager.get_profiles().len(), 1);
+        assert!(manager.get_profiles()[0].validate().is_ok());
+    }
+    
+    #[test]
+    fn test_load_profiles_renames_duplicates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        
+        let profile = |name: &str, brightness: u8| {
+            let mut profile = Profile::default_profile();
+            profile.name = name.to_string();
+            profile.keyboard_backlight.brightness = brightness;
+            profile
+        };
+        // "Gaming (2)" exists already, so the third "Gaming" becomes "Gaming (3)"
+        let profiles = vec![
+            profile("Gaming", 10),
+            profile("Office", 20),
+            profile("Gaming", 30),
+            profile("Gaming (2)", 40),
+            profile("Gaming", 50),
+        ];
+        fs::write(manager.profiles_file(), serde_json::to_string(&profiles).unwrap()).unwrap();
+        
+        manager.load_profiles().unwrap();
+        let loaded: Vec<(&str, u8)> = manager
+            .get_profiles()
+            .iter()
+            .map(|p| (p.name.as_str(), p.keyboard_backlight.brightness))
+            .collect();
+        assert_eq!(
+            loaded,
+            [("Gaming", 10), ("Office", 20), ("Gaming (3)", 30), ("Gaming (2)", 40), ("Gaming (4)", 50)]
+        );
+    }
+    
+    #[test]
+    fn test_reload_if_changed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager {
+            profiles: vec![Profile::default_profile()],
+            active_profile_index: 0,
+            config_dir: temp_dir.path().to_path_buf(),
+            synced_mtime: None,
+            last_applied: HashMap::new(),
+            read_only: false,
+            on_save_error: None,
+        };
+        
+        // Our own save doesn't trigger a reload
+        manager.save_profiles().unwrap();
+        assert!(!manager.reload_if_changed().unwrap());
+        
+        let profiles_file = manager.profiles_file();
+        let touch = |content: String, secs: u64| {
+            fs::write(&profiles_file, content).unwrap();
+            let file = fs::File::options().write(true).open(&profiles_file).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        
+        let mut quiet = Profile::default_profile();
+        quiet.name = "Quiet".to_string();
+        quiet.is_default = false;
+        touch(
+            serde_json::to_string(&vec![Profile::default_profile(), quiet]).unwrap(),
+            1000,
+        );
+        assert!(manager.reload_if_changed().unwrap());
+        assert_eq!(manager.get_profiles().len(), 2);
+        
+        // A broken edit keeps the last good state
+        touch("[{".to_string(), 2000);
+        assert!(manager.reload_if_changed().is_err());
+        assert_eq!(manager.get_profiles().len(), 2);
+    }
+    
+    #[test]
+    fn test_reset_to_default() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager {
+            profiles: Vec::new(),
+            active_profile_index: 0,
+            config_dir: temp_dir.path().to_path_buf(),
+            synced_mtime: None,
+            last_applied: HashMap::new(),
+            read_only: false,
+            on_save_error: None,
+        };
+        
+        let mut custom_default = Profile::default_profile();
+        custom_default.keyboard_backlight.brightness = 10;
+        let mut quiet = Profile::default_profile();
+        quiet.name = "Quiet".to_string();
+        quiet.is_default = false;
+        manager.profiles = vec![quiet.clone(), custom_default];
+        manager.active_profile_index = 1;
+        
+        // The user's default profile survives, other profiles are removed
+        manager.reset_to_default().unwrap();
+        assert_eq!(manager.get_profiles().len(), 1);
+        assert!(manager.get_active_profile().is_default);
+        assert_eq!(manager.get_active_profile().keyboard_backlight.brightness, 10);
+        
+        // Without a default profile a fresh one is created
+        manager.profiles = vec![quiet];
+        manager.reset_to_default().unwrap();
+        assert_eq!(manager.get_profiles().len(), 1);
+        assert!(manager.get_active_profile().is_default);
+        
+        manager.load_profiles().unwrap();
+        assert_eq!(manager.get_profiles().len(), 1);
+    }
+    
+    #[test]
+    fn test_locked_profile() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        let mut tuned = Profile::default_profile();
+        tuned.name = "Tuned".to_string();
+        tuned.is_default = false;
+        manager.add_profile(tuned.clone()).unwrap();
+        let index = manager.get_profiles().len() - 1;
+        manager.set_locked(index, true).unwrap();
+        
+        let mut edited = manager.get_profiles()[index].clone();
+        edited.keyboard_backlight.brightness = 5;
+        let err = manager.update_profile(index, edited.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "Profile 'Tuned' is locked, unlock it first");
+        assert!(manager.delete_profile(index).is_err());
+        
+        // Stays locked after a reload
+        manager.load_profiles().unwrap();
+        assert!(manager.get_profiles()[index].locked);
+        assert_eq!(manager.get_profiles()[index].keyboard_backlight.brightness, 50);
+        
+        manager.set_locked(index, false).unwrap();
+        edited.locked = false;
+        manager.update_profile(index, edited).unwrap();
+        manager.delete_profile(index).unwrap();
+        
+        // Files from before the lock read as unlocked
+        let mut json = serde_json::to_value(&tuned).unwrap();
+        json.as_object_mut().unwrap().remove("locked");
+        assert!(!serde_json::from_value::<Profile>(json).unwrap().locked);
+    }
+    
+    #[test]
+    fn test_profile_errors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        
+        let invalid = |err: HardwareError| matches!(err, HardwareError::InvalidInput(_));
+        assert!(invalid(manager.delete_profile(0).unwrap_err()));
+        assert!(invalid(manager.set_active_profile(5).unwrap_err()));
+        assert!(invalid(manager.add_profile(Profile::default_profile()).unwrap_err()));
+        let mut broken = Profile::default_profile();
+        broken.name = "Broken".to_string();
+        broken.keyboard_backlight.brightness = 150;
+        let err = manager.add_profile(broken).unwrap_err();
+        assert_eq!(format!("{err:#}"), "Profile validation failed: Keyboard brightness must be 0-100");
+        assert!(invalid(err));
+    }
+    
+    #[test]
+    fn test_read_only_config_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // Can't be created even by root, unlike a dir without write permission
+        let blocker = temp_dir.path().join("blocker");
+        fs::write(&blocker, "").unwrap();
+        
+        let mut manager = ProfileManager::with_config_dir(blocker.join("tuxedo-control")).unwrap();
+        assert!(!manager.is_writable());
+        assert_eq!(manager.get_profiles().len(), 1);
+        
+        // Edits work for the session
+        let mut quiet = Profile::default_profile();
+        quiet.name = "Quiet".to_string();
+        quiet.is_default = false;
+        manager.add_profile(quiet).unwrap();
+        manager.mark_applied("Quiet").unwrap();
+        assert_eq!(manager.get_profiles().len(), 2);
+        assert!(manager.last_applied("Quiet").is_some());
+        
+        let manager = ProfileManager::with_config_dir(temp_dir.path().join("writable")).unwrap();
+        assert!(manager.is_writable());
+        assert!(!temp_dir.path().join("writable/.write_test").exists());
+    }
+    
+    #[test]
+    fn test_full_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        let errors = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = std::sync::Arc::clone(&errors);
+        manager.set_save_error_handler(move |err| reported.lock().unwrap().push(err.to_string()));
+        
+        // Writes to /dev/full fail with ENOSPC
+        std::os::unix::fs::symlink("/dev/full", manager.last_applied_file()).unwrap();
+        let err = manager.mark_applied("Default").unwrap_err();
+        assert!(format!("{:#}", err).contains("last_applied.json"));
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        // Unlike a read-only dir, it isn't given up on
+        assert!(manager.is_writable());
+        assert!(manager.mark_applied("Default").is_err());
+        assert_eq!(errors.lock().unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_next_profile_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        // Only the default profile, cycling stays on it
+        assert_eq!(manager.next_profile_index(), 0);
+        
+        let mut quiet = Profile::default_profile();
+        quiet.name = "Quiet".to_string();
+        quiet.is_default = false;
+        manager.add_profile(quiet).unwrap();
+        assert_eq!(manager.next_profile_index(), 1);
+        manager.set_active_profile(1).unwrap();
+        assert_eq!(manager.next_profile_index(), 0);
+    }
+    
+    #[test]
+    fn test_last_applied() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        let mut quiet = Profile::default_profile();
+        quiet.name = "Quiet".to_string();
+        quiet.is_default = false;
+        manager.add_profile(quiet.clone()).unwrap();
+        
+        assert_eq!(manager.last_applied("Quiet"), None);
+        manager.mark_applied("Quiet").unwrap();
+        let applied = manager.last_applied("Quiet").unwrap();
+        
+        // Survives a restart and follows renames
+        let mut manager = ProfileManager::with_config_dir(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(manager.last_applied("Quiet"), Some(applied));
+        quiet.name = "Silent".to_string();
+        manager.update_profile(1, quiet).unwrap();
+        assert_eq!(manager.last_applied("Quiet"), None);
+        assert_eq!(manager.last_applied("Silent"), Some(applied));
+        
+        manager.delete_profile(1).unwrap();
+        assert_eq!(manager.last_applied("Silent"), None);
+    }
+    
+    #[test]
+    fn test_format_last_applied() {
+        let now = Utc::now();
+        let ago = |seconds| format_last_applied(now - chrono::Duration::seconds(seconds), now);
+        
+        assert_eq!(ago(20), "Applied just now");
+        assert_eq!(ago(-60), "Applied just now");
+        assert_eq!(ago(60), "Applied 1 minute ago");
+        assert_eq!(ago(5 * 60 + 30), "Applied 5 minutes ago");
+        assert_eq!(ago(2 * 3600), "Applied 2 hours ago");
+        assert_eq!(ago(3 * 86400), "Applied 3 days ago");
+    }
+    
+    fn changes(profile: &Profile, stored: &Profile) -> Vec<String> {
+        profile.diff(stored).iter().map(ToString::to_string).collect()
+    }
+    
+    #[test]
+    fn test_diff_unchanged() {
+        let profile = Profile::default_profile();
+        assert!(profile.diff(&profile.clone()).is_empty());
+    }
+    
+    #[test]
+    fn test_diff_general() {
+        let stored = Profile::default_profile();
+        let mut profile = stored.clone();
+        profile.name = "Gaming".to_string();
+        profile.auto_switch_enabled = true;
+        profile.trigger_apps = vec![
+            TriggerMatch::new("steam", MatchKind::Substring),
+            TriggerMatch::new("lutris", MatchKind::Substring),
+        ];
+        
+        assert_eq!(changes(&profile, &stored), [
+            "Name: Default → Gaming",
+            "Auto switching: off → on",
+            "Trigger apps:  → steam, lutris",
+        ]);
+    }
+    
+    #[test]
+    fn test_diff_keyboard() {
+        let stored = Profile::default_profile();
+        let mut profile = stored.clone();
+        profile.keyboard_backlight.brightness = 100;
+        profile.keyboard_backlight.color = RGBColor { r: 255, g: 0, b: 16 };
+        profile.keyboard_backlight.battery_brightness = Some(20);
+        profile.keyboard_backlight.hw_effect = Some("wave".to_string());
+        profile.keyboard_backlight.effect = Some(KeyboardEffect::TempReactive {
+            cold: RGBColor { r: 0, g: 0, b: 255 },
+            hot: RGBColor { r: 255, g: 0, b: 0 },
+            min_temp: 40.0,
+            max_temp: 90.0,
+        });
+        
+        assert_eq!(changes(&profile, &stored), [
+            "Keyboard color: #ffffff → #ff0010",
+            "Keyboard brightness: 50 → 100",
+            "Keyboard brightness on battery: same → 20",
+            "Keyboard effect: static → wave",
+            "Keyboard software effect: none → #0000ff at 40°C to #ff0000 at 90°C",
+        ]);
+    }
+    
+    #[test]
+    fn test_diff_fans() {
+        let stored = Profile::default_profile();
+        let mut profile = stored.clone();
+        profile.fan_control_mode = FanControlMode::Software;
+        profile.fan_curves.remove("fan2");
+        profile.fan_curves.get_mut("fan1").unwrap().points = vec![
+            FanCurvePoint { temp: 40, speed: 20 },
+            FanCurvePoint { temp: 90, speed: 100 },
+        ];
+        profile.fan_curves.get_mut("fan1").unwrap().interpolation = Interpolation::Step;
+        profile.fan_curves.get_mut("fan1").unwrap().allow_fan_stop = true;
+        
+        let diff = profile.diff(&stored);
+        let fields: Vec<&str> = diff.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, ["Fan control", "Fan curve fan1", "Fan curve fan2"]);
+        assert_eq!(diff[0].to_string(), "Fan control: Hardware → Software");
+        assert_eq!(diff[1].new, "40°C 20%, 90°C 100%, stepped, fan may stop");
+        assert_eq!(diff[2].new, "none");
+    }
+    
+    #[test]
+    fn test_diff_cpu() {
+        let stored = Profile::default_profile();
+        let mut profile = stored.clone();
+        profile.cpu_settings.performance_profile = CpuPerformanceProfile::Performance;
+        profile.cpu_settings.max_freq_mhz = Some(3000);
+        profile.cpu_settings.disable_boost = true;
+        profile.cpu_settings.energy_performance_preference = Some("performance".to_string());
+        
+        assert_eq!(changes(&profile, &stored), [
+            "Governor: balanced → performance",
+            "Maximum frequency: hardware limit → 3000 MHz",
+            "Boost: on → off",
+            "Energy preference: unchanged → performance",
+        ]);
+    }
+    
+    #[test]
+    fn test_diff_screen() {
+        let stored = Profile::default_profile();
+        let mut profile = stored.clone();
+        profile.screen_settings.brightness = 40;
+        profile.screen_settings.color_temp_kelvin = Some(4000);
+        profile.screen_settings.apply_to_all_displays = Some(false);
+        
+        assert_eq!(changes(&profile, &stored), [
+            "Screen brightness: 70 → 40",
+            "Color temperature: neutral → 4000K",
+            "Brightness on all displays: app setting → off",
+        ]);
+        // Old values are taken from the argument
+        assert_eq!(changes(&stored, &profile)[0], "Screen brightness: 40 → 70");
+    }
+    
+    #[test]
+    fn test_json_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&json_schema()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        
+        let mut profile = Profile::default_profile();
+        profile.fan_control_mode = FanControlMode::Software;
+        let curve = profile.fan_curves.get_mut("fan1").unwrap();
+        curve.interpolation = Interpolation::Step;
+        curve.temp_source = TempSource::Hwmon {
+            chip: "nvme".to_string(),
+            label: "Composite".to_string(),
+        };
+        let mut instance = serde_json::to_value(&profile).unwrap();
+        assert!(validator.is_valid(&instance));
+        
+        // Fields with a serde default may be left out
+        instance["fan_curves"]["fan1"].as_object_mut().unwrap().remove("interpolation");
+        assert!(validator.is_valid(&instance));
+        
+        instance["fan_curves"]["fan1"]["points"][0]["speed"] = 150.into();
+        assert!(!validator.is_valid(&instance));
+        instance["fan_curves"]["fan1"]["points"][0]["speed"] = 30.into();
+        instance["cpu_settings"]["performance_profile"] = "Turbo".into();
+        assert!(!validator.is_valid(&instance));
+        instance["cpu_settings"]["performance_profile"] = "Balanced".into();
+        
+        // Triggers are plain names from older versions or pattern objects
+        instance["trigger_apps"] = serde_json::json!(["steam", {"pattern": "^wine", "kind": "regex"}]);
+        assert!(validator.is_valid(&instance));
+        instance["trigger_apps"] = serde_json::json!([{"pattern": "steam", "kind": "glob"}]);
+        assert!(!validator.is_valid(&instance));
+    }
+    
+    #[test]
+    fn test_validate_profile_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+        let write = |value: &serde_json::Value| {
+            fs::write(&path, serde_json::to_string_pretty(value).unwrap()).unwrap();
+        };
+        
+        // A single profile and a whole profiles.json
+        let profile = serde_json::to_value(Profile::default_profile()).unwrap();
+        write(&profile);
+        assert!(validate_profile_file(&path).unwrap().is_empty());
+        write(&serde_json::json!([profile]));
+        assert!(validate_profile_file(&path).unwrap().is_empty());
+        
+        // Each broken profile gets its own message
+        let mut unsorted = profile.clone();
+        unsorted["name"] = "Unsorted".into();
+        unsorted["fan_curves"]["fan1"]["points"][2]["temp"] = 10.into();
+        let mut bright = profile.clone();
+        bright["name"] = "Bright".into();
+        bright["screen_settings"]["brightness"] = 150.into();
+        write(&serde_json::json!([profile, unsorted, bright, profile]));
+        assert_eq!(
+            validate_profile_file(&path).unwrap(),
+            [
+                "Profile 'Unsorted': Invalid fan curve for fan1: Fan curve temperatures must be \
+                 in ascending order, point 3 (10°C) isn't hotter than point 2 (50°C)",
+                "Profile 'Bright': Screen brightness must be 0-100",
+                "Profile 'Default': The name is used more than once",
+            ]
+        );
+        
+        // Not a profile at all
+        let mut untyped = profile.clone();
+        untyped["cpu_settings"]["performance_profile"] = "Turbo".into();
+        write(&untyped);
+        let err = format!("{:#}", validate_profile_file(&path).unwrap_err());
+        assert!(err.contains("isn't a valid profile file"), "{}", err);
+        assert!(err.contains("Turbo") && err.contains("line"), "{}", err);
+        fs::write(&path, "{ \"name\": ").unwrap();
+        assert!(validate_profile_file(&path).is_err());
+        assert!(validate_profile_file(&temp_dir.path().join("missing.json")).is_err());
+    }
+    
+    #[test]
+    fn test_run_validate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+        let run = |path: &Path| {
+            let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+            let code = run_validate(path, &mut stdout, &mut stderr);
+            (code, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+        };
+        
+        let mut profile = serde_json::to_value(Profile::default_profile()).unwrap();
+        fs::write(&path, profile.to_string()).unwrap();
+        let (code, stdout, stderr) = run(&path);
+        assert_eq!(code, 0);
+        assert!(stdout.ends_with("profiles.json is valid\n"), "{}", stdout);
+        assert!(stderr.is_empty());
+        
+        // Invalid profiles and files that aren't profiles fail alike
+        profile["screen_settings"]["brightness"] = 150.into();
+        fs::write(&path, profile.to_string()).unwrap();
+        let (code, stdout, stderr) = run(&path);
+        assert_eq!(code, 1);
+        assert!(stdout.is_empty());
+        assert_eq!(stderr, "Profile 'Default': Screen brightness must be 0-100\n");
+        
+        let (code, _, stderr) = run(&temp_dir.path().join("missing.json"));
+        assert_eq!(code, 1);
+        assert!(stderr.contains("Failed to read"), "{}", stderr);
+    }
+    
+    #[test]
+    fn test_trigger_match() {
+        let exact = TriggerMatch::new("Steam", MatchKind::Exact);
+        assert!(exact.matches("steam"));
+        assert!(exact.matches("/usr/bin/steam"));
+        assert!(!exact.matches("steamwebhelper"));
+        
+        let substring = TriggerMatch::new("Steam", MatchKind::Substring);
+        assert!(substring.matches("steamwebhelper"));
+        assert!(substring.matches("/usr/bin/steam"));
+        assert!(!substring.matches("lutris"));
+        // An empty trigger doesn't match every app
+        assert!(!TriggerMatch::new("", MatchKind::Substring).matches("lutris"));
+        
+        let regex = TriggerMatch::new(r"^(wine|proton)\b", MatchKind::Regex);
+        assert!(regex.validate().is_ok());
+        assert!(regex.matches("Proton Experimental"));
+        assert!(!regex.matches("steam"));
+        
+        // Broken regexes are rejected when saving and never match
+        let broken = TriggerMatch::new("steam(", MatchKind::Regex);
+        assert!(broken.validate().is_err());
+        assert!(!broken.matches("steam("));
+        let mut profile = Profile::default_profile();
+        profile.trigger_apps.push(broken);
+        assert!(profile.validate().is_err());
+    }
+    
+    #[test]
+    fn test_trigger_match_migration() {
+        let mut profile = serde_json::to_value(Profile::default_profile()).unwrap();
+        profile["trigger_apps"] = serde_json::json!(["steam", {"pattern": "lutris", "kind": "exact"}]);
+        let profile: Profile = serde_json::from_value(profile).unwrap();
+        assert_eq!(profile.trigger_apps, [
+            TriggerMatch::new("steam", MatchKind::Substring),
+            TriggerMatch::new("lutris", MatchKind::Exact),
+        ]);
+        
+        // Saved as objects from then on
+        let json = serde_json::to_value(&profile).unwrap();
+        assert_eq!(json["trigger_apps"][0], serde_json::json!({"pattern": "steam", "kind": "substring"}));
+    }
+    
+    #[test]
+    fn test_import_tcc() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/tcc_profiles.json");
+        let profiles = import_tcc(&path).unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["TUXEDO Defaults", "Quiet office", "Gaming"]);
+        for profile in &profiles {
+            assert!(profile.validate().is_ok(), "{}", profile.name);
+            assert!(!profile.is_default);
+            assert_eq!(profile.fan_control_mode, FanControlMode::Hardware);
+        }
+        
+        // Built-in fan profiles and unused brightness keep our defaults
+        let defaults = &profiles[0];
+        let default_profile = Profile::default_profile();
+        assert_eq!(
+            describe_curve(defaults.fan_curves.get("fan1")),
+            describe_curve(default_profile.fan_curves.get("fan1"))
+        );
+        assert_eq!(defaults.screen_settings.brightness, default_profile.screen_settings.brightness);
+        assert!(matches!(defaults.cpu_settings.performance_profile, CpuPerformanceProfile::Balanced));
+        assert_eq!(
+            defaults.cpu_settings.energy_performance_preference.as_deref(),
+            Some("balance_performance")
+        );
+        assert_eq!(defaults.cpu_settings.max_freq_mhz, Some(4700));
+        
+        let quiet = &profiles[1];
+        assert_eq!(quiet.screen_settings.brightness, 60);
+        assert!(matches!(quiet.cpu_settings.performance_profile, CpuPerformanceProfile::PowerSave));
+        assert_eq!(quiet.cpu_settings.min_freq_mhz, Some(400));
+        assert_eq!(quiet.cpu_settings.max_freq_mhz, Some(2200));
+        assert!(quiet.cpu_settings.disable_boost);
+        // 9 points from 20°C to 100°C resampled to 8, held within 20-80%
+        let cpu_curve: Vec<(u8, u8)> =
+            quiet.fan_curves["fan1"].points.iter().map(|p| (p.temp, p.speed)).collect();
+        assert_eq!(
+            cpu_curve,
+            [(20, 20), (31, 20), (43, 20), (54, 24), (66, 39), (77, 56), (89, 78), (100, 80)]
+        );
+        assert_eq!(quiet.fan_curves["fan2"].points.len(), FAN_CURVE_POINTS);
+        
+        let gaming = &profiles[2];
+        assert!(matches!(gaming.cpu_settings.performance_profile, CpuPerformanceProfile::Performance));
+        assert_eq!(gaming.cpu_settings.energy_performance_preference, None);
+        // The offset raises every point
+        let gaming_curve = &gaming.fan_curves["fan1"].points;
+        assert_eq!((gaming_curve[0].temp, gaming_curve[0].speed), (30, 30));
+        assert_eq!((gaming_curve[7].temp, gaming_curve[7].speed), (90, 100));
+    }
+    
+    #[test]
+    fn test_profile_names() {
+        assert!(validate_profile_name("Quiet").is_ok());
+        assert!(validate_profile_name("Café ☕ 夜").is_ok());
+        assert!(validate_profile_name("v1.2").is_ok());
+        assert!(validate_profile_name("work/home").is_err());
+        assert!(validate_profile_name("C:\\games").is_err());
+        assert!(validate_profile_name("..").is_err());
+        assert!(validate_profile_name("tab\there").is_err());
+        assert!(validate_profile_name("  ").is_err());
+        
+        let mut profile = Profile::default_profile();
+        for (name, filename) in [
+            ("work/home", "work_home"),
+            ("../secret", "_secret"),
+            ("Café ☕", "Café ☕"),
+            ("..", "profile"),
+            (".hidden.", "hidden"),
+        ] {
+            profile.name = name.to_string();
+            assert_eq!(profile.sanitized_filename(), filename);
+        }
     }
 }
@@ -1,15 +1,29 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use relm4::tokio::sync::OnceCell;
-use relm4::{Reducer, Reducible};
+use relm4::{Reducer, Reducible, SharedState};
 use tailor_api::{Color, ColorProfile, FanProfilePoint, LedDeviceInfo, ProfileInfo};
 use tailor_client::{ClientError, TailorConnection};
 
 use crate::app::FullProfileInfo;
+use crate::profile_controller::ProfileController;
+use crate::profile_system::ProfileManager;
 
 pub static STATE: Reducer<TailorState> = Reducer::new();
+/// Bumped when another tool changes the charge thresholds or the
+/// performance profile, views showing them read them again
+pub static POWER_SETTINGS_CHANGED: SharedState<u64> = SharedState::new();
+/// Bumped when the local profiles were reset or reloaded from disk, views
+/// listing them read them again
+pub static LOCAL_PROFILES_CHANGED: SharedState<u64> = SharedState::new();
 static CONNECTION: OnceCell<TailorConnection<'static>> = OnceCell::const_new();
 static HARDWARE_CAPABILITIES: OnceCell<HardwareCapabilities> = OnceCell::const_new();
+static PROFILE_CONTROLLER: once_cell::sync::OnceCell<ProfileController> =
+    once_cell::sync::OnceCell::new();
 
 pub fn tailor_connection() -> Option<&'static TailorConnection<'static>> {
     CONNECTION.get()
@@ -19,6 +33,14 @@ pub fn hardware_capabilities() -> Option<&'static HardwareCapabilities> {
     HARDWARE_CAPABILITIES.get()
 }
 
+/// Controller for the locally applied profiles, shared by the whole app.
+pub fn profile_controller() -> Option<&'static ProfileController> {
+    PROFILE_CONTROLLER
+        .get_or_try_init(ProfileController::new)
+        .map_err(|err| tracing::error!("Failed to initialize profile controller: {err:#}"))
+        .ok()
+}
+
 #[derive(Clone)]
 pub struct HardwareCapabilities {
     pub num_of_fans: u8,
@@ -65,6 +87,9 @@ pub struct TailorStateInner {
     pub profiles: Vec<FullProfileInfo>,
     pub led_profiles: Vec<String>,
     pub fan_profiles: Vec<String>,
+    /// When each tailord profile was last activated. Local profiles keep
+    /// their own apply times, see [`ProfileController::last_applied`].
+    pub last_applied: HashMap<String, DateTime<Utc>>,
     pub error: Option<String>,
 }
 
@@ -72,6 +97,8 @@ pub struct TailorStateInner {
 pub enum TailorStateMsg {
     Load(TailorStateInner),
     SetActiveProfile(String),
+    /// tailord activated the profile
+    ProfileApplied(String),
     AddProfile {
         name: String,
         profile: ProfileInfo,
@@ -138,14 +165,24 @@ impl Reducible for TailorState {
                         let name = name.clone();
                         let connection = state.connection.clone();
                         relm4::spawn(async move {
-                            handle_result(connection.set_active_global_profile_name(&name).await);
-                            handle_result(connection.reload().await);
+                            let applied =
+                                handle_result(connection.set_active_global_profile_name(&name).await)
+                                    .and(handle_result(connection.reload().await));
+                            if applied.is_some() {
+                                STATE.emit(TailorStateMsg::ProfileApplied(name));
+                            }
                         });
                     }
                     *state.get_mut_active_profile_name() = name;
                 }
                 return false;
             }
+            TailorStateMsg::ProfileApplied(name) => {
+                if let Some(state) = self.get_mut() {
+                    state.get_mut_last_applied().insert(name, Utc::now());
+                    save_last_applied(&state.last_applied);
+                }
+            }
             TailorStateMsg::AddProfile { name, profile } => {
                 if let Some(state) = self.get_mut() {
                     {
@@ -438,6 +475,7 @@ pub async fn initialize_tailor_state() -> Result<(), String> {
         profiles,
         led_profiles,
         fan_profiles,
+        last_applied: load_last_applied(),
         tracker: 0,
         error: None,
     };
@@ -449,6 +487,30 @@ pub async fn initialize_tailor_state() -> Result<(), String> {
     Ok(())
 }
 
+fn last_applied_file() -> Option<PathBuf> {
+    let dir = ProfileManager::get_config_dir().ok()?;
+    Some(dir.join("tailord_last_applied.json"))
+}
+
+fn load_last_applied() -> HashMap<String, DateTime<Utc>> {
+    last_applied_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_last_applied(last_applied: &HashMap<String, DateTime<Utc>>) {
+    let Some(path) = last_applied_file() else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(last_applied)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(fs::write(&path, content)?));
+    if let Err(err) = result {
+        tracing::warn!("Failed to save when the profiles were applied: {err:#}");
+    }
+}
+
 fn handle_result<T>(result: Result<T, ClientError>) -> Option<T> {
     match result {
         Ok(value) => Some(value),
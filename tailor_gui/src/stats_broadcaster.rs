@@ -0,0 +1,464 @@
+// src/stats_broadcaster.rs
+use crate::hardware_monitor::SystemStats;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// How often the broadcaster thread checks whether it should stop
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Load difference in percentage points that is worth redrawing a sparkline
+const REDRAW_THRESHOLD: f32 = 1.0;
+
+type Callback = Box<dyn Fn(&SystemStats) + Send>;
+type Subscribers = Mutex<HashMap<u64, Subscriber>>;
+
+struct Subscriber {
+    /// Whether the subscriber wants the next reading
+    wanted: Box<dyn Fn() -> bool + Send>,
+    /// Gets every reading, but never asks for one, see
+    /// [`StatsBroadcaster::observe`]
+    passive: bool,
+    on_stats: Callback,
+}
+
+/// Reads hardware stats once per interval and hands them to every
+/// subscriber, so views and watchers share one set of sysfs reads and
+/// show the same values. Nothing is read while no subscriber wants stats.
+///
+/// The thread stops when this handle is dropped, subscriptions outliving
+/// it simply get no more stats.
+pub struct StatsBroadcaster {
+    subscribers: Arc<Subscribers>,
+    next_id: AtomicU64,
+    running: Arc<AtomicBool>,
+}
+
+/// Subscription to a [`StatsBroadcaster`], unsubscribes when dropped
+pub struct StatsSubscription {
+    id: u64,
+    subscribers: Weak<Subscribers>,
+}
+
+impl StatsBroadcaster {
+    /// Call `read_stats` every `interval` and pass the result to all
+    /// subscribers
+    pub fn spawn<S>(interval: Duration, mut read_stats: S) -> Self
+    where
+        S: FnMut() -> anyhow::Result<SystemStats> + Send + 'static,
+    {
+        let subscribers: Arc<Subscribers> = Arc::new(Mutex::new(HashMap::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_subscribers = Arc::clone(&subscribers);
+        let thread_running = Arc::clone(&running);
+        let tick = TICK_INTERVAL.min(interval);
+
+        thread::spawn(move || {
+            let mut elapsed = interval;
+            // Whether the last interval was read. After a pause the first
+            // reading only restarts the deltas, e.g. the CPU load would
+            // otherwise be the average of the whole pause.
+            let mut primed = false;
+
+            while thread_running.load(Ordering::Relaxed) {
+                let wanted = || {
+                    let subscribers = thread_subscribers.lock().unwrap();
+                    subscribers
+                        .values()
+                        .any(|subscriber| !subscriber.passive && (subscriber.wanted)())
+                };
+                if elapsed >= interval && !wanted() {
+                    primed = false;
+                } else if elapsed >= interval && !primed {
+                    elapsed = Duration::ZERO;
+                    primed = true;
+                    if let Err(e) = read_stats() {
+                        eprintln!("Warning: Failed to read stats: {}", e);
+                    }
+                } else if elapsed >= interval {
+                    elapsed = Duration::ZERO;
+                    match read_stats() {
+                        Ok(stats) => {
+                            for subscriber in thread_subscribers.lock().unwrap().values() {
+                                if subscriber.passive || (subscriber.wanted)() {
+                                    (subscriber.on_stats)(&stats);
+                                }
+                            }
+                        }
+                        Err(e) => eprintln!("Warning: Failed to read stats: {}", e),
+                    }
+                }
+
+                thread::sleep(tick);
+                elapsed += tick;
+            }
+        });
+
+        StatsBroadcaster {
+            subscribers,
+            next_id: AtomicU64::new(0),
+            running,
+        }
+    }
+
+    /// Call `on_stats` on the broadcaster thread with every new reading.
+    /// It must not drop a subscription of the same broadcaster.
+    pub fn subscribe<F>(&self, on_stats: F) -> StatsSubscription
+    where
+        F: Fn(&SystemStats) + Send + 'static,
+    {
+        self.subscribe_while(|| true, on_stats)
+    }
+
+    /// Like [`Self::subscribe`], but only while `wanted` returns true. It's
+    /// asked on the broadcaster thread before every reading, readings
+    /// nobody wants are skipped. The same rules as for `on_stats` apply.
+    pub fn subscribe_while<W, F>(&self, wanted: W, on_stats: F) -> StatsSubscription
+    where
+        W: Fn() -> bool + Send + 'static,
+        F: Fn(&SystemStats) + Send + 'static,
+    {
+        self.insert(Subscriber {
+            wanted: Box::new(wanted),
+            passive: false,
+            on_stats: Box::new(on_stats),
+        })
+    }
+
+    /// Like [`Self::subscribe`], but without causing any reads: `on_stats`
+    /// only gets the readings other subscribers wanted
+    pub fn observe<F>(&self, on_stats: F) -> StatsSubscription
+    where
+        F: Fn(&SystemStats) + Send + 'static,
+    {
+        self.insert(Subscriber {
+            wanted: Box::new(|| false),
+            passive: true,
+            on_stats: Box::new(on_stats),
+        })
+    }
+
+    fn insert(&self, subscriber: Subscriber) -> StatsSubscription {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribers.lock().unwrap().insert(id, subscriber);
+        StatsSubscription {
+            id,
+            subscribers: Arc::downgrade(&self.subscribers),
+        }
+    }
+}
+
+impl Drop for StatsBroadcaster {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for StatsSubscription {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+/// Ring buffer of the recent total CPU loads, e.g. for a sparkline, and
+/// core frequencies
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    loads: VecDeque<f32>,
+    /// MHz of every core, one entry per reading
+    frequencies: VecDeque<Vec<u32>>,
+    capacity: usize,
+}
+
+impl StatsHistory {
+    pub fn new(capacity: usize) -> Self {
+        StatsHistory {
+            loads: VecDeque::with_capacity(capacity),
+            frequencies: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add the load of `stats`, dropping the oldest one when full. Returns
+    /// whether a sparkline of the loads changed visibly, a steady load
+    /// scrolling by doesn't.
+    pub fn push(&mut self, stats: &SystemStats) -> bool {
+        let previous: Vec<f32> = self.loads.iter().copied().collect();
+        if self.loads.len() == self.capacity {
+            self.loads.pop_front();
+            self.frequencies.pop_front();
+        }
+        self.loads.push_back(stats.cpu.mean_load_percent);
+        let frequencies = stats.cpu.cores.iter().map(|core| core.frequency_mhz);
+        self.frequencies.push_back(frequencies.collect());
+
+        // Values at the same index are drawn at the same x
+        self.loads.len() != previous.len()
+            || self
+                .loads
+                .iter()
+                .zip(previous.iter())
+                .any(|(new, old)| (new - old).abs() >= REDRAW_THRESHOLD)
+    }
+
+    /// Loads in percent, oldest first
+    pub fn loads(&self) -> Vec<f32> {
+        self.loads.iter().copied().collect()
+    }
+
+    /// Share of the core readings in each `bucket_mhz` wide frequency range,
+    /// from 0 up to the highest reading. Shows whether cores mostly sit
+    /// parked or boost. Empty without frequency readings.
+    pub fn frequency_histogram(&self, bucket_mhz: u32) -> Vec<(Range<u32>, f32)> {
+        // Cores without a readable frequency report 0
+        let readings: Vec<u32> = self
+            .frequencies
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|mhz| *mhz > 0)
+            .collect();
+        let Some(max) = readings.iter().max() else {
+            return Vec::new();
+        };
+        let bucket_mhz = bucket_mhz.max(1);
+
+        let mut counts = vec![0usize; (max / bucket_mhz) as usize + 1];
+        for mhz in &readings {
+            counts[(mhz / bucket_mhz) as usize] += 1;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let start = i as u32 * bucket_mhz;
+                (
+                    start..start + bucket_mhz,
+                    count as f32 / readings.len() as f32,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware_monitor::{CpuCoreInfo, CpuInfo, GpuType};
+    use std::sync::atomic::AtomicUsize;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    fn stats(cpu_temp: f32) -> SystemStats {
+        SystemStats {
+            cpu: CpuInfo::from_cores(Vec::new(), Some(cpu_temp), None),
+            gpus: Vec::new(),
+            fans: Vec::new(),
+            batteries: Vec::new(),
+            active_gpu: GpuType::Integrated,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Broadcaster counting its reads, every reading is one degree hotter
+    fn broadcaster() -> (StatsBroadcaster, Arc<AtomicUsize>) {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let thread_reads = Arc::clone(&reads);
+        let broadcaster = StatsBroadcaster::spawn(INTERVAL, move || {
+            let count = thread_reads.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(stats(count as f32))
+        });
+        (broadcaster, reads)
+    }
+
+    fn collect(broadcaster: &StatsBroadcaster) -> (StatsSubscription, Arc<Mutex<Vec<f32>>>) {
+        let temps = Arc::new(Mutex::new(Vec::new()));
+        let subscriber_temps = Arc::clone(&temps);
+        let subscription = broadcaster.subscribe(move |stats| {
+            subscriber_temps.lock().unwrap().push(stats.cpu.package_temp.unwrap());
+        });
+        (subscription, temps)
+    }
+
+    /// Wait until `done`, without relying on how fast a busy machine reads
+    fn wait_for(done: impl Fn() -> bool) {
+        for _ in 0..1000 {
+            if done() {
+                return;
+            }
+            thread::sleep(INTERVAL);
+        }
+        panic!("Timed out waiting for readings");
+    }
+
+    #[test]
+    fn test_shared_reads() {
+        let (broadcaster, reads) = broadcaster();
+
+        // Nobody listens, nothing is read
+        thread::sleep(INTERVAL * 5);
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        let (first, first_temps) = collect(&broadcaster);
+        let (second, second_temps) = collect(&broadcaster);
+        wait_for(|| second_temps.lock().unwrap().len() >= 3);
+        let second_count = second_temps.lock().unwrap().len();
+        drop(first);
+        let first_count = first_temps.lock().unwrap().len();
+        wait_for(|| second_temps.lock().unwrap().len() >= second_count + 3);
+        drop(second);
+
+        // Each reading went to both subscribers instead of being read twice
+        let first_temps = first_temps.lock().unwrap().clone();
+        let second_temps = second_temps.lock().unwrap().clone();
+        assert_eq!(first_temps.len(), first_count);
+        assert!(second_temps[..second_count]
+            .iter()
+            .all(|temp| first_temps.contains(temp)));
+        let mut all: Vec<f32> = first_temps.into_iter().chain(second_temps).collect();
+        all.sort_by(f32::total_cmp);
+        all.dedup();
+        // The first read only primed the deltas
+        assert_eq!(all, (2..all.len() + 2).map(|read| read as f32).collect::<Vec<_>>());
+        // A read racing the last unsubscribe reaches nobody
+        assert!(reads.load(Ordering::SeqCst) - 1 - all.len() <= 1);
+    }
+
+    #[test]
+    fn test_subscribe_while() {
+        let (broadcaster, reads) = broadcaster();
+        let wanted = Arc::new(AtomicBool::new(false));
+        let subscriber_wanted = Arc::clone(&wanted);
+        let temps = Arc::new(Mutex::new(Vec::new()));
+        let subscriber_temps = Arc::clone(&temps);
+        let _subscription = broadcaster.subscribe_while(
+            move || subscriber_wanted.load(Ordering::SeqCst),
+            move |stats| subscriber_temps.lock().unwrap().push(stats.cpu.package_temp.unwrap()),
+        );
+
+        // Not wanted, nothing is read
+        thread::sleep(INTERVAL * 5);
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        wanted.store(true, Ordering::SeqCst);
+        wait_for(|| temps.lock().unwrap().len() >= 2);
+        wanted.store(false, Ordering::SeqCst);
+        let count = reads.load(Ordering::SeqCst);
+        thread::sleep(INTERVAL * 5);
+        // Only a reading that was already decided on races the change
+        assert!(reads.load(Ordering::SeqCst) - count <= 1);
+        assert!(temps.lock().unwrap().len() <= count);
+    }
+
+    #[test]
+    fn test_observe() {
+        let (broadcaster, reads) = broadcaster();
+        let temps = Arc::new(Mutex::new(Vec::new()));
+        let observer_temps = Arc::clone(&temps);
+        let _observer = broadcaster.observe(move |stats| {
+            observer_temps.lock().unwrap().push(stats.cpu.package_temp.unwrap());
+        });
+
+        // Observing alone reads nothing
+        thread::sleep(INTERVAL * 5);
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        // It sees the readings of the others
+        let (subscription, subscriber_temps) = collect(&broadcaster);
+        wait_for(|| subscriber_temps.lock().unwrap().len() >= 2);
+        drop(subscription);
+        let subscriber_temps = subscriber_temps.lock().unwrap().clone();
+        assert!(subscriber_temps
+            .iter()
+            .all(|temp| temps.lock().unwrap().contains(temp)));
+    }
+
+    #[test]
+    fn test_stats_history() {
+        let mut history = StatsHistory::new(3);
+        let load = |percent: f32| {
+            let mut stats = stats(50.0);
+            stats.cpu.mean_load_percent = percent;
+            stats
+        };
+
+        // Filling up always changes the sparkline
+        assert!(history.push(&load(10.0)));
+        assert!(history.push(&load(10.0)));
+        assert!(history.push(&load(10.2)));
+        // Steady load scrolling by
+        assert!(!history.push(&load(10.5)));
+        assert_eq!(history.loads(), [10.0, 10.2, 10.5]);
+        assert!(history.push(&load(40.0)));
+        assert_eq!(history.loads(), [10.2, 10.5, 40.0]);
+    }
+
+    #[test]
+    fn test_frequency_histogram() {
+        let mut history = StatsHistory::new(3);
+        assert!(history.frequency_histogram(1000).is_empty());
+
+        let frequencies = |mhz: &[u32]| {
+            let cores = mhz
+                .iter()
+                .enumerate()
+                .map(|(core_id, mhz)| CpuCoreInfo {
+                    core_id,
+                    frequency_mhz: *mhz,
+                    load_percent: 0.0,
+                    temperature: None,
+                })
+                .collect();
+            let mut stats = stats(50.0);
+            stats.cpu = CpuInfo::from_cores(cores, None, None);
+            stats
+        };
+        // Dropped once the buffer is full
+        history.push(&frequencies(&[4800, 4800]));
+        history.push(&frequencies(&[400, 1200]));
+        history.push(&frequencies(&[800, 0]));
+        history.push(&frequencies(&[3000, 999]));
+
+        // 400, 1200, 800, 3000 and 999, the unreadable core is left out
+        assert_eq!(
+            history.frequency_histogram(1000),
+            [
+                (0..1000, 0.6),
+                (1000..2000, 0.2),
+                (2000..3000, 0.0),
+                (3000..4000, 0.2),
+            ]
+        );
+        let histogram = history.frequency_histogram(500);
+        assert_eq!(histogram.len(), 7);
+        assert_eq!(histogram[0], (0..500, 0.2));
+        assert_eq!(histogram[1], (500..1000, 0.4));
+        let total: f32 = histogram.iter().map(|(_, share)| share).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_drop_stops_thread() {
+        let (broadcaster, reads) = broadcaster();
+        let (_subscription, temps) = collect(&broadcaster);
+        thread::sleep(INTERVAL * 5);
+        drop(broadcaster);
+
+        // The thread owned the other handle of the read counter
+        for _ in 0..100 {
+            if Arc::strong_count(&reads) == 1 {
+                break;
+            }
+            thread::sleep(INTERVAL);
+        }
+        assert_eq!(Arc::strong_count(&reads), 1);
+        let received = temps.lock().unwrap().len();
+        thread::sleep(INTERVAL * 5);
+        assert_eq!(temps.lock().unwrap().len(), received);
+    }
+}
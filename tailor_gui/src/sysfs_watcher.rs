@@ -0,0 +1,191 @@
+// src/sysfs_watcher.rs
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often the watcher thread checks for events or polls the files
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Interval used when inotify is not available
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+type Watches = Vec<(WatchDescriptor, PathBuf)>;
+
+/// Watches sysfs files that other tools (or the kernel) may change and calls
+/// a callback with the changed path. Uses inotify where possible and falls
+/// back to comparing file contents periodically.
+///
+/// The watcher thread stops when this handle is dropped.
+pub struct SysfsWatcher {
+    running: Arc<AtomicBool>,
+}
+
+impl SysfsWatcher {
+    pub fn spawn<F>(paths: Vec<PathBuf>, on_change: F) -> Self
+    where
+        F: Fn(&Path) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = Arc::clone(&running);
+
+        thread::spawn(move || match Self::init_inotify(&paths) {
+            Some((inotify, watches)) => {
+                Self::inotify_loop(inotify, watches, &thread_running, &on_change)
+            }
+            None => Self::poll_loop(&paths, &thread_running, &on_change),
+        });
+
+        SysfsWatcher { running }
+    }
+
+    /// Same as [`Self::spawn`], but never uses inotify
+    pub fn spawn_polling<F>(paths: Vec<PathBuf>, on_change: F) -> Self
+    where
+        F: Fn(&Path) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = Arc::clone(&running);
+
+        thread::spawn(move || Self::poll_loop(&paths, &thread_running, &on_change));
+
+        SysfsWatcher { running }
+    }
+
+    fn init_inotify(paths: &[PathBuf]) -> Option<(Inotify, Watches)> {
+        let inotify = Inotify::init().ok()?;
+        let mut watches = Vec::new();
+
+        for path in paths {
+            match inotify
+                .watches()
+                .add(path, WatchMask::MODIFY | WatchMask::CLOSE_WRITE)
+            {
+                Ok(wd) => watches.push((wd, path.clone())),
+                Err(e) => {
+                    eprintln!("Warning: Cannot watch {}: {}", path.display(), e);
+                    return None;
+                }
+            }
+        }
+
+        Some((inotify, watches))
+    }
+
+    fn inotify_loop<F>(
+        mut inotify: Inotify,
+        watches: Watches,
+        running: &AtomicBool,
+        on_change: &F,
+    ) where
+        F: Fn(&Path),
+    {
+        let mut buffer = [0; 1024];
+
+        while running.load(Ordering::Relaxed) {
+            match inotify.read_events(&mut buffer) {
+                Ok(events) => {
+                    let mut changed: Vec<&PathBuf> = events
+                        .filter_map(|event| {
+                            watches
+                                .iter()
+                                .find(|(wd, _)| *wd == event.wd)
+                                .map(|(_, path)| path)
+                        })
+                        .collect();
+                    // A single write usually produces MODIFY and CLOSE_WRITE
+                    changed.dedup();
+
+                    for path in changed {
+                        on_change(path);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    eprintln!("Warning: inotify failed, falling back to polling: {}", e);
+                    let paths: Vec<PathBuf> = watches.into_iter().map(|(_, path)| path).collect();
+                    return Self::poll_loop(&paths, running, on_change);
+                }
+            }
+
+            thread::sleep(WATCH_INTERVAL);
+        }
+    }
+
+    fn poll_loop<F>(paths: &[PathBuf], running: &AtomicBool, on_change: &F)
+    where
+        F: Fn(&Path),
+    {
+        let read_all = || -> Vec<Option<String>> {
+            paths.iter().map(|path| fs::read_to_string(path).ok()).collect()
+        };
+
+        let mut last_values = read_all();
+        let mut elapsed = Duration::ZERO;
+
+        while running.load(Ordering::Relaxed) {
+            thread::sleep(WATCH_INTERVAL);
+            elapsed += WATCH_INTERVAL;
+            if elapsed < POLL_INTERVAL {
+                continue;
+            }
+            elapsed = Duration::ZERO;
+
+            let values = read_all();
+            for ((path, old), new) in paths.iter().zip(&last_values).zip(&values) {
+                if old != new {
+                    on_change(path);
+                }
+            }
+            last_values = values;
+        }
+    }
+}
+
+impl Drop for SysfsWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    type SpawnFn = fn(Vec<PathBuf>, Box<dyn Fn(&Path) + Send>) -> SysfsWatcher;
+
+    fn assert_change_reported(spawn: SpawnFn) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("charge_control_end_threshold");
+        fs::write(&file, "100").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = spawn(
+            vec![file.clone()],
+            Box::new(move |path: &Path| {
+                let _ = tx.send(path.to_path_buf());
+            }),
+        );
+
+        // Give the watcher time to take its initial snapshot
+        thread::sleep(Duration::from_millis(100));
+        fs::write(&file, "80").unwrap();
+
+        let changed = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changed, file);
+    }
+
+    #[test]
+    fn test_inotify_watcher() {
+        assert_change_reported(|paths, on_change| SysfsWatcher::spawn(paths, on_change));
+    }
+
+    #[test]
+    fn test_polling_watcher() {
+        assert_change_reported(|paths, on_change| SysfsWatcher::spawn_polling(paths, on_change));
+    }
+}
@@ -0,0 +1,156 @@
+// src/test_fixtures.rs
+//! Fake sysfs/procfs tree for tests of the hardware layer.
+//!
+//! The tree resembles a TUXEDO laptop with a 4 core CPU, an AMD GPU,
+//! one battery, the tuxedo_io fan interface, an RGB keyboard and DMI
+//! model/BIOS information.
+
+use crate::hardware_control::HardwareController;
+use crate::hardware_monitor::HardwareMonitor;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+pub const CPU_COUNT: usize = 4;
+
+pub struct SysfsFixture {
+    dir: TempDir,
+}
+
+impl SysfsFixture {
+    pub fn new() -> Self {
+        let fixture = SysfsFixture {
+            dir: TempDir::new().unwrap(),
+        };
+        fixture.add_cpus();
+        fixture.add_hwmon();
+        fixture.add_amd_gpu();
+        fixture.add_power_supply();
+        fixture.add_tuxedo_io();
+        fixture.add_keyboard();
+        fixture.add_dmi();
+        fixture
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Path of an absolute sysfs/procfs path inside the fixture
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root().join(path.trim_start_matches('/'))
+    }
+
+    /// Write a file, creating missing parent directories
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.path(path)).unwrap()
+    }
+
+    pub fn monitor(&self) -> HardwareMonitor {
+        HardwareMonitor::with_root(self.root()).unwrap()
+    }
+
+    pub fn controller(&self) -> HardwareController {
+        HardwareController::with_root(self.root()).unwrap()
+    }
+
+    fn add_cpus(&self) {
+        let mut cpuinfo = String::new();
+        let mut stat = String::from("cpu  400 0 200 4000 0 0 0 0 0 0\n");
+
+        for cpu in 0..CPU_COUNT {
+            cpuinfo.push_str(&format!("processor\t: {cpu}\nmodel name\t: Fixture CPU\n\n"));
+            stat.push_str(&format!("cpu{cpu} 100 0 50 1000 0 0 0 0 0 0\n"));
+
+            let cpufreq = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq");
+            self.write(&format!("{cpufreq}/scaling_cur_freq"), &format!("{}\n", 2_000_000 + cpu * 100_000));
+            self.write(&format!("{cpufreq}/scaling_driver"), "amd-pstate-epp\n");
+            self.write(&format!("{cpufreq}/scaling_governor"), "powersave\n");
+            self.write(&format!("{cpufreq}/cpuinfo_min_freq"), "400000\n");
+            self.write(&format!("{cpufreq}/cpuinfo_max_freq"), "4800000\n");
+            self.write(
+                &format!("{cpufreq}/energy_performance_available_preferences"),
+                "default performance balance_performance balance_power power \n",
+            );
+            self.write(&format!("{cpufreq}/energy_performance_preference"), "balance_performance\n");
+            if cpu > 0 {
+                self.write(&format!("/sys/devices/system/cpu/cpu{cpu}/online"), "1\n");
+            }
+        }
+
+        self.write("/sys/devices/system/cpu/amd_pstate/status", "active\n");
+        self.write("/sys/devices/system/cpu/amd_pstate/prefcore", "enabled\n");
+        self.write("/proc/cpuinfo", &cpuinfo);
+        self.write("/proc/stat", &stat);
+    }
+
+    fn add_hwmon(&self) {
+        self.write("/sys/class/hwmon/hwmon0/name", "k10temp\n");
+        self.write("/sys/class/hwmon/hwmon0/temp1_label", "Tctl\n");
+        self.write("/sys/class/hwmon/hwmon0/temp1_input", "62000\n");
+        self.write("/sys/class/hwmon/hwmon0/temp2_label", "Tdie\n");
+        self.write("/sys/class/hwmon/hwmon0/temp2_input", "61500\n");
+
+        self.write("/sys/class/hwmon/hwmon1/name", "acpitz\n");
+        self.write("/sys/class/hwmon/hwmon1/temp1_input", "45000\n");
+
+        self.write("/sys/class/hwmon/hwmon2/name", "tuxedo\n");
+        self.write("/sys/class/hwmon/hwmon2/fan1_label", "CPU fan\n");
+        self.write("/sys/class/hwmon/hwmon2/fan1_input", "2400\n");
+        self.write("/sys/class/hwmon/hwmon2/pwm1", "153\n");
+        self.write("/sys/class/hwmon/hwmon2/pwm1_enable", "2\n");
+    }
+
+    fn add_amd_gpu(&self) {
+        let device = "/sys/class/drm/card0/device";
+        self.write(&format!("{device}/vendor"), "0x1002\n");
+        self.write(&format!("{device}/uevent"), "DRIVER=amdgpu\nPCI_ID=1002:1681\n");
+        self.write(&format!("{device}/gpu_busy_percent"), "30\n");
+        self.write(&format!("{device}/pp_dpm_sclk"), "0: 200Mhz\n1: 1800Mhz *\n");
+        self.write(&format!("{device}/mem_info_vram_used"), "536870912\n");
+        self.write(&format!("{device}/mem_info_vram_total"), "2147483648\n");
+        self.write(&format!("{device}/hwmon/hwmon9/temp1_input"), "48000\n");
+        self.write(&format!("{device}/hwmon/hwmon9/power1_average"), "15000000\n");
+        // Connectors must not be mistaken for cards
+        self.write("/sys/class/drm/card0-eDP-1/status", "connected\n");
+    }
+
+    fn add_power_supply(&self) {
+        self.write("/sys/class/power_supply/BAT0/type", "Battery\n");
+        self.write("/sys/class/power_supply/BAT0/capacity", "80\n");
+        self.write("/sys/class/power_supply/BAT0/status", "Discharging\n");
+        self.write("/sys/class/power_supply/BAT0/energy_full", "73000000\n");
+        self.write("/sys/class/power_supply/BAT0/power_now", "12000000\n");
+        self.write("/sys/class/power_supply/ADP1/type", "Mains\n");
+        self.write("/sys/class/power_supply/ADP1/online", "0\n");
+    }
+
+    fn add_tuxedo_io(&self) {
+        for fan in 1..=2 {
+            for point in 0..8 {
+                self.write(&format!("/sys/devices/platform/tuxedo_io/fan{fan}_temp{point}"), "0\n");
+                self.write(&format!("/sys/devices/platform/tuxedo_io/fan{fan}_speed{point}"), "0\n");
+            }
+        }
+    }
+
+    fn add_dmi(&self) {
+        self.write("/sys/class/dmi/id/product_name", "InfinityBook Pro 14 Gen9\n");
+        self.write("/sys/class/dmi/id/sys_vendor", "TUXEDO\n");
+        self.write("/sys/class/dmi/id/bios_version", "N.1.07A09\n");
+        self.write("/sys/class/dmi/id/bios_date", "05/17/2024\n");
+    }
+
+    fn add_keyboard(&self) {
+        let kbd = "/sys/class/leds/rgb:kbd_backlight";
+        self.write(&format!("{kbd}/max_brightness"), "255\n");
+        self.write(&format!("{kbd}/brightness"), "0\n");
+        self.write(&format!("{kbd}/multi_intensity"), "255 255 255\n");
+    }
+}
@@ -0,0 +1,367 @@
+// src/thermal_history.rs
+use crate::hardware_monitor::SystemStats;
+use crate::profile_system::ProfileManager;
+use crate::stats_broadcaster::{StatsBroadcaster, StatsSubscription};
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Days kept in the history file, older ones are dropped. Over longer
+/// spans the seasons change the room temperature too much.
+pub const HISTORY_DAYS: usize = 35;
+
+/// Growth (°C) of the heat-up from idle to load that counts as worse cooling
+pub const DEFAULT_RISE_THRESHOLD: f32 = 5.0;
+
+/// Days that make up the old and the recent baseline
+const WINDOW_DAYS: usize = 5;
+
+/// History needs to cover this many days before it's judged
+const MIN_SPAN_DAYS: i64 = 14;
+
+/// Mean CPU load (percent) up to which a reading counts as idle
+const IDLE_LOAD_PERCENT: f32 = 10.0;
+
+/// Mean CPU load (percent) from which a reading counts as full load
+const FULL_LOAD_PERCENT: f32 = 80.0;
+
+/// How often a running session writes its readings to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Coolest CPU package temperature while idle and hottest one under full
+/// load of one day. Both follow the room temperature, the difference
+/// between them hardly does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DailyTemps {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub idle: Option<f32>,
+    #[serde(default)]
+    pub load: Option<f32>,
+}
+
+impl DailyTemps {
+    /// How much the CPU heats up under load, `None` unless the day had
+    /// both idle and full load readings
+    pub fn rise(&self) -> Option<f32> {
+        Some(self.load? - self.idle?)
+    }
+}
+
+/// Daily CPU temperature aggregates over the last months, to notice
+/// cooling that gets worse, e.g. dust or dried out thermal paste
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThermalHistory {
+    days: Vec<DailyTemps>,
+}
+
+/// The CPU heats up more under load than in the oldest days of the history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoolingWarning {
+    /// °C from idle to full load, see [`DailyTemps::rise`]
+    pub baseline_rise: f32,
+    pub recent_rise: f32,
+}
+
+impl fmt::Display for CoolingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Under load the CPU now gets {:.0}°C hotter than idle, {:.0}°C a few \
+             weeks ago. The fans may need cleaning or the thermal paste replacing.",
+            self.recent_rise, self.baseline_rise
+        )
+    }
+}
+
+impl ThermalHistory {
+    pub fn history_file() -> Result<PathBuf> {
+        Ok(ProfileManager::get_config_dir()?.join("thermal_history.json"))
+    }
+
+    /// Load the history, empty if the file doesn't exist yet
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse the thermal history")
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        let content =
+            serde_json::to_string(self).context("Failed to serialize the thermal history")?;
+        fs::write(path, content).context(format!("Failed to write {}", path.display()))
+    }
+
+    /// Days with readings, oldest first
+    pub fn days(&self) -> &[DailyTemps] {
+        &self.days
+    }
+
+    /// Add a reading of `temp` at a mean CPU load of `load_percent` on
+    /// `date` to the day's aggregate. Readings between idle and full load
+    /// are skipped.
+    pub fn record(&mut self, date: NaiveDate, temp: f32, load_percent: f32) {
+        let idle = load_percent <= IDLE_LOAD_PERCENT;
+        if !temp.is_finite() || !(idle || load_percent >= FULL_LOAD_PERCENT) {
+            return;
+        }
+        let index = match self.days.binary_search_by_key(&date, |day| day.date) {
+            Ok(index) => index,
+            Err(index) => {
+                self.days.insert(
+                    index,
+                    DailyTemps {
+                        date,
+                        idle: None,
+                        load: None,
+                    },
+                );
+                index
+            }
+        };
+        let day = &mut self.days[index];
+        if idle {
+            day.idle = Some(day.idle.map_or(temp, |idle| idle.min(temp)));
+        } else {
+            day.load = Some(day.load.map_or(temp, |load| load.max(temp)));
+        }
+
+        if self.days.len() > HISTORY_DAYS {
+            self.days.drain(..self.days.len() - HISTORY_DAYS);
+        }
+    }
+
+    /// Compare the median heat-up from idle to load of the oldest and the
+    /// latest days in the history. `None` while the cooling looks fine or
+    /// the history is too short to tell.
+    pub fn check_cooling_health(&self, rise_threshold: f32) -> Option<CoolingWarning> {
+        let days: Vec<(NaiveDate, f32)> = self
+            .days
+            .iter()
+            .filter_map(|day| Some((day.date, day.rise()?)))
+            .collect();
+        let (first, last) = (days.first()?, days.last()?);
+        let span = (last.0 - first.0).num_days();
+        if days.len() < 2 * WINDOW_DAYS || span < MIN_SPAN_DAYS {
+            return None;
+        }
+
+        let warning = CoolingWarning {
+            baseline_rise: median(days[..WINDOW_DAYS].iter().map(|day| day.1)),
+            recent_rise: median(days[days.len() - WINDOW_DAYS..].iter().map(|day| day.1)),
+        };
+        (warning.recent_rise - warning.baseline_rise >= rise_threshold).then_some(warning)
+    }
+}
+
+/// Median, a single hot or cold day doesn't move it much
+fn median(values: impl Iterator<Item = f32>) -> f32 {
+    let mut values: Vec<f32> = values.collect();
+    values.sort_by(f32::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// Adds the package temperature of every reading of a [`StatsBroadcaster`]
+/// to the history file. Saves hourly and when dropped.
+pub struct ThermalHistoryRecorder {
+    history: Arc<Mutex<ThermalHistory>>,
+    path: PathBuf,
+    paused: Arc<AtomicBool>,
+    _subscription: StatsSubscription,
+}
+
+impl ThermalHistoryRecorder {
+    /// Continue the history in `path` with the stats `broadcaster` reads
+    pub fn subscribe(broadcaster: &StatsBroadcaster, path: PathBuf) -> Self {
+        let history = ThermalHistory::load_from(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: Starting a new thermal history: {:#}", e);
+            ThermalHistory::default()
+        });
+        let history = Arc::new(Mutex::new(history));
+
+        let subscriber_history = Arc::clone(&history);
+        let subscriber_path = path.clone();
+        let last_saved = Mutex::new(Instant::now());
+        let paused = Arc::new(AtomicBool::new(false));
+        let subscriber_paused = Arc::clone(&paused);
+        let wanted = move || !subscriber_paused.load(Ordering::Relaxed);
+        let subscription = broadcaster.subscribe_while(wanted, move |stats: &SystemStats| {
+            let Some(temp) = stats.cpu.package_temp.or(stats.cpu.max_core_temp) else {
+                return;
+            };
+            let mut history = subscriber_history.lock().unwrap();
+            history.record(Local::now().date_naive(), temp, stats.cpu.mean_load_percent);
+
+            let mut last_saved = last_saved.lock().unwrap();
+            if last_saved.elapsed() >= SAVE_INTERVAL {
+                *last_saved = Instant::now();
+                if let Err(e) = history.save_to(&subscriber_path) {
+                    eprintln!("Warning: {:#}", e);
+                }
+            }
+        });
+
+        ThermalHistoryRecorder {
+            history,
+            path,
+            paused,
+            _subscription: subscription,
+        }
+    }
+
+    /// Stop recording until unpaused, no stats are read for it meanwhile
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn history(&self) -> ThermalHistory {
+        self.history.lock().unwrap().clone()
+    }
+}
+
+impl Drop for ThermalHistoryRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.history.lock().unwrap().save_to(&self.path) {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, 1).unwrap() + chrono::Duration::days(day)
+    }
+
+    /// One day per entry with an idle reading at 40°C and a full load
+    /// reading the given rise above it
+    fn history(rises: impl IntoIterator<Item = f32>) -> ThermalHistory {
+        let mut history = ThermalHistory::default();
+        for (day, rise) in rises.into_iter().enumerate() {
+            history.record(date(day as i64), 40.0 + rise, 100.0);
+            history.record(date(day as i64), 40.0, 0.0);
+        }
+        history
+    }
+
+    #[test]
+    fn test_record() {
+        let mut history = ThermalHistory::default();
+        history.record(date(1), 50.0, 5.0);
+        history.record(date(1), 42.0, 2.0);
+        history.record(date(1), f32::NAN, 0.0);
+        // Neither idle nor full load
+        history.record(date(1), 90.0, 50.0);
+        history.record(date(0), 45.0, 0.0);
+        history.record(date(1), 80.0, 95.0);
+        history.record(date(1), 75.0, 90.0);
+        assert_eq!(
+            history.days(),
+            [
+                DailyTemps {
+                    date: date(0),
+                    idle: Some(45.0),
+                    load: None,
+                },
+                DailyTemps {
+                    date: date(1),
+                    idle: Some(42.0),
+                    load: Some(80.0),
+                },
+            ]
+        );
+        assert_eq!(history.days()[0].rise(), None);
+        assert_eq!(history.days()[1].rise(), Some(38.0));
+
+        for day in 0..HISTORY_DAYS as i64 + 10 {
+            history.record(date(day), 40.0, 0.0);
+        }
+        assert_eq!(history.days().len(), HISTORY_DAYS);
+        assert_eq!(history.days()[0].date, date(10));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("thermal_history.json");
+        // First run
+        assert_eq!(
+            ThermalHistory::load_from(&path).unwrap(),
+            ThermalHistory::default()
+        );
+        history.save_to(&path).unwrap();
+        assert_eq!(ThermalHistory::load_from(&path).unwrap(), history);
+
+        // Days of older versions weren't load normalized and are ignored
+        fs::write(
+            &path,
+            r#"{"days":[{"date":"2026-01-01","min":40.0,"max":80.0}]}"#,
+        )
+        .unwrap();
+        let old = ThermalHistory::load_from(&path).unwrap();
+        assert_eq!(old.days()[0].rise(), None);
+    }
+
+    #[test]
+    fn test_cooling_health() {
+        // Steady, with daily noise
+        let steady = history((0..30).map(|day| 40.0 + (day % 3) as f32));
+        assert_eq!(steady.check_cooling_health(DEFAULT_RISE_THRESHOLD), None);
+
+        // A warmer room heats up idle and load alike
+        let mut summer = ThermalHistory::default();
+        for day in 0..30 {
+            let room = day as f32 * 0.5;
+            summer.record(date(day), 40.0 + room, 0.0);
+            summer.record(date(day), 80.0 + room, 100.0);
+        }
+        assert_eq!(summer.check_cooling_health(DEFAULT_RISE_THRESHOLD), None);
+
+        // Heating up 8°C more within a month
+        let rising = history((0..30).map(|day| 40.0 + day as f32 * 8.0 / 30.0));
+        let warning = rising.check_cooling_health(DEFAULT_RISE_THRESHOLD).unwrap();
+        assert!(warning.recent_rise - warning.baseline_rise >= DEFAULT_RISE_THRESHOLD);
+        assert!(warning
+            .to_string()
+            .starts_with("Under load the CPU now gets 47°C hotter than idle, 41°C"));
+        assert_eq!(rising.check_cooling_health(10.0), None);
+
+        // A single hot day is no trend
+        let mut spike: Vec<f32> = vec![40.0; 30];
+        spike[28] = 70.0;
+        assert_eq!(
+            history(spike).check_cooling_health(DEFAULT_RISE_THRESHOLD),
+            None
+        );
+
+        // Too little history to judge, even with a jump
+        assert_eq!(
+            ThermalHistory::default().check_cooling_health(DEFAULT_RISE_THRESHOLD),
+            None
+        );
+        let short = history((0..12).map(|day| if day < 6 { 40.0 } else { 60.0 }));
+        assert_eq!(short.check_cooling_health(DEFAULT_RISE_THRESHOLD), None);
+        // Days without a full load reading don't count
+        let mut idle_only = history((0..30).map(|day| if day < 15 { 40.0 } else { 60.0 }));
+        for day in 30..60 {
+            idle_only.record(date(day), 40.0, 0.0);
+        }
+        assert_eq!(idle_only.check_cooling_health(DEFAULT_RISE_THRESHOLD), None);
+    }
+}
@@ -0,0 +1,282 @@
+// src/threshold_monitor.rs
+use crate::hardware_monitor::SystemStats;
+use crate::stats_broadcaster::{StatsBroadcaster, StatsSubscription};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Metric {
+    CpuTemp,
+    CpuLoad,
+    GpuTemp,
+    BatteryPercent,
+}
+
+impl Metric {
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::CpuTemp => "CPU temperature",
+            Metric::CpuLoad => "CPU load",
+            Metric::GpuTemp => "GPU temperature",
+            Metric::BatteryPercent => "Battery",
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        match self {
+            Metric::CpuTemp | Metric::GpuTemp => "°C",
+            Metric::CpuLoad | Metric::BatteryPercent => "%",
+        }
+    }
+
+    /// Current value of the metric, `None` if the hardware doesn't report it
+    pub fn value(self, stats: &SystemStats) -> Option<f32> {
+        match self {
+            Metric::CpuTemp => stats.cpu.package_temp.or(stats.cpu.max_core_temp),
+            Metric::CpuLoad => Some(stats.cpu.mean_load_percent),
+            Metric::GpuTemp => stats
+                .gpus
+                .iter()
+                .filter_map(|gpu| gpu.temperature)
+                .reduce(f32::max),
+            Metric::BatteryPercent => stats.total_battery_percent(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparator {
+    Above,
+    Below,
+}
+
+impl Comparator {
+    fn matches(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparator::Above => value > threshold,
+            Comparator::Below => value < threshold,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparator::Above => ">",
+            Comparator::Below => "<",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdAction {
+    /// Only show a notification
+    Notify,
+    /// Show a notification and switch to the given profile
+    ApplyProfile(String),
+}
+
+/// A rule like "CPU temperature > 90" stored in the app config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdRule {
+    pub metric: Metric,
+    pub comparator: Comparator,
+    pub value: f32,
+    pub action: ThresholdAction,
+}
+
+impl ThresholdRule {
+    /// Human readable condition, e.g. "CPU temperature > 90°C"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} {}{}",
+            self.metric.label(),
+            self.comparator.symbol(),
+            self.value,
+            self.metric.unit()
+        )
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.value.is_finite() {
+            anyhow::bail!("Threshold for {} must be a number", self.metric.label());
+        }
+        if let ThresholdAction::ApplyProfile(name) = &self.action {
+            if name.is_empty() {
+                anyhow::bail!("Profile name of threshold rule must not be empty");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rule that started to match
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdEvent {
+    pub rule: ThresholdRule,
+    pub value: f32,
+}
+
+impl ThresholdEvent {
+    /// Notification text, e.g. "CPU temperature > 90°C (now 93°C)"
+    pub fn message(&self) -> String {
+        format!(
+            "{} (now {:.0}{})",
+            self.rule.describe(),
+            self.value,
+            self.rule.metric.unit()
+        )
+    }
+}
+
+/// Evaluates threshold rules against consecutive stats. A rule fires once
+/// when its condition starts to hold and is re-armed only after it stopped
+/// holding, so staying above a limit doesn't fire on every poll.
+pub struct ThresholdMonitor {
+    rules: Vec<ThresholdRule>,
+    active: Vec<bool>,
+}
+
+impl ThresholdMonitor {
+    pub fn new(rules: Vec<ThresholdRule>) -> Self {
+        let active = vec![false; rules.len()];
+        ThresholdMonitor { rules, active }
+    }
+
+    pub fn rules(&self) -> &[ThresholdRule] {
+        &self.rules
+    }
+
+    /// Evaluate all rules and return the ones that just crossed their threshold
+    pub fn evaluate(&mut self, stats: &SystemStats) -> Vec<ThresholdEvent> {
+        let mut events = Vec::new();
+
+        for (rule, active) in self.rules.iter().zip(self.active.iter_mut()) {
+            // Keep the state if the metric is unavailable for a moment
+            let Some(value) = rule.metric.value(stats) else {
+                continue;
+            };
+
+            let matches = rule.comparator.matches(value, rule.value);
+            if matches && !*active {
+                events.push(ThresholdEvent {
+                    rule: rule.clone(),
+                    value,
+                });
+            }
+            *active = matches;
+        }
+
+        events
+    }
+}
+
+/// Reports threshold crossings in the stats of a [`StatsBroadcaster`].
+/// Stops watching when this handle is dropped.
+pub struct ThresholdWatcher {
+    _subscription: StatsSubscription,
+}
+
+impl ThresholdWatcher {
+    /// Call `on_event` for each crossing in the stats `broadcaster` reads
+    pub fn subscribe<F>(
+        broadcaster: &StatsBroadcaster,
+        rules: Vec<ThresholdRule>,
+        on_event: F,
+    ) -> Self
+    where
+        F: Fn(ThresholdEvent) + Send + 'static,
+    {
+        let monitor = Mutex::new(ThresholdMonitor::new(rules));
+        let subscription = broadcaster.subscribe(move |stats| {
+            let events = monitor.lock().unwrap().evaluate(stats);
+            events.into_iter().for_each(&on_event);
+        });
+        ThresholdWatcher { _subscription: subscription }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware_monitor::{BatteryInfo, CpuInfo, GpuType};
+
+    fn stats(cpu_temp: Option<f32>, battery: Option<u8>) -> SystemStats {
+        SystemStats {
+            cpu: CpuInfo::from_cores(Vec::new(), cpu_temp, None),
+            gpus: Vec::new(),
+            fans: Vec::new(),
+            batteries: vec![BatteryInfo {
+                name: "BAT0".to_string(),
+                charge_percent: battery,
+                status: None,
+                energy_full_wh: None,
+                power_watts: None,
+            }],
+            active_gpu: GpuType::Integrated,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn rule(metric: Metric, comparator: Comparator, value: f32) -> ThresholdRule {
+        ThresholdRule {
+            metric,
+            comparator,
+            value,
+            action: ThresholdAction::Notify,
+        }
+    }
+
+    #[test]
+    fn test_fires_once_per_crossing() {
+        let mut monitor =
+            ThresholdMonitor::new(vec![rule(Metric::CpuTemp, Comparator::Above, 90.0)]);
+
+        let temps = [85.0, 91.0, 95.0, 92.0, 80.0, 93.0];
+        let fired: Vec<bool> = temps
+            .iter()
+            .map(|&temp| !monitor.evaluate(&stats(Some(temp), None)).is_empty())
+            .collect();
+
+        assert_eq!(fired, [false, true, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_missing_metric_keeps_state() {
+        let mut monitor =
+            ThresholdMonitor::new(vec![rule(Metric::BatteryPercent, Comparator::Below, 15.0)]);
+
+        let events = monitor.evaluate(&stats(None, Some(12)));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].value, 12.0);
+        assert_eq!(events[0].message(), "Battery < 15% (now 12%)");
+
+        // A failed read doesn't re-arm the rule
+        assert!(monitor.evaluate(&stats(None, None)).is_empty());
+        assert!(monitor.evaluate(&stats(None, Some(10))).is_empty());
+    }
+
+    #[test]
+    fn test_rules_are_independent() {
+        let mut monitor = ThresholdMonitor::new(vec![
+            rule(Metric::CpuTemp, Comparator::Above, 90.0),
+            rule(Metric::BatteryPercent, Comparator::Below, 15.0),
+        ]);
+
+        let events = monitor.evaluate(&stats(Some(95.0), Some(50)));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule.metric, Metric::CpuTemp);
+
+        let events = monitor.evaluate(&stats(Some(95.0), Some(10)));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule.metric, Metric::BatteryPercent);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(rule(Metric::CpuLoad, Comparator::Above, 90.0).validate().is_ok());
+        assert!(rule(Metric::CpuLoad, Comparator::Above, f32::NAN).validate().is_err());
+
+        let mut apply = rule(Metric::CpuTemp, Comparator::Above, 90.0);
+        apply.action = ThresholdAction::ApplyProfile(String::new());
+        assert!(apply.validate().is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Tray icon through the `org.kde.StatusNotifierItem` D-Bus protocol, shown
+//! by KDE Plasma, most other panels and GNOME with the AppIndicator
+//! extension. Clicking the icon presents the window.
+//!
+//! The icon is registered with the `org.kde.StatusNotifierWatcher`, which
+//! belongs to the panel. A restarted panel starts a new watcher that knows
+//! nothing about the icon, so it registers again whenever the watcher
+//! appears. Without a watcher there's no icon, see [`TrayManager::is_shown`].
+//!
+//! The tooltip shows temperatures and the active profile, see
+//! [`TrayManager::update_tooltip`].
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use gtk::gio;
+use gtk::glib::{self, ToVariant, Variant};
+use relm4::gtk;
+
+use crate::app_config::{AppConfig, TemperatureUnit};
+use crate::config::APP_ID;
+use crate::hardware_monitor::SystemStats;
+
+const WATCHER_BUS_NAME: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+
+/// Delay before registering again if the watcher is there but refuses the
+/// icon, e.g. while the panel is still starting. Doubles on every failure.
+const RETRY_DELAY_MIN: Duration = Duration::from_secs(1);
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(60);
+
+/// The parts of the interface Tailor implements. There's no menu, hosts
+/// fall back to calling `ContextMenu`, which does nothing.
+const ITEM_XML: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+    <signal name="NewToolTip"/>
+  </interface>
+</node>
+"#;
+
+/// Exported tray icon. Dropping it removes the icon.
+#[derive(Debug)]
+pub struct TrayManager {
+    connection: gio::DBusConnection,
+    registration: Option<gio::RegistrationId>,
+    watcher: Option<gio::WatcherId>,
+    state: Rc<TrayState>,
+    /// Read once like in the overlay, changing it needs a restart
+    unit: TemperatureUnit,
+}
+
+#[derive(Debug, Default)]
+struct TrayState {
+    /// Whether the current watcher accepted the icon. Shared, so stats
+    /// are only read for the tooltip while it's shown.
+    shown: Arc<AtomicBool>,
+    tooltip: RefCell<String>,
+    /// Bumped whenever the watcher appears or vanishes, so pending retries
+    /// for an earlier watcher give up
+    generation: Cell<u64>,
+}
+
+impl TrayState {
+    fn next_generation(&self) -> u64 {
+        self.shown.store(false, Ordering::Relaxed);
+        self.generation.set(self.generation.get() + 1);
+        self.generation.get()
+    }
+
+    fn property(&self, name: &str) -> Variant {
+        match name {
+            "Category" => "Hardware".to_variant(),
+            "Id" => "tailor".to_variant(),
+            "Title" => "Tailor".to_variant(),
+            "Status" => "Active".to_variant(),
+            "IconName" => APP_ID.to_variant(),
+            "ToolTip" => (
+                String::new(),
+                Vec::<(i32, i32, Vec<u8>)>::new(),
+                "Tailor".to_owned(),
+                self.tooltip.borrow().clone(),
+            )
+                .to_variant(),
+            // ItemIsMenu, the only other property of ITEM_XML
+            _ => false.to_variant(),
+        }
+    }
+}
+
+impl Drop for TrayManager {
+    fn drop(&mut self) {
+        if let Some(id) = self.watcher.take() {
+            gio::bus_unwatch_name(id);
+        }
+        if let Some(id) = self.registration.take() {
+            let _ = self.connection.unregister_object(id);
+        }
+        // Stops pending retries
+        self.state.next_generation();
+    }
+}
+
+impl TrayManager {
+    /// Export the icon and keep it registered with whichever panel is
+    /// running. `on_activate` runs on the main thread when it's clicked.
+    pub async fn show<F>(on_activate: F) -> Result<Self>
+    where
+        F: Fn() + 'static,
+    {
+        let connection = gio::bus_get_future(gio::BusType::Session)
+            .await
+            .context("Failed to connect to the session bus")?;
+
+        let node = gio::DBusNodeInfo::for_xml(ITEM_XML).context("Invalid tray interface")?;
+        let interface = node
+            .lookup_interface(ITEM_INTERFACE)
+            .context("Tray interface is missing")?;
+
+        let state = Rc::new(TrayState::default());
+        let registration = connection
+            .register_object(ITEM_PATH, &interface)
+            .method_call(move |_, _, _, _, method, _, invocation| {
+                if matches!(method, "Activate" | "SecondaryActivate") {
+                    on_activate();
+                }
+                invocation.return_value(None);
+            })
+            .property({
+                let state = state.clone();
+                move |_, _, _, _, property| state.property(property)
+            })
+            .build()
+            .context("Failed to export the tray icon")?;
+
+        let watcher = gio::bus_watch_name_on_connection(
+            &connection,
+            WATCHER_BUS_NAME,
+            gio::BusNameWatcherFlags::NONE,
+            {
+                let state = state.clone();
+                move |connection, _, _| {
+                    let generation = state.next_generation();
+                    register(connection, state.clone(), generation, RETRY_DELAY_MIN);
+                }
+            },
+            {
+                let state = state.clone();
+                move |_, _| {
+                    state.next_generation();
+                    tracing::info!("Tray icon host went away, waiting for it to come back");
+                }
+            },
+        );
+
+        Ok(Self {
+            connection,
+            registration: Some(registration),
+            watcher: Some(watcher),
+            state,
+            unit: AppConfig::load().temperature_unit,
+        })
+    }
+
+    /// Whether a panel currently shows the icon
+    pub fn is_shown(&self) -> bool {
+        self.state.shown.load(Ordering::Relaxed)
+    }
+
+    /// Shared [`Self::is_shown`] for other threads
+    pub fn shown_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.state.shown)
+    }
+
+    /// Show the temperatures of `stats` and the active profile in the
+    /// tooltip, e.g. "CPU 64°C · GPU 52°C · Profile: Balanced". The panel
+    /// is only told if the text changed.
+    pub fn update_tooltip(&self, stats: &SystemStats, active_profile: Option<&str>) {
+        let summary = stats.status_summary(self.unit, active_profile);
+        if *self.state.tooltip.borrow() == summary {
+            return;
+        }
+        self.state.tooltip.replace(summary);
+
+        if let Err(err) =
+            self.connection
+                .emit_signal(None, ITEM_PATH, ITEM_INTERFACE, "NewToolTip", None)
+        {
+            tracing::warn!("Failed to update the tray tooltip: {err}");
+        }
+    }
+}
+
+/// Register the icon with the watcher, retrying with a growing delay until
+/// it works or `generation` is outdated.
+fn register(
+    connection: gio::DBusConnection,
+    state: Rc<TrayState>,
+    generation: u64,
+    retry_delay: Duration,
+) {
+    let Some(service) = connection.unique_name() else {
+        return;
+    };
+    relm4::spawn_local(async move {
+        let result = connection
+            .call_future(
+                Some(WATCHER_BUS_NAME),
+                WATCHER_PATH,
+                WATCHER_BUS_NAME,
+                "RegisterStatusNotifierItem",
+                Some(&(service.as_str(),).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+            )
+            .await;
+        if state.generation.get() != generation {
+            return;
+        }
+
+        match result {
+            Ok(_) => {
+                state.shown.store(true, Ordering::Relaxed);
+                tracing::info!("Tray icon registered");
+            }
+            Err(err) => {
+                tracing::info!(
+                    "Failed to register the tray icon, retrying in {}s: {err}",
+                    retry_delay.as_secs()
+                );
+                glib::timeout_add_local_once(retry_delay, move || {
+                    if state.generation.get() == generation {
+                        let next_delay = (retry_delay * 2).min(RETRY_DELAY_MAX);
+                        register(connection, state, generation, next_delay);
+                    }
+                });
+            }
+        }
+    });
+}
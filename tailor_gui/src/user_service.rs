@@ -0,0 +1,196 @@
+// src/user_service.rs
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the unit placed in ~/.config/systemd/user
+pub const SERVICE_NAME: &str = "tuxedo-control.service";
+
+/// Whether the system runs systemd, `systemctl --user` fails otherwise
+pub fn systemd_available() -> bool {
+    Path::new("/run/systemd/system").is_dir()
+}
+
+/// systemd user service that starts the GUI with the graphical session.
+/// Unlike the XDG autostart entry, systemd restarts the app if it crashes.
+pub struct UserService {
+    unit_dir: PathBuf,
+}
+
+impl UserService {
+    pub fn new() -> Result<Self> {
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => {
+                let home = std::env::var("HOME").context("HOME environment variable not set")?;
+                PathBuf::from(home).join(".config")
+            }
+        };
+        Ok(Self::with_unit_dir(config_dir.join("systemd/user")))
+    }
+
+    pub fn with_unit_dir(unit_dir: PathBuf) -> Self {
+        UserService { unit_dir }
+    }
+
+    pub fn unit_file(&self) -> PathBuf {
+        self.unit_dir.join(SERVICE_NAME)
+    }
+
+    /// Unit starting `exec` once the graphical session is up
+    pub fn unit_contents(exec: &Path) -> String {
+        format!(
+            "[Unit]\n\
+             Description=Tailor hardware control\n\
+             PartOf=graphical-session.target\n\
+             After=graphical-session.target\n\
+             \n\
+             [Service]\n\
+             ExecStart={}\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=graphical-session.target\n",
+            quote_exec(exec)
+        )
+    }
+
+    /// Write the unit file for `exec`
+    pub fn write_unit(&self, exec: &Path) -> Result<()> {
+        fs::create_dir_all(&self.unit_dir).context("Failed to create systemd user directory")?;
+        let path = self.unit_file();
+        fs::write(&path, Self::unit_contents(exec))
+            .context(format!("Failed to write {}", path.display()))
+    }
+
+    /// Remove the unit file if it exists
+    pub fn remove_unit(&self) -> Result<()> {
+        let path = self.unit_file();
+        if path.exists() {
+            fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Install and enable the service for the running executable
+    pub fn install(&self) -> Result<()> {
+        let exec = std::env::current_exe().context("Failed to find the executable")?;
+        self.write_unit(&exec)?;
+        systemctl(&["daemon-reload"])?;
+        systemctl(&["enable", SERVICE_NAME])?;
+
+        if !self.is_enabled()? {
+            anyhow::bail!("{} is not enabled after installing it", SERVICE_NAME);
+        }
+        println!("  ✓ Installed {}", SERVICE_NAME);
+        Ok(())
+    }
+
+    /// Disable the service and remove its unit file
+    pub fn uninstall(&self) -> Result<()> {
+        if self.unit_file().exists() {
+            systemctl(&["disable", SERVICE_NAME])?;
+        }
+        self.remove_unit()?;
+        systemctl(&["daemon-reload"])?;
+        println!("  ✓ Removed {}", SERVICE_NAME);
+        Ok(())
+    }
+
+    /// Whether the unit file exists and systemd starts it on login
+    pub fn is_enabled(&self) -> Result<bool> {
+        if !self.unit_file().exists() {
+            return Ok(false);
+        }
+        // Exits with an error for disabled units, so only the output counts
+        let output = Command::new("systemctl")
+            .args(["--user", "is-enabled", SERVICE_NAME])
+            .output()
+            .context("Failed to execute systemctl")?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim() == "enabled")
+    }
+
+    /// Install or uninstall the service to match `enabled`
+    pub fn sync(&self, enabled: bool) -> Result<()> {
+        if enabled == self.is_enabled()? {
+            return Ok(());
+        }
+        if enabled {
+            self.install()
+        } else {
+            self.uninstall()
+        }
+    }
+}
+
+/// `path` as a quoted systemd command line word, so spaces and quotes in
+/// it survive and `%` isn't taken for a specifier
+fn quote_exec(path: &Path) -> String {
+    let escaped = path
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+fn systemctl(args: &[&str]) -> Result<()> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .context("Failed to execute systemctl")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "systemctl --user {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let service = UserService::with_unit_dir(temp_dir.path().join("systemd/user"));
+
+        // Without a unit file systemctl isn't asked
+        assert!(!service.is_enabled().unwrap());
+
+        service
+            .write_unit(Path::new("/usr/bin/tailor_gui"))
+            .unwrap();
+        let unit = fs::read_to_string(service.unit_file()).unwrap();
+        assert!(service
+            .unit_file()
+            .ends_with("systemd/user/tuxedo-control.service"));
+        assert!(unit.contains("ExecStart=\"/usr/bin/tailor_gui\"\n"));
+        assert!(unit.contains("WantedBy=graphical-session.target\n"));
+
+        service.remove_unit().unwrap();
+        assert!(!service.unit_file().exists());
+        // Removing twice is fine
+        assert!(service.remove_unit().is_ok());
+    }
+
+    #[test]
+    fn test_quote_exec() {
+        assert_eq!(
+            quote_exec(Path::new("/home/me/My Apps/tailor_gui")),
+            "\"/home/me/My Apps/tailor_gui\""
+        );
+        assert_eq!(
+            quote_exec(Path::new("/opt/50%/a\"b")),
+            "\"/opt/50%%/a\\\"b\""
+        );
+    }
+}
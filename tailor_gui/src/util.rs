@@ -1,5 +1,10 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
 use gtk::gdk::RGBA;
 use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+use gtk::prelude::{Cast, CastNone, IsA, NativeExt, ObjectExt, ToplevelExt, WidgetExt};
+use gtk::{cairo, gdk, glib};
 use relm4::gtk;
 use tailor_api::Color;
 
@@ -30,3 +35,138 @@ pub fn fill_pixbuf(pixbuf: &Pixbuf, color: &Color) {
     let color: u32 = u32::from_be_bytes([color.r, color.g, color.b, 0]);
     pixbuf.fill(color);
 }
+
+/// Call `on_change` with whether `window` can be seen, whenever that
+/// changes: when it's shown, hidden, minimized or restored. Windows can't
+/// tell whether they're covered, so that counts as seen.
+pub fn connect_shown_changed<W, F>(window: &W, on_change: F)
+where
+    W: IsA<gtk::Window>,
+    F: Fn(bool) + 'static,
+{
+    let last_shown = Cell::new(None);
+    let update = Rc::new(move |window: &gtk::Window, mapped: bool| {
+        let minimized = window
+            .surface()
+            .and_downcast::<gdk::Toplevel>()
+            .is_some_and(|toplevel| toplevel.state().contains(gdk::ToplevelState::MINIMIZED));
+        let shown = mapped && !minimized;
+        if last_shown.replace(Some(shown)) != Some(shown) {
+            on_change(shown);
+        }
+    });
+
+    let window = window.upcast_ref::<gtk::Window>();
+    let on_map = Rc::clone(&update);
+    window.connect_map(move |window| on_map(window, true));
+    let on_unmap = Rc::clone(&update);
+    window.connect_unmap(move |window| on_unmap(window, false));
+    // The surface only exists once realized and is replaced when the
+    // window is realized again
+    window.connect_realize(move |window| {
+        let Some(surface) = window.surface() else {
+            return;
+        };
+        let update = Rc::clone(&update);
+        let window = window.downgrade();
+        surface.connect_notify_local(Some("state"), move |_, _| {
+            if let Some(window) = window.upgrade() {
+                update(&window, window.is_mapped());
+            }
+        });
+    });
+}
+
+/// Line of `values` (0-100%) filling `width`x`height` pixels, oldest value
+/// on the left. Drawn light on transparent for dark backgrounds.
+pub fn render_sparkline(values: &[f32], width: i32, height: i32) -> Pixbuf {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+    {
+        let ctx = cairo::Context::new(&surface).unwrap();
+        if values.len() > 1 {
+            let step = f64::from(width - 1) / (values.len() - 1) as f64;
+            // Keep the line width inside the image at 0% and 100%
+            let y = |value: f32| {
+                let fraction = f64::from(value.clamp(0.0, 100.0)) / 100.0;
+                1.0 + (1.0 - fraction) * f64::from(height - 2)
+            };
+
+            ctx.move_to(0.0, y(values[0]));
+            for (i, value) in values.iter().enumerate().skip(1) {
+                ctx.line_to(i as f64 * step, y(*value));
+            }
+            ctx.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+            ctx.set_line_width(1.5);
+            ctx.stroke_preserve().unwrap();
+
+            ctx.line_to(f64::from(width - 1), f64::from(height));
+            ctx.line_to(0.0, f64::from(height));
+            ctx.close_path();
+            ctx.set_source_rgba(1.0, 1.0, 1.0, 0.25);
+            ctx.fill().unwrap();
+        }
+    }
+    surface_to_pixbuf(surface)
+}
+
+/// One bar per share (0-1) filling `width`x`height` pixels, the largest
+/// share reaches the top. Drawn like [`render_sparkline`].
+pub fn render_histogram(shares: &[f32], width: i32, height: i32) -> Pixbuf {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+    {
+        let ctx = cairo::Context::new(&surface).unwrap();
+        let largest = shares.iter().copied().fold(0.0, f32::max);
+        if largest > 0.0 {
+            let slot = f64::from(width) / shares.len() as f64;
+            // A pixel between the bars, unless they get too thin for it
+            let gap = if slot > 3.0 { 1.0 } else { 0.0 };
+            for (i, share) in shares.iter().enumerate() {
+                let bar = f64::from(share / largest) * f64::from(height);
+                ctx.rectangle(i as f64 * slot, f64::from(height) - bar, slot - gap, bar);
+            }
+            ctx.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+            ctx.fill().unwrap();
+        }
+    }
+    surface_to_pixbuf(surface)
+}
+
+fn surface_to_pixbuf(mut surface: cairo::ImageSurface) -> Pixbuf {
+    surface.flush();
+    let (width, height) = (surface.width(), surface.height());
+
+    // Cairo stores premultiplied ARGB in native byte order, pixbufs want RGBA
+    let stride = surface.stride() as usize;
+    let width_px = width as usize;
+    let data = surface.data().unwrap();
+    let mut rgba = Vec::with_capacity(width_px * height as usize * 4);
+    for row in data.chunks(stride) {
+        for pixel in row[..width_px * 4].chunks_exact(4) {
+            let argb = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            let alpha = argb >> 24;
+            let unpremultiply = |channel: u32| {
+                if alpha == 0 {
+                    0
+                } else {
+                    ((channel & 0xff) * 255 / alpha) as u8
+                }
+            };
+            rgba.extend([
+                unpremultiply(argb >> 16),
+                unpremultiply(argb >> 8),
+                unpremultiply(argb),
+                alpha as u8,
+            ]);
+        }
+    }
+
+    Pixbuf::from_bytes(
+        &glib::Bytes::from_owned(rgba),
+        Colorspace::Rgb,
+        true,
+        8,
+        width,
+        height,
+        width * 4,
+    )
+}
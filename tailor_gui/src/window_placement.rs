@@ -0,0 +1,85 @@
+//! Placement of the undecorated overlay window. GTK 4 leaves position and
+//! stacking to the compositor, on X11 `wmctrl` and `xwininfo` do it instead.
+//! Wayland doesn't let regular windows do either.
+
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use crate::color_temperature::{is_installed, SessionType};
+
+/// Whether windows can be placed: an X11 session with wmctrl and xwininfo
+pub fn supported() -> bool {
+    SessionType::detect() == Some(SessionType::X11)
+        && is_installed("wmctrl")
+        && is_installed("xwininfo")
+}
+
+/// Top left corner of the window titled `title` on the screen
+pub fn position(title: &str) -> Result<(i32, i32)> {
+    let output = Command::new("xwininfo")
+        .args(["-name", title])
+        .output()
+        .context("Failed to execute xwininfo")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "xwininfo failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_xwininfo(&String::from_utf8_lossy(&output.stdout))
+        .context("xwininfo reported no position")
+}
+
+/// Keep the window titled `title` above all others and move it to
+/// `position` if there is one
+pub fn place(title: &str, position: Option<(i32, i32)>) -> Result<()> {
+    wmctrl(&["-F", "-r", title, "-b", "add,above"])?;
+    if let Some((x, y)) = position {
+        // Gravity 0 is the window's own, -1 keeps the size
+        wmctrl(&["-F", "-r", title, "-e", &format!("0,{x},{y},-1,-1")])?;
+    }
+    Ok(())
+}
+
+fn wmctrl(args: &[&str]) -> Result<()> {
+    let status = Command::new("wmctrl")
+        .args(args)
+        .status()
+        .context("Failed to execute wmctrl")?;
+    if !status.success() {
+        anyhow::bail!("wmctrl {} failed", args.join(" "));
+    }
+    Ok(())
+}
+
+fn parse_xwininfo(output: &str) -> Option<(i32, i32)> {
+    let value = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(key))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    Some((
+        value("Absolute upper-left X:")?,
+        value("Absolute upper-left Y:")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xwininfo() {
+        let output = "\n\
+            xwininfo: Window id: 0x3a00003 \"Tailor overlay\"\n\
+            \n\
+            \x20 Absolute upper-left X:  1620\n\
+            \x20 Absolute upper-left Y:  -12\n\
+            \x20 Relative upper-left X:  0\n\
+            \x20 Width: 142\n";
+        assert_eq!(parse_xwininfo(output), Some((1620, -12)));
+        assert_eq!(parse_xwininfo("xwininfo: error: No window with name"), None);
+    }
+}